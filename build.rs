@@ -0,0 +1,17 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
+    compile_protos()?;
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+fn compile_protos() -> Result<(), Box<dyn std::error::Error>> {
+    // So building doesn't need protoc installed.
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure()
+        // The generated `connect` needs the 2021 prelude. Clients can make a
+        // `Channel` themselves instead.
+        .build_transport(false)
+        .compile(&["proto/status.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,28 @@
+//! Checks UP SAIS once with your own login details, read from the same
+//! environment variables as the bot:
+//!
+//! ```sh
+//! TIMEZONE_OFFSET=-480 USER_ID=... PASSWORD=... REQUEST_ID=... cargo run --example check
+//! ```
+
+use is_sais_down_bot::{DownReason, LoginDetails, SaisClient, SaisConfig, ServiceStatus};
+
+#[tokio::main]
+async fn main() -> Result<(), is_sais_down_bot::Error> {
+    let mut sais_config = SaisConfig::new(
+        "https://sais.up.edu.ph/psp/ps/?cmd=login&languageCd=ENG",
+        "<title>Employee-facing registry content</title>",
+    );
+    sais_config.maintenance_strings = vec!["under maintenance".to_string()];
+    sais_config.logout_url = Some("https://sais.up.edu.ph/psp/ps/?cmd=logout".to_string());
+
+    let mut client = SaisClient::with_config(sais_config, LoginDetails::get());
+    let status = client.check().await?;
+    match status {
+        ServiceStatus::Up | ServiceStatus::Degraded(_) => println!("Go enlist!"),
+        ServiceStatus::Down(DownReason::LoginFailed) => println!("Logging in doesn't work."),
+        _ => println!("SAIS is {}.", status),
+    }
+    println!("The check took {:?}.", client.last_latency());
+    Ok(())
+}
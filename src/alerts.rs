@@ -0,0 +1,1180 @@
+use crate::{
+    current_time_utc_plus_8, outage_duration_string, self_test,
+    surfaces::{StatusMessage, StatusMessageContent},
+    systemd, AlertsConfig, PostMortemConfig,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use is_sais_down_bot::{
+    anomaly::{AnomalyConfig, LatencyAnomaly, LatencyBaseline},
+    calendar::CalendarConfig,
+    digest::{digest_message, DigestLog, DigestPeriod},
+    escalation::{EscalationPolicy, EscalationStep, EscalationTarget, EscalationTracker},
+    exporters::CheckExporter,
+    gateway::{GatewayWatch, QueuedAnnouncement},
+    guilds::GuildSettingsStore,
+    heartbeat::Heartbeat,
+    history::{CheckRecord, HistoryStore},
+    hooks::Hooks,
+    incidents::{self, IncidentIssues},
+    layout::LayoutWatcher,
+    leader::{self, Leadership},
+    maintenance::{MaintenanceStore, MaintenanceWindow},
+    monitor::{
+        self, Alert, ConfirmedOutage, CredentialWatch, Monitor, MonitorConfig, MonitorHealth,
+        Transition,
+    },
+    oncall::OnCall,
+    outages::{OutageLog, OutageSummary},
+    pager::Pager,
+    postmortem::{self, PostMortem},
+    preferences::{NotificationKind, PreferencesStore},
+    reminders::{ReminderConfig, ReminderSchedule},
+    routes::RouteChecker,
+    screenshot::{self, ScreenshotConfig},
+    settings::SettingsStore,
+    sla::{self, ErrorBudget, SlaConfig},
+    state::{self, SharedState},
+    status::ServiceStatus,
+    uptime::HourlyUptime,
+};
+use poise::serenity_prelude::{
+    self as serenity, AttachmentType, ChannelId, ChannelType, CreateMessage, Http, Message,
+    MessageBuilder, MessageFlags, MessageId, RoleId, StatusCode, UserId, Webhook,
+};
+use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
+use tokio::{
+    sync::watch,
+    time::{self, MissedTickBehavior},
+};
+
+/// Checks SAIS on an interval in the background.
+pub struct MonitorTask {
+    pub http: Arc<Http>,
+    pub route_checker: Arc<RouteChecker>,
+    pub monitor_config: MonitorConfig,
+    // Changes the monitor config during busy parts of the academic year.
+    pub calendar: CalendarConfig,
+    // Confirmed changes are announced here, when set.
+    pub alerts_config: Option<AlertsConfig>,
+    // How often long outages are brought up again, under their
+    // announcements.
+    pub reminders: ReminderConfig,
+    // What happened during the current outage, for the recovery
+    // announcement.
+    pub outage_log: Arc<OutageLog>,
+    // Each outage gets a post-mortem draft, when set.
+    pub post_mortems: Option<PostMortemConfig>,
+    // Who else to tell as an outage goes on, along with each server's own
+    // policy.
+    pub escalation: EscalationPolicy,
+    // Paged by escalation steps, when set.
+    pub pager: Option<Pager>,
+    // Maintainers escalations only go to whoever is on call, when set.
+    pub oncall: Option<Arc<OnCall>>,
+    // Long outages get a GitHub issue, closed once SAIS is back, when set.
+    pub incident_issues: Option<IncidentIssues>,
+    // Slowdowns are posted quietly in this channel, when set.
+    pub latency_alerts: Option<AnomalyConfig>,
+    // Overrides the monitor and alerts config while running.
+    pub settings: Arc<SettingsStore>,
+    // Messaged directly when the login page layout changes, when known.
+    pub owner_id: Option<UserId>,
+    // Told when SAIS keeps rejecting the bot's login details, along with the
+    // owner.
+    pub maintainer_ids: Vec<UserId>,
+    // Confirmed changes are also announced here, when set.
+    pub webhook: Option<Webhook>,
+    // Every check is recorded here, when set.
+    pub history: Option<Arc<dyn HistoryStore>>,
+    // Tracked from the history, and reported in the alerts channel.
+    pub sla_config: Option<SlaConfig>,
+    // Servers that chose an announcement channel with /setup.
+    pub guild_settings: Arc<GuildSettingsStore>,
+    // Users who chose what they're told about with /notify.
+    pub preferences: Arc<PreferencesStore>,
+    // Changes aren't announced during these.
+    pub maintenance: Arc<MaintenanceStore>,
+    pub health: Arc<MonitorHealth>,
+    // Told every confirmed status, for the status surfaces.
+    pub confirmed_statuses: watch::Sender<Option<ServiceStatus>>,
+    // A pinned message here is edited after every check, when set. Not
+    // announced in.
+    pub status_message_channel_id: Option<u64>,
+    // Outages are announced with a screenshot of SAIS, when set.
+    pub screenshot: Option<ScreenshotConfig>,
+    // Every check is also sent to these.
+    pub exporters: Vec<Box<dyn CheckExporter>>,
+    // Beaten on every check, for the supervisor.
+    pub heartbeat: Heartbeat,
+    // While the bot is cut off from Discord, channel announcements are held
+    // back and checks are recorded as no data. Not set in webhook-only mode.
+    pub gateway: Option<Arc<GatewayWatch>>,
+    // Only the leader checks and announces, when set.
+    pub leadership: Option<Arc<Leadership>>,
+    // Checks take its lock, and share their result through it.
+    pub state: Arc<dyn SharedState>,
+    pub instance_id: String,
+    // Decides which changes are announced, when set.
+    pub hooks: Option<Arc<Hooks>>,
+}
+
+impl MonitorTask {
+    /// bot.ron's monitor config, changed by the calendar and then by
+    /// `&config set`.
+    fn effective_monitor_config(&self) -> MonitorConfig {
+        let today = current_time_utc_plus_8().date_naive();
+        self.settings
+            .monitor_config(&self.calendar.monitor_config(&self.monitor_config, today))
+    }
+
+    /// Runs until the bot exits.
+    pub async fn run(self: Arc<Self>) {
+        let mut monitor_config = self.effective_monitor_config();
+        let mut monitor = Monitor::new(&monitor_config);
+        let mut layout_watcher = LayoutWatcher::default();
+        let mut credential_watch = CredentialWatch::new(self.monitor_config.confirm_after);
+        let mut latency_baseline = self.latency_alerts.clone().map(LatencyBaseline::new);
+        // The latest outage announcement in each channel, which reminders
+        // reply to.
+        let mut outage_announcements = Vec::<(ChannelId, MessageId)>::new();
+        let mut reminder_schedule = None;
+        // The declared maintenance under way, which people were told about.
+        let mut started_window = None;
+        let mut escalation = EscalationTracker::default();
+        let mut confirmed_outage = ConfirmedOutage::default();
+        // When the outage the pager was triggered for started.
+        let mut paged_since = None;
+        // The current outage's GitHub issue, once opened.
+        let mut incident_issue = None;
+        // Announcements kept for digests, and when each kind was last sent.
+        let mut digest_log = DigestLog::default();
+        let mut digests_sent = DigestPeriod::ALL
+            .iter()
+            .map(|period| (*period, period.last_due(Utc::now())))
+            .collect::<BTreeMap<_, _>>();
+        let mut status_message = self.status_message_channel_id.map(StatusMessage::new);
+        let mut sla_tracker = SlaTracker {
+            reported_on: current_time_utc_plus_8().date_naive(),
+            warned_about: None,
+        };
+        let mut interval = time::interval(monitor_config.interval());
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // Set up for bot.ron's interval, which the settings can't go over.
+        systemd::check_watchdog_timeout(self.monitor_config.interval());
+
+        loop {
+            interval.tick().await;
+            self.heartbeat.beat();
+            // Only fed from here, so a wedged monitor gets the bot restarted.
+            systemd::watchdog();
+            let latest_config = self.effective_monitor_config();
+            if latest_config != monitor_config {
+                tracing::info!("Monitoring with changed settings: {:?}", latest_config);
+                if latest_config.interval_secs != monitor_config.interval_secs {
+                    interval = time::interval_at(
+                        time::Instant::now() + latest_config.interval(),
+                        latest_config.interval(),
+                    );
+                    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                }
+                monitor.reconfigure(&latest_config);
+                monitor_config = latest_config;
+            }
+            if !leader::leads(self.leadership.as_deref()) {
+                // Taking over starts afresh, like a restart would.
+                monitor = Monitor::new(&monitor_config);
+                continue;
+            }
+            let checked_at = Utc::now();
+            let checked = state::with_check_lock(
+                self.state.as_ref(),
+                &self.instance_id,
+                state::CHECK_LOCK_TTL,
+                self.route_checker.check_all(),
+            )
+            .await;
+            let route_results = match checked {
+                Ok(route_results) => route_results,
+                Err(why) => {
+                    self.health.errored(checked_at);
+                    tracing::error!("Error while monitoring SAIS: {:?}", why);
+                    sentry::capture_error(why.as_ref());
+                    continue;
+                }
+            };
+            let status = route_results[0].status;
+            tracing::info!("Monitor check: SAIS is {}", status);
+            self.health.checked(checked_at, status);
+            let latest = CheckRecord::new(checked_at, &route_results[0]);
+            if let Err(why) = self.state.set_latest_status(&latest).await {
+                tracing::warn!("Could not share the latest status: {:?}", why);
+            }
+
+            let mut results = route_results.clone();
+            results.extend(self.route_checker.probe_tcp().await);
+            self.route_checker.probe_timings().await;
+            let cut_off = self
+                .gateway
+                .as_ref()
+                .is_some_and(|gateway| gateway.is_cut_off(checked_at));
+            if let Some(history) = &self.history {
+                let records = results
+                    .iter()
+                    .map(|result| {
+                        if cut_off {
+                            CheckRecord::no_data(checked_at, result, "gateway_disconnected")
+                        } else {
+                            CheckRecord::new(checked_at, result)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if let Err(why) = history.record(&records).await {
+                    tracing::error!("Could not record the check: {:?}", why);
+                    sentry::capture_error(why.as_ref());
+                }
+            }
+            for exporter in &self.exporters {
+                if let Err(why) = exporter.export(checked_at, &results).await {
+                    tracing::warn!("Could not export the check: {:?}", why);
+                }
+            }
+
+            if let (Some(baseline), Some(latency_alerts)) =
+                (&mut latency_baseline, &self.latency_alerts)
+            {
+                let anomaly = baseline.observe(&status, route_results[0].latency);
+                if let Some(anomaly) = anomaly.filter(|_| !cut_off) {
+                    tracing::info!("Posting {:?}", anomaly);
+                    let posted = ChannelId(latency_alerts.channel_id)
+                        .send_message(&self.http, |message| {
+                            message
+                                .content(latency_report(anomaly, latency_alerts.deviations))
+                                .flags(MessageFlags::SUPPRESS_NOTIFICATIONS)
+                        })
+                        .await;
+                    if let Err(why) = posted {
+                        tracing::error!("Could not post the latency: {:?}", why);
+                    }
+                }
+            }
+
+            if let (Some(sla_config), Some(history), Some(alerts_config)) =
+                (&self.sla_config, &self.history, self.alerts_config())
+            {
+                if let Err(why) = sla_tracker
+                    .update(
+                        &self.http,
+                        sla_config,
+                        history.as_ref(),
+                        &alerts_config,
+                        &self.calendar,
+                    )
+                    .await
+                {
+                    tracing::error!("Could not check the uptime target: {:?}", why);
+                    sentry::capture_error(why.as_ref());
+                }
+            }
+
+            if let Some(layout) = self.route_checker.login_page_layout().await {
+                if let Some(changes) = layout_watcher.observe(layout) {
+                    tracing::warn!("The SAIS login page layout changed: {}", changes);
+                    if let Some(owner_id) = self.owner_id {
+                        if let Err(why) = warn_owner(&self.http, owner_id, &changes).await {
+                            tracing::error!("Could not message the owner: {:?}", why);
+                            sentry::capture_error(&why);
+                        }
+                    }
+                }
+            }
+
+            if credential_watch.observe(status) {
+                tracing::warn!("SAIS keeps rejecting the bot's login details");
+                let warned = self_test::warn_maintainers(&self.http, &self.maintainers()).await;
+                if let Err(why) = warned {
+                    tracing::error!("Could not message the maintainers: {:?}", why);
+                    sentry::capture_error(&why);
+                }
+            }
+            // Only the maintainers hear about the bot's own login details.
+            let alert = monitor.observe(status.public());
+            self.outage_log.observe(checked_at, &status.public());
+            let confirmed = monitor.confirmed();
+            // Escalation counts from here, so a flapping outage still
+            // reaches the later steps.
+            let outage_started =
+                confirmed_outage.observe(self.route_checker.outage_since(), confirmed.as_ref());
+            self.confirmed_statuses.send_if_modified(|shown| {
+                let changed =
+                    shown.map(|status| status.kind()) != confirmed.map(|status| status.kind());
+                *shown = confirmed;
+                changed
+            });
+            if let Some(status_message) = status_message.as_mut().filter(|_| !cut_off) {
+                let content = StatusMessageContent {
+                    confirmed: confirmed.unwrap_or_else(|| status.public()),
+                    latest: status.public(),
+                    latency: route_results[0].latency,
+                    checked_at,
+                    outage_since: self.route_checker.outage_since(),
+                    last_day: self.last_day_uptime(checked_at).await,
+                };
+                if let Err(why) = status_message.update(&self.http, &content).await {
+                    tracing::error!("Could not update the status message: {:?}", why);
+                }
+            }
+            let window = self.maintenance.active(checked_at);
+            if window != started_window {
+                if let Some(window) = &window {
+                    let content = maintenance_started(window);
+                    digest_log.record(checked_at, NotificationKind::Maintenance, content.clone());
+                    if !cut_off {
+                        self.notify_maintenance(&content).await;
+                    }
+                }
+                started_window = window.clone();
+            }
+            let alert = match (alert, window) {
+                (Some(alert), Some(window)) => {
+                    tracing::info!("Not announcing {:?} during \"{}\"", alert, window.note);
+                    None
+                }
+                (alert, None) => alert,
+                (None, Some(_)) => None,
+            };
+            let alert = match (alert, &self.hooks) {
+                (Some(alert), Some(hooks)) if !hooks.should_announce(alert) => {
+                    tracing::info!("The alert script held back {:?}", alert);
+                    None
+                }
+                (alert, _) => alert,
+            };
+            if let Some(alert) = alert {
+                if cut_off {
+                    tracing::warn!("Holding back {:?} until Discord is back", alert);
+                } else {
+                    tracing::info!("Announcing {:?}", alert);
+                }
+                let outage_since = self.route_checker.outage_since();
+                let holiday = self
+                    .calendar
+                    .holiday_on(current_time_utc_plus_8().date_naive());
+                let screenshot = self.outage_screenshot(alert).await;
+                let is_outage = match alert {
+                    Alert::Changed(transition) => monitor::is_outage(&transition.to),
+                    Alert::Stable(status) => monitor::is_outage(&status),
+                    Alert::Unstable => false,
+                };
+                let summary = match alert {
+                    Alert::Changed(transition)
+                        if monitor::is_outage(&transition.from)
+                            && !monitor::is_outage(&transition.to) =>
+                    {
+                        self.outage_log.summary()
+                    }
+                    _ => None,
+                };
+                outage_announcements.clear();
+                reminder_schedule = if is_outage {
+                    ReminderSchedule::new(&self.reminders, checked_at)
+                } else {
+                    None
+                };
+                let kind = NotificationKind::of(alert);
+                digest_log.record(
+                    checked_at,
+                    kind,
+                    announcement(None, alert, outage_since, holiday, summary.as_ref()),
+                );
+                for (channel_id, role_id, guild_id) in self.announcement_targets() {
+                    let pings = guild_id.map_or(vec![], |guild_id| {
+                        self.preferences.pings(guild_id, kind, Utc::now())
+                    });
+                    let content = with_pings(
+                        announcement(role_id, alert, outage_since, holiday, summary.as_ref()),
+                        &pings,
+                    );
+                    if let Some(gateway) = self.gateway.as_ref().filter(|_| cut_off) {
+                        // The screenshot isn't worth holding on to.
+                        gateway.queue(QueuedAnnouncement {
+                            channel_id,
+                            content,
+                        });
+                        continue;
+                    }
+                    let announced = ChannelId(channel_id)
+                        .send_message(&self.http, |message| {
+                            message.content(content);
+                            if let Some(png) = &screenshot {
+                                message.add_file(screenshot_attachment(png));
+                            }
+                            message
+                        })
+                        .await;
+                    if let Ok(message) = &announced {
+                        publish(&self.http, message).await;
+                    }
+                    match announced {
+                        Ok(message) if is_outage => {
+                            outage_announcements.push((message.channel_id, message.id));
+                        }
+                        Ok(_) => {}
+                        Err(why) => {
+                            tracing::error!("Could not announce SAIS status change: {:?}", why);
+                            sentry::capture_error(&why);
+                        }
+                    }
+                }
+                if let Some(webhook) = &self.webhook {
+                    let announced = webhook
+                        .execute(&self.http, false, |message| {
+                            message.content(announcement(
+                                None,
+                                alert,
+                                outage_since,
+                                holiday,
+                                summary.as_ref(),
+                            ));
+                            if let Some(png) = &screenshot {
+                                message.add_file(screenshot_attachment(png));
+                            }
+                            message
+                        })
+                        .await;
+                    if let Err(why) = announced {
+                        tracing::error!("Could not announce SAIS status change: {:?}", why);
+                        sentry::capture_error(&why);
+                    }
+                }
+                if !cut_off {
+                    let content =
+                        announcement(None, alert, outage_since, holiday, summary.as_ref());
+                    self.send_dms(kind, &content).await;
+                }
+                // The pager doesn't go through Discord, so it isn't held back.
+                let pages = self.pager.as_ref().is_some_and(Pager::pages_on_outage);
+                if let Some(since) = outage_started.filter(|_| is_outage && pages) {
+                    let content = format!("UP SAIS has been {}.", outage_duration_string(since));
+                    self.page(since, &content, &mut paged_since).await;
+                }
+                if let (Some(summary), Some(post_mortem_config), false) =
+                    (summary, &self.post_mortems, cut_off)
+                {
+                    if let Err(why) = self.send_post_mortem(summary, post_mortem_config).await {
+                        tracing::error!("Could not send the post-mortem draft: {:?}", why);
+                        sentry::capture_error(why.as_ref());
+                    }
+                }
+            }
+
+            // Before the outage log is cleared, for the closing summary.
+            if let Some(incident_issues) = &self.incident_issues {
+                self.track_incident(
+                    incident_issues,
+                    &mut incident_issue,
+                    outage_started,
+                    checked_at,
+                )
+                .await;
+            }
+            if !confirmed.as_ref().is_some_and(monitor::is_outage) {
+                self.outage_log.clear_if_working();
+            }
+            if let Some(schedule) = &mut reminder_schedule {
+                if !confirmed.as_ref().is_some_and(monitor::is_outage) {
+                    reminder_schedule = None;
+                    outage_announcements.clear();
+                } else if !cut_off
+                    && self.maintenance.active(checked_at).is_none()
+                    && schedule.due(checked_at)
+                {
+                    self.remind(&outage_announcements).await;
+                }
+            }
+
+            match outage_started {
+                Some(since) if !cut_off && self.maintenance.active(checked_at).is_none() => {
+                    self.escalate(&mut escalation, &mut paged_since, since, checked_at)
+                        .await;
+                }
+                Some(_) => {}
+                None => {
+                    escalation.reset();
+                    if let (Some(since), Some(pager)) = (paged_since.take(), &self.pager) {
+                        if let Err(why) = pager.resolve(since).await {
+                            tracing::error!("Could not resolve the page: {:?}", why);
+                        }
+                    }
+                }
+            }
+
+            // Held back while cut off, then cover the whole time since.
+            if !cut_off {
+                for (period, sent) in digests_sent.iter_mut() {
+                    let due = period.last_due(checked_at);
+                    if due > *sent {
+                        self.send_digests(*period, *sent, due, &digest_log).await;
+                        *sent = due;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl MonitorTask {
+    // Replies to each outage announcement that SAIS is still down, without
+    // pinging anyone again.
+    async fn remind(&self, outage_announcements: &[(ChannelId, MessageId)]) {
+        let content = match self.route_checker.outage_since() {
+            Some(since) => format!(
+                "UP SAIS is still down. It's been {}.",
+                outage_duration_string(since)
+            ),
+            None => "UP SAIS is still down.".to_string(),
+        };
+        for (channel_id, message_id) in outage_announcements {
+            tracing::info!("Reminding {} that SAIS is still down", channel_id);
+            let reminded = channel_id
+                .send_message(&self.http, |message| {
+                    message
+                        .content(&content)
+                        .reference_message((*channel_id, *message_id))
+                })
+                .await;
+            if let Err(why) = reminded {
+                tracing::error!("Could not remind that SAIS is still down: {:?}", why);
+            }
+        }
+    }
+
+    // Tells the people who want to hear about maintenance that a declared
+    // window started, since changes aren't announced during it.
+    async fn notify_maintenance(&self, content: &str) {
+        self.send_dms(NotificationKind::Maintenance, content).await;
+        for (channel_id, _, guild_id) in self.announcement_targets() {
+            let pings = guild_id.map_or(vec![], |guild_id| {
+                self.preferences
+                    .pings(guild_id, NotificationKind::Maintenance, Utc::now())
+            });
+            if pings.is_empty() {
+                continue;
+            }
+            let sent = ChannelId(channel_id)
+                .say(&self.http, with_pings(content.to_string(), &pings))
+                .await;
+            if let Err(why) = sent {
+                tracing::warn!("Could not say that maintenance started: {:?}", why);
+            }
+        }
+    }
+
+    // Sends the announcements from `since` up to `until` to the users and
+    // servers that want a digest this often.
+    async fn send_digests(
+        &self,
+        period: DigestPeriod,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        digest_log: &DigestLog,
+    ) {
+        for (user_id, kinds) in self.preferences.digests(period, until) {
+            let entries = digest_log.between(since, until, Some(&kinds));
+            let content = match digest_message(since, &entries) {
+                Some(content) => content,
+                None => continue,
+            };
+            let sent = match UserId(user_id).create_dm_channel(&self.http).await {
+                Ok(dm_channel) => dm_channel.say(&self.http, content).await.map(|_| ()),
+                Err(why) => Err(why),
+            };
+            if let Err(why) = sent {
+                tracing::warn!("Could not send user {} their digest: {:?}", user_id, why);
+            }
+        }
+        let content = match digest_message(since, &digest_log.between(since, until, None)) {
+            Some(content) => content,
+            None => return,
+        };
+        for (guild_id, settings) in self.guild_settings.all() {
+            let channel_id = match settings.announcement_channel_id {
+                Some(channel_id) if settings.digest == Some(period) => channel_id,
+                _ => continue,
+            };
+            if let Err(why) = ChannelId(channel_id).say(&self.http, &content).await {
+                tracing::warn!("Could not post the digest in guild {}: {:?}", guild_id, why);
+            }
+        }
+    }
+
+    // Takes the escalation steps that are due, from discord.ron's policy and
+    // each server's.
+    async fn escalate(
+        &self,
+        escalation: &mut EscalationTracker,
+        paged_since: &mut Option<DateTime<Utc>>,
+        since: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) {
+        let alerts_config = self.alerts_config();
+        let channel = alerts_config
+            .as_ref()
+            .map(|alerts_config| (alerts_config.channel_id, alerts_config.role_id));
+        for step in escalation.due(None, &self.escalation, since, now) {
+            self.take_step(step, channel, since, paged_since).await;
+        }
+        for (guild_id, settings) in self.guild_settings.all() {
+            let channel = settings
+                .announcement_channel_id
+                .map(|channel_id| (channel_id, settings.alert_role(now)));
+            for step in escalation.due(Some(guild_id), &settings.escalation, since, now) {
+                if step.target.allowed_in_guilds() {
+                    self.take_step(step, channel, since, paged_since).await;
+                }
+            }
+        }
+    }
+
+    // Pings, DMs or pages for one escalation step. Roles are pinged in
+    // `channel`, which comes with the alert role.
+    async fn take_step(
+        &self,
+        step: &EscalationStep,
+        channel: Option<(u64, Option<u64>)>,
+        since: DateTime<Utc>,
+        paged_since: &mut Option<DateTime<Utc>>,
+    ) {
+        tracing::info!("Escalating to {}", step);
+        let content = format!("UP SAIS has been {}.", outage_duration_string(since));
+        let mut users = vec![];
+        let role_id = match &step.target {
+            EscalationTarget::AlertRole => channel.and_then(|(_, role_id)| role_id),
+            EscalationTarget::Role(role_id) => Some(*role_id),
+            EscalationTarget::User(user_id) => {
+                users.push(UserId(*user_id));
+                None
+            }
+            EscalationTarget::Maintainers => {
+                users = match self
+                    .oncall
+                    .as_ref()
+                    .and_then(|oncall| oncall.current(Utc::now()))
+                {
+                    Some(user_id) => vec![UserId(user_id)],
+                    None => self.maintainers(),
+                };
+                None
+            }
+            EscalationTarget::Pager => {
+                self.page(since, &content, paged_since).await;
+                None
+            }
+        };
+        if let (Some(role_id), Some((channel_id, _))) = (role_id, channel) {
+            let content = MessageBuilder::new()
+                .mention(&RoleId(role_id))
+                .push(" ")
+                .push(&content)
+                .build();
+            if let Err(why) = ChannelId(channel_id).say(&self.http, content).await {
+                tracing::warn!("Could not escalate in {}: {:?}", channel_id, why);
+            }
+        }
+        for user_id in users {
+            let sent = match user_id.create_dm_channel(&self.http).await {
+                Ok(dm_channel) => dm_channel.say(&self.http, &content).await.map(|_| ()),
+                Err(why) => Err(why),
+            };
+            if let Err(why) = sent {
+                tracing::warn!("Could not escalate to user {}: {:?}", user_id, why);
+            }
+        }
+    }
+
+    // Opens an incident for the outage since `since`, when there's a pager.
+    async fn page(
+        &self,
+        since: DateTime<Utc>,
+        content: &str,
+        paged_since: &mut Option<DateTime<Utc>>,
+    ) {
+        if let Some(pager) = &self.pager {
+            match pager.trigger(since, content).await {
+                Ok(()) => *paged_since = Some(since),
+                Err(why) => {
+                    tracing::error!("Could not page: {:?}", why);
+                    sentry::capture_error(why.as_ref());
+                }
+            }
+        }
+    }
+
+    // Opens an issue once the outage since `outage_started` has gone on long
+    // enough, outside declared maintenance, and closes it with a summary once
+    // the confirmed status leaves it.
+    async fn track_incident(
+        &self,
+        incident_issues: &IncidentIssues,
+        incident_issue: &mut Option<u64>,
+        outage_started: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) {
+        match (outage_started, *incident_issue) {
+            (Some(since), None)
+                if incident_issues.due(since, now) && self.maintenance.active(now).is_none() =>
+            {
+                match incident_issues.open(since, now).await {
+                    Ok(number) => {
+                        tracing::info!("Opened incident issue #{}", number);
+                        *incident_issue = Some(number);
+                    }
+                    Err(why) => {
+                        tracing::error!("Could not open the incident issue: {:?}", why);
+                        sentry::capture_error(why.as_ref());
+                    }
+                }
+            }
+            (None, Some(number)) => {
+                *incident_issue = None;
+                let summary = self.outage_log.summary();
+                let timeline = match (&summary, &self.history) {
+                    (Some(summary), Some(history)) => {
+                        match history
+                            .since(summary.started_at - postmortem::margin())
+                            .await
+                        {
+                            Ok(records) => PostMortem::new(summary.clone(), &records).timeline,
+                            Err(why) => {
+                                tracing::warn!("Could not read the outage's checks: {:?}", why);
+                                vec![]
+                            }
+                        }
+                    }
+                    _ => vec![],
+                };
+                let comment = incidents::closing_comment(summary.as_ref(), &timeline);
+                if let Err(why) = incident_issues.close(number, &comment).await {
+                    tracing::error!("Could not close incident issue #{}: {:?}", number, why);
+                    sentry::capture_error(why.as_ref());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Sends `content` to everyone who wants to hear about `kind` directly.
+    async fn send_dms(&self, kind: NotificationKind, content: &str) {
+        for user_id in self.preferences.dms(kind, Utc::now()) {
+            let sent = match UserId(user_id).create_dm_channel(&self.http).await {
+                Ok(dm_channel) => dm_channel.say(&self.http, content).await.map(|_| ()),
+                Err(why) => Err(why),
+            };
+            if let Err(why) = sent {
+                tracing::warn!("Could not notify user {}: {:?}", user_id, why);
+            }
+        }
+    }
+
+    // The main route's uptime over the last day, if there's a history.
+    async fn last_day_uptime(&self, now: DateTime<Utc>) -> Option<HourlyUptime> {
+        let history = self.history.as_ref()?;
+        match history.since(now - chrono::Duration::hours(24)).await {
+            Ok(records) => Some(HourlyUptime::from_records(&records, now)),
+            Err(why) => {
+                tracing::warn!("Could not read the last day's checks: {:?}", why);
+                None
+            }
+        }
+    }
+
+    // Drafts a post-mortem from the history around the outage, and posts it
+    // or sends it to the maintainers.
+    async fn send_post_mortem(
+        &self,
+        summary: OutageSummary,
+        post_mortem_config: &PostMortemConfig,
+    ) -> Result<(), is_sais_down_bot::Error> {
+        let history = match &self.history {
+            Some(history) => history,
+            None => return Ok(()),
+        };
+        let records = history
+            .since(summary.started_at - postmortem::margin())
+            .await?;
+        let post_mortem = PostMortem::new(summary, &records);
+        let markdown = post_mortem.to_markdown();
+        let png = post_mortem.latency_png()?;
+        let file_name = post_mortem
+            .summary
+            .started_at
+            .with_timezone(&chrono::FixedOffset::east_opt(3600 * 8).unwrap())
+            .format("sais-outage-%Y-%m-%d-%H%M");
+        let content = "Here's a draft post-mortem of the last outage, with each check's latency \
+                       around it. The outage is shaded red, and failed checks are red dots.";
+        let send = |message: &mut CreateMessage<'_>| {
+            message
+                .content(content)
+                .add_file(AttachmentType::Bytes {
+                    data: Cow::Owned(markdown.clone().into_bytes()),
+                    filename: format!("{}.md", file_name),
+                })
+                .add_file(AttachmentType::Bytes {
+                    data: Cow::Owned(png.clone()),
+                    filename: format!("{}.png", file_name),
+                });
+        };
+        match post_mortem_config.channel_id {
+            Some(channel_id) => {
+                ChannelId(channel_id)
+                    .send_message(&self.http, |message| {
+                        send(message);
+                        message
+                    })
+                    .await?;
+            }
+            None => {
+                for maintainer in self.maintainers() {
+                    let dm_channel = maintainer.create_dm_channel(&self.http).await?;
+                    dm_channel
+                        .send_message(&self.http, |message| {
+                            send(message);
+                            message
+                        })
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // What SAIS is serving, for alerts that it's out.
+    async fn outage_screenshot(&self, alert: Alert) -> Option<Vec<u8>> {
+        let screenshot_config = self.screenshot.as_ref()?;
+        let status = match alert {
+            Alert::Changed(transition) => transition.to,
+            Alert::Stable(status) => status,
+            Alert::Unstable => return None,
+        };
+        if !monitor::is_outage(&status) {
+            return None;
+        }
+        let login_url = self.route_checker.login_url().await;
+        match screenshot::capture(screenshot_config, &login_url).await {
+            Ok(png) => Some(png),
+            Err(why) => {
+                tracing::error!("Could not take a screenshot of SAIS: {:?}", why);
+                None
+            }
+        }
+    }
+
+    fn maintainers(&self) -> Vec<UserId> {
+        let mut maintainers = self.owner_id.into_iter().collect::<Vec<_>>();
+        for maintainer_id in &self.maintainer_ids {
+            if !maintainers.contains(maintainer_id) {
+                maintainers.push(*maintainer_id);
+            }
+        }
+        maintainers
+    }
+
+    // The alerts channel from discord.ron, unless another was set with
+    // `&config set`. Never set in webhook-only mode.
+    fn alerts_config(&self) -> Option<AlertsConfig> {
+        if self.webhook.is_some() {
+            return None;
+        }
+        let base = self
+            .alerts_config
+            .as_ref()
+            .map(|alerts_config| (alerts_config.channel_id, alerts_config.role_id));
+        self.settings
+            .alerts_target(base)
+            .map(|(channel_id, role_id)| AlertsConfig {
+                channel_id,
+                role_id,
+            })
+    }
+
+    // The alerts channel, and every server's chosen channel, with the role to
+    // ping in each and the server it's in. The status message's channel, and
+    // servers that get digests instead, are left out.
+    fn announcement_targets(&self) -> Vec<(u64, Option<u64>, Option<u64>)> {
+        let mut targets = self
+            .alerts_config()
+            .iter()
+            .map(|alerts_config| (alerts_config.channel_id, alerts_config.role_id, None))
+            .collect::<Vec<_>>();
+        for (guild_id, settings) in self.guild_settings.all() {
+            // Those get digests instead.
+            if settings.digest.is_some() {
+                continue;
+            }
+            if let Some(channel_id) = settings.announcement_channel_id {
+                let role_id = settings.alert_role(Utc::now());
+                targets.push((channel_id, role_id, Some(guild_id)));
+            }
+        }
+        targets.retain(|(channel_id, _, _)| Some(*channel_id) != self.status_message_channel_id);
+        targets
+    }
+}
+
+/// Remembers what has been said about the uptime target, so it's only
+/// reported once a day, and a breach is only warned about once a month.
+struct SlaTracker {
+    reported_on: NaiveDate,
+    warned_about: Option<(i32, u32)>,
+}
+
+impl SlaTracker {
+    async fn update(
+        &mut self,
+        http: &Http,
+        sla_config: &SlaConfig,
+        history: &dyn HistoryStore,
+        alerts_config: &AlertsConfig,
+        calendar: &CalendarConfig,
+    ) -> Result<(), is_sais_down_bot::Error> {
+        let now = current_time_utc_plus_8();
+        let budget =
+            ErrorBudget::for_month(history, sla_config.monthly_target_percent, now).await?;
+        let month = (now.year(), now.month());
+        if budget.is_exceeded() && self.warned_about != Some(month) {
+            self.warned_about = Some(month);
+            tracing::warn!("SAIS missed its uptime target this month: {:?}", budget);
+            let mut message = MessageBuilder::new();
+            if let Some(role_id) = alerts_config.role_id {
+                message.mention(&RoleId(role_id)).push(" ");
+            }
+            message.push(format!(
+                "UP SAIS has been down for about {} this month, more than the {} \
+                 its {}% uptime target allows.",
+                sla::format_duration(budget.downtime),
+                sla::format_duration(budget.budget),
+                budget.target_percent
+            ));
+            ChannelId(alerts_config.channel_id)
+                .say(http, message.build())
+                .await?;
+        }
+
+        if now.date_naive() != self.reported_on {
+            self.reported_on = now.date_naive();
+            ChannelId(alerts_config.channel_id)
+                .say(
+                    http,
+                    daily_report(&budget, calendar.holiday_on(now.date_naive())),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn daily_report(budget: &ErrorBudget, holiday: Option<&str>) -> String {
+    let remaining = if budget.is_exceeded() {
+        format!(
+            "the error budget is used up, by {}",
+            sla::format_duration(-budget.remaining())
+        )
+    } else {
+        format!(
+            "{} of the error budget is left",
+            sla::format_duration(budget.remaining())
+        )
+    };
+    let mut report = format!(
+        "Daily report: UP SAIS has been up {:.2}% of this month (target: {}%), and {}.",
+        budget.uptime_percent, budget.target_percent, remaining
+    );
+    if let Some(holiday) = holiday {
+        report.push_str(&format!(" It's {}, a low traffic period.", holiday));
+    }
+    report
+}
+
+// Like "It was down for 2h 13m, from 11:50 PHT, with 27 failed checks
+// (read_timeout 20, http_502 7). People checked with &sais 14 times
+// meanwhile."
+fn outage_summary(summary: &OutageSummary) -> String {
+    let utc_plus_8 = chrono::FixedOffset::east_opt(3600 * 8).unwrap();
+    let reasons = summary
+        .reasons
+        .iter()
+        .map(|(reason, count)| format!("{} {}", reason, count))
+        .collect::<Vec<_>>();
+    let mut text = format!(
+        "It was down for {}, from {} PHT, with {} failed checks ({}).",
+        sla::format_duration(summary.downtime()),
+        summary
+            .started_at
+            .with_timezone(&utc_plus_8)
+            .format("%b %-d %H:%M"),
+        summary.failed_checks,
+        reasons.join(", ")
+    );
+    if summary.user_checks > 0 {
+        text.push_str(&format!(
+            " People checked with &sais {} times meanwhile.",
+            summary.user_checks
+        ));
+    }
+    text
+}
+
+fn latency_report(anomaly: LatencyAnomaly, deviations: f64) -> String {
+    match anomaly {
+        LatencyAnomaly::Slow {
+            latency_ms,
+            mean_ms,
+            std_dev_ms,
+        } => format!(
+            "UP SAIS is slower than usual: the latest check took {} ms, \
+             more than {} standard deviations over the recent average of {:.0} ms (± {:.0} ms).",
+            latency_ms, deviations, mean_ms, std_dev_ms
+        ),
+        LatencyAnomaly::Recovered {
+            latency_ms,
+            mean_ms,
+        } => format!(
+            "UP SAIS is back to its usual speed: the latest check took {} ms, \
+             against a recent average of {:.0} ms.",
+            latency_ms, mean_ms
+        ),
+    }
+}
+
+fn screenshot_attachment(png: &[u8]) -> AttachmentType<'_> {
+    AttachmentType::Bytes {
+        data: Cow::Borrowed(png),
+        filename: "sais.png".to_string(),
+    }
+}
+
+// Maintenance announced on `holiday` is said to be likely planned.
+fn announcement(
+    role_id: Option<u64>,
+    alert: Alert,
+    outage_since: Option<DateTime<Utc>>,
+    holiday: Option<&str>,
+    summary: Option<&OutageSummary>,
+) -> String {
+    let mut message = MessageBuilder::new();
+    if let Some(role_id) = role_id {
+        message.mention(&RoleId(role_id)).push(" ");
+    }
+    let query_time_string = current_time_utc_plus_8().format("%H:%M:%S");
+    message.push(match alert {
+        Alert::Changed(transition) => format!(
+            "As of {}, UP SAIS is {} (it was {}).",
+            query_time_string, transition.to, transition.from
+        ),
+        Alert::Unstable => format!(
+            "As of {}, UP SAIS is unstable, it keeps going up and down. \
+             I'll say when it settles.",
+            query_time_string
+        ),
+        Alert::Stable(status) => format!(
+            "As of {}, UP SAIS has settled, and is {}.",
+            query_time_string, status
+        ),
+    });
+    if let (Alert::Changed(_) | Alert::Stable(_), Some(since)) = (alert, outage_since) {
+        message.push(format!(" It's been {}.", outage_duration_string(since)));
+    }
+    if let Some(summary) = summary {
+        message.push(format!(" {}", outage_summary(summary)));
+    }
+    if let (
+        Alert::Changed(Transition {
+            to: ServiceStatus::Maintenance,
+            ..
+        })
+        | Alert::Stable(ServiceStatus::Maintenance),
+        Some(holiday),
+    ) = (alert, holiday)
+    {
+        message.push(format!(" It's {}, so this is likely planned.", holiday));
+    }
+    message.build()
+}
+
+fn maintenance_started(window: &MaintenanceWindow) -> String {
+    format!(
+        "Planned maintenance on UP SAIS has started: {}. It should be over by {} PHT.",
+        window.note,
+        window
+            .ends_at
+            .with_timezone(&chrono::FixedOffset::east_opt(3600 * 8).unwrap())
+            .format("%b %-d %H:%M")
+    )
+}
+
+// Mentions the users who chose to be pinged, after the announcement.
+fn with_pings(content: String, user_ids: &[u64]) -> String {
+    if user_ids.is_empty() {
+        return content;
+    }
+    let mut message = MessageBuilder::new();
+    message.push(content).push("\n");
+    for user_id in user_ids {
+        message.mention(&UserId(*user_id)).push(" ");
+    }
+    message.build().trim_end().to_string()
+}
+
+async fn warn_owner(
+    http: &Http,
+    owner_id: UserId,
+    changes: &str,
+) -> Result<(), poise::serenity_prelude::Error> {
+    let dm_channel = owner_id.create_dm_channel(http).await?;
+    dm_channel
+        .say(
+            http,
+            format!(
+                "The UP SAIS login page changed ({}). \
+                 The login check might need updating before it starts misreporting.",
+                changes
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Publishes an announcement to the servers following its channel, if it was
+/// posted in an announcement channel. Failing to is only logged, since the
+/// announcement itself went out.
+pub async fn publish(http: &Http, message: &Message) {
+    let is_news = match message.channel_id.to_channel(http).await {
+        Ok(channel) => channel
+            .guild()
+            .is_some_and(|channel| channel.kind == ChannelType::News),
+        Err(why) => {
+            tracing::warn!(
+                "Could not look up channel {}: {:?}",
+                message.channel_id,
+                why
+            );
+            return;
+        }
+    };
+    if !is_news {
+        return;
+    }
+    match message.crosspost(http).await {
+        Ok(_) => tracing::info!("Published the announcement in {}", message.channel_id),
+        Err(serenity::Error::Http(why)) if why.status_code() == Some(StatusCode::FORBIDDEN) => {
+            tracing::warn!(
+                "Not allowed to publish in announcement channel {}. \
+                 It needs the Send Messages permission there.",
+                message.channel_id
+            );
+        }
+        Err(why) => {
+            tracing::error!("Could not publish the announcement: {:?}", why);
+            sentry::capture_error(&why);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Notices SAIS getting slower than usual, measured against its own recent
+//! checks, often before it goes down altogether.
+
+use crate::status::ServiceStatus;
+use serde::Deserialize;
+use std::{collections::VecDeque, time::Duration};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnomalyConfig {
+    /// Where slowdowns are posted, without pinging anyone.
+    pub channel_id: u64,
+    /// How many standard deviations above the average latency count as slow.
+    #[serde(default = "default_deviations")]
+    pub deviations: f64,
+    /// How many of the latest checks the average is taken over.
+    #[serde(default = "default_baseline_checks")]
+    pub baseline_checks: usize,
+    /// Nothing counts as slow until this many checks are in the baseline.
+    #[serde(default = "default_min_checks")]
+    pub min_checks: usize,
+    /// Slow checks also have to be at least this much over the average, so
+    /// a very steady SAIS isn't called slow over a few milliseconds.
+    #[serde(default = "default_min_deviation_ms")]
+    pub min_deviation_ms: u64,
+    /// How many slow checks in a row it takes to post about it.
+    #[serde(default = "default_slow_after")]
+    pub slow_after: u32,
+}
+
+fn default_deviations() -> f64 {
+    3.0
+}
+
+fn default_baseline_checks() -> usize {
+    288
+}
+
+fn default_min_checks() -> usize {
+    30
+}
+
+fn default_min_deviation_ms() -> u64 {
+    500
+}
+
+fn default_slow_after() -> u32 {
+    2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyAnomaly {
+    /// Checks have been this slow, against the baseline's average and
+    /// standard deviation.
+    Slow {
+        latency_ms: u64,
+        mean_ms: f64,
+        std_dev_ms: f64,
+    },
+    /// The latest check was back within the usual.
+    Recovered { latency_ms: u64, mean_ms: f64 },
+}
+
+/// The latency of the latest working checks. Checks that failed say nothing
+/// about how fast SAIS is, so they're left out.
+#[derive(Debug)]
+pub struct LatencyBaseline {
+    config: AnomalyConfig,
+    recent: VecDeque<f64>,
+    slow_streak: u32,
+    slow: bool,
+}
+
+impl LatencyBaseline {
+    pub fn new(config: AnomalyConfig) -> Self {
+        LatencyBaseline {
+            config,
+            recent: VecDeque::new(),
+            slow_streak: 0,
+            slow: false,
+        }
+    }
+
+    /// The average and standard deviation of the baseline, in milliseconds.
+    pub fn mean_and_std_dev(&self) -> Option<(f64, f64)> {
+        if self.recent.is_empty() {
+            return None;
+        }
+        let count = self.recent.len() as f64;
+        let mean = self.recent.iter().sum::<f64>() / count;
+        let variance = self
+            .recent
+            .iter()
+            .map(|latency| (latency - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        Some((mean, variance.sqrt()))
+    }
+
+    /// Compares a check with the baseline before adding it, returning what
+    /// is worth posting. A slowdown that lasts becomes the new baseline
+    /// after `baseline_checks` checks.
+    pub fn observe(&mut self, status: &ServiceStatus, latency: Duration) -> Option<LatencyAnomaly> {
+        if !matches!(status, ServiceStatus::Up | ServiceStatus::Degraded(_)) {
+            return None;
+        }
+        let latency_ms = latency.as_millis() as u64;
+        let anomaly = match self.mean_and_std_dev() {
+            Some((mean_ms, std_dev_ms)) if self.recent.len() >= self.config.min_checks => {
+                let allowed =
+                    (self.config.deviations * std_dev_ms).max(self.config.min_deviation_ms as f64);
+                if latency_ms as f64 > mean_ms + allowed {
+                    self.slow_streak += 1;
+                    (self.slow_streak >= self.config.slow_after && !self.slow).then(|| {
+                        self.slow = true;
+                        LatencyAnomaly::Slow {
+                            latency_ms,
+                            mean_ms,
+                            std_dev_ms,
+                        }
+                    })
+                } else {
+                    self.slow_streak = 0;
+                    let recovered = self.slow;
+                    self.slow = false;
+                    recovered.then_some(LatencyAnomaly::Recovered {
+                        latency_ms,
+                        mean_ms,
+                    })
+                }
+            }
+            _ => None,
+        };
+        self.recent.push_back(latency_ms as f64);
+        while self.recent.len() > self.config.baseline_checks {
+            self.recent.pop_front();
+        }
+        anomaly
+    }
+}
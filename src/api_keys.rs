@@ -0,0 +1,89 @@
+//! API keys for the HTTP API, each with its own rate limit, so the status
+//! data can be shared without the API being hammered.
+
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    /// Who the key was given to, for the logs.
+    pub name: String,
+    pub key: String,
+    /// Also how many requests can be made at once after a quiet spell.
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+}
+
+fn default_requests_per_minute() -> u32 {
+    60
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyCheck {
+    Allowed,
+    /// No key, or one that isn't configured.
+    Unauthorized,
+    RateLimited {
+        retry_after: Duration,
+    },
+}
+
+// Refills continuously, up to a minute's worth of requests.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+#[derive(Debug)]
+pub struct ApiKeys {
+    keys: Vec<ApiKey>,
+    // Keyed by the key's name.
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl ApiKeys {
+    pub fn new(keys: Vec<ApiKey>) -> ApiKeys {
+        ApiKeys {
+            keys,
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// Takes one request from the key's allowance, if it's a known key and
+    /// has any left.
+    pub fn check(&self, key: Option<&str>, now: Instant) -> ApiKeyCheck {
+        let api_key = match key.and_then(|key| self.keys.iter().find(|api_key| api_key.key == key))
+        {
+            Some(api_key) => api_key,
+            None => return ApiKeyCheck::Unauthorized,
+        };
+
+        let capacity = f64::from(api_key.requests_per_minute);
+        let per_second = capacity / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(api_key.name.clone()).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * per_second).min(capacity);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return ApiKeyCheck::Allowed;
+        }
+        tracing::info!("API key '{}' is over its rate limit", api_key.name);
+        let retry_after = if per_second > 0.0 {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / per_second)
+        } else {
+            Duration::from_secs(60)
+        };
+        ApiKeyCheck::RateLimited { retry_after }
+    }
+}
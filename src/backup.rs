@@ -0,0 +1,180 @@
+//! Encrypted archives of the bot's state, for moving it between hosts.
+
+use crate::{
+    guilds::GUILD_SETTINGS_FILEPATH,
+    history::{CheckRecord, HistoryStore, HourlyRollup},
+    maintenance::MAINTENANCE_FILEPATH,
+    oncall::ONCALL_FILEPATH,
+    preferences::PREFERENCES_FILEPATH,
+    settings::RUNTIME_SETTINGS_FILEPATH,
+    Error,
+};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+pub const CONFIG_DIRECTORY: &str = "config";
+
+/// The files the bot writes as it runs, backed up along with the config.
+pub const DATA_FILEPATHS: &[&str] = &[
+    GUILD_SETTINGS_FILEPATH,
+    PREFERENCES_FILEPATH,
+    RUNTIME_SETTINGS_FILEPATH,
+    MAINTENANCE_FILEPATH,
+    ONCALL_FILEPATH,
+];
+
+// Archives start with this, then the key derivation salt, then the nonce.
+const MAGIC: &[u8] = b"SAISBAK1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    pub created_at: DateTime<Utc>,
+    /// Every file in the config directory, by file name.
+    pub config_files: Vec<(String, String)>,
+    /// Each of [`DATA_FILEPATHS`] that exists, by path. Archives from before
+    /// these were backed up have none.
+    #[serde(default)]
+    pub data_files: Vec<(String, String)>,
+    pub history: Vec<CheckRecord>,
+    /// What's left of checks compacted out of the history.
+    #[serde(default)]
+    pub rollups: Vec<HourlyRollup>,
+}
+
+impl Backup {
+    /// Reads the config directory, and [`DATA_FILEPATHS`] from under
+    /// `data_root`.
+    pub async fn collect(
+        config_directory: impl AsRef<Path>,
+        data_root: impl AsRef<Path>,
+        history: Option<&dyn HistoryStore>,
+    ) -> Result<Backup, Error> {
+        let mut config_files = vec![];
+        for entry in fs::read_dir(config_directory)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                config_files.push((name, fs::read_to_string(entry.path())?));
+            }
+        }
+        config_files.sort();
+
+        let mut data_files = vec![];
+        for path in DATA_FILEPATHS {
+            match fs::read_to_string(data_root.as_ref().join(path)) {
+                Ok(contents) => data_files.push((path.to_string(), contents)),
+                Err(why) if why.kind() == std::io::ErrorKind::NotFound => {}
+                Err(why) => return Err(why.into()),
+            }
+        }
+
+        let (history, rollups) = match history {
+            Some(history) => (
+                history.since(DateTime::UNIX_EPOCH).await?,
+                history.rollups_since(DateTime::UNIX_EPOCH).await?,
+            ),
+            None => (vec![], vec![]),
+        };
+        Ok(Backup {
+            created_at: Utc::now(),
+            config_files,
+            data_files,
+            history,
+            rollups,
+        })
+    }
+
+    /// Writes the config files back, replacing the ones that are there.
+    pub fn restore_config(&self, config_directory: impl AsRef<Path>) -> Result<(), Error> {
+        fs::create_dir_all(&config_directory)?;
+        for (name, contents) in &self.config_files {
+            // Names come from the archive, so don't let them point elsewhere.
+            let name = Path::new(name)
+                .file_name()
+                .ok_or_else(|| format!("Bad config file name {:?}", name))?;
+            fs::write(config_directory.as_ref().join(name), contents)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the data files back under `data_root`, replacing the ones that
+    /// are there. Only [`DATA_FILEPATHS`] are written.
+    pub fn restore_data(&self, data_root: impl AsRef<Path>) -> Result<(), Error> {
+        for (path, contents) in &self.data_files {
+            // Paths come from the archive, so don't let them point elsewhere.
+            if !DATA_FILEPATHS.contains(&path.as_str()) {
+                return Err(format!("Bad data file path {:?}", path).into());
+            }
+            let path = data_root.as_ref().join(path);
+            if let Some(directory) = path.parent() {
+                fs::create_dir_all(directory)?;
+            }
+            fs::write(path, contents)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the backed up history and rollups to `history`, unless it
+    /// already has either, so restoring twice doesn't duplicate them.
+    pub async fn restore_history(&self, history: &dyn HistoryStore) -> Result<bool, Error> {
+        if !history.since(DateTime::UNIX_EPOCH).await?.is_empty()
+            || !history
+                .rollups_since(DateTime::UNIX_EPOCH)
+                .await?
+                .is_empty()
+        {
+            return Ok(false);
+        }
+        history.record(&self.history).await?;
+        history.record_rollups(&self.rollups).await?;
+        Ok(true)
+    }
+
+    pub fn encrypt(&self, passphrase: &str) -> Result<Vec<u8>, Error> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let cipher = cipher(passphrase, &salt)?;
+        let plaintext = serde_json::to_vec(self)?;
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| "Could not encrypt the backup")?;
+
+        let mut archive = MAGIC.to_vec();
+        archive.extend_from_slice(&salt);
+        archive.extend_from_slice(&nonce);
+        archive.extend_from_slice(&ciphertext);
+        Ok(archive)
+    }
+
+    pub fn decrypt(archive: &[u8], passphrase: &str) -> Result<Backup, Error> {
+        let rest = archive
+            .strip_prefix(MAGIC)
+            .ok_or("Not a backup made by this bot")?;
+        if rest.len() < SALT_LEN + NONCE_LEN {
+            return Err("The backup is cut short".into());
+        }
+        let (salt, rest) = rest.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let plaintext = cipher(passphrase, salt)?
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Wrong passphrase, or the backup is damaged")?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305, Error> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|why| format!("Could not derive the backup key: {}", why))?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
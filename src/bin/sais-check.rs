@@ -0,0 +1,182 @@
+//! Checks UP SAIS from the terminal, with the same config and environment
+//! variables as the bot.
+//!
+//! Exits with 0 if SAIS is working, 1 if it's down, and 2 if it couldn't be
+//! checked. With `--watch`, checks again every interval until stopped. With
+//! `--latest`, prints the latest status from the shared state instead.
+
+use chrono::Utc;
+use is_sais_down_bot::{
+    history::CheckRecord,
+    leader, monitor,
+    routes::{RouteChecker, RouteResult},
+    sais::SaisClient,
+    state::{self, SharedState, StateBackend},
+    Error,
+};
+use serde::Deserialize;
+use std::{env, fs, process, sync::Arc, time::Duration};
+
+const USAGE: &str = "\
+Usage: sais-check [--json] [--watch [SECONDS] | --latest]
+
+  --json              Print each route's result as a line of JSON
+  --watch [SECONDS]   Check again every SECONDS seconds (60 by default)
+  --latest            Print the latest status from the shared state";
+
+const BOT_CONFIG_FILEPATH: &str = "config/bot.ron";
+
+#[derive(Debug, Default)]
+struct Args {
+    json: bool,
+    watch: Option<Duration>,
+    latest: bool,
+}
+
+// The part of the bot's config shared with it.
+#[derive(Debug, Default, Deserialize)]
+struct BotConfig {
+    #[serde(default)]
+    state_backend: StateBackend,
+}
+
+// The memory backend, when config/bot.ron is missing.
+fn shared_state() -> Result<Arc<dyn SharedState>, Error> {
+    let bot_config = match fs::read_to_string(BOT_CONFIG_FILEPATH) {
+        Ok(contents) => ron::de::from_str::<BotConfig>(&contents)?,
+        Err(_) => BotConfig::default(),
+    };
+    bot_config.state_backend.open()
+}
+
+fn parse_args() -> Result<Args, Error> {
+    let mut parsed = Args::default();
+    let mut args = env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            "--watch" => {
+                let secs = match args.peek().and_then(|secs| secs.parse().ok()) {
+                    Some(secs) => {
+                        args.next();
+                        secs
+                    }
+                    None => 60,
+                };
+                parsed.watch = Some(Duration::from_secs(secs));
+            }
+            "--latest" => parsed.latest = true,
+            _ => return Err(format!("Unknown argument {:?}", arg).into()),
+        }
+    }
+    Ok(parsed)
+}
+
+fn print_results(results: &[RouteResult], json: bool) -> Result<(), Error> {
+    let checked_at = Utc::now();
+    for result in results {
+        if json {
+            let record = CheckRecord::new(checked_at, result);
+            println!("{}", serde_json::to_string(&record)?);
+        } else {
+            println!(
+                "{} {}: {} ({} ms)",
+                checked_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                result.route,
+                result.status,
+                result.latency.as_millis()
+            );
+            for page in &result.failing_pages {
+                println!("  failing page: {}", page);
+            }
+            if let Some(error_page) = &result.error_page {
+                println!("  error page: {}", error_page);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Returns the exit code, as for a check.
+async fn print_latest(state: &dyn SharedState, json: bool) -> i32 {
+    match state.latest_status().await {
+        Ok(Some(record)) => {
+            if json {
+                println!("{}", serde_json::to_string(&record).unwrap_or_default());
+            } else {
+                println!(
+                    "{} {}: {} ({} ms)",
+                    record.checked_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                    record.route,
+                    record.status,
+                    record.latency_ms
+                );
+            }
+            match record.status.as_str() {
+                "down" | "pages_failing" => 1,
+                _ => 0,
+            }
+        }
+        Ok(None) => {
+            eprintln!("Nothing has checked SAIS yet");
+            2
+        }
+        Err(why) => {
+            eprintln!("Could not get the latest status: {}", why);
+            2
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(why) => {
+            eprintln!("{}\n\n{}", why, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let state = match shared_state() {
+        Ok(state) => state,
+        Err(why) => {
+            eprintln!("Could not set up the state backend: {}", why);
+            process::exit(2);
+        }
+    };
+    if args.latest {
+        process::exit(print_latest(state.as_ref(), args.json).await);
+    }
+
+    let route_checker = RouteChecker::new(SaisClient::new());
+    let instance_id = leader::default_instance_id();
+    loop {
+        let outcome = state::with_check_lock(
+            state.as_ref(),
+            &instance_id,
+            state::CHECK_LOCK_TTL,
+            route_checker.check_all(),
+        )
+        .await
+        .and_then(|results| print_results(&results, args.json).map(|()| results));
+        if let Ok(results) = &outcome {
+            let latest = CheckRecord::new(Utc::now(), &results[0]);
+            if let Err(why) = state.set_latest_status(&latest).await {
+                eprintln!("Could not share the latest status: {}", why);
+            }
+        }
+        let exit_code = match &outcome {
+            Ok(results) if monitor::is_outage(&results[0].status) => 1,
+            Ok(_) => 0,
+            Err(why) => {
+                eprintln!("Could not check SAIS: {}", why);
+                2
+            }
+        };
+        match args.watch {
+            Some(interval) => tokio::time::sleep(interval).await,
+            None => process::exit(exit_code),
+        }
+    }
+}
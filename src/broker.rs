@@ -0,0 +1,271 @@
+//! Publishes status changes to a NATS subject or an MQTT topic, so other
+//! campus systems, like digital signage or web portals, can react to SAIS
+//! outages as they happen. Each change is published over a fresh
+//! connection, since they are rare.
+
+use crate::{status::ServiceStatus, Error};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{env, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::watch,
+};
+
+// Covers connecting and publishing.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum BrokerConfig {
+    /// The token, if the server needs one, is read from `NATS_TOKEN`.
+    Nats {
+        /// Like "127.0.0.1:4222".
+        address: String,
+        #[serde(default = "default_subject")]
+        subject: String,
+    },
+    /// MQTT 3.1.1, publishing at most once. The password, if the broker
+    /// needs one, is read from `MQTT_PASSWORD`.
+    Mqtt {
+        /// Like "127.0.0.1:1883".
+        address: String,
+        #[serde(default = "default_topic")]
+        topic: String,
+        #[serde(default = "default_client_id")]
+        client_id: String,
+        #[serde(default)]
+        username: Option<String>,
+        /// Keeps the latest status on the broker for new subscribers.
+        #[serde(default = "default_retain")]
+        retain: bool,
+    },
+}
+
+fn default_subject() -> String {
+    "sais.status".to_string()
+}
+
+fn default_topic() -> String {
+    "sais/status".to_string()
+}
+
+fn default_client_id() -> String {
+    "is-sais-down-bot".to_string()
+}
+
+fn default_retain() -> bool {
+    true
+}
+
+/// What's published, as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusChange {
+    /// See [`ServiceStatus::kind`].
+    pub kind: String,
+    /// See [`ServiceStatus::reason`].
+    pub reason: Option<String>,
+    /// Like "down (503 Service Unavailable)".
+    pub description: String,
+    /// The kind before, unless this is the first status since the bot
+    /// started.
+    pub previous_kind: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl StatusChange {
+    pub fn new(
+        status: ServiceStatus,
+        previous: Option<ServiceStatus>,
+        changed_at: DateTime<Utc>,
+    ) -> StatusChange {
+        StatusChange {
+            kind: status.kind().to_string(),
+            reason: status.reason(),
+            description: status.to_string(),
+            previous_kind: previous.map(|previous| previous.kind().to_string()),
+            changed_at,
+        }
+    }
+}
+
+/// Publishes every change of the confirmed status's kind, until the
+/// monitor stops.
+pub async fn publish_changes(
+    config: BrokerConfig,
+    mut statuses: watch::Receiver<Option<ServiceStatus>>,
+) {
+    let mut previous: Option<ServiceStatus> = None;
+    while statuses.changed().await.is_ok() {
+        let status = match *statuses.borrow_and_update() {
+            Some(status) => status,
+            None => continue,
+        };
+        if previous.map(|previous| previous.kind()) == Some(status.kind()) {
+            continue;
+        }
+        let change = StatusChange::new(status, previous, Utc::now());
+        previous = Some(status);
+        match publish(&config, &change).await {
+            Ok(()) => tracing::info!("Published that SAIS is {}", status),
+            Err(why) => tracing::warn!("Could not publish the status change: {:?}", why),
+        }
+    }
+}
+
+pub async fn publish(config: &BrokerConfig, change: &StatusChange) -> Result<(), Error> {
+    let payload = serde_json::to_vec(change)?;
+    let published = async {
+        match config {
+            BrokerConfig::Nats { address, subject } => {
+                let token = env::var("NATS_TOKEN").ok();
+                publish_nats(address, subject, token.as_deref(), &payload).await
+            }
+            BrokerConfig::Mqtt {
+                address,
+                topic,
+                client_id,
+                username,
+                retain,
+            } => {
+                let password = env::var("MQTT_PASSWORD").ok();
+                let connect =
+                    mqtt_connect_packet(client_id, username.as_deref(), password.as_deref());
+                let publish = mqtt_publish_packet(topic, &payload, *retain);
+                publish_mqtt(address, &connect, &publish).await
+            }
+        }
+    };
+    tokio::time::timeout(PUBLISH_TIMEOUT, published)
+        .await
+        .map_err(|_| format!("Publishing took over {}s", PUBLISH_TIMEOUT.as_secs()))?
+}
+
+// Waits for the PONG to a PING after publishing, so errors aren't missed.
+async fn publish_nats(
+    address: &str,
+    subject: &str,
+    token: Option<&str>,
+    payload: &[u8],
+) -> Result<(), Error> {
+    let mut stream = BufReader::new(TcpStream::connect(address).await?);
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+    if !line.starts_with("INFO") {
+        return Err(format!("Expected INFO from NATS, got {:?}", line.trim_end()).into());
+    }
+
+    let mut connect = serde_json::json!({
+        "verbose": false,
+        "pedantic": false,
+        "name": "is-sais-down-bot",
+    });
+    if let Some(token) = token {
+        connect["auth_token"] = token.into();
+    }
+    let mut message = format!(
+        "CONNECT {}\r\nPUB {} {}\r\n",
+        connect,
+        subject,
+        payload.len()
+    )
+    .into_bytes();
+    message.extend(payload);
+    message.extend(b"\r\nPING\r\n");
+    stream.get_mut().write_all(&message).await?;
+
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await? == 0 {
+            return Err("NATS closed the connection".into());
+        }
+        match line.trim_end() {
+            "PONG" => return Ok(()),
+            // The server's own keepalive.
+            "PING" => stream.get_mut().write_all(b"PONG\r\n").await?,
+            reply if reply.starts_with("-ERR") => {
+                return Err(format!("NATS replied with an error: {}", reply).into())
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn publish_mqtt(address: &str, connect: &[u8], publish: &[u8]) -> Result<(), Error> {
+    let mut stream = TcpStream::connect(address).await?;
+    stream.write_all(connect).await?;
+    let mut connack = [0; 4];
+    stream.read_exact(&mut connack).await?;
+    if connack[0] != 0x20 {
+        return Err(format!(
+            "Expected CONNACK from MQTT, got packet type {:#x}",
+            connack[0]
+        )
+        .into());
+    }
+    if connack[3] != 0 {
+        return Err(format!(
+            "The MQTT broker refused the connection (code {})",
+            connack[3]
+        )
+        .into());
+    }
+    stream.write_all(publish).await?;
+    // DISCONNECT.
+    stream.write_all(&[0xe0, 0x00]).await?;
+    Ok(())
+}
+
+pub fn mqtt_connect_packet(
+    client_id: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+) -> Vec<u8> {
+    let mut flags = 0x02; // Clean session.
+    let mut payload = mqtt_string(client_id);
+    if let Some(username) = username {
+        flags |= 0x80;
+        payload.extend(mqtt_string(username));
+        if let Some(password) = password {
+            flags |= 0x40;
+            payload.extend(mqtt_string(password));
+        }
+    }
+    let mut body = mqtt_string("MQTT");
+    // Protocol level 4 is MQTT 3.1.1, with a 60 second keep alive.
+    body.extend([0x04, flags, 0x00, 0x3c]);
+    body.extend(payload);
+    mqtt_packet(0x10, body)
+}
+
+/// A PUBLISH at QoS 0.
+pub fn mqtt_publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = mqtt_string(topic);
+    body.extend(payload);
+    mqtt_packet(0x30 | retain as u8, body)
+}
+
+fn mqtt_packet(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![header];
+    // The remaining length, seven bits at a time.
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+fn mqtt_string(text: &str) -> Vec<u8> {
+    let mut encoded = (text.len() as u16).to_be_bytes().to_vec();
+    encoded.extend(text.as_bytes());
+    encoded
+}
@@ -0,0 +1,208 @@
+//! The academic calendar, so SAIS is watched closely while everyone needs
+//! it, like during enrollment or when grades come out, and less so the rest
+//! of the year. Holidays and breaks are noted in reports as quiet days, when
+//! maintenance is likely planned.
+
+use crate::monitor::MonitorConfig;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use std::fmt;
+
+/// How closely SAIS is watched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Profile {
+    Relaxed,
+    Aggressive,
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Profile::Relaxed => "relaxed",
+            Profile::Aggressive => "aggressive",
+        })
+    }
+}
+
+/// A stretch of the calendar, like an enrollment period.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Period {
+    pub name: String,
+    pub start: NaiveDate,
+    /// The last day of the period, included.
+    pub end: NaiveDate,
+    #[serde(default = "default_period_profile")]
+    pub profile: Profile,
+}
+
+fn default_period_profile() -> Profile {
+    Profile::Aggressive
+}
+
+impl Period {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+/// What a profile changes from the monitor settings in bot.ron. Unset ones
+/// are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ProfileConfig {
+    /// Can't go over the interval in bot.ron, which the monitor's watchdogs
+    /// are set up for.
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    #[serde(default)]
+    pub confirm_after: Option<u32>,
+    #[serde(default)]
+    pub flap_window: Option<usize>,
+    #[serde(default)]
+    pub flap_threshold: Option<usize>,
+}
+
+fn default_aggressive() -> ProfileConfig {
+    ProfileConfig {
+        interval_secs: Some(60),
+        confirm_after: Some(2),
+        flap_window: None,
+        flap_threshold: None,
+    }
+}
+
+/// A day off, or a break of several days, when few use SAIS.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Holiday {
+    pub name: String,
+    pub start: NaiveDate,
+    /// The last day of a break, included. One day when not set.
+    #[serde(default)]
+    pub end: Option<NaiveDate>,
+}
+
+impl Holiday {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end.unwrap_or(self.start)
+    }
+}
+
+/// The Philippine holidays that fall on the same date every year, by month
+/// and day. The ones that move, like Holy Week, are left to the config.
+const PH_HOLIDAYS: &[(u32, u32, &str)] = &[
+    (1, 1, "New Year's Day"),
+    (4, 9, "Araw ng Kagitingan"),
+    (5, 1, "Labor Day"),
+    (6, 12, "Independence Day"),
+    (8, 21, "Ninoy Aquino Day"),
+    (11, 1, "All Saints' Day"),
+    (11, 2, "All Souls' Day"),
+    (11, 30, "Bonifacio Day"),
+    (12, 8, "the Feast of the Immaculate Conception"),
+    (12, 24, "Christmas Eve"),
+    (12, 25, "Christmas Day"),
+    (12, 30, "Rizal Day"),
+    (12, 31, "New Year's Eve"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CalendarConfig {
+    /// Days in none of these are relaxed. The first period a day is in
+    /// decides its profile.
+    #[serde(default)]
+    pub periods: Vec<Period>,
+    #[serde(default)]
+    pub relaxed: ProfileConfig,
+    #[serde(default = "default_aggressive")]
+    pub aggressive: ProfileConfig,
+    /// Holidays and university breaks, besides the fixed Philippine ones.
+    #[serde(default)]
+    pub holidays: Vec<Holiday>,
+    /// Whether the Philippine holidays with fixed dates count.
+    #[serde(default = "default_ph_holidays")]
+    pub ph_holidays: bool,
+}
+
+fn default_ph_holidays() -> bool {
+    true
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            periods: vec![],
+            relaxed: ProfileConfig::default(),
+            aggressive: default_aggressive(),
+            holidays: vec![],
+            ph_holidays: default_ph_holidays(),
+        }
+    }
+}
+
+impl CalendarConfig {
+    /// The period `date` (PHT) is in, if any.
+    pub fn period_on(&self, date: NaiveDate) -> Option<&Period> {
+        self.periods.iter().find(|period| period.contains(date))
+    }
+
+    pub fn profile_on(&self, date: NaiveDate) -> Profile {
+        self.period_on(date)
+            .map_or(Profile::Relaxed, |period| period.profile)
+    }
+
+    /// `base` from bot.ron, changed by the profile for `date`.
+    pub fn monitor_config(&self, base: &MonitorConfig, date: NaiveDate) -> MonitorConfig {
+        let profile = match self.profile_on(date) {
+            Profile::Relaxed => &self.relaxed,
+            Profile::Aggressive => &self.aggressive,
+        };
+        MonitorConfig {
+            interval_secs: profile
+                .interval_secs
+                .map_or(base.interval_secs, |interval_secs| {
+                    interval_secs.clamp(1, base.interval_secs)
+                }),
+            confirm_after: profile.confirm_after.unwrap_or(base.confirm_after).max(1),
+            flap_window: profile.flap_window.unwrap_or(base.flap_window),
+            flap_threshold: profile.flap_threshold.unwrap_or(base.flap_threshold),
+        }
+    }
+
+    /// The name of the holiday or break `date` (PHT) is in, if any.
+    pub fn holiday_on(&self, date: NaiveDate) -> Option<&str> {
+        if let Some(holiday) = self.holidays.iter().find(|holiday| holiday.contains(date)) {
+            return Some(&holiday.name);
+        }
+        PH_HOLIDAYS
+            .iter()
+            .filter(|_| self.ph_holidays)
+            .find(|(month, day, _)| date.month() == *month && date.day() == *day)
+            .map(|(_, _, name)| *name)
+    }
+
+    /// Which profile `date` gets and why, for the owner.
+    pub fn describe(&self, date: NaiveDate) -> String {
+        match self.period_on(date) {
+            Some(period) => format!(
+                "Monitoring is {} for {} until {}.",
+                period.profile,
+                period.name,
+                period.end.format("%b %-d")
+            ),
+            None => {
+                let next = self
+                    .periods
+                    .iter()
+                    .filter(|period| period.start > date)
+                    .min_by_key(|period| period.start);
+                match next {
+                    Some(next) => format!(
+                        "Monitoring is relaxed until {} starts on {}.",
+                        next.name,
+                        next.start.format("%b %-d")
+                    ),
+                    None => "Monitoring is relaxed.".to_string(),
+                }
+            }
+        }
+    }
+}
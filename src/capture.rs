@@ -0,0 +1,209 @@
+//! Record-and-replay of SAIS responses, so that a misclassified check can be
+//! reproduced offline from what SAIS actually served.
+//!
+//! Set `SAIS_RECORD_DIR` to save every exchange the checker makes, or
+//! `SAIS_REPLAY_DIR` to answer checks from previously saved exchanges
+//! instead of contacting SAIS.
+
+use crate::{
+    redact::{self, REDACTED},
+    transport::{HttpRequest, HttpResponse, HttpTransport},
+    Error,
+};
+use async_trait::async_trait;
+use chrono::prelude::*;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, SET_COOKIE},
+    StatusCode, Version,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// One request and what came back for it, as saved to disk.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    pub method: String,
+    pub url: String,
+    /// The error message when the request failed outright.
+    pub response: Result<RecordedResponse, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    #[serde(default)]
+    pub redirects: Vec<String>,
+}
+
+/// Passes requests on to another transport, saving each exchange as a RON
+/// file in a directory.
+pub struct RecordingTransport<T> {
+    inner: T,
+    dir: PathBuf,
+    secrets: Vec<String>,
+    sequence: AtomicUsize,
+}
+
+impl<T: HttpTransport> RecordingTransport<T> {
+    /// Every occurrence of `secrets` is redacted from the saved files, as are
+    /// cookie values.
+    pub fn new(inner: T, dir: impl Into<PathBuf>, secrets: Vec<String>) -> Result<Self, Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(RecordingTransport {
+            inner,
+            dir,
+            secrets: secrets.into_iter().filter(|s| !s.is_empty()).collect(),
+            sequence: AtomicUsize::new(0),
+        })
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let text = self.secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        });
+        redact::redact(&text)
+    }
+
+    fn record(
+        &self,
+        request: &HttpRequest,
+        response: &Result<HttpResponse, Error>,
+    ) -> Result<(), Error> {
+        let exchange = RecordedExchange {
+            method: request.method.to_string(),
+            url: self.redact(&request.url),
+            response: match response {
+                Ok(response) => Ok(RecordedResponse {
+                    status: response.status.as_u16(),
+                    headers: response
+                        .headers
+                        .iter()
+                        .map(|(name, value)| {
+                            let value = String::from_utf8_lossy(value.as_bytes());
+                            let value = if name == SET_COOKIE {
+                                redact_cookie_value(&value)
+                            } else {
+                                self.redact(&value)
+                            };
+                            (name.to_string(), value)
+                        })
+                        .collect(),
+                    body: self.redact(&response.body),
+                    redirects: response
+                        .redirects
+                        .iter()
+                        .map(|url| self.redact(url))
+                        .collect(),
+                }),
+                Err(why) => Err(self.redact(&why.to_string())),
+            },
+        };
+
+        let file_name = format!(
+            "{}-{:04}-{}.ron",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            self.sequence.fetch_add(1, Ordering::Relaxed),
+            exchange.method
+        );
+        let contents = ron::ser::to_string_pretty(&exchange, Default::default())?;
+        fs::write(self.dir.join(file_name), contents)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: HttpTransport> HttpTransport for RecordingTransport<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let recorded_request = request.clone();
+        let response = self.inner.send(request).await;
+        // A broken recording shouldn't break the check itself.
+        if let Err(why) = self.record(&recorded_request, &response) {
+            tracing::warn!("Could not record SAIS response: {:?}", why);
+        }
+        response
+    }
+}
+
+/// Answers requests with exchanges saved by [`RecordingTransport`], in file
+/// name order, ignoring what was actually requested.
+pub struct ReplayTransport {
+    exchanges: Mutex<VecDeque<RecordedExchange>>,
+}
+
+impl ReplayTransport {
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "ron"));
+        paths.sort();
+
+        let mut exchanges = VecDeque::new();
+        for path in paths {
+            exchanges.push_back(ron::de::from_str(&fs::read_to_string(path)?)?);
+        }
+        Ok(ReplayTransport {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReplayTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let exchange = self
+            .exchanges
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or("No recorded responses left to replay")?;
+        tracing::info!(
+            "Replaying {} {} for {} {}",
+            exchange.method,
+            exchange.url,
+            request.method,
+            request.url
+        );
+
+        let response = exchange.response?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in response.headers {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(&value)?,
+            );
+        }
+        Ok(HttpResponse {
+            status: StatusCode::from_u16(response.status)?,
+            headers,
+            body: response.body,
+            redirects: response.redirects,
+            version: Version::HTTP_11,
+            connection: None,
+        })
+    }
+}
+
+// Keeps the cookie name and attributes, which are all that matter when
+// replaying, but not the session itself.
+pub(crate) fn redact_cookie_value(cookie: &str) -> String {
+    let (pair, attributes) = match cookie.find(';') {
+        Some(i) => cookie.split_at(i),
+        None => (cookie, ""),
+    };
+    match pair.find('=') {
+        Some(i) => format!("{}={}{}", &pair[..i], REDACTED, attributes),
+        None => cookie.to_string(),
+    }
+}
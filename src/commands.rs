@@ -0,0 +1,1326 @@
+use crate::{
+    current_time_utc_plus_8, outage_duration_string, sample_config, setup::pht, Context, Error,
+};
+use chrono::{FixedOffset, Utc};
+use is_sais_down_bot::{
+    backup::{self, Backup},
+    config::{LoginDetails, SaisConfig},
+    diagnostics::ConnectionLog,
+    guilds::EmojiStyle,
+    har::HarRecorder,
+    heatmap::OutageHeatmap,
+    history::{self, CheckRecord, HourlyRollup},
+    hostinfo,
+    oncall::OnCallOverride,
+    redact, releases,
+    report::{self, Reliability, ServiceSummary},
+    routes::routes_disagree,
+    runtime_stats, settings, sla, state,
+    status::{DownReason, ServiceStatus},
+    tcp_probe::TcpProbe,
+    timing::TimingProbe,
+    trace,
+    transaction::{self, TransactionOutcome},
+    transport::ReqwestTransport,
+    SaisClient,
+};
+use poise::serenity_prelude::{self as serenity, AttachmentType, MessageBuilder};
+use std::{
+    env, fs,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const BACKUP_DIRECTORY: &str = "backups";
+
+// `&forecast` has nothing to go by with less history than this.
+const MIN_FORECAST_DAYS: u32 = 7;
+
+// How long each user waits between uses of these commands, in seconds. Kept
+// in the shared state, so every process agrees.
+const USER_COOLDOWNS: &[(&str, u64)] = &[
+    ("sais", 5),
+    ("check", 30),
+    ("diagnose", 30),
+    ("hostinfo", 30),
+    ("probe", 5),
+    ("export", 30),
+    ("heatmap", 30),
+    ("reliability", 30),
+    ("compare", 30),
+];
+
+pub fn user_cooldown(command: &str) -> Option<Duration> {
+    USER_COOLDOWNS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map(|(_, secs)| Duration::from_secs(*secs))
+}
+
+/// Checks whether UP SAIS is down.
+#[poise::command(prefix_command, slash_command)]
+pub async fn sais(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Let me check... :thinking:").await?;
+
+    let data = ctx.data();
+    let checked_at = Utc::now();
+    let route_results = state::with_check_lock(
+        data.state.as_ref(),
+        &data.instance_id,
+        state::CHECK_LOCK_TTL,
+        data.route_checker.check_all(),
+    )
+    .await?;
+    let latest = CheckRecord::new(checked_at, &route_results[0]);
+    if let Err(why) = data.state.set_latest_status(&latest).await {
+        tracing::warn!("Could not share the latest status: {:?}", why);
+    }
+    if let Some(history) = &data.history {
+        history.record_results(checked_at, &route_results).await?;
+    }
+    let status = route_results[0].status;
+    data.outage_log.user_checked();
+    let emoji_style = ctx
+        .guild_id()
+        .and_then(|guild_id| data.guild_settings.get(guild_id.0))
+        .map(|settings| settings.emoji_style)
+        .unwrap_or_default();
+
+    let mut reply_message = MessageBuilder::new();
+    let query_time_string = current_time_utc_plus_8().format("%H:%M:%S").to_string();
+    reply_message
+        .push("As of ")
+        .push(query_time_string)
+        .push(", ");
+
+    let (message, emoji_name) = match status {
+        ServiceStatus::Down(reason) => (
+            format!(
+                "{} ",
+                data.settings
+                    .down_phrases(&data.down_phrases)
+                    .describe(reason)
+            ),
+            match reason {
+                DownReason::HttpStatus(_) => "status_code_fail",
+                DownReason::LoginFailed => "login_fail",
+                _ => "response_fail",
+            },
+        ),
+        ServiceStatus::Up => ("UP SAIS is up! ".to_string(), "login_ok"),
+        ServiceStatus::Degraded(latency) => (
+            format!(
+                "UP SAIS is up, but slow. Logging in took {} seconds. ",
+                latency.as_secs()
+            ),
+            "degraded",
+        ),
+        ServiceStatus::PagesFailing => (
+            format!(
+                "UP SAIS is up, but these aren't working: {}. ",
+                route_results[0].failing_pages.join(", ")
+            ),
+            "status_code_fail",
+        ),
+        ServiceStatus::Maintenance => {
+            ("UP SAIS is under maintenance. ".to_string(), "maintenance")
+        }
+        ServiceStatus::CredentialProblem => (
+            "UP SAIS is up, but it rejected my login details, so I can't tell if logging in works. "
+                .to_string(),
+            "login_fail",
+        ),
+    };
+    reply_message.push(message);
+    match (emoji_style, data.emoji_cache.get(emoji_name)) {
+        (EmojiStyle::Custom, Some(emoji)) => reply_message.emoji(emoji),
+        // Older configs have no emoji for some statuses.
+        _ => reply_message.push(unicode_emoji(emoji_name)),
+    };
+    if let Some(window) = data.maintenance.active(checked_at) {
+        reply_message.push(format!(
+            "\nThere's planned maintenance until {}: {}",
+            window
+                .ends_at
+                .with_timezone(&FixedOffset::east_opt(3600 * 8).unwrap())
+                .format("%b %-d, %H:%M PHT"),
+            window.note
+        ));
+    }
+    if let Some(since) = data.route_checker.outage_since() {
+        reply_message.push(format!("\nIt's been {}.", outage_duration_string(since)));
+    }
+    if routes_disagree(&route_results) {
+        reply_message.push("\nIt depends on where you check from:");
+        for route_result in &route_results {
+            reply_message.push(format!(
+                "\n- from {}, it's {}",
+                route_result.route, route_result.status
+            ));
+        }
+    }
+    if let Some(error_page) = &route_results[0].error_page {
+        reply_message.push(format!("\nSAIS is showing a {} page.", error_page));
+    }
+    if let Some(redirect) = &route_results[0].suspicious_redirect {
+        reply_message.push(format!(
+            "\nAlong the way, SAIS sent me to {}, which doesn't look right.",
+            redirect
+        ));
+    }
+    let mut content = reply_message.build();
+    if let Some(hooks) = &data.hooks {
+        content = hooks.reply(status, content);
+    }
+    reply(ctx, content).await?;
+
+    Ok(())
+}
+
+/// Checks whether a part of SAIS works after logging in, e.g. enlistment.
+#[poise::command(prefix_command, slash_command)]
+pub async fn check(
+    ctx: Context<'_>,
+    #[description = "The part of SAIS to check, or all"] module: Option<String>,
+) -> Result<(), Error> {
+    let mut sais_config = SaisConfig::get().map_err(|why| why.to_string())?;
+    let names = sais_config
+        .deep_checks
+        .iter()
+        .map(|deep_check| deep_check.name.clone())
+        .collect::<Vec<_>>();
+    let module = match module {
+        Some(module) if module.eq_ignore_ascii_case("all") && !names.is_empty() => None,
+        Some(module) if names.iter().any(|name| name.eq_ignore_ascii_case(&module)) => Some(module),
+        _ if names.is_empty() => {
+            reply(
+                ctx,
+                "There are no parts of SAIS set up to check.".to_string(),
+            )
+            .await?;
+            return Ok(());
+        }
+        _ => {
+            reply(
+                ctx,
+                format!("I can check: {}, or all of them", names.join(", ")),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    // Only the asked for page is loaded after logging in.
+    if let Some(module) = &module {
+        sais_config
+            .deep_checks
+            .retain(|deep_check| deep_check.name.eq_ignore_ascii_case(module));
+    }
+    let checking = sais_config
+        .deep_checks
+        .iter()
+        .map(|deep_check| deep_check.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    ctx.say(format!("Checking {}... :thinking:", checking))
+        .await?;
+
+    let data = ctx.data();
+    let mut sais_client = SaisClient::with_config(sais_config, LoginDetails::get());
+    let status = state::with_check_lock(
+        data.state.as_ref(),
+        &data.instance_id,
+        state::CHECK_LOCK_TTL,
+        sais_client.check(),
+    )
+    .await?;
+    let lines = match status {
+        ServiceStatus::Up | ServiceStatus::Degraded(_) | ServiceStatus::PagesFailing => sais_client
+            .page_results()
+            .iter()
+            .map(|page| match &page.problem {
+                None => format!(
+                    "{} is working ({:.1}s).",
+                    page.name,
+                    page.latency.as_secs_f64()
+                ),
+                Some(problem) => format!("{} isn't working: {}.", page.name, problem),
+            })
+            .collect::<Vec<_>>(),
+        status => vec![format!(
+            "UP SAIS itself is {}, so I couldn't get to {}.",
+            status, checking
+        )],
+    };
+    tracing::info!("Checked {}: {}", checking, lines.join(" "));
+    reply(
+        ctx,
+        format!(
+            "As of {}:\n{}",
+            current_time_utc_plus_8().format("%H:%M:%S"),
+            lines.join("\n")
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Shows the HTTP version and connection each request of a check used.
+#[poise::command(prefix_command, slash_command)]
+pub async fn diagnose(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Let me check... :thinking:").await?;
+
+    let sais_config = SaisConfig::get().map_err(|why| why.to_string())?;
+    let transport = ReqwestTransport::new(
+        &sais_config.timeouts,
+        sais_config.proxy.as_deref(),
+        sais_config.ip_family,
+        sais_config.max_redirects,
+    )?;
+    let tcp_probe = TcpProbe::new(&sais_config);
+    let timing_probe = TimingProbe::new(&sais_config);
+    let log = Arc::new(ConnectionLog::new(transport));
+    let mut sais_client = SaisClient::with_transport(sais_config, LoginDetails::get(), log.clone());
+    let status = sais_client.check().await?;
+
+    let mut lines = vec![format!("SAIS is {}.", status)];
+    if let Some(tcp_probe) = tcp_probe {
+        let tcp_result = tcp_probe.run().await;
+        lines.push(format!(
+            "TCP connect: {} ({} ms)",
+            tcp_result.status,
+            tcp_result.latency.as_millis()
+        ));
+    }
+    if let Some(timing_probe) = timing_probe {
+        lines.push(match timing_probe.run().await {
+            Ok(timings) => format!("Phases: {}", timings),
+            Err(why) => format!("Phases: could not be timed ({})", why),
+        });
+    }
+    lines.extend(log.requests().iter().map(ToString::to_string));
+    reply(ctx, format!("```\n{}\n```", lines.join("\n"))).await?;
+    Ok(())
+}
+
+/// DMs you everything one check sent and got back, as a HAR file. Owner only.
+#[poise::command(prefix_command, slash_command, owners_only, hide_in_help)]
+pub async fn har(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Let me check... :thinking:").await?;
+
+    let sais_config = SaisConfig::get().map_err(|why| why.to_string())?;
+    let transport = ReqwestTransport::new(
+        &sais_config.timeouts,
+        sais_config.proxy.as_deref(),
+        sais_config.ip_family,
+        sais_config.max_redirects,
+    )?;
+    let login_details = LoginDetails::get();
+    let secrets = vec![login_details.userid.clone(), login_details.pwd.clone()];
+    let recorder = Arc::new(HarRecorder::new(transport, secrets));
+    let mut sais_client = SaisClient::with_transport(sais_config, login_details, recorder.clone());
+    let status = sais_client.check().await?;
+
+    let har = recorder.to_json()?;
+    let filename = format!(
+        "sais-check-{}.har",
+        current_time_utc_plus_8().format("%Y%m%dT%H%M%S")
+    );
+    ctx.author()
+        .direct_message(ctx, |m| {
+            m.content(format!("SAIS was {}.", status))
+                .add_file(AttachmentType::Bytes {
+                    data: har.into_bytes().into(),
+                    filename,
+                })
+        })
+        .await?;
+    reply(ctx, "Sent you the HAR file.".to_string()).await?;
+    Ok(())
+}
+
+/// DMs you the last response SAIS sent the bot. Owner only.
+#[poise::command(prefix_command, slash_command, owners_only, hide_in_help)]
+pub async fn lastresponse(ctx: Context<'_>) -> Result<(), Error> {
+    let snippet = match ctx.data().route_checker.last_response().await {
+        Some(snippet) => snippet,
+        None => {
+            reply(ctx, "SAIS hasn't sent anything yet.".to_string()).await?;
+            return Ok(());
+        }
+    };
+
+    let received_at = snippet
+        .received_at
+        .with_timezone(&FixedOffset::east_opt(3600 * 8).unwrap());
+    ctx.author()
+        .direct_message(ctx, |m| {
+            m.content(format!(
+                "{} from {} {} at {}.",
+                snippet.status,
+                snippet.method,
+                snippet.url,
+                received_at.format("%Y-%m-%d %H:%M:%S")
+            ))
+            .add_file(AttachmentType::Bytes {
+                data: snippet.body.into_bytes().into(),
+                filename: "last-response.html".to_string(),
+            })
+        })
+        .await?;
+    reply(ctx, "Sent you the last response.".to_string()).await?;
+    Ok(())
+}
+
+/// Shows the addresses the SAIS hostname resolves to, and who owns them.
+#[poise::command(prefix_command, slash_command)]
+pub async fn hostinfo(ctx: Context<'_>) -> Result<(), Error> {
+    let sais_config = SaisConfig::get().map_err(|why| why.to_string())?;
+    let login_url = reqwest::Url::parse(&sais_config.login_url)?;
+    let host = login_url.host_str().ok_or("The login URL has no host")?;
+    let host_info = hostinfo::look_up(host, &ctx.data().ip_lookup_url).await?;
+    reply(ctx, format!("```\n{}\n```", host_info)).await?;
+    Ok(())
+}
+
+/// Traces the network path to SAIS, to see where packets get lost. Owner only.
+#[poise::command(prefix_command, slash_command, owners_only, hide_in_help)]
+pub async fn trace(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Tracing the path to SAIS... :thinking:").await?;
+
+    let sais_config = SaisConfig::get().map_err(|why| why.to_string())?;
+    let login_url = reqwest::Url::parse(&sais_config.login_url)?;
+    let host = login_url.host_str().ok_or("The login URL has no host")?;
+    let trace = trace::run(&ctx.data().trace, host).await?;
+    reply(ctx, format!("```\n{}\n```", trace)).await?;
+    Ok(())
+}
+
+/// Runs one of the configured transactions against another UP system, or
+/// all of them at once.
+#[poise::command(prefix_command, slash_command)]
+pub async fn probe(
+    ctx: Context<'_>,
+    #[description = "Name of the transaction to run, or all"] name: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let names = data
+        .transactions
+        .iter()
+        .map(|transaction| transaction.name.as_str())
+        .collect::<Vec<_>>();
+    if name.as_deref() == Some("all") && !names.is_empty() {
+        ctx.say(format!("Probing {}... :thinking:", names.join(", ")))
+            .await?;
+        let outcomes = transaction::run_all(&data.probe_transport, &data.transactions).await;
+        let lines = data
+            .transactions
+            .iter()
+            .zip(outcomes)
+            .map(|(transaction, outcome)| {
+                tracing::info!("Transaction {} {}", transaction.name, outcome);
+                format!("{} {}.", transaction.name, outcome)
+            })
+            .collect::<Vec<_>>();
+        reply(
+            ctx,
+            format!(
+                "As of {}:\n{}",
+                current_time_utc_plus_8().format("%H:%M:%S"),
+                lines.join("\n")
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+    let transaction = match name {
+        Some(name) => data
+            .transactions
+            .iter()
+            .find(|transaction| transaction.name.eq_ignore_ascii_case(&name)),
+        None => None,
+    };
+    let transaction = match transaction {
+        Some(transaction) => transaction,
+        None if names.is_empty() => {
+            reply(ctx, "There are no transactions set up.".to_string()).await?;
+            return Ok(());
+        }
+        None => {
+            reply(
+                ctx,
+                format!("I can probe: {}, or all of them", names.join(", ")),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    ctx.say(format!("Probing {}... :thinking:", transaction.name))
+        .await?;
+    let outcome = transaction::run(&data.probe_transport, transaction).await;
+    tracing::info!("Transaction {} {}", transaction.name, outcome);
+    reply(
+        ctx,
+        format!(
+            "As of {}, {} {}.",
+            current_time_utc_plus_8().format("%H:%M:%S"),
+            transaction.name,
+            outcome
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Sends the recorded check results as a file, e.g. `&export 7d csv`.
+#[poise::command(prefix_command, slash_command)]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "How far back to go, like 24h or 7d"] range: Option<String>,
+    #[description = "csv or json"] format: Option<String>,
+) -> Result<(), Error> {
+    let (range, records, rollups) = match records_in_range(ctx, range, "7d").await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let format = format.unwrap_or_else(|| "csv".to_string()).to_lowercase();
+
+    let contents = match format.as_str() {
+        "csv" => history::to_csv(&records),
+        "json" => history::to_json(&records)?,
+        _ => {
+            reply(ctx, "I can only export csv or json.".to_string()).await?;
+            return Ok(());
+        }
+    };
+    let filename = format!("sais-history-{}.{}", range, format);
+    let mut content = format!("{} checks from the last {}.", records.len(), range);
+    if let Some(clipped) = clipped(&records, &rollups) {
+        content.push_str(&clipped);
+    }
+    ctx.send(|m| {
+        m.content(content)
+            .reply(true)
+            .attachment(AttachmentType::Bytes {
+                data: contents.into_bytes().into(),
+                filename,
+            })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Shows when SAIS tends to be down, by hour and weekday, e.g. `&heatmap 30d`.
+#[poise::command(prefix_command, slash_command)]
+pub async fn heatmap(
+    ctx: Context<'_>,
+    #[description = "How far back to go, like 7d or 90d"] range: Option<String>,
+) -> Result<(), Error> {
+    let (range, records, rollups) = match records_in_range(ctx, range, "30d").await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let utc_plus_8 = FixedOffset::east_opt(3600 * 8).unwrap();
+    let png = OutageHeatmap::from_records(&records, utc_plus_8)
+        .with_rollups(&rollups, utc_plus_8)
+        .to_png()?;
+    let content = format!(
+        "When SAIS was down over the last {}. Rows are Monday to Sunday, and \
+         columns are the hours from midnight to 11 PM (PHT). The redder the \
+         square, the more checks failed then. Grey means there were no checks.",
+        range
+    );
+    ctx.send(|m| {
+        m.content(content)
+            .reply(true)
+            .attachment(AttachmentType::Bytes {
+                data: png.into(),
+                filename: format!("sais-heatmap-{}.png", range),
+            })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Shows the mean time to recovery and between failures, e.g. `&reliability 90d`.
+#[poise::command(prefix_command, slash_command)]
+pub async fn reliability(
+    ctx: Context<'_>,
+    #[description = "How far back to go, like 7d or 90d"] range: Option<String>,
+) -> Result<(), Error> {
+    let (range, records, rollups) = match records_in_range(ctx, range, "30d").await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    // Only the time actually covered by the history counts, in case it
+    // doesn't go back as far as asked.
+    let period = match records.first() {
+        Some(first) => Utc::now() - first.checked_at,
+        None => chrono::Duration::zero(),
+    };
+    let outages = report::outages(&records);
+    let reliability = Reliability::from_outages(&outages, period);
+    let mut content = match (
+        reliability.mean_time_to_recovery,
+        reliability.mean_time_between_failures,
+    ) {
+        (Some(mttr), Some(mtbf)) => format!(
+            "Over the last {}, UP SAIS went down {} times. On average, it took {} to come back, \
+             and stayed up for {} in between.",
+            range,
+            reliability.outages,
+            sla::format_duration(mttr),
+            sla::format_duration(mtbf)
+        ),
+        _ => format!("UP SAIS hasn't gone down in the last {}.", range),
+    };
+    if let Some(clipped) = clipped(&records, &rollups) {
+        content.push_str(&clipped);
+    }
+    reply(ctx, content).await?;
+    Ok(())
+}
+
+/// Guesses how likely SAIS is to have problems in the next 24 hours.
+#[poise::command(prefix_command, slash_command)]
+pub async fn forecast(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    if data.history.is_none() {
+        reply(ctx, "I'm not keeping a history of checks.".to_string()).await?;
+        return Ok(());
+    }
+    let patterns = match data.pattern_watch.patterns() {
+        Some(patterns) if patterns.days_checked() >= MIN_FORECAST_DAYS => patterns,
+        Some(_) => {
+            reply(
+                ctx,
+                format!(
+                    "I need at least {} days of checks to go by.",
+                    MIN_FORECAST_DAYS
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+        None => {
+            reply(
+                ctx,
+                "I'm still looking through the history. Try again in a bit.".to_string(),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let forecast = patterns.forecast(&data.calendar, Utc::now());
+    let mut content = format!(
+        "The chance of UP SAIS having problems in the next 24 hours looks {} (about {:.0}%), \
+         going by the last {} days of checks. It's only a rough guess.",
+        forecast.level(),
+        forecast.likelihood * 100.0,
+        patterns.days_checked()
+    );
+    for reason in &forecast.reasons {
+        content.push_str("\n- ");
+        content.push_str(reason);
+    }
+    reply(ctx, content).await?;
+    Ok(())
+}
+
+/// Compares SAIS with the other UP systems that have transactions set up.
+#[poise::command(prefix_command, slash_command)]
+pub async fn compare(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    ctx.say("Let me check everything... :thinking:").await?;
+
+    // Only SAIS has a history, so the other systems are only run once now.
+    let mut rows = vec![[
+        "Service".to_string(),
+        "Uptime 7d".to_string(),
+        "Now".to_string(),
+        "Latency".to_string(),
+    ]];
+    let summary = match &data.history {
+        Some(history) => Some(ServiceSummary::from_records(
+            &history
+                .since(Utc::now() - chrono::Duration::days(7))
+                .await?,
+        )),
+        None => None,
+    };
+    let summary = summary.as_ref();
+    rows.push([
+        "SAIS".to_string(),
+        summary
+            .and_then(|summary| summary.uptime_percent)
+            .map_or("-".to_string(), |uptime| format!("{:.2}%", uptime)),
+        summary
+            .and_then(|summary| summary.latest_status.clone())
+            .unwrap_or_else(|| "-".to_string()),
+        summary
+            .and_then(|summary| summary.median_latency_ms)
+            .map_or("-".to_string(), |latency| {
+                format!("{} ms (median)", latency)
+            }),
+    ]);
+    let outcomes =
+        futures::future::join_all(data.transactions.iter().map(|transaction| async move {
+            let started_at = std::time::Instant::now();
+            let outcome = transaction::run(&data.probe_transport, transaction).await;
+            (transaction, outcome, started_at.elapsed())
+        }))
+        .await;
+    for (transaction, outcome, latency) in outcomes {
+        let now = match outcome {
+            TransactionOutcome::Passed => "passed".to_string(),
+            TransactionOutcome::Failed { step, .. } => format!("failed at step {}", step),
+        };
+        rows.push([
+            transaction.name.clone(),
+            "-".to_string(),
+            now,
+            format!("{} ms", latency.as_millis()),
+        ]);
+    }
+
+    reply(ctx, format!("```\n{}```", table(&rows))).await?;
+    Ok(())
+}
+
+/// Shows how the bot is doing, and how each command has been used since it
+/// started. Owner only.
+#[poise::command(prefix_command, slash_command, owners_only, hide_in_help)]
+pub async fn botstats(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let memory = runtime_stats::resident_memory_bytes()
+        .map_or("Unknown".to_string(), runtime_stats::format_bytes);
+    let tasks = runtime_stats::task_counts();
+    let cache = &ctx.serenity_context().cache;
+    let caches = format!(
+        "{} guilds, {} channels, {} users, {} emojis",
+        cache.guild_count(),
+        cache.guild_channel_count(),
+        cache.user_count(),
+        data.emoji_cache.len()
+    );
+    let database = match &data.history {
+        Some(history) => match history.size_bytes().await {
+            Ok(size) => runtime_stats::format_bytes(size),
+            Err(why) => {
+                tracing::warn!("Could not get the history's size: {:?}", why);
+                "Unknown".to_string()
+            }
+        },
+        None => "No history kept".to_string(),
+    };
+    let monitor_lag = runtime_stats::loop_lag(
+        &data.monitor_heartbeat,
+        data.settings
+            .monitor_config(&data.monitor_config)
+            .interval(),
+        Instant::now(),
+    );
+    let shard_latencies = {
+        let shard_manager = ctx.framework().shard_manager();
+        let shard_manager = shard_manager.lock().await;
+        let runners = shard_manager.runners.lock().await;
+        runners
+            .iter()
+            .map(|(id, runner)| match runner.latency {
+                Some(latency) => format!("Shard {}: {} ms", id.0, latency.as_millis()),
+                None => format!("Shard {}: not measured yet", id.0),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut rows = vec![[
+        "Command".to_string(),
+        "Uses".to_string(),
+        "Errors".to_string(),
+        "Latency".to_string(),
+    ]];
+    for (command, stats) in ctx.data().command_metrics.snapshot() {
+        rows.push([
+            command,
+            stats.invocations.to_string(),
+            stats.errors.to_string(),
+            format!(
+                "{} ms mean, {} ms max",
+                stats.mean_latency().as_millis(),
+                stats.max_latency.as_millis()
+            ),
+        ]);
+    }
+    ctx.send(|m| {
+        m.content(format!("```\n{}```", table(&rows)))
+            .reply(true)
+            .embed(|e| {
+                e.title("How I'm doing")
+                    .field("Memory", memory, true)
+                    .field(
+                        "Tasks",
+                        format!("{} on {} workers", tasks.alive, tasks.workers),
+                        true,
+                    )
+                    .field("Database", database, true)
+                    .field("Caches", caches, false)
+                    .field(
+                        "Monitor lag",
+                        format!("{} ms", monitor_lag.as_millis()),
+                        true,
+                    )
+                    .field(
+                        "Shard latency",
+                        if shard_latencies.is_empty() {
+                            "No shards".to_string()
+                        } else {
+                            shard_latencies.join("\n")
+                        },
+                        true,
+                    )
+            })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Makes an encrypted backup of the config, settings and history. Owner
+/// only.
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn backup(
+    ctx: Context<'_>,
+    #[description = "dm (the default) or disk"] destination: Option<String>,
+) -> Result<(), Error> {
+    let passphrase = match env::var("BACKUP_PASSPHRASE") {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            reply(
+                ctx,
+                "BACKUP_PASSPHRASE isn't set, so I can't encrypt backups.".to_string(),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let backup =
+        Backup::collect(backup::CONFIG_DIRECTORY, ".", ctx.data().history.as_deref()).await?;
+    let archive = backup.encrypt(&passphrase)?;
+    let filename = format!(
+        "is-sais-down-bot-{}.bak",
+        backup.created_at.format("%Y%m%dT%H%M%S")
+    );
+
+    match destination.as_deref().unwrap_or("dm") {
+        "dm" => {
+            ctx.author()
+                .direct_message(ctx, |m| {
+                    m.content(format!(
+                        "Backup with {} checks and {} hourly rollups.",
+                        backup.history.len(),
+                        backup.rollups.len()
+                    ))
+                    .add_file(AttachmentType::Bytes {
+                        data: archive.into(),
+                        filename,
+                    })
+                })
+                .await?;
+            reply(ctx, "Sent you the backup.".to_string()).await?;
+        }
+        "disk" => {
+            let path = Path::new(BACKUP_DIRECTORY).join(filename);
+            fs::create_dir_all(BACKUP_DIRECTORY)?;
+            fs::write(&path, archive)?;
+            reply(ctx, format!("Saved the backup to {}.", path.display())).await?;
+        }
+        _ => reply(ctx, "I can only back up to dm or disk.".to_string()).await?,
+    }
+    Ok(())
+}
+
+/// Manages users ignored for sending too many commands. Owner only.
+#[poise::command(
+    prefix_command,
+    owners_only,
+    hide_in_help,
+    subcommands("ignored", "pardon")
+)]
+pub async fn spam(ctx: Context<'_>) -> Result<(), Error> {
+    reply(
+        ctx,
+        "Try `&spam ignored` or `&spam pardon <user>`.".to_string(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Lists the users being ignored for sending too many commands. Owner only.
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn ignored(ctx: Context<'_>) -> Result<(), Error> {
+    let ignored = ctx.data().spam_guard.ignored(Instant::now());
+    if ignored.is_empty() {
+        reply(ctx, "Nobody is being ignored.".to_string()).await?;
+        return Ok(());
+    }
+    let lines = ignored
+        .iter()
+        .map(|(user_id, left)| format!("{} for {}m more", user_id, left.as_secs() / 60 + 1))
+        .collect::<Vec<_>>();
+    reply(ctx, lines.join("\n")).await?;
+    Ok(())
+}
+
+/// Stops ignoring a user for sending too many commands. Owner only.
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn pardon(
+    ctx: Context<'_>,
+    #[description = "The user to stop ignoring"] user: serenity::User,
+) -> Result<(), Error> {
+    let message = if ctx.data().spam_guard.pardon(user.id.0, Instant::now()) {
+        tracing::info!("Stopped ignoring {} for spam", user.id);
+        format!("I'll listen to {} again.", user.tag())
+    } else {
+        format!("{} wasn't being ignored.", user.tag())
+    };
+    reply(ctx, message).await?;
+    Ok(())
+}
+
+/// Shows who answers escalations this week.
+#[poise::command(
+    prefix_command,
+    hide_in_help,
+    check = "in_rotation",
+    subcommands("cover")
+)]
+pub async fn oncall(ctx: Context<'_>) -> Result<(), Error> {
+    let oncall = match &ctx.data().oncall {
+        Some(oncall) => oncall,
+        None => {
+            reply(
+                ctx,
+                "There's no on-call rotation, so escalations go to every maintainer.".to_string(),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let now = Utc::now();
+    let mut message = match oncall.current(now) {
+        Some(user_id) => MessageBuilder::new()
+            .mention(&serenity::UserId(user_id))
+            .push(" is on call")
+            .build(),
+        None => "The rotation hasn't started yet".to_string(),
+    };
+    if let Some(cover) = oncall.cover_at(now) {
+        message.push_str(&format!(", covering until {}", pht(cover.until)));
+        if let Some(user_id) = oncall.rotation.scheduled(now) {
+            message.push_str(&format!(" for <@{}>", user_id));
+        }
+    } else if let Some(ends) = oncall.rotation.shift_ends(now) {
+        message.push_str(&format!(" until {}", pht(ends)));
+    }
+    message.push('.');
+    if let Some(next) = oncall.rotation.shift_ends(now) {
+        if let Some(user_id) = oncall.rotation.scheduled(next) {
+            message.push_str(&format!(" <@{}> is next.", user_id));
+        }
+    }
+    ctx.send(|m| {
+        m.content(message)
+            .allowed_mentions(|mentions| mentions.empty_parse())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Puts someone else on call, for a swap.
+///
+/// For example, `oncall override @Someone` until the current turn ends,
+/// `oncall override @Someone 2d`, or `oncall override off`.
+#[poise::command(
+    prefix_command,
+    hide_in_help,
+    check = "in_rotation",
+    rename = "override"
+)]
+pub async fn cover(
+    ctx: Context<'_>,
+    #[description = "Who covers, or off"] user: String,
+    #[description = "For how long, like 12h or 2d"] duration: Option<String>,
+) -> Result<(), Error> {
+    let oncall = match &ctx.data().oncall {
+        Some(oncall) => oncall,
+        None => {
+            reply(ctx, "There's no on-call rotation to override.".to_string()).await?;
+            return Ok(());
+        }
+    };
+    if user.eq_ignore_ascii_case("off") {
+        oncall.set_override(None)?;
+        tracing::info!("{} ended the on-call override", ctx.author().tag());
+        reply(ctx, "Back to the rotation.".to_string()).await?;
+        return Ok(());
+    }
+    let user_id = match user
+        .trim_start_matches("<@")
+        .trim_start_matches('!')
+        .trim_end_matches('>')
+        .parse::<u64>()
+    {
+        Ok(user_id) => user_id,
+        Err(_) => {
+            reply(ctx, format!("{} isn't a user.", user)).await?;
+            return Ok(());
+        }
+    };
+    let now = Utc::now();
+    let until = match duration.as_deref().map(history::parse_range) {
+        Some(Some(duration)) => now + duration,
+        Some(None) => {
+            reply(ctx, "I don't understand that. Try 12h or 2d.".to_string()).await?;
+            return Ok(());
+        }
+        None => match oncall.rotation.shift_ends(now) {
+            Some(ends) => ends,
+            None => {
+                reply(
+                    ctx,
+                    "The rotation hasn't started, so say for how long.".to_string(),
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+    oncall.set_override(Some(OnCallOverride { user_id, until }))?;
+    tracing::info!(
+        "{} put {} on call until {}",
+        ctx.author().tag(),
+        user_id,
+        until
+    );
+    reply(
+        ctx,
+        format!("<@{}> is on call until {}.", user_id, pht(until)),
+    )
+    .await?;
+    Ok(())
+}
+
+// The owner and everyone in the on-call rotation.
+async fn in_rotation(ctx: Context<'_>) -> Result<bool, Error> {
+    let author_id = ctx.author().id;
+    Ok(ctx.framework().options().owners.contains(&author_id)
+        || ctx
+            .data()
+            .oncall
+            .as_ref()
+            .is_some_and(|oncall| oncall.rotation.user_ids.contains(&author_id.0)))
+}
+
+/// Works with the config files. Owner only.
+#[poise::command(
+    prefix_command,
+    owners_only,
+    hide_in_help,
+    subcommands("get", "set", "generate")
+)]
+pub async fn config(ctx: Context<'_>) -> Result<(), Error> {
+    reply(
+        ctx,
+        "Try `&config get`, `&config set <key> <value>` or `&config generate`.".to_string(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Shows the settings `&config set` can change. Owner only.
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn get(
+    ctx: Context<'_>,
+    #[description = "Only show this key"] key: Option<String>,
+) -> Result<(), Error> {
+    let lines = setting_lines(ctx.data())
+        .into_iter()
+        .filter(|(name, _)| key.as_deref().is_none_or(|key| name == key))
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        reply(ctx, "That isn't a setting.".to_string()).await?;
+        return Ok(());
+    }
+    reply(
+        ctx,
+        format!(
+            "```\n{}\n```Settings marked (set) were changed with `&config set`, and the rest come from the config files. `&config set <key> default` changes one back. {}",
+            lines.join("\n"),
+            ctx.data()
+                .calendar
+                .describe(current_time_utc_plus_8().date_naive())
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Changes a setting without restarting, and keeps it. Owner only.
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "The setting, from &config get"] key: String,
+    #[description = "Its new value, or default"]
+    #[rest]
+    value: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    if let Err(why) = data.settings.set(&key, value.trim()) {
+        let keys = settings::KEYS
+            .iter()
+            .map(|(key, takes)| format!("- `{}`: {}", key, takes))
+            .collect::<Vec<_>>();
+        reply(
+            ctx,
+            format!(
+                "Couldn't set that: {}.\nThese can be set:\n{}",
+                why,
+                keys.join("\n")
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+    tracing::info!("{} set {} to {:?}", ctx.author().tag(), key, value);
+    let applies = if key.starts_with("monitor.") {
+        "It applies after the next check."
+    } else {
+        "It applies straight away."
+    };
+    reply(ctx, format!("Set `{}`. {}", key, applies)).await?;
+    Ok(())
+}
+
+// Every setting `&config set` can change, with its current value.
+fn setting_lines(data: &crate::Data) -> Vec<(String, String)> {
+    let overrides = data.settings.overrides();
+    let today = current_time_utc_plus_8().date_naive();
+    let monitor_config = data
+        .settings
+        .monitor_config(&data.calendar.monitor_config(&data.monitor_config, today));
+    let marked = |value: String, set: bool| {
+        if set {
+            format!("{} (set)", value)
+        } else {
+            value
+        }
+    };
+    let alerts_target = data.settings.alerts_target(
+        data.alerts_config
+            .as_ref()
+            .map(|alerts_config| (alerts_config.channel_id, alerts_config.role_id)),
+    );
+    let mut lines = vec![
+        (
+            "monitor.interval_secs",
+            marked(
+                monitor_config.interval_secs.to_string(),
+                overrides.interval_secs.is_some(),
+            ),
+        ),
+        (
+            "monitor.confirm_after",
+            marked(
+                monitor_config.confirm_after.to_string(),
+                overrides.confirm_after.is_some(),
+            ),
+        ),
+        (
+            "monitor.flap_window",
+            marked(
+                monitor_config.flap_window.to_string(),
+                overrides.flap_window.is_some(),
+            ),
+        ),
+        (
+            "monitor.flap_threshold",
+            marked(
+                monitor_config.flap_threshold.to_string(),
+                overrides.flap_threshold.is_some(),
+            ),
+        ),
+        (
+            "alerts.channel_id",
+            marked(
+                alerts_target.map_or("none".to_string(), |(channel_id, _)| channel_id.to_string()),
+                overrides.alerts_channel_id.is_some(),
+            ),
+        ),
+        (
+            "alerts.role_id",
+            marked(
+                alerts_target
+                    .and_then(|(_, role_id)| role_id)
+                    .map_or("none".to_string(), |role_id| role_id.to_string()),
+                overrides.alerts_role_id.is_some(),
+            ),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect::<Vec<_>>();
+    let phrases = data.settings.down_phrases(&data.down_phrases);
+    let mut reasons = phrases.0.keys().collect::<Vec<_>>();
+    reasons.sort();
+    for reason in reasons {
+        lines.push((
+            format!("down_phrases.{}", reason),
+            marked(
+                format!("{:?}", phrases.0[reason]),
+                overrides.down_phrases.contains_key(reason),
+            ),
+        ));
+    }
+    lines
+}
+
+/// DMs you sample config files with every option, and a JSON Schema for
+/// each. Owner only.
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn generate(ctx: Context<'_>) -> Result<(), Error> {
+    let files = sample_config::files()?;
+    ctx.author()
+        .direct_message(ctx, |m| {
+            m.content("Sample config files for this version, and their schemas.");
+            for (filename, contents) in files {
+                m.add_file(AttachmentType::Bytes {
+                    data: contents.into_bytes().into(),
+                    filename,
+                });
+            }
+            m
+        })
+        .await?;
+    reply(ctx, "Sent you the sample config.".to_string()).await?;
+    Ok(())
+}
+
+/// Shows which version of the bot this is, and whether there's a newer one.
+#[poise::command(prefix_command, slash_command)]
+pub async fn about(ctx: Context<'_>) -> Result<(), Error> {
+    let mut lines = vec![format!(
+        "is-sais-down-bot {}, from {}",
+        releases::CURRENT_VERSION,
+        env!("CARGO_PKG_REPOSITORY")
+    )];
+    if let Some(release) = ctx.data().releases.newer_release() {
+        lines.push(format!("{} is out: {}", release.tag_name, release.html_url));
+    }
+    reply(ctx, lines.join("\n")).await?;
+    Ok(())
+}
+
+/// Shows the available commands, or help about a specific command.
+#[poise::command(prefix_command, slash_command)]
+pub async fn help(
+    ctx: Context<'_>,
+    #[description = "Specific command to show help about"] command: Option<String>,
+) -> Result<(), Error> {
+    let config = poise::builtins::HelpConfiguration {
+        extra_text_at_bottom: "Commands work with both the & prefix and as slash commands.",
+        ..Default::default()
+    };
+    poise::builtins::help(ctx, command.as_deref(), config).await?;
+    Ok(())
+}
+
+// The recorded checks from the last `range`, or `default` if not given,
+// with the hourly rollups of those compacted since. Replies and returns
+// `None` if there's no history, or the range can't be understood.
+async fn records_in_range(
+    ctx: Context<'_>,
+    range: Option<String>,
+    default: &str,
+) -> Result<Option<(String, Vec<CheckRecord>, Vec<HourlyRollup>)>, Error> {
+    let history = match &ctx.data().history {
+        Some(history) => history,
+        None => {
+            reply(ctx, "I'm not keeping a history of checks.".to_string()).await?;
+            return Ok(None);
+        }
+    };
+    let range = range.unwrap_or_else(|| default.to_string());
+    let duration = match history::parse_range(&range) {
+        Some(duration) => duration,
+        None => {
+            reply(
+                ctx,
+                format!("I don't understand {:?}. Try 24h or 7d.", range),
+            )
+            .await?;
+            return Ok(None);
+        }
+    };
+    let since = Utc::now() - duration;
+    let records = history.since(since).await?;
+    let rollups = history.rollups_since(since).await?;
+    Ok(Some((range, records, rollups)))
+}
+
+// Says the range was cut short, for replies that can only go by each check
+// when some were compacted into rollups.
+fn clipped(records: &[CheckRecord], rollups: &[HourlyRollup]) -> Option<String> {
+    if rollups.is_empty() {
+        return None;
+    }
+    Some(match records.first() {
+        Some(first) => format!(
+            " Checks from before {} are only kept as hourly totals, so they're left out.",
+            pht(first.checked_at)
+        ),
+        None => " Older checks are only kept as hourly totals, so they're left out.".to_string(),
+    })
+}
+
+// Stands in for the custom server emojis.
+fn unicode_emoji(name: &str) -> &'static str {
+    match name {
+        "login_ok" => ":white_check_mark:",
+        "login_fail" => ":no_entry:",
+        "status_code_fail" => ":warning:",
+        "response_fail" => ":hourglass:",
+        "degraded" => ":turtle:",
+        "maintenance" => ":construction:",
+        _ => ":grey_question:",
+    }
+}
+
+// Lines up the columns of a table meant for a code block.
+fn table(rows: &[[String; 4]]) -> String {
+    let mut widths = [0; 4];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in rows {
+        let cells = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+// Replies to the invoking message for prefix commands, and follows up on the
+// interaction for slash commands.
+async fn reply(ctx: Context<'_>, content: String) -> Result<(), Error> {
+    ctx.send(|m| m.content(redact::redact(&content)).reply(true))
+        .await?;
+    Ok(())
+}
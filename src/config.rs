@@ -0,0 +1,324 @@
+use crate::{transport::DEFAULT_MAX_REDIRECTS, Error};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use serde::Deserialize;
+use std::{
+    env, fmt,
+    fs::File,
+    io::prelude::*,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+pub const SAIS_CONFIG_FILEPATH: &str = "config/sais.ron";
+
+/// What the login form is sent with. The field names match the form's.
+#[derive(Debug, Clone)]
+#[allow(non_snake_case)]
+pub struct LoginDetails {
+    /// The browser's offset from UTC in minutes, e.g. -480 for PHT.
+    pub timezoneOffset: i32,
+    /// The UP email to log in with.
+    pub userid: String,
+    pub pwd: String,
+    /// Copied from a real login request.
+    pub request_id: u64,
+}
+
+impl LoginDetails {
+    /// Reads `TIMEZONE_OFFSET`, `USER_ID`, `PASSWORD` and `REQUEST_ID`,
+    /// panicking if any is missing.
+    pub fn get() -> Self {
+        LoginDetails {
+            timezoneOffset: env::var("TIMEZONE_OFFSET")
+                .expect("Expected TIMEZONE_OFFSET")
+                .parse::<i32>()
+                .expect("Could not parse TIMEZONE_OFFSET"),
+            userid: env::var("USER_ID").expect("Expected USER_ID"),
+            pwd: env::var("PASSWORD").expect("Expected PASSWORD"),
+            request_id: env::var("REQUEST_ID")
+                .expect("Expected REQUEST_ID")
+                .parse::<u64>()
+                .expect("Could not parse REQUEST_ID"),
+        }
+    }
+}
+
+/// How SAIS is checked, as read from `config/sais.ron`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SaisConfig {
+    pub login_url: String,
+    /// Text the page after logging in has to contain for the login to count
+    /// as working.
+    pub login_success_string: String,
+    /// Text that marks a page as a maintenance notice, matched ignoring case.
+    #[serde(default)]
+    pub maintenance_strings: Vec<String>,
+    /// How many redirects a single request may follow.
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+    /// Hosts besides the login URL's that SAIS is expected to redirect to.
+    #[serde(default)]
+    pub trusted_redirect_hosts: Vec<String>,
+    #[serde(default)]
+    pub timeouts: Timeouts,
+    /// A successful check that takes longer than this many seconds counts
+    /// as degraded rather than up.
+    #[serde(default)]
+    pub degraded_after_secs: Option<u64>,
+    /// An `http://`, `https://` or `socks5://` proxy to send checks through.
+    /// Only affects requests to SAIS, not the bot's Discord traffic.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Only connects over this IP version when set.
+    #[serde(default)]
+    pub ip_family: Option<IpFamily>,
+    /// Also checks over IPv4 and IPv6 separately, for whichever of the two
+    /// the SAIS host has addresses for.
+    #[serde(default)]
+    pub dual_stack: bool,
+    /// Other ways out to check SAIS through, whose results are compared with
+    /// the check made through `proxy` (or directly).
+    #[serde(default)]
+    pub routes: Vec<Route>,
+    /// Pages behind the login that are also checked after logging in.
+    #[serde(default)]
+    pub deep_checks: Vec<DeepCheck>,
+    /// Also times a plain TCP connection to the SAIS host on every
+    /// background check, recorded in the history as the "tcp" route.
+    #[serde(default)]
+    pub tcp_probe: bool,
+    /// Also times each phase of loading the login page on every background
+    /// check, for the metrics and `&diagnose`.
+    #[serde(default)]
+    pub timing_probe: bool,
+    /// Known pages SAIS serves when something specific is wrong, so a
+    /// failed check can say which one it got.
+    #[serde(default)]
+    pub error_pages: Vec<ErrorPage>,
+    /// Reuses the session from the last login when set, instead of logging
+    /// in on every check.
+    #[serde(default)]
+    pub session: Option<SessionConfig>,
+    /// Loaded after each successful login when sessions aren't reused.
+    #[serde(default)]
+    pub logout_url: Option<String>,
+    #[serde(default)]
+    pub headers: RequestHeaders,
+}
+
+pub const DEFAULT_USER_AGENT: &str = "Is UP SAIS down?/1.0";
+
+/// The User-Agent and other headers sent with every request to a service,
+/// for when its firewall starts filtering the ones the bot sends.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestHeaders {
+    /// Taken in turn, one for each check.
+    #[serde(default = "default_user_agents")]
+    pub user_agents: Vec<String>,
+    /// e.g. ("Accept-Language", "en-US").
+    #[serde(default)]
+    pub extra: Vec<(String, String)>,
+    // Shared between clones, so every client made from the same config
+    // takes the next User-Agent in turn.
+    #[serde(skip)]
+    next_user_agent: Arc<AtomicUsize>,
+}
+
+fn default_user_agents() -> Vec<String> {
+    vec![DEFAULT_USER_AGENT.to_string()]
+}
+
+impl Default for RequestHeaders {
+    fn default() -> Self {
+        RequestHeaders {
+            user_agents: default_user_agents(),
+            extra: vec![],
+            next_user_agent: Arc::default(),
+        }
+    }
+}
+
+impl RequestHeaders {
+    /// The User-Agent for the next check.
+    pub fn next_user_agent(&self) -> String {
+        if self.user_agents.is_empty() {
+            return DEFAULT_USER_AGENT.to_string();
+        }
+        let index = self.next_user_agent.fetch_add(1, Ordering::Relaxed);
+        self.user_agents[index % self.user_agents.len()].clone()
+    }
+
+    /// Sets `user_agent` and the extra headers on a request's headers.
+    pub fn apply(&self, user_agent: &str, headers: &mut HeaderMap) -> Result<(), Error> {
+        headers.insert(USER_AGENT, HeaderValue::from_str(user_agent)?);
+        for (name, value) in &self.extra {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionConfig {
+    /// A cheap page that only loads while logged in.
+    pub keepalive_url: String,
+    /// Text the keepalive page has to contain while the session is valid.
+    pub expect: String,
+    /// How long a session is reused before logging in again anyway, so the
+    /// login itself keeps getting checked.
+    #[serde(default = "default_max_session_age_secs")]
+    pub max_age_secs: u64,
+}
+
+fn default_max_session_age_secs() -> u64 {
+    30 * 60
+}
+
+/// A page behind the login, like the Student Center or enlistment, that has
+/// to work for SAIS to count as fully up.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeepCheck {
+    /// Shown when the page fails, e.g. "enlistment".
+    pub name: String,
+    pub url: String,
+    /// Text the page has to contain to count as working.
+    pub expect: String,
+}
+
+/// A failure page recognized by text it contains, like PeopleSoft's error
+/// screen or a load balancer's block page.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorPage {
+    /// Shown when the page is served, e.g. "PeopleSoft application error".
+    pub name: String,
+    /// Text the page has to contain all of, matched ignoring case.
+    pub matches: Vec<String>,
+}
+
+impl ErrorPage {
+    pub fn matches(&self, body: &str) -> bool {
+        let body = body.to_lowercase();
+        !self.matches.is_empty()
+            && self
+                .matches
+                .iter()
+                .all(|text| body.contains(&text.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+    /// Shown when results differ, e.g. "campus" or "outside PH".
+    pub name: String,
+    /// Checks directly when not set.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub ip_family: Option<IpFamily>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    pub fn matches(self, addr: &IpAddr) -> bool {
+        match self {
+            IpFamily::V4 => addr.is_ipv4(),
+            IpFamily::V6 => addr.is_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for IpFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpFamily::V4 => write!(f, "IPv4"),
+            IpFamily::V6 => write!(f, "IPv6"),
+        }
+    }
+}
+
+/// How long a check waits on SAIS before giving up, in seconds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Timeouts {
+    /// For a connection to be established.
+    pub connect_secs: u64,
+    /// For the server to send anything, once connected.
+    pub read_secs: u64,
+    /// For the whole check, across every request it makes.
+    pub check_secs: u64,
+}
+
+impl Timeouts {
+    pub fn connect(&self) -> Duration {
+        Duration::from_secs(self.connect_secs)
+    }
+
+    pub fn read(&self) -> Duration {
+        Duration::from_secs(self.read_secs)
+    }
+
+    pub fn check(&self) -> Duration {
+        Duration::from_secs(self.check_secs)
+    }
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            connect_secs: 10,
+            read_secs: 30,
+            check_secs: 60,
+        }
+    }
+}
+
+fn default_max_redirects() -> usize {
+    DEFAULT_MAX_REDIRECTS
+}
+
+impl SaisConfig {
+    /// A config with every optional setting left at its default: no proxy,
+    /// extra routes, deep checks or session reuse.
+    pub fn new(login_url: impl Into<String>, login_success_string: impl Into<String>) -> Self {
+        SaisConfig {
+            login_url: login_url.into(),
+            login_success_string: login_success_string.into(),
+            maintenance_strings: vec![],
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            trusted_redirect_hosts: vec![],
+            timeouts: Timeouts::default(),
+            degraded_after_secs: None,
+            proxy: None,
+            ip_family: None,
+            dual_stack: false,
+            routes: vec![],
+            deep_checks: vec![],
+            tcp_probe: false,
+            timing_probe: false,
+            error_pages: vec![],
+            session: None,
+            logout_url: None,
+            headers: RequestHeaders::default(),
+        }
+    }
+
+    /// Reads `config/sais.ron`.
+    pub fn get() -> Result<SaisConfig, Box<dyn std::error::Error>> {
+        let sais_config_file = File::open(SAIS_CONFIG_FILEPATH)?;
+        let mut buf_reader = std::io::BufReader::new(sais_config_file);
+        let mut contents = String::new();
+        buf_reader.read_to_string(&mut contents)?;
+        Ok(ron::de::from_str(&contents)?)
+    }
+}
@@ -0,0 +1,105 @@
+//! The cookies SAIS hands out, kept until they expire.
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+#[derive(Debug, Clone)]
+struct Cookie {
+    name: String,
+    value: String,
+    // `None` for cookies that last as long as the session.
+    expires: Option<DateTime<Utc>>,
+}
+
+impl Cookie {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// Cookies by name, with the expiry from their `Expires` or `Max-Age`.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// Stores the cookie from a `Set-Cookie` header, replacing any cookie
+    /// with the same name. Cookies that are already expired, like the ones
+    /// servers use to delete a cookie, are removed instead.
+    pub fn store(&mut self, set_cookie: &str, now: DateTime<Utc>) {
+        let mut parts = set_cookie.split(';');
+        let (name, value) = match parts.next().and_then(|pair| pair.split_once('=')) {
+            Some((name, value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+            _ => return,
+        };
+        let mut expires = None;
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, attribute_value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            match key.trim().to_lowercase().as_str() {
+                "expires" => expires = parse_expires(attribute_value.trim()),
+                "max-age" => max_age = attribute_value.trim().parse::<i64>().ok(),
+                _ => {}
+            }
+        }
+        // Max-Age wins over Expires when a cookie has both.
+        let cookie = Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            expires: max_age
+                .map(|secs| now + Duration::seconds(secs))
+                .or(expires),
+        };
+
+        self.cookies.retain(|stored| stored.name != cookie.name);
+        if !cookie.is_expired(now) {
+            self.cookies.push(cookie);
+        }
+    }
+
+    /// Drops the cookies that have expired by `now`, returning whether there
+    /// were any.
+    pub fn remove_expired(&mut self, now: DateTime<Utc>) -> bool {
+        let before = self.cookies.len();
+        self.cookies.retain(|cookie| !cookie.is_expired(now));
+        self.cookies.len() != before
+    }
+
+    /// The `Cookie` header for the cookies that haven't expired by `now`.
+    pub fn header(&self, now: DateTime<Utc>) -> String {
+        self.cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now))
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+}
+
+// Both "Wed, 21 Oct 2026 07:28:00 GMT" and the older
+// "Wed, 21-Oct-2026 07:28:00 GMT" are still sent.
+fn parse_expires(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .map(|expires| expires.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%a, %d-%b-%Y %H:%M:%S GMT")
+                .map(|expires| expires.and_utc())
+        })
+        .ok()
+}
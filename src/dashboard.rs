@@ -0,0 +1,547 @@
+//! A small web UI for operators, behind a Discord login, to look at the
+//! history and monitor, and to change what would otherwise be edited on
+//! disk.
+
+use crate::{
+    guilds::{EmojiStyle, GuildSettings, GuildSettingsStore, Language},
+    history::HistoryStore,
+    maintenance::{MaintenanceStore, MaintenanceWindow},
+    monitor::MonitorHealth,
+    routes::MAIN_ROUTE_NAME,
+    sla::{counts, failed_check},
+    Error,
+};
+use axum::{
+    extract::{Form, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use chrono::{Duration, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+const DISCORD_API: &str = "https://discord.com/api";
+const SESSION_COOKIE: &str = "sais_dashboard_session";
+// Sessions have to log in again after this long.
+const SESSION_LIFETIME: std::time::Duration = std::time::Duration::from_secs(12 * 60 * 60);
+// Logins have to be finished on Discord within this long.
+const LOGIN_STATE_LIFETIME: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DashboardConfig {
+    /// The bot's application ID, from the Discord developer portal.
+    pub client_id: u64,
+    /// Where Discord sends operators back to after they log in. Has to end
+    /// in /admin/callback, and be listed in the developer portal.
+    pub redirect_url: String,
+    /// The Discord users allowed in.
+    pub admin_user_ids: Vec<u64>,
+}
+
+/// What the dashboard shows and edits, shared with the bot.
+pub struct DashboardData {
+    pub history: Option<Arc<dyn HistoryStore>>,
+    pub guild_settings: Arc<GuildSettingsStore>,
+    pub maintenance: Arc<MaintenanceStore>,
+    pub monitor_health: Arc<MonitorHealth>,
+}
+
+pub struct Dashboard {
+    config: DashboardConfig,
+    client_secret: String,
+    discord_api: String,
+    http: reqwest::Client,
+    // Session tokens, and who they belong to.
+    sessions: Mutex<HashMap<String, (u64, Instant)>>,
+    // Sent with each login, so a callback can be matched to it, and when
+    // they expire.
+    login_states: Mutex<HashMap<String, Instant>>,
+    data: DashboardData,
+}
+
+impl Dashboard {
+    pub fn new(config: DashboardConfig, client_secret: String, data: DashboardData) -> Dashboard {
+        Dashboard::with_discord_api(config, client_secret, data, DISCORD_API)
+    }
+
+    /// Like [`Dashboard::new`], but talks to another Discord API, like a
+    /// mock one in tests.
+    pub fn with_discord_api(
+        config: DashboardConfig,
+        client_secret: String,
+        data: DashboardData,
+        discord_api: &str,
+    ) -> Dashboard {
+        Dashboard {
+            config,
+            client_secret,
+            discord_api: discord_api.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            sessions: Mutex::new(HashMap::new()),
+            login_states: Mutex::new(HashMap::new()),
+            data,
+        }
+    }
+
+    // The logged in admin, if any.
+    fn admin(&self, headers: &HeaderMap) -> Option<u64> {
+        let token = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|cookies| cookies.split(';'))
+            .find_map(|cookie| cookie.trim().strip_prefix(&format!("{}=", SESSION_COOKIE)))?;
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(token) {
+            Some((user_id, expires_at)) if *expires_at > Instant::now() => Some(*user_id),
+            Some(_) => {
+                sessions.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn discord_user_id(&self, code: &str) -> Result<u64, Error> {
+        #[derive(Deserialize)]
+        struct Token {
+            access_token: String,
+        }
+        #[derive(Deserialize)]
+        struct User {
+            id: String,
+        }
+
+        let token: Token = self
+            .http
+            .post(format!("{}/oauth2/token", self.discord_api))
+            .form(&[
+                ("client_id", self.config.client_id.to_string().as_str()),
+                ("client_secret", &self.client_secret),
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.config.redirect_url),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let user: User = self
+            .http
+            .get(format!("{}/users/@me", self.discord_api))
+            .bearer_auth(token.access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(user.id.parse()?)
+    }
+}
+
+pub fn router() -> Router<Arc<Dashboard>> {
+    Router::new()
+        .route("/admin", get(overview))
+        .route("/admin/login", get(login))
+        .route("/admin/callback", get(callback))
+        .route("/admin/guilds/:guild_id", post(update_guild))
+        .route("/admin/maintenance", post(add_maintenance))
+        .route("/admin/maintenance/:index/delete", post(delete_maintenance))
+}
+
+fn random_token() -> String {
+    let mut bytes = [0; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+async fn login(State(dashboard): State<Arc<Dashboard>>) -> Redirect {
+    let state = random_token();
+    {
+        let mut login_states = dashboard.login_states.lock().unwrap();
+        // Abandoned logins would otherwise pile up.
+        let now = Instant::now();
+        login_states.retain(|_, expires_at| *expires_at > now);
+        login_states.insert(state.clone(), now + LOGIN_STATE_LIFETIME);
+    }
+    let url = reqwest::Url::parse_with_params(
+        "https://discord.com/oauth2/authorize",
+        &[
+            ("response_type", "code"),
+            ("client_id", &dashboard.config.client_id.to_string()),
+            ("scope", "identify"),
+            ("state", &state),
+            ("redirect_uri", &dashboard.config.redirect_url),
+        ],
+    )
+    .expect("The authorize URL is valid");
+    Redirect::to(url.as_str())
+}
+
+#[derive(Deserialize)]
+struct Callback {
+    code: String,
+    state: String,
+}
+
+async fn callback(
+    State(dashboard): State<Arc<Dashboard>>,
+    Query(callback): Query<Callback>,
+) -> Response {
+    let expires_at = dashboard
+        .login_states
+        .lock()
+        .unwrap()
+        .remove(&callback.state);
+    if expires_at.is_none_or(|expires_at| expires_at <= Instant::now()) {
+        return (StatusCode::BAD_REQUEST, "That login link has expired.").into_response();
+    }
+    let user_id = match dashboard.discord_user_id(&callback.code).await {
+        Ok(user_id) => user_id,
+        Err(why) => {
+            tracing::warn!("Dashboard login failed: {:?}", why);
+            return (StatusCode::BAD_GATEWAY, "Could not log in with Discord.").into_response();
+        }
+    };
+    if !dashboard.config.admin_user_ids.contains(&user_id) {
+        tracing::warn!("User {} tried to log in to the dashboard", user_id);
+        return (StatusCode::FORBIDDEN, "You're not allowed in here.").into_response();
+    }
+
+    tracing::info!("User {} logged in to the dashboard", user_id);
+    let token = random_token();
+    {
+        let mut sessions = dashboard.sessions.lock().unwrap();
+        // Sessions that are never used again are only dropped here.
+        let now = Instant::now();
+        sessions.retain(|_, (_, expires_at)| *expires_at > now);
+        sessions.insert(token.clone(), (user_id, now + SESSION_LIFETIME));
+    }
+    let cookie = format!(
+        "{}={}; Path=/admin; HttpOnly; SameSite=Lax; Max-Age={}",
+        SESSION_COOKIE,
+        token,
+        SESSION_LIFETIME.as_secs()
+    );
+    ([(header::SET_COOKIE, cookie)], Redirect::to("/admin")).into_response()
+}
+
+async fn overview(State(dashboard): State<Arc<Dashboard>>, headers: HeaderMap) -> Response {
+    if dashboard.admin(&headers).is_none() {
+        return Redirect::to("/admin/login").into_response();
+    }
+    match render_overview(&dashboard).await {
+        Ok(page) => Html(page).into_response(),
+        Err(why) => {
+            tracing::error!("Could not render the dashboard: {:?}", why);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong.").into_response()
+        }
+    }
+}
+
+fn utc_plus_8() -> FixedOffset {
+    FixedOffset::east_opt(3600 * 8).unwrap()
+}
+
+async fn render_overview(dashboard: &Dashboard) -> Result<String, Error> {
+    let data = &dashboard.data;
+    let mut page = String::from(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Is SAIS down? Admin</title>\
+         <style>body{font-family:sans-serif;max-width:60em;margin:auto}\
+         table{border-collapse:collapse}td,th{padding:.2em .6em;text-align:left}</style>\
+         </head><body><h1>Is SAIS down?</h1>",
+    );
+
+    let health = data.monitor_health.snapshot();
+    page.push_str("<h2>Monitor</h2><ul>");
+    match (health.last_checked_at, health.last_status) {
+        (Some(checked_at), Some(status)) => {
+            let _ = write!(
+                page,
+                "<li>Last check: {} at {} PHT</li>",
+                escape(&status.to_string()),
+                checked_at
+                    .with_timezone(&utc_plus_8())
+                    .format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        _ => page.push_str("<li>No checks yet</li>"),
+    }
+    let _ = write!(
+        page,
+        "<li>{} checks since starting, {} of which couldn't run</li></ul>",
+        health.checks, health.errors
+    );
+
+    page.push_str("<h2>Uptime, last 30 days</h2>");
+    match &data.history {
+        Some(history) => page.push_str(&uptime_chart(history.as_ref()).await?),
+        None => page.push_str("<p>The history isn't being kept.</p>"),
+    }
+
+    page.push_str(
+        "<h2>Servers</h2><table><tr><th>Guild</th><th>Channel</th><th>Role</th>\
+         <th>Language</th><th>Emojis</th><th></th></tr>",
+    );
+    for (guild_id, settings) in data.guild_settings.all() {
+        // Forms can't wrap table rows, so the inputs point at theirs.
+        let form = format!("guild-{}", guild_id);
+        let _ = write!(
+            page,
+            "<tr><td>{guild}<form id=\"{form}\" method=\"post\" action=\"/admin/guilds/{guild}\">\
+             </form></td>\
+             <td><input form=\"{form}\" name=\"announcement_channel_id\" value=\"{channel}\"></td>\
+             <td><input form=\"{form}\" name=\"alert_role_id\" value=\"{role}\"></td>\
+             <td>{language}</td><td>{emoji_style}</td>\
+             <td><button form=\"{form}\">Save</button></td></tr>",
+            guild = guild_id,
+            form = form,
+            channel = settings
+                .announcement_channel_id
+                .map_or(String::new(), |id| id.to_string()),
+            role = settings
+                .alert_role_id
+                .map_or(String::new(), |id| id.to_string()),
+            language = select(
+                &form,
+                "language",
+                &["English", "Cebuano"],
+                &format!("{:?}", settings.language)
+            ),
+            emoji_style = select(
+                &form,
+                "emoji_style",
+                &["Custom", "Unicode"],
+                &format!("{:?}", settings.emoji_style)
+            ),
+        );
+    }
+    page.push_str("</table>");
+
+    page.push_str(
+        "<h2>Planned maintenance</h2><p>Status changes aren't announced during these. \
+         Times are in PHT.</p><ul>",
+    );
+    for (index, window) in data.maintenance.all().iter().enumerate() {
+        let _ = write!(
+            page,
+            "<li>{} to {}: {} <form style=\"display:inline\" method=\"post\" \
+             action=\"/admin/maintenance/{}/delete\"><button>Remove</button></form></li>",
+            window
+                .starts_at
+                .with_timezone(&utc_plus_8())
+                .format("%Y-%m-%d %H:%M"),
+            window
+                .ends_at
+                .with_timezone(&utc_plus_8())
+                .format("%Y-%m-%d %H:%M"),
+            escape(&window.note),
+            index
+        );
+    }
+    page.push_str(
+        "</ul><form method=\"post\" action=\"/admin/maintenance\">\
+         <input type=\"datetime-local\" name=\"starts_at\" required> to \
+         <input type=\"datetime-local\" name=\"ends_at\" required> \
+         <input name=\"note\" placeholder=\"What's happening\" required> \
+         <button>Add</button></form></body></html>",
+    );
+    Ok(page)
+}
+
+// A bar for each day's share of working main route checks, in PHT.
+async fn uptime_chart(history: &dyn HistoryStore) -> Result<String, Error> {
+    let since = Utc::now() - Duration::days(30);
+    let mut days = BTreeMap::<chrono::NaiveDate, (u64, u64)>::new();
+    for record in history.since(since).await? {
+        if record.route == MAIN_ROUTE_NAME && counts(&record.status) {
+            let (failed, total) = days
+                .entry(record.checked_at.with_timezone(&utc_plus_8()).date_naive())
+                .or_default();
+            *failed += failed_check(&record.status) as u64;
+            *total += 1;
+        }
+    }
+    for rollup in history.rollups_since(since).await? {
+        if rollup.route == MAIN_ROUTE_NAME && counts(&rollup.status) {
+            let (failed, total) = days
+                .entry(rollup.hour.with_timezone(&utc_plus_8()).date_naive())
+                .or_default();
+            if failed_check(&rollup.status) {
+                *failed += rollup.checks;
+            }
+            *total += rollup.checks;
+        }
+    }
+    if days.is_empty() {
+        return Ok("<p>No checks yet.</p>".to_string());
+    }
+
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"120\" xmlns=\"http://www.w3.org/2000/svg\">",
+        days.len() * 20
+    );
+    for (index, (day, (failed, total))) in days.iter().enumerate() {
+        let uptime = 1.0 - *failed as f64 / *total as f64;
+        let height = (100.0 * uptime).round();
+        let _ = write!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"16\" height=\"{}\" fill=\"{}\">\
+             <title>{}: {:.2}%</title></rect>",
+            index * 20,
+            100.0 - height,
+            height,
+            if uptime >= 0.99 { "#3a3" } else { "#c33" },
+            day,
+            100.0 * uptime
+        );
+    }
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+fn select(form: &str, name: &str, options: &[&str], selected: &str) -> String {
+    let mut html = format!("<select form=\"{}\" name=\"{}\">", form, name);
+    for option in options {
+        let _ = write!(
+            html,
+            "<option{}>{}</option>",
+            if *option == selected { " selected" } else { "" },
+            option
+        );
+    }
+    html.push_str("</select>");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[derive(Deserialize)]
+struct GuildForm {
+    announcement_channel_id: String,
+    alert_role_id: String,
+    language: String,
+    emoji_style: String,
+}
+
+async fn update_guild(
+    State(dashboard): State<Arc<Dashboard>>,
+    headers: HeaderMap,
+    Path(guild_id): Path<u64>,
+    Form(form): Form<GuildForm>,
+) -> Response {
+    let admin = match dashboard.admin(&headers) {
+        Some(admin) => admin,
+        None => return StatusCode::UNAUTHORIZED.into_response(),
+    };
+    let settings = GuildSettings {
+        announcement_channel_id: form.announcement_channel_id.trim().parse().ok(),
+        alert_role_id: form.alert_role_id.trim().parse().ok(),
+        language: match form.language.as_str() {
+            "Cebuano" => Language::Cebuano,
+            _ => Language::English,
+        },
+        emoji_style: match form.emoji_style.as_str() {
+            "Unicode" => EmojiStyle::Unicode,
+            _ => EmojiStyle::Custom,
+        },
+        // Command access, the role's mute, the digest and escalation aren't
+        // on the form, so they're kept as they were.
+        ..dashboard
+            .data
+            .guild_settings
+            .get(guild_id)
+            .unwrap_or_default()
+    };
+    if let Err(why) = dashboard.data.guild_settings.set(guild_id, settings) {
+        tracing::error!(
+            "Could not save the settings for guild {}: {:?}",
+            guild_id,
+            why
+        );
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    tracing::info!("User {} changed the settings for guild {}", admin, guild_id);
+    Redirect::to("/admin").into_response()
+}
+
+#[derive(Deserialize)]
+struct MaintenanceForm {
+    starts_at: String,
+    ends_at: String,
+    note: String,
+}
+
+// Parses the value of a datetime-local input, in PHT.
+fn parse_local_time(time: &str) -> Option<chrono::DateTime<Utc>> {
+    let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").ok()?;
+    let time = utc_plus_8().from_local_datetime(&time).single()?;
+    Some(time.with_timezone(&Utc))
+}
+
+async fn add_maintenance(
+    State(dashboard): State<Arc<Dashboard>>,
+    headers: HeaderMap,
+    Form(form): Form<MaintenanceForm>,
+) -> Response {
+    let admin = match dashboard.admin(&headers) {
+        Some(admin) => admin,
+        None => return StatusCode::UNAUTHORIZED.into_response(),
+    };
+    let window = match (
+        parse_local_time(&form.starts_at),
+        parse_local_time(&form.ends_at),
+    ) {
+        (Some(starts_at), Some(ends_at)) if starts_at < ends_at => MaintenanceWindow {
+            starts_at,
+            ends_at,
+            note: form.note,
+        },
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "The window has to end after it starts.",
+            )
+                .into_response()
+        }
+    };
+    let maintenance = &dashboard.data.maintenance;
+    let saved = maintenance
+        .remove_ended(Utc::now())
+        .and_then(|()| maintenance.add(window));
+    if let Err(why) = saved {
+        tracing::error!("Could not save the maintenance window: {:?}", why);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    tracing::info!("User {} declared a maintenance window", admin);
+    Redirect::to("/admin").into_response()
+}
+
+async fn delete_maintenance(
+    State(dashboard): State<Arc<Dashboard>>,
+    headers: HeaderMap,
+    Path(index): Path<usize>,
+) -> Response {
+    if dashboard.admin(&headers).is_none() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    if let Err(why) = dashboard.data.maintenance.remove(index) {
+        tracing::error!("Could not remove the maintenance window: {:?}", why);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    Redirect::to("/admin").into_response()
+}
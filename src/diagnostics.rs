@@ -0,0 +1,94 @@
+//! What each request of a check went over, for `&diagnose`. Protocol
+//! downgrades have shown up alongside SAIS's infrastructure problems before.
+
+use crate::{
+    transport::{ConnectionInfo, HttpRequest, HttpResponse, HttpTransport},
+    Error,
+};
+use async_trait::async_trait;
+use reqwest::{Method, StatusCode, Version};
+use std::{fmt, sync::Mutex};
+
+/// One request and the connection its response came over.
+#[derive(Debug, Clone)]
+pub struct RequestDetails {
+    pub method: Method,
+    pub url: String,
+    /// The error message when the request failed outright.
+    pub outcome: Result<ResponseDetails, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseDetails {
+    pub status: StatusCode,
+    pub version: Version,
+    pub connection: Option<ConnectionInfo>,
+}
+
+impl fmt::Display for RequestDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: ", self.method, self.url)?;
+        let response = match &self.outcome {
+            Ok(response) => response,
+            Err(why) => return write!(f, "failed ({})", why),
+        };
+        write!(
+            f,
+            "{} over {:?}",
+            response.status.as_u16(),
+            response.version
+        )?;
+        match response.connection {
+            Some(connection) => write!(
+                f,
+                ", {} connection to {}",
+                if connection.reused { "reused" } else { "new" },
+                connection.remote_addr
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Passes requests on to another transport, keeping the details of each.
+pub struct ConnectionLog<T> {
+    inner: T,
+    requests: Mutex<Vec<RequestDetails>>,
+}
+
+impl<T: HttpTransport> ConnectionLog<T> {
+    pub fn new(inner: T) -> Self {
+        ConnectionLog {
+            inner,
+            requests: Mutex::default(),
+        }
+    }
+
+    /// Every request sent so far, in order.
+    pub fn requests(&self) -> Vec<RequestDetails> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl<T: HttpTransport> HttpTransport for ConnectionLog<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let method = request.method.clone();
+        let url = request.url.clone();
+        let response = self.inner.send(request).await;
+        let outcome = match &response {
+            Ok(response) => Ok(ResponseDetails {
+                status: response.status,
+                version: response.version,
+                connection: response.connection,
+            }),
+            Err(why) => Err(why.to_string()),
+        };
+        self.requests.lock().unwrap().push(RequestDetails {
+            method,
+            url,
+            outcome,
+        });
+        response
+    }
+}
@@ -0,0 +1,101 @@
+//! Rollups of status changes, for people and servers that would rather not
+//! hear about each one as it happens.
+
+use crate::preferences::NotificationKind;
+use chrono::{DateTime, Duration, DurationRound, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt};
+
+// Digests are cut short past this, to fit in a Discord message.
+const MAX_DIGEST_CHARS: usize = 1900;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DigestPeriod {
+    /// At the top of every hour.
+    Hourly,
+    /// Every morning at 8.
+    Daily,
+}
+
+impl DigestPeriod {
+    pub const ALL: [DigestPeriod; 2] = [DigestPeriod::Hourly, DigestPeriod::Daily];
+
+    /// When the latest digest for this period was due, at or before `time`.
+    /// Daily digests are due at 08:00 PHT, which is midnight in UTC.
+    pub fn last_due(self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let period = match self {
+            DigestPeriod::Hourly => Duration::hours(1),
+            DigestPeriod::Daily => Duration::days(1),
+        };
+        time.duration_trunc(period).unwrap_or(time)
+    }
+}
+
+impl fmt::Display for DigestPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DigestPeriod::Hourly => "hourly",
+            DigestPeriod::Daily => "daily",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestEntry {
+    pub at: DateTime<Utc>,
+    pub kind: NotificationKind,
+    /// The announcement, without any pings.
+    pub text: String,
+}
+
+/// Every announcement from the last day, to roll up into digests.
+#[derive(Debug, Default)]
+pub struct DigestLog {
+    entries: Vec<DigestEntry>,
+}
+
+impl DigestLog {
+    /// Keeps an announcement, and forgets ones too old for any digest.
+    pub fn record(&mut self, at: DateTime<Utc>, kind: NotificationKind, text: String) {
+        self.entries
+            .retain(|entry| entry.at > at - Duration::days(2));
+        self.entries.push(DigestEntry { at, kind, text });
+    }
+
+    /// The announcements from `since` up to `until`, of the given kinds, or
+    /// of every kind.
+    pub fn between(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        kinds: Option<&BTreeSet<NotificationKind>>,
+    ) -> Vec<&DigestEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| since <= entry.at && entry.at < until)
+            .filter(|entry| kinds.is_none_or(|kinds| kinds.contains(&entry.kind)))
+            .collect()
+    }
+}
+
+/// A digest's message, or `None` if nothing happened.
+pub fn digest_message(since: DateTime<Utc>, entries: &[&DigestEntry]) -> Option<String> {
+    if entries.is_empty() {
+        return None;
+    }
+    let mut message = format!(
+        "What happened with UP SAIS since {} PHT:",
+        since
+            .with_timezone(&FixedOffset::east_opt(3600 * 8).unwrap())
+            .format("%b %-d %H:%M")
+    );
+    for (shown, entry) in entries.iter().enumerate() {
+        let line = format!("\n- {}", entry.text);
+        if message.len() + line.len() > MAX_DIGEST_CHARS {
+            message.push_str(&format!("\n...and {} more.", entries.len() - shown));
+            break;
+        }
+        message.push_str(&line);
+    }
+    Some(message)
+}
@@ -0,0 +1,166 @@
+//! Keeps the history in redb, a pure Rust embedded database, for small
+//! deployments that shouldn't need to build SQLite.
+
+use crate::{
+    history::{start_of_hour, CheckRecord, HistoryStore, HourlyRollup},
+    Error,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redb::{Database, ReadableTable, TableDefinition};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+// Keyed by when the check was made, in microseconds, and then by an ever
+// increasing ID so checks made at the same time don't overwrite each other.
+// Records are stored as JSON.
+const CHECKS: TableDefinition<(i64, u64), &str> = TableDefinition::new("checks");
+
+// Keyed by the start of the hour in microseconds, the route, and the status
+// kind, holding the number of checks and their total latency.
+const HOURLY_ROLLUPS: TableDefinition<(i64, &str, &str), (u64, u64)> =
+    TableDefinition::new("hourly_rollups");
+
+pub struct RedbHistory {
+    database: Database,
+    path: PathBuf,
+    next_id: AtomicU64,
+}
+
+impl RedbHistory {
+    /// Opens the history database, creating it and its directory if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<RedbHistory, Error> {
+        if let Some(directory) = path.as_ref().parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let database = Database::create(&path)?;
+
+        let transaction = database.begin_write()?;
+        transaction.open_table(HOURLY_ROLLUPS)?;
+        let next_id = {
+            let table = transaction.open_table(CHECKS)?;
+            let mut next_id = 0;
+            for entry in table.iter()? {
+                let (key, _) = entry?;
+                next_id = next_id.max(key.value().1 + 1);
+            }
+            next_id
+        };
+        transaction.commit()?;
+
+        Ok(RedbHistory {
+            database,
+            path: path.as_ref().to_path_buf(),
+            next_id: AtomicU64::new(next_id),
+        })
+    }
+}
+
+#[async_trait]
+impl HistoryStore for RedbHistory {
+    async fn record(&self, records: &[CheckRecord]) -> Result<(), Error> {
+        let transaction = self.database.begin_write()?;
+        {
+            let mut table = transaction.open_table(CHECKS)?;
+            for record in records {
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                let value = serde_json::to_string(record)?;
+                table.insert((record.checked_at.timestamp_micros(), id), value.as_str())?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn since(&self, since: DateTime<Utc>) -> Result<Vec<CheckRecord>, Error> {
+        let transaction = self.database.begin_read()?;
+        let table = transaction.open_table(CHECKS)?;
+        let mut records = vec![];
+        for entry in table.range((since.timestamp_micros(), 0)..)? {
+            let (_, value) = entry?;
+            records.push(serde_json::from_str(value.value())?);
+        }
+        Ok(records)
+    }
+
+    async fn compact(
+        &self,
+        raw_before: DateTime<Utc>,
+        rollups_before: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let transaction = self.database.begin_write()?;
+        {
+            let mut checks = transaction.open_table(CHECKS)?;
+            let mut rollups = transaction.open_table(HOURLY_ROLLUPS)?;
+            let compacted =
+                checks.extract_from_if(..(raw_before.timestamp_micros(), 0), |_, _| true)?;
+            for entry in compacted {
+                let (_, value) = entry?;
+                let record: CheckRecord = serde_json::from_str(value.value())?;
+                let key = (
+                    start_of_hour(record.checked_at).timestamp_micros(),
+                    record.route.as_str(),
+                    record.status.as_str(),
+                );
+                let (checks, total_latency_ms) = rollups
+                    .get(key)?
+                    .map(|value| value.value())
+                    .unwrap_or((0, 0));
+                rollups.insert(key, (checks + 1, total_latency_ms + record.latency_ms))?;
+            }
+            rollups.retain_in(..(rollups_before.timestamp_micros(), "", ""), |_, _| false)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn record_rollups(&self, rollups: &[HourlyRollup]) -> Result<(), Error> {
+        let transaction = self.database.begin_write()?;
+        {
+            let mut table = transaction.open_table(HOURLY_ROLLUPS)?;
+            for rollup in rollups {
+                let key = (
+                    rollup.hour.timestamp_micros(),
+                    rollup.route.as_str(),
+                    rollup.status.as_str(),
+                );
+                let (checks, total_latency_ms) =
+                    table.get(key)?.map(|value| value.value()).unwrap_or((0, 0));
+                table.insert(
+                    key,
+                    (
+                        checks + rollup.checks,
+                        total_latency_ms + rollup.total_latency_ms,
+                    ),
+                )?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn rollups_since(&self, since: DateTime<Utc>) -> Result<Vec<HourlyRollup>, Error> {
+        let transaction = self.database.begin_read()?;
+        let table = transaction.open_table(HOURLY_ROLLUPS)?;
+        let mut rollups = vec![];
+        for entry in table.range((since.timestamp_micros(), "", "")..)? {
+            let (key, value) = entry?;
+            let (hour, route, status) = key.value();
+            let (checks, total_latency_ms) = value.value();
+            rollups.push(HourlyRollup {
+                hour: DateTime::from_timestamp_micros(hour).ok_or("Invalid rollup hour")?,
+                route: route.to_string(),
+                status: status.to_string(),
+                checks,
+                total_latency_ms,
+            });
+        }
+        Ok(rollups)
+    }
+
+    async fn size_bytes(&self) -> Result<u64, Error> {
+        Ok(std::fs::metadata(&self.path)?.len())
+    }
+}
@@ -0,0 +1,138 @@
+//! Who else to tell as an outage drags on.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EscalationTarget {
+    /// The role pinged with announcements, in the announcement channel.
+    AlertRole,
+    /// Another role, pinged in the announcement channel.
+    Role(u64),
+    /// Sent a DM.
+    User(u64),
+    /// The bot's owner and `maintainer_ids`, or only whoever is on call when
+    /// there's a rotation. Only in discord.ron.
+    Maintainers,
+    /// The pager set in discord.ron. Only in discord.ron.
+    Pager,
+}
+
+impl EscalationTarget {
+    /// Whether servers may escalate to this, rather than only the bot's
+    /// own config.
+    pub fn allowed_in_guilds(&self) -> bool {
+        !matches!(
+            self,
+            EscalationTarget::Maintainers | EscalationTarget::Pager
+        )
+    }
+}
+
+/// Tells `target` once SAIS has been down for `after_mins`, written like
+/// "15m:role", "1h:<@&1234>" or "4h:<@5678>" in commands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscalationStep {
+    pub after_mins: u64,
+    pub target: EscalationTarget,
+}
+
+impl fmt::Display for EscalationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.after_mins.is_multiple_of(60) {
+            write!(f, "{}h:", self.after_mins / 60)?;
+        } else {
+            write!(f, "{}m:", self.after_mins)?;
+        }
+        match &self.target {
+            EscalationTarget::AlertRole => write!(f, "role"),
+            EscalationTarget::Role(role_id) => write!(f, "<@&{}>", role_id),
+            EscalationTarget::User(user_id) => write!(f, "<@{}>", user_id),
+            EscalationTarget::Maintainers => write!(f, "maintainers"),
+            EscalationTarget::Pager => write!(f, "pager"),
+        }
+    }
+}
+
+impl std::str::FromStr for EscalationStep {
+    type Err = String;
+
+    fn from_str(step: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} isn't an escalation step", step);
+        let (after, target) = step.split_once(':').ok_or_else(invalid)?;
+        let after = crate::history::parse_range(after.trim()).ok_or_else(invalid)?;
+        let target = target.trim();
+        let target = if target.eq_ignore_ascii_case("role") {
+            EscalationTarget::AlertRole
+        } else if target.eq_ignore_ascii_case("maintainers") {
+            EscalationTarget::Maintainers
+        } else if target.eq_ignore_ascii_case("pager") {
+            EscalationTarget::Pager
+        } else if let Some(role_id) = target
+            .strip_prefix("<@&")
+            .and_then(|target| target.strip_suffix('>'))
+        {
+            EscalationTarget::Role(role_id.parse().map_err(|_| invalid())?)
+        } else if let Some(user_id) = target
+            .strip_prefix("<@")
+            .and_then(|target| target.strip_suffix('>'))
+        {
+            EscalationTarget::User(
+                user_id
+                    .trim_start_matches('!')
+                    .parse()
+                    .map_err(|_| invalid())?,
+            )
+        } else {
+            return Err(invalid());
+        };
+        Ok(EscalationStep {
+            after_mins: after.num_minutes() as u64,
+            target,
+        })
+    }
+}
+
+/// The steps to take as an outage goes on, each once per outage.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscalationPolicy {
+    pub steps: Vec<EscalationStep>,
+}
+
+/// Which steps each policy has taken during the current outage. Policies are
+/// told apart by the server they're from, with `None` for discord.ron's.
+#[derive(Debug, Default)]
+pub struct EscalationTracker {
+    taken: BTreeMap<Option<u64>, BTreeSet<usize>>,
+}
+
+impl EscalationTracker {
+    /// The steps of `policy` that are due at `now` for an outage since
+    /// `since`, and haven't been taken yet. They count as taken from here.
+    pub fn due<'a>(
+        &mut self,
+        key: Option<u64>,
+        policy: &'a EscalationPolicy,
+        since: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Vec<&'a EscalationStep> {
+        let taken = self.taken.entry(key).or_default();
+        policy
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| now - since >= Duration::minutes(step.after_mins as i64))
+            .filter(|(index, _)| taken.insert(*index))
+            .map(|(_, step)| step)
+            .collect()
+    }
+
+    /// Starts afresh for the next outage.
+    pub fn reset(&mut self) {
+        self.taken.clear();
+    }
+}
@@ -0,0 +1,16 @@
+//! Somewhere every check result is sent as it happens, for operators who
+//! already collect metrics in their own systems.
+
+use crate::{routes::RouteResult, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+#[async_trait]
+pub trait CheckExporter: Send + Sync {
+    /// Every route's result from one check, plus the TCP probe's if it ran.
+    async fn export(
+        &self,
+        checked_at: DateTime<Utc>,
+        route_results: &[RouteResult],
+    ) -> Result<(), Error>;
+}
@@ -0,0 +1,246 @@
+//! Recurring problems mined from the history, like blips at the same hour
+//! most nights or meltdowns during enrollment, and a rough guess from them
+//! at how likely SAIS is to have problems in the next day.
+
+use crate::{
+    calendar::{CalendarConfig, Profile},
+    history::CheckRecord,
+    routes::MAIN_ROUTE_NAME,
+    sla::{counts, failed_check},
+};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Timelike, Utc};
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// How far back the history is mined. Older checks are rolled up by
+/// default, and don't say when within the hour they failed.
+pub const LOOKBACK_DAYS: i64 = 30;
+
+/// How often the history is mined again.
+pub const MINE_EVERY: std::time::Duration = std::time::Duration::from_secs(6 * 60 * 60);
+
+/// An hour needs problems on at least this many days to count as a pattern.
+const MIN_RECURRING_DAYS: u32 = 3;
+
+/// An hour also needs problems on at least this share of the days it was
+/// checked on.
+const MIN_RECURRING_SHARE: f64 = 0.25;
+
+fn utc_plus_8() -> FixedOffset {
+    FixedOffset::east_opt(3600 * 8).unwrap()
+}
+
+/// Days with a failed check, out of the days with any.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DayCount {
+    pub failed: u32,
+    pub checked: u32,
+}
+
+impl DayCount {
+    /// The share of days with problems, evened out a little so a handful of
+    /// days can't make it 0% or 100%.
+    pub fn rate(&self) -> f64 {
+        (self.failed as f64 + 1.0) / (self.checked as f64 + 2.0)
+    }
+}
+
+/// An hour of the day (PHT) SAIS keeps having problems in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecurringHour {
+    pub hour: u32,
+    pub days: DayCount,
+}
+
+/// What the history says about when SAIS has problems.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patterns {
+    /// By hour of the day (PHT).
+    pub hours: [DayCount; 24],
+    /// Days in the calendar's aggressive periods, like enrollment.
+    pub busy_days: DayCount,
+    pub other_days: DayCount,
+    pub mined_at: DateTime<Utc>,
+}
+
+impl Patterns {
+    /// Mines the main route's `records`, with `calendar` telling which days
+    /// were busy.
+    pub fn mine(
+        records: &[CheckRecord],
+        calendar: &CalendarConfig,
+        mined_at: DateTime<Utc>,
+    ) -> Patterns {
+        // Whether each day, and each hour of each day, had a failed check.
+        let mut days = BTreeMap::<NaiveDate, bool>::new();
+        let mut hours = BTreeMap::<(NaiveDate, u32), bool>::new();
+        for record in records {
+            if record.route != MAIN_ROUTE_NAME || !counts(&record.status) {
+                continue;
+            }
+            let checked_at = record.checked_at.with_timezone(&utc_plus_8());
+            let failed = failed_check(&record.status);
+            *days.entry(checked_at.date_naive()).or_default() |= failed;
+            *hours
+                .entry((checked_at.date_naive(), checked_at.hour()))
+                .or_default() |= failed;
+        }
+
+        let mut patterns = Patterns {
+            hours: [DayCount::default(); 24],
+            busy_days: DayCount::default(),
+            other_days: DayCount::default(),
+            mined_at,
+        };
+        for ((_, hour), failed) in hours {
+            let count = &mut patterns.hours[hour as usize];
+            count.failed += failed as u32;
+            count.checked += 1;
+        }
+        for (date, failed) in days {
+            let count = match calendar.profile_on(date) {
+                Profile::Aggressive => &mut patterns.busy_days,
+                Profile::Relaxed => &mut patterns.other_days,
+            };
+            count.failed += failed as u32;
+            count.checked += 1;
+        }
+        patterns
+    }
+
+    /// Days the main route was checked on.
+    pub fn days_checked(&self) -> u32 {
+        self.busy_days.checked + self.other_days.checked
+    }
+
+    /// The hours SAIS keeps having problems in, the most often first.
+    pub fn recurring(&self) -> Vec<RecurringHour> {
+        let mut recurring = self
+            .hours
+            .iter()
+            .enumerate()
+            .filter(|(_, days)| {
+                days.failed >= MIN_RECURRING_DAYS
+                    && days.failed as f64 >= MIN_RECURRING_SHARE * days.checked as f64
+            })
+            .map(|(hour, days)| RecurringHour {
+                hour: hour as u32,
+                days: *days,
+            })
+            .collect::<Vec<_>>();
+        recurring.sort_by(|a, b| b.days.rate().total_cmp(&a.days.rate()));
+        recurring
+    }
+
+    /// How likely problems are in the 24 hours from `now`, going by how
+    /// often days like those had them.
+    pub fn forecast(&self, calendar: &CalendarConfig, now: DateTime<Utc>) -> Forecast {
+        let today = now.with_timezone(&utc_plus_8()).date_naive();
+        let tomorrow = (now + Duration::hours(24))
+            .with_timezone(&utc_plus_8())
+            .date_naive();
+        let busy_period = [today, tomorrow]
+            .iter()
+            .filter_map(|date| calendar.period_on(*date))
+            .find(|period| period.profile == Profile::Aggressive);
+
+        let when = busy_period.map(|period| {
+            if period.contains(today) {
+                format!("It's {}", period.name)
+            } else {
+                format!("{} starts tomorrow", period.name)
+            }
+        });
+
+        let mut reasons = vec![];
+        let likelihood = match busy_period {
+            Some(_) if self.busy_days.checked > 0 => {
+                reasons.push(format!(
+                    "{}, and SAIS had problems on {} of the {} busy days checked, against {} of the other {}.",
+                    when.unwrap_or_default(),
+                    self.busy_days.failed,
+                    self.busy_days.checked,
+                    self.other_days.failed,
+                    self.other_days.checked
+                ));
+                self.busy_days.rate()
+            }
+            _ => {
+                let days = DayCount {
+                    failed: self.busy_days.failed + self.other_days.failed,
+                    checked: self.busy_days.checked + self.other_days.checked,
+                };
+                if let Some(when) = when {
+                    reasons.push(format!(
+                        "{}, but there's no history from busy days yet to go by.",
+                        when
+                    ));
+                }
+                reasons.push(format!(
+                    "SAIS had problems on {} of the {} days checked.",
+                    days.failed, days.checked
+                ));
+                days.rate()
+            }
+        };
+        for recurring in self.recurring().into_iter().take(3) {
+            reasons.push(format!(
+                "Problems between {} and {} on {} of the {} days checked then.",
+                hour_string(recurring.hour),
+                hour_string((recurring.hour + 1) % 24),
+                recurring.days.failed,
+                recurring.days.checked
+            ));
+        }
+        Forecast {
+            likelihood,
+            reasons,
+        }
+    }
+}
+
+/// A rough guess at the chance of problems, with what it's based on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    /// From 0 to 1.
+    pub likelihood: f64,
+    pub reasons: Vec<String>,
+}
+
+impl Forecast {
+    pub fn level(&self) -> &'static str {
+        if self.likelihood < 0.2 {
+            "low"
+        } else if self.likelihood < 0.5 {
+            "moderate"
+        } else {
+            "high"
+        }
+    }
+}
+
+/// Like 2 AM or 11 PM.
+pub fn hour_string(hour: u32) -> String {
+    let suffix = if hour < 12 { "AM" } else { "PM" };
+    let hour = match hour % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    format!("{} {}", hour, suffix)
+}
+
+/// The latest patterns mined, shared with `&forecast`.
+#[derive(Debug, Default)]
+pub struct PatternWatch {
+    patterns: Mutex<Option<Patterns>>,
+}
+
+impl PatternWatch {
+    /// `None` until the history is first mined.
+    pub fn patterns(&self) -> Option<Patterns> {
+        self.patterns.lock().unwrap().clone()
+    }
+
+    pub fn mined(&self, patterns: Patterns) {
+        *self.patterns.lock().unwrap() = Some(patterns);
+    }
+}
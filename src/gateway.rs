@@ -0,0 +1,75 @@
+//! How long the bot has been cut off from the Discord gateway, so that
+//! announcements made meanwhile can wait for it to come back, and checks made
+//! meanwhile aren't trusted.
+
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Mutex;
+
+/// An announcement held back while the gateway was down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedAnnouncement {
+    pub channel_id: u64,
+    pub content: String,
+}
+
+/// A disconnection that lasted longer than the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl Gap {
+    pub fn duration(&self) -> Duration {
+        self.to - self.from
+    }
+}
+
+#[derive(Debug)]
+pub struct GatewayWatch {
+    threshold: Duration,
+    disconnected_at: Mutex<Option<DateTime<Utc>>>,
+    queued: Mutex<Vec<QueuedAnnouncement>>,
+}
+
+impl GatewayWatch {
+    /// Disconnections count once they last longer than `threshold`; shorter
+    /// ones are just the gateway reconnecting as usual.
+    pub fn new(threshold: Duration) -> GatewayWatch {
+        GatewayWatch {
+            threshold,
+            disconnected_at: Mutex::default(),
+            queued: Mutex::default(),
+        }
+    }
+
+    pub fn disconnected(&self, now: DateTime<Utc>) {
+        self.disconnected_at.lock().unwrap().get_or_insert(now);
+    }
+
+    /// Returns the gap, if the bot was disconnected for longer than the
+    /// threshold.
+    pub fn connected(&self, now: DateTime<Utc>) -> Option<Gap> {
+        let from = self.disconnected_at.lock().unwrap().take()?;
+        let gap = Gap { from, to: now };
+        (gap.duration() > self.threshold).then_some(gap)
+    }
+
+    /// Whether the bot has been disconnected for longer than the threshold
+    /// as of `now`.
+    pub fn is_cut_off(&self, now: DateTime<Utc>) -> bool {
+        self.disconnected_at
+            .lock()
+            .unwrap()
+            .is_some_and(|from| now - from > self.threshold)
+    }
+
+    pub fn queue(&self, announcement: QueuedAnnouncement) {
+        self.queued.lock().unwrap().push(announcement);
+    }
+
+    /// Everything queued so far, oldest first.
+    pub fn take_queued(&self) -> Vec<QueuedAnnouncement> {
+        std::mem::take(&mut *self.queued.lock().unwrap())
+    }
+}
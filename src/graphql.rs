@@ -0,0 +1,121 @@
+//! A GraphQL API over the status history, for students building on top of
+//! the bot's data.
+
+use crate::{
+    history::HistoryStore,
+    report::{self, ServiceSummary},
+    status::ServiceStatus,
+};
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use chrono::{DateTime, Utc};
+use std::{collections::BTreeSet, sync::Arc};
+use tokio::sync::watch;
+
+pub type StatusSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// What the resolvers read from the bot.
+pub struct GraphqlData {
+    pub history: Option<Arc<dyn HistoryStore>>,
+    /// The monitor's confirmed status, `None` until its first check.
+    pub statuses: watch::Receiver<Option<ServiceStatus>>,
+}
+
+pub fn schema(data: GraphqlData) -> StatusSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(data)
+        .finish()
+}
+
+#[derive(SimpleObject)]
+pub struct Status {
+    /// See `ServiceStatus::kind`, e.g. "down".
+    kind: String,
+    /// Why SAIS counted as down, e.g. "http_503".
+    reason: Option<String>,
+    description: String,
+}
+
+#[derive(SimpleObject)]
+pub struct Outage {
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    duration_secs: i64,
+    cause: String,
+}
+
+#[derive(SimpleObject)]
+pub struct ServiceUptime {
+    /// The route SAIS was checked through, like "direct".
+    service: String,
+    /// Null without any checks that count.
+    uptime_percent: Option<f64>,
+    median_latency_ms: Option<u64>,
+    latest_status: Option<String>,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// SAIS's confirmed status, or null before the first check.
+    async fn latest_status(&self, ctx: &Context<'_>) -> Option<Status> {
+        let status = (*ctx.data_unchecked::<GraphqlData>().statuses.borrow())?;
+        Some(Status {
+            kind: status.kind().to_string(),
+            reason: status.reason(),
+            description: status.to_string(),
+        })
+    }
+
+    /// Outages that started between `from` and `to`.
+    async fn outages(
+        &self,
+        ctx: &Context<'_>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> async_graphql::Result<Vec<Outage>> {
+        let records = history(ctx)?.since(from).await?;
+        Ok(report::outages(&records)
+            .into_iter()
+            .filter(|outage| outage.started_at < to)
+            .map(|outage| Outage {
+                duration_secs: outage.duration().num_seconds(),
+                started_at: outage.started_at,
+                ended_at: outage.ended_at,
+                cause: outage.cause,
+            })
+            .collect())
+    }
+
+    /// The uptime of each route since `since`.
+    async fn uptime_by_service(
+        &self,
+        ctx: &Context<'_>,
+        since: DateTime<Utc>,
+    ) -> async_graphql::Result<Vec<ServiceUptime>> {
+        let records = history(ctx)?.since(since).await?;
+        let routes = records
+            .iter()
+            .map(|record| record.route.as_str())
+            .collect::<BTreeSet<_>>();
+        Ok(routes
+            .into_iter()
+            .map(|route| {
+                let summary = ServiceSummary::for_route(&records, route);
+                ServiceUptime {
+                    service: route.to_string(),
+                    uptime_percent: summary.uptime_percent,
+                    median_latency_ms: summary.median_latency_ms,
+                    latest_status: summary.latest_status,
+                }
+            })
+            .collect())
+    }
+}
+
+fn history<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<dyn HistoryStore>> {
+    ctx.data_unchecked::<GraphqlData>()
+        .history
+        .as_ref()
+        .ok_or_else(|| "The history is turned off".into())
+}
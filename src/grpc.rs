@@ -0,0 +1,79 @@
+//! A gRPC service for other bots and services to follow SAIS's status
+//! with, as described in `proto/status.proto`.
+
+use crate::{status::ServiceStatus, Error};
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use std::{net::SocketAddr, pin::Pin};
+use tokio::{net::TcpListener, sync::watch};
+use tokio_stream::wrappers::{TcpListenerStream, WatchStream};
+use tonic::{transport::Server, Request, Response};
+
+/// The code generated from `proto/status.proto`.
+pub mod proto {
+    tonic::include_proto!("sais.status.v1");
+}
+
+use proto::{
+    status_server::{Status, StatusServer},
+    GetStatusRequest, StatusUpdate, StreamStatusChangesRequest,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrpcConfig {
+    /// Where to listen, e.g. "127.0.0.1:50051".
+    pub address: SocketAddr,
+}
+
+impl From<ServiceStatus> for StatusUpdate {
+    fn from(status: ServiceStatus) -> Self {
+        StatusUpdate {
+            kind: status.kind().to_string(),
+            reason: status.reason(),
+            description: status.to_string(),
+        }
+    }
+}
+
+pub struct StatusService {
+    /// The monitor's confirmed status, `None` until its first check.
+    pub statuses: watch::Receiver<Option<ServiceStatus>>,
+}
+
+#[tonic::async_trait]
+impl Status for StatusService {
+    async fn get_status(
+        &self,
+        _request: Request<GetStatusRequest>,
+    ) -> Result<Response<StatusUpdate>, tonic::Status> {
+        match *self.statuses.borrow() {
+            Some(status) => Ok(Response::new(status.into())),
+            None => Err(tonic::Status::unavailable("SAIS hasn't been checked yet")),
+        }
+    }
+
+    type StreamStatusChangesStream =
+        Pin<Box<dyn Stream<Item = Result<StatusUpdate, tonic::Status>> + Send>>;
+
+    async fn stream_status_changes(
+        &self,
+        _request: Request<StreamStatusChangesRequest>,
+    ) -> Result<Response<Self::StreamStatusChangesStream>, tonic::Status> {
+        let updates = WatchStream::new(self.statuses.clone())
+            .filter_map(|status| async move { status.map(StatusUpdate::from) })
+            .map(Ok);
+        Ok(Response::new(Box::pin(updates)))
+    }
+}
+
+/// Serves until the bot exits.
+pub async fn serve(
+    listener: TcpListener,
+    statuses: watch::Receiver<Option<ServiceStatus>>,
+) -> Result<(), Error> {
+    Server::builder()
+        .add_service(StatusServer::new(StatusService { statuses }))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//! Settings each Discord server chooses for itself with `/setup` and
+//! `/access`, kept in a file the bot writes.
+
+use crate::{digest::DigestPeriod, escalation::EscalationPolicy, Error};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub const GUILD_SETTINGS_FILEPATH: &str = "data/guilds.ron";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Cebuano,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmojiStyle {
+    /// The custom emojis from the configured server.
+    #[default]
+    Custom,
+    /// Standard emojis, for servers that would rather not see custom ones.
+    Unicode,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuildSettings {
+    /// Status changes are announced here, when set.
+    #[serde(default)]
+    pub announcement_channel_id: Option<u64>,
+    /// Pinged with every announcement, when set.
+    #[serde(default)]
+    pub alert_role_id: Option<u64>,
+    /// The role isn't pinged until then, set with `&mute-alerts`.
+    #[serde(default)]
+    pub role_muted_until: Option<DateTime<Utc>>,
+    /// Changes are rolled up and posted this often instead, when set.
+    #[serde(default)]
+    pub digest: Option<DigestPeriod>,
+    /// Who else to tell as an outage goes on, set with `&escalation`.
+    #[serde(default)]
+    pub escalation: EscalationPolicy,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub emoji_style: EmojiStyle,
+    /// Who may use each command, by name, e.g. "diagnose" or "config set".
+    /// Commands without rules can be used by anyone their own checks allow.
+    #[serde(default)]
+    pub command_access: BTreeMap<String, Vec<Access>>,
+}
+
+impl GuildSettings {
+    /// The role to ping with announcements at `time`, unless it's muted.
+    pub fn alert_role(&self, time: DateTime<Utc>) -> Option<u64> {
+        match self.role_muted_until {
+            Some(until) if time < until => None,
+            _ => self.alert_role_id,
+        }
+    }
+
+    /// Whether `caller` may use `command` here, going by the rules for it or
+    /// else for its parent command. The bot's owners always may.
+    pub fn allows(&self, command: &str, caller: &Caller) -> bool {
+        let parent = command.split(' ').next().unwrap_or(command);
+        let rules = match self
+            .command_access
+            .get(command)
+            .or_else(|| self.command_access.get(parent))
+        {
+            Some(rules) => rules,
+            None => return true,
+        };
+        caller.is_owner || rules.iter().any(|rule| rule.allows(caller))
+    }
+}
+
+/// A rule for who may use a command, written like "everyone", "owner",
+/// "role:Techs" (by name or ID), "user:1234" or "permission:MANAGE_GUILD".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Access {
+    Everyone,
+    /// The bot's owners.
+    Owner,
+    Role(String),
+    User(u64),
+    /// A Discord permission, named as in the API, e.g. "MANAGE_MESSAGES".
+    Permission(String),
+}
+
+impl Access {
+    pub fn allows(&self, caller: &Caller) -> bool {
+        match self {
+            Access::Everyone => true,
+            Access::Owner => caller.is_owner,
+            Access::Role(role) => caller
+                .roles
+                .iter()
+                .any(|(id, name)| id.to_string() == *role || name.eq_ignore_ascii_case(role)),
+            Access::User(user_id) => caller.user_id == *user_id,
+            Access::Permission(permission) => caller
+                .permissions
+                .iter()
+                .any(|name| name == permission || name == "ADMINISTRATOR"),
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Everyone => write!(f, "everyone"),
+            Access::Owner => write!(f, "owner"),
+            Access::Role(role) => write!(f, "role:{}", role),
+            Access::User(user_id) => write!(f, "user:{}", user_id),
+            Access::Permission(permission) => write!(f, "permission:{}", permission),
+        }
+    }
+}
+
+impl std::str::FromStr for Access {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} isn't a rule", rule);
+        let (kind, value) = match rule.split_once(':') {
+            Some((kind, value)) => (kind, Some(value.trim())),
+            None => (rule, None),
+        };
+        match (kind.trim().to_ascii_lowercase().as_str(), value) {
+            ("everyone", None) => Ok(Access::Everyone),
+            ("owner", None) => Ok(Access::Owner),
+            ("role", Some(role)) if !role.is_empty() => Ok(Access::Role(
+                role.trim_start_matches("<@&")
+                    .trim_end_matches('>')
+                    .to_string(),
+            )),
+            ("user", Some(user_id)) => user_id
+                .trim_start_matches("<@")
+                .trim_start_matches('!')
+                .trim_end_matches('>')
+                .parse()
+                .map(Access::User)
+                .map_err(|_| invalid()),
+            ("permission", Some(permission)) if !permission.is_empty() => Ok(Access::Permission(
+                permission.to_ascii_uppercase().replace(' ', "_"),
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for Access {
+    type Error = String;
+
+    fn try_from(rule: String) -> Result<Self, Self::Error> {
+        rule.parse()
+    }
+}
+
+impl From<Access> for String {
+    fn from(access: Access) -> Self {
+        access.to_string()
+    }
+}
+
+/// Who is using a command, to check against [`Access`] rules.
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    pub user_id: u64,
+    pub is_owner: bool,
+    /// Their roles in the server, by ID and name.
+    pub roles: Vec<(u64, String)>,
+    /// Their permissions in the server, named as in the API.
+    pub permissions: Vec<String>,
+}
+
+/// Every server's settings by guild ID, saved to disk whenever one changes.
+#[derive(Debug)]
+pub struct GuildSettingsStore {
+    path: PathBuf,
+    guilds: Mutex<BTreeMap<u64, GuildSettings>>,
+}
+
+impl GuildSettingsStore {
+    /// Loads the settings, starting with none if the file doesn't exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<GuildSettingsStore, Error> {
+        let path = path.as_ref().to_path_buf();
+        let guilds = match fs::read_to_string(&path) {
+            Ok(contents) => ron::de::from_str(&contents)?,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(why) => return Err(why.into()),
+        };
+        Ok(GuildSettingsStore {
+            path,
+            guilds: Mutex::new(guilds),
+        })
+    }
+
+    pub fn get(&self, guild_id: u64) -> Option<GuildSettings> {
+        self.guilds.lock().unwrap().get(&guild_id).cloned()
+    }
+
+    pub fn all(&self) -> Vec<(u64, GuildSettings)> {
+        let guilds = self.guilds.lock().unwrap();
+        guilds
+            .iter()
+            .map(|(guild_id, settings)| (*guild_id, settings.clone()))
+            .collect()
+    }
+
+    pub fn set(&self, guild_id: u64, settings: GuildSettings) -> Result<(), Error> {
+        let mut guilds = self.guilds.lock().unwrap();
+        guilds.insert(guild_id, settings);
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let contents = ron::ser::to_string_pretty(&*guilds, ron::ser::PrettyConfig::default())?;
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
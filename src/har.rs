@@ -0,0 +1,324 @@
+//! Everything one check sent and got back, as an HTTP Archive (HAR) file
+//! that browsers' developer tools can open, for debugging odd SAIS behavior
+//! offline.
+
+use crate::{
+    capture::redact_cookie_value,
+    redact::{self, REDACTED},
+    transport::{HttpRequest, HttpResponse, HttpTransport},
+    Error,
+};
+use async_trait::async_trait;
+use chrono::prelude::*;
+use reqwest::{
+    header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE},
+    Version,
+};
+use serde::Serialize;
+use std::{sync::Mutex, time::Instant};
+
+/// Response bodies longer than this many bytes are cut short.
+pub const BODY_LIMIT: usize = 64 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HarLog {
+    pub version: &'static str,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HarCreator {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: DateTime<Utc>,
+    /// In milliseconds.
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub timings: HarTimings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    pub server_ip_address: Option<String>,
+    /// Why the request failed outright, when it did.
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub headers: Vec<HarNameValue>,
+    pub query_string: Vec<HarNameValue>,
+    pub cookies: Vec<HarNameValue>,
+    pub headers_size: i64,
+    pub body_size: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: &'static str,
+    pub params: Vec<HarNameValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    /// 0 when the request failed outright.
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub headers: Vec<HarNameValue>,
+    pub cookies: Vec<HarNameValue>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    /// The length of the whole body, even when `text` was cut short.
+    pub size: usize,
+    pub mime_type: String,
+    pub text: String,
+}
+
+/// Only how long the whole exchange took is known, so it's all `wait`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HarTimings {
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HarNameValue {
+    pub name: String,
+    pub value: String,
+}
+
+/// Passes requests on to another transport, keeping each exchange for the
+/// archive.
+pub struct HarRecorder<T> {
+    inner: T,
+    secrets: Vec<String>,
+    entries: Mutex<Vec<HarEntry>>,
+}
+
+impl<T: HttpTransport> HarRecorder<T> {
+    /// Every occurrence of `secrets` is redacted from the archive, as are
+    /// cookie values and `Authorization` headers.
+    pub fn new(inner: T, secrets: Vec<String>) -> Self {
+        HarRecorder {
+            inner,
+            secrets: secrets.into_iter().filter(|s| !s.is_empty()).collect(),
+            entries: Mutex::default(),
+        }
+    }
+
+    pub fn har(&self) -> Har {
+        Har {
+            log: HarLog {
+                version: "1.2",
+                creator: HarCreator {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+                entries: self.entries.lock().unwrap().clone(),
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self.har())?)
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let text = self.secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        });
+        redact::redact(&text)
+    }
+
+    fn headers(&self, headers: &HeaderMap) -> Vec<HarNameValue> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes());
+                let value = if name == SET_COOKIE {
+                    redact_cookie_value(&value)
+                } else if name == COOKIE {
+                    value
+                        .split("; ")
+                        .map(redact_cookie_value)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                } else if name == AUTHORIZATION {
+                    REDACTED.to_string()
+                } else {
+                    self.redact(&value)
+                };
+                HarNameValue {
+                    name: name.to_string(),
+                    value,
+                }
+            })
+            .collect()
+    }
+
+    fn request(&self, request: &HttpRequest) -> HarRequest {
+        let url = reqwest::Url::parse(&request.url).ok();
+        let params = request
+            .form
+            .iter()
+            .map(|(name, value)| HarNameValue {
+                name: name.clone(),
+                value: self.redact(value),
+            })
+            .collect::<Vec<_>>();
+        HarRequest {
+            method: request.method.to_string(),
+            url: self.redact(&request.url),
+            http_version: http_version(Version::HTTP_11),
+            headers: self.headers(&request.headers),
+            query_string: url
+                .iter()
+                .flat_map(|url| url.query_pairs())
+                .map(|(name, value)| HarNameValue {
+                    name: name.into_owned(),
+                    value: self.redact(&value),
+                })
+                .collect(),
+            cookies: vec![],
+            headers_size: -1,
+            body_size: if params.is_empty() { 0 } else { -1 },
+            post_data: (!params.is_empty()).then_some(HarPostData {
+                mime_type: "application/x-www-form-urlencoded",
+                params,
+            }),
+        }
+    }
+
+    fn response(&self, response: &Result<HttpResponse, Error>) -> HarResponse {
+        let response = match response {
+            Ok(response) => response,
+            Err(_) => {
+                return HarResponse {
+                    status: 0,
+                    status_text: String::new(),
+                    http_version: String::new(),
+                    headers: vec![],
+                    cookies: vec![],
+                    content: HarContent {
+                        size: 0,
+                        mime_type: String::new(),
+                        text: String::new(),
+                    },
+                    redirect_url: String::new(),
+                    headers_size: -1,
+                    body_size: -1,
+                }
+            }
+        };
+        let header = |name| {
+            response
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+        };
+        HarResponse {
+            status: response.status.as_u16(),
+            status_text: response
+                .status
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            http_version: http_version(response.version),
+            headers: self.headers(&response.headers),
+            cookies: vec![],
+            content: HarContent {
+                size: response.body.len(),
+                mime_type: header(CONTENT_TYPE).to_string(),
+                text: truncate(&self.redact(&response.body), BODY_LIMIT),
+            },
+            redirect_url: self.redact(header(LOCATION)),
+            headers_size: -1,
+            body_size: response.body.len() as i64,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: HttpTransport> HttpTransport for HarRecorder<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let started_date_time = Utc::now();
+        let har_request = self.request(&request);
+        let start = Instant::now();
+        let response = self.inner.send(request).await;
+        let time = start.elapsed().as_secs_f64() * 1000.0;
+
+        let entry = HarEntry {
+            started_date_time,
+            time,
+            request: har_request,
+            response: self.response(&response),
+            timings: HarTimings {
+                send: 0.0,
+                wait: time,
+                receive: 0.0,
+            },
+            server_ip_address: response
+                .as_ref()
+                .ok()
+                .and_then(|response| Some(response.connection?.remote_addr.ip().to_string())),
+            error: response
+                .as_ref()
+                .err()
+                .map(|why| self.redact(&why.to_string())),
+        };
+        self.entries.lock().unwrap().push(entry);
+        response
+    }
+}
+
+fn http_version(version: Version) -> String {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_2 => "HTTP/2",
+        Version::HTTP_3 => "HTTP/3",
+        _ => "HTTP/1.1",
+    }
+    .to_string()
+}
+
+/// Cuts `body` short after `limit` bytes, saying so.
+pub(crate) fn truncate(body: &str, limit: usize) -> String {
+    if body.len() <= limit {
+        return body.to_string();
+    }
+    let mut end = limit;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n[truncated]", &body[..end])
+}
@@ -0,0 +1,56 @@
+//! Whether the bot is doing its job, for orchestrators deciding when to
+//! send it traffic or restart it.
+
+use crate::monitor::MonitorHealth;
+use chrono::{DateTime, Utc};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Ready means connected to the Discord gateway, and having run a round of
+/// checks within the last two monitor intervals.
+#[derive(Debug)]
+pub struct Readiness {
+    monitor_health: Arc<MonitorHealth>,
+    monitor_interval: Duration,
+    // Without a gateway, as in webhook-only mode, this stays true.
+    gateway_connected: AtomicBool,
+}
+
+impl Readiness {
+    pub fn new(
+        monitor_health: Arc<MonitorHealth>,
+        monitor_interval: Duration,
+        uses_gateway: bool,
+    ) -> Readiness {
+        Readiness {
+            monitor_health,
+            monitor_interval,
+            gateway_connected: AtomicBool::new(!uses_gateway),
+        }
+    }
+
+    pub fn set_gateway_connected(&self, connected: bool) {
+        self.gateway_connected.store(connected, Ordering::Relaxed);
+    }
+
+    /// Why the bot isn't ready at `now`, or `None` if it is.
+    pub fn not_ready_because(&self, now: DateTime<Utc>) -> Option<&'static str> {
+        if !self.gateway_connected.load(Ordering::Relaxed) {
+            return Some("not connected to the Discord gateway");
+        }
+        let last_cycle_at = match self.monitor_health.snapshot().last_cycle_at {
+            Some(last_cycle_at) => last_cycle_at,
+            None => return Some("the monitor hasn't run yet"),
+        };
+        let max_age = chrono::Duration::from_std(self.monitor_interval * 2).ok();
+        if max_age.is_some_and(|max_age| now - last_cycle_at > max_age) {
+            return Some("the monitor is stuck");
+        }
+        None
+    }
+}
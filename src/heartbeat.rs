@@ -0,0 +1,52 @@
+//! Progress reports from long-running tasks, so that one stuck on an await
+//! that never finishes can be told apart from one that is just idle.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Shared between a task, which beats whenever it makes progress or is
+/// idle, and whatever watches it.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    name: &'static str,
+    threshold: Duration,
+    last_beat: Arc<Mutex<Instant>>,
+}
+
+impl Heartbeat {
+    /// `name` is for logs. The task counts as stuck once it goes
+    /// `threshold` without a beat.
+    pub fn new(name: &'static str, threshold: Duration) -> Heartbeat {
+        Heartbeat {
+            name,
+            threshold,
+            last_beat: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    pub fn beat(&self) {
+        self.beat_at(Instant::now());
+    }
+
+    pub fn beat_at(&self, now: Instant) {
+        *self.last_beat.lock().unwrap() = now;
+    }
+
+    pub fn silent_for(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(*self.last_beat.lock().unwrap())
+    }
+
+    pub fn is_stuck(&self, now: Instant) -> bool {
+        self.silent_for(now) > self.threshold
+    }
+}
@@ -0,0 +1,99 @@
+//! When SAIS tends to be down, by hour of the day and day of the week.
+
+use crate::{
+    history::{CheckRecord, HourlyRollup},
+    routes::MAIN_ROUTE_NAME,
+    sla::{counts, failed_check},
+    Error,
+};
+use chrono::{Datelike, FixedOffset, Timelike};
+use image::{ImageOutputFormat, RgbImage};
+use plotters::prelude::*;
+use std::io::Cursor;
+
+// Each hour of each day is drawn as a square this many pixels wide.
+const CELL_SIZE: u32 = 24;
+
+/// The main route's failed and counted checks, by day of the week (Monday
+/// first) and hour of the day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutageHeatmap {
+    pub cells: [[(u64, u64); 24]; 7],
+}
+
+impl OutageHeatmap {
+    /// Buckets `records` by their time in `timezone`.
+    pub fn from_records(records: &[CheckRecord], timezone: FixedOffset) -> OutageHeatmap {
+        let mut cells = [[(0, 0); 24]; 7];
+        for record in records {
+            if record.route != MAIN_ROUTE_NAME || !counts(&record.status) {
+                continue;
+            }
+            let checked_at = record.checked_at.with_timezone(&timezone);
+            let weekday = checked_at.weekday().num_days_from_monday() as usize;
+            let (failed, total) = &mut cells[weekday][checked_at.hour() as usize];
+            *failed += failed_check(&record.status) as u64;
+            *total += 1;
+        }
+        OutageHeatmap { cells }
+    }
+
+    /// Adds checks that were compacted into `rollups`, bucketed by their
+    /// hour in `timezone`.
+    pub fn with_rollups(mut self, rollups: &[HourlyRollup], timezone: FixedOffset) -> Self {
+        for rollup in rollups {
+            if rollup.route != MAIN_ROUTE_NAME || !counts(&rollup.status) {
+                continue;
+            }
+            let hour = rollup.hour.with_timezone(&timezone);
+            let weekday = hour.weekday().num_days_from_monday() as usize;
+            let (failed, total) = &mut self.cells[weekday][hour.hour() as usize];
+            if failed_check(&rollup.status) {
+                *failed += rollup.checks;
+            }
+            *total += rollup.checks;
+        }
+        self
+    }
+
+    /// The share of checks that failed, or `None` if there were none.
+    pub fn failure_rate(&self, weekday: usize, hour: usize) -> Option<f64> {
+        let (failed, total) = self.cells[weekday][hour];
+        (total > 0).then(|| failed as f64 / total as f64)
+    }
+
+    /// Draws the heatmap as a PNG, with a row per day starting from Monday
+    /// and a column per hour starting from midnight. Hours without checks
+    /// are grey, and the rest go from white to red as more checks failed.
+    pub fn to_png(&self) -> Result<Vec<u8>, Error> {
+        let (width, height) = (24 * CELL_SIZE, 7 * CELL_SIZE);
+        let mut buffer = vec![0; (width * height * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area();
+            root.fill(&WHITE).map_err(|why| why.to_string())?;
+            for (weekday, hours) in self.cells.iter().enumerate() {
+                for hour in 0..hours.len() {
+                    let color = match self.failure_rate(weekday, hour) {
+                        Some(rate) => {
+                            let fade = (255.0 * (1.0 - rate)).round() as u8;
+                            RGBColor(255, fade, fade)
+                        }
+                        None => RGBColor(200, 200, 200),
+                    };
+                    let x = hour as i32 * CELL_SIZE as i32;
+                    let y = weekday as i32 * CELL_SIZE as i32;
+                    let cell = [(x, y), (x + CELL_SIZE as i32 - 2, y + CELL_SIZE as i32 - 2)];
+                    root.draw(&Rectangle::new(cell, color.filled()))
+                        .map_err(|why| why.to_string())?;
+                }
+            }
+            root.present().map_err(|why| why.to_string())?;
+        }
+
+        let image =
+            RgbImage::from_raw(width, height, buffer).ok_or("Heatmap buffer is too small")?;
+        let mut png = Cursor::new(vec![]);
+        image.write_to(&mut png, ImageOutputFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
@@ -0,0 +1,240 @@
+//! Every check's result, kept in a database so it can be looked back on.
+
+use crate::{routes::RouteResult, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckRecord {
+    pub checked_at: DateTime<Utc>,
+    pub route: String,
+    /// See [`ServiceStatus::kind`](crate::status::ServiceStatus::kind).
+    pub status: String,
+    /// See [`ServiceStatus::reason`](crate::status::ServiceStatus::reason).
+    pub reason: Option<String>,
+    pub latency_ms: u64,
+}
+
+impl CheckRecord {
+    pub fn new(checked_at: DateTime<Utc>, route_result: &RouteResult) -> Self {
+        CheckRecord {
+            checked_at,
+            route: route_result.route.clone(),
+            status: route_result.status.kind().to_string(),
+            reason: route_result.status.reason(),
+            latency_ms: route_result.latency.as_millis() as u64,
+        }
+    }
+
+    /// A check made while the bot itself was cut off, which counts as
+    /// neither up nor down.
+    pub fn no_data(checked_at: DateTime<Utc>, route_result: &RouteResult, reason: &str) -> Self {
+        CheckRecord {
+            status: NO_DATA.to_string(),
+            reason: Some(reason.to_string()),
+            ..CheckRecord::new(checked_at, route_result)
+        }
+    }
+}
+
+/// The status of checks that can't be trusted, like those made while the bot
+/// was disconnected from Discord.
+pub const NO_DATA: &str = "no_data";
+
+/// How many checks of one route had one status kind within an hour, kept
+/// after the checks themselves are compacted away.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HourlyRollup {
+    pub hour: DateTime<Utc>,
+    pub route: String,
+    pub status: String,
+    pub checks: u64,
+    pub total_latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionConfig {
+    /// How long every check is kept before being rolled up by the hour.
+    #[serde(default = "default_raw_days")]
+    pub raw_days: u32,
+    /// How long the hourly rollups are kept.
+    #[serde(default = "default_rollup_days")]
+    pub rollup_days: u32,
+    #[serde(default = "default_compact_every_hours")]
+    pub compact_every_hours: u64,
+}
+
+fn default_raw_days() -> u32 {
+    30
+}
+
+fn default_rollup_days() -> u32 {
+    365
+}
+
+fn default_compact_every_hours() -> u64 {
+    24
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            raw_days: default_raw_days(),
+            rollup_days: default_rollup_days(),
+            compact_every_hours: default_compact_every_hours(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn compact_every(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.compact_every_hours * 60 * 60)
+    }
+
+    /// Compacts `history` according to this policy, as of `now`.
+    pub async fn compact(
+        &self,
+        history: &dyn HistoryStore,
+        now: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let raw_before = start_of_hour(now - Duration::days(self.raw_days.into()));
+        let rollups_before = start_of_hour(now - Duration::days(self.rollup_days.into()));
+        history.compact(raw_before, rollups_before).await
+    }
+}
+
+pub fn start_of_hour(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(Duration::hours(1)).unwrap_or(time)
+}
+
+/// Where the history is kept. Each kind of store needs the cargo feature of
+/// the same name.
+#[derive(Debug, Clone, Deserialize)]
+pub enum HistoryConfig {
+    /// A path to an SQLite database file, e.g. `Sqlite("data/history.sqlite3")`.
+    Sqlite(String),
+    /// A connection string, e.g. `Postgres("host=localhost user=bot")`.
+    Postgres(String),
+    /// A path to a redb database file, e.g. `Redb("data/history.redb")`.
+    Redb(String),
+}
+
+impl HistoryConfig {
+    pub async fn open(&self) -> Result<Arc<dyn HistoryStore>, Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            HistoryConfig::Sqlite(path) => Ok(Arc::new(crate::sqlite::SqliteHistory::open(path)?)),
+            #[cfg(not(feature = "sqlite"))]
+            HistoryConfig::Sqlite(_) => Err("The bot was built without the sqlite feature".into()),
+            #[cfg(feature = "postgres")]
+            HistoryConfig::Postgres(config) => Ok(Arc::new(
+                crate::postgres::PostgresHistory::connect(config).await?,
+            )),
+            #[cfg(not(feature = "postgres"))]
+            HistoryConfig::Postgres(_) => {
+                Err("The bot was built without the postgres feature".into())
+            }
+            #[cfg(feature = "redb")]
+            HistoryConfig::Redb(path) => Ok(Arc::new(crate::embedded::RedbHistory::open(path)?)),
+            #[cfg(not(feature = "redb"))]
+            HistoryConfig::Redb(_) => Err("The bot was built without the redb feature".into()),
+        }
+    }
+}
+
+/// Storage for check results, implemented by `SqliteHistory`,
+/// `PostgresHistory` and `RedbHistory`.
+#[async_trait]
+pub trait HistoryStore: Send + Sync {
+    async fn record(&self, records: &[CheckRecord]) -> Result<(), Error>;
+
+    /// Every record from `since` on, oldest first.
+    async fn since(&self, since: DateTime<Utc>) -> Result<Vec<CheckRecord>, Error>;
+
+    /// Rolls records from before `raw_before` up into hourly rollups, then
+    /// deletes rollups from before `rollups_before`. Both should be on the
+    /// hour, so each hour is rolled up in one go.
+    async fn compact(
+        &self,
+        raw_before: DateTime<Utc>,
+        rollups_before: DateTime<Utc>,
+    ) -> Result<(), Error>;
+
+    /// Adds `rollups` to the ones kept, as if their checks had been
+    /// compacted, like when restoring a backup.
+    async fn record_rollups(&self, rollups: &[HourlyRollup]) -> Result<(), Error>;
+
+    /// Every hourly rollup from `since` on, oldest first.
+    async fn rollups_since(&self, since: DateTime<Utc>) -> Result<Vec<HourlyRollup>, Error>;
+
+    /// Roughly how many bytes the history takes up where it's kept.
+    async fn size_bytes(&self) -> Result<u64, Error>;
+
+    /// Gives the lease called `name` to `holder` until `expires_at`, if it's
+    /// free, expired as of `now`, or already theirs. Returns whether they
+    /// hold it. Only stores that several processes can share support this.
+    async fn try_lease(
+        &self,
+        _name: &str,
+        _holder: &str,
+        _now: DateTime<Utc>,
+        _expires_at: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        Err("This history store can't be shared, so it can't hold leases".into())
+    }
+
+    /// Records every route's result of one round of checks.
+    async fn record_results(
+        &self,
+        checked_at: DateTime<Utc>,
+        route_results: &[RouteResult],
+    ) -> Result<(), Error> {
+        let records = route_results
+            .iter()
+            .map(|route_result| CheckRecord::new(checked_at, route_result))
+            .collect::<Vec<_>>();
+        self.record(&records).await
+    }
+}
+
+/// Parses ranges like `30m`, `24h` or `7d`.
+pub fn parse_range(range: &str) -> Option<Duration> {
+    let (amount, unit) = range.split_at(range.len().checked_sub(1)?);
+    let amount = amount.parse::<i64>().ok().filter(|amount| *amount > 0)?;
+    match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        _ => None,
+    }
+}
+
+pub fn to_csv(records: &[CheckRecord]) -> String {
+    let mut csv = "checked_at,route,status,reason,latency_ms\n".to_string();
+    for record in records {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            record.checked_at.to_rfc3339(),
+            csv_field(&record.route),
+            record.status,
+            record.reason.as_deref().unwrap_or_default(),
+            record.latency_ms
+        ));
+    }
+    csv
+}
+
+// Route names come from the config, so they could contain anything.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn to_json(records: &[CheckRecord]) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(records)?)
+}
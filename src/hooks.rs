@@ -0,0 +1,279 @@
+//! Operator scripts that post-process check results: reclassifying them,
+//! rewording `&sais`'s reply and deciding which changes are announced.
+//!
+//! Each hook is a Rhai script in the scripts directory named after it, e.g.
+//! `scripts/classify.rhai`, run by an engine embedded in the bot. A script
+//! is compiled again whenever its file changes, so adding, editing or
+//! removing one takes effect without a restart. A script that fails to
+//! compile or run is logged and otherwise ignored.
+
+use crate::{
+    monitor::Alert,
+    routes::RouteResult,
+    status::{DownReason, ServiceStatus},
+    transport::TimeoutKind,
+};
+use chrono::{FixedOffset, Timelike, Utc};
+use reqwest::StatusCode;
+use rhai::{module_resolvers::FileModuleResolver, Dynamic, Engine, Scope, AST};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HooksConfig {
+    #[serde(default = "default_dir")]
+    pub dir: PathBuf,
+    /// Scripts doing more than this many operations in one run are stopped.
+    #[serde(default = "default_max_operations")]
+    pub max_operations: u64,
+}
+
+fn default_dir() -> PathBuf {
+    PathBuf::from("scripts")
+}
+
+fn default_max_operations() -> u64 {
+    1_000_000
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        HooksConfig {
+            dir: default_dir(),
+            max_operations: default_max_operations(),
+        }
+    }
+}
+
+/// Gets every route's result as `result`, and can return a different
+/// status for it, as a kind like "maintenance", or "down" and a reason like
+/// "down http_503". Returning nothing keeps the result.
+pub const CLASSIFY: &str = "classify";
+/// Gets `&sais`'s reply as `text` and its status as `status`, and can
+/// return a different reply. Returning nothing keeps the reply.
+pub const REPLY: &str = "reply";
+/// Gets each confirmed change as `alert` before it is announced, and holds
+/// it back by returning false.
+pub const ALERT: &str = "alert";
+
+// A compiled script, and the file it was compiled from, to notice changes.
+struct Script {
+    modified: SystemTime,
+    len: u64,
+    // None when it didn't compile, so the error is only logged once.
+    ast: Option<Arc<AST>>,
+}
+
+pub struct Hooks {
+    config: HooksConfig,
+    engine: Engine,
+    scripts: Mutex<HashMap<String, Script>>,
+}
+
+impl Hooks {
+    pub fn new(config: HooksConfig) -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(config.max_operations);
+        // Scripts can share code with `import "helpers" as helpers;`, which
+        // is read again on every run too.
+        let mut resolver = FileModuleResolver::new_with_path(&config.dir);
+        resolver.enable_cache(false);
+        engine.set_module_resolver(resolver);
+        engine.on_print(|text| tracing::info!("Script: {}", text));
+        engine.on_debug(|text, _, position| tracing::debug!("Script at {}: {}", position, text));
+        engine.register_fn("pht_hour", || {
+            Utc::now()
+                .with_timezone(&FixedOffset::east_opt(3600 * 8).unwrap())
+                .hour() as i64
+        });
+        Hooks {
+            config,
+            engine,
+            scripts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The file the script for `hook` is read from.
+    pub fn script_path(&self, hook: &str) -> PathBuf {
+        self.config.dir.join(format!("{}.rhai", hook))
+    }
+
+    pub fn classify(&self, result: &RouteResult) -> ServiceStatus {
+        let input = json!({
+            "route": result.route,
+            "kind": result.status.kind(),
+            "reason": result.status.reason(),
+            "description": result.status.to_string(),
+            "latency_ms": result.latency.as_millis() as u64,
+            "failing_pages": result.failing_pages,
+            "error_page": result.error_page,
+        });
+        let output = match self.run(CLASSIFY, &[("result", input)]) {
+            Some(output) if !output.is_unit() => output,
+            _ => return result.status,
+        };
+        let status = output
+            .into_immutable_string()
+            .ok()
+            .and_then(|output| parse_status(&output, result.latency));
+        match status {
+            Some(status) => status,
+            None => {
+                tracing::warn!("The classify script returned an unknown status");
+                result.status
+            }
+        }
+    }
+
+    pub fn reply(&self, status: ServiceStatus, text: String) -> String {
+        let status = json!({
+            "kind": status.kind(),
+            "reason": status.reason(),
+            "description": status.to_string(),
+        });
+        let output = match self.run(REPLY, &[("status", status), ("text", json!(text))]) {
+            Some(output) if !output.is_unit() => output,
+            _ => return text,
+        };
+        match output.into_immutable_string() {
+            Ok(reply) if !reply.trim().is_empty() => reply.to_string(),
+            Ok(_) => text,
+            Err(kind) => {
+                tracing::warn!("The reply script returned a {} instead of text", kind);
+                text
+            }
+        }
+    }
+
+    /// Whether `alert` should be announced. It is when there is no alert
+    /// script, or it fails.
+    pub fn should_announce(&self, alert: Alert) -> bool {
+        let input = match alert {
+            Alert::Changed(transition) => json!({
+                "alert": "changed",
+                "kind": transition.to.kind(),
+                "reason": transition.to.reason(),
+                "description": transition.to.to_string(),
+                "previous_kind": transition.from.kind(),
+            }),
+            Alert::Unstable => json!({ "alert": "unstable" }),
+            Alert::Stable(status) => json!({
+                "alert": "stable",
+                "kind": status.kind(),
+                "reason": status.reason(),
+                "description": status.to_string(),
+            }),
+        };
+        match self.run(ALERT, &[("alert", input)]) {
+            Some(output) if output.is_unit() => true,
+            Some(output) => output.as_bool().unwrap_or_else(|kind| {
+                tracing::warn!(
+                    "The alert script returned a {} instead of true or false",
+                    kind
+                );
+                true
+            }),
+            None => true,
+        }
+    }
+
+    // What the script for `hook` evaluated to with `variables` set, if it
+    // exists and ran successfully.
+    fn run(&self, hook: &str, variables: &[(&str, serde_json::Value)]) -> Option<Dynamic> {
+        let ast = self.script(hook)?;
+        let mut scope = Scope::new();
+        for (name, value) in variables {
+            scope.push_constant_dynamic(*name, rhai::serde::to_dynamic(value).ok()?);
+        }
+        match self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast) {
+            Ok(output) => Some(output),
+            Err(why) => {
+                tracing::warn!("The {} script failed: {}", hook, why);
+                None
+            }
+        }
+    }
+
+    // The compiled script for `hook`, compiling it again if its file
+    // changed since.
+    fn script(&self, hook: &str) -> Option<Arc<AST>> {
+        let path = self.script_path(hook);
+        let mut scripts = self.scripts.lock().unwrap();
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => {
+                if scripts.remove(hook).is_some() {
+                    tracing::info!("Unloaded {}", path.display());
+                }
+                return None;
+            }
+        };
+        let modified = metadata.modified().ok()?;
+        if let Some(script) = scripts.get(hook) {
+            if script.modified == modified && script.len == metadata.len() {
+                return script.ast.clone();
+            }
+        }
+        let ast = match self.engine.compile_file(path.clone()) {
+            Ok(ast) => {
+                tracing::info!("Loaded {}", path.display());
+                Some(Arc::new(ast))
+            }
+            Err(why) => {
+                tracing::warn!("Could not compile {}: {}", path.display(), why);
+                None
+            }
+        };
+        scripts.insert(
+            hook.to_string(),
+            Script {
+                modified,
+                len: metadata.len(),
+                ast: ast.clone(),
+            },
+        );
+        ast
+    }
+}
+
+/// Reads a status returned by a script: a kind from
+/// [`ServiceStatus::kind`], followed by a reason from
+/// [`ServiceStatus::reason`] for "down". A "degraded" status takes the
+/// check's latency.
+pub fn parse_status(output: &str, latency: Duration) -> Option<ServiceStatus> {
+    let mut words = output.split_whitespace();
+    let kind = words.next()?;
+    let reason = words.next();
+    if words.next().is_some() {
+        return None;
+    }
+    let status = match (kind, reason) {
+        ("up", None) => ServiceStatus::Up,
+        ("degraded", None) => ServiceStatus::Degraded(latency),
+        ("pages_failing", None) => ServiceStatus::PagesFailing,
+        ("maintenance", None) => ServiceStatus::Maintenance,
+        ("credential_problem", None) => ServiceStatus::CredentialProblem,
+        ("down", reason) => ServiceStatus::Down(match reason.unwrap_or("no_response") {
+            "no_response" => DownReason::NoResponse,
+            "connection_refused" => DownReason::ConnectionRefused,
+            "connect_timeout" => DownReason::TimedOut(TimeoutKind::Connect),
+            "read_timeout" => DownReason::TimedOut(TimeoutKind::Read),
+            "login_timeout" => DownReason::TimedOut(TimeoutKind::Login),
+            "check_timeout" => DownReason::TimedOut(TimeoutKind::Check),
+            "login_failed" => DownReason::LoginFailed,
+            reason => {
+                let code = reason.strip_prefix("http_")?.parse().ok()?;
+                DownReason::HttpStatus(StatusCode::from_u16(code).ok()?)
+            }
+        }),
+        _ => return None,
+    };
+    Some(status)
+}
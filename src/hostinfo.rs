@@ -0,0 +1,91 @@
+//! Where the SAIS hostname points, for spotting DNS that suddenly points
+//! somewhere unexpected.
+
+use crate::Error;
+use serde::Deserialize;
+use std::{fmt, net::IpAddr};
+
+/// An ipinfo.io-style API, where `{ip}` is replaced with each address.
+pub const DEFAULT_IP_LOOKUP_URL: &str = "https://ipinfo.io/{ip}/json";
+
+/// What the lookup API knows about an address. Every field is optional,
+/// since addresses without reverse DNS or geolocation are common.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct IpDetails {
+    /// The reverse DNS name.
+    pub hostname: Option<String>,
+    /// The ASN and its owner, e.g. "AS9821 University of the Philippines".
+    pub org: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct HostInfo {
+    pub host: String,
+    /// The error message when the lookup of an address failed.
+    pub addresses: Vec<(IpAddr, Result<IpDetails, String>)>,
+}
+
+/// Resolves `host`, then looks up each of its addresses.
+pub async fn look_up(host: &str, lookup_url: &str) -> Result<HostInfo, Error> {
+    let mut ips = tokio::net::lookup_host((host, 443))
+        .await?
+        .map(|addr| addr.ip())
+        .collect::<Vec<_>>();
+    ips.sort();
+    ips.dedup();
+
+    let http_client = reqwest::Client::new();
+    let mut addresses = vec![];
+    for ip in ips {
+        let details = async {
+            let url = lookup_url.replace("{ip}", &ip.to_string());
+            let response = http_client.get(url).send().await?.error_for_status()?;
+            response.json::<IpDetails>().await
+        }
+        .await
+        .map_err(|why| why.to_string());
+        addresses.push((ip, details));
+    }
+    Ok(HostInfo {
+        host: host.to_string(),
+        addresses,
+    })
+}
+
+impl fmt::Display for HostInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.addresses.is_empty() {
+            return write!(f, "{} has no addresses", self.host);
+        }
+        write!(f, "{} resolves to:", self.host)?;
+        for (ip, details) in &self.addresses {
+            write!(f, "\n{}", ip)?;
+            let details = match details {
+                Ok(details) => details,
+                Err(why) => {
+                    write!(f, " (could not look it up: {})", why)?;
+                    continue;
+                }
+            };
+            let location = [details.city.as_deref(), details.country.as_deref()]
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ");
+            for detail in [
+                details.hostname.as_deref(),
+                details.org.as_deref(),
+                Some(location.as_str()).filter(|location| !location.is_empty()),
+            ]
+            .iter()
+            .flatten()
+            {
+                write!(f, "\n  {}", detail)?;
+            }
+        }
+        Ok(())
+    }
+}
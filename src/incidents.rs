@@ -0,0 +1,163 @@
+//! Opens a GitHub issue for each long outage, and closes it with a summary
+//! once SAIS is back, so past incidents can be looked up publicly.
+
+use crate::{
+    outages::OutageSummary,
+    postmortem::{self, TimelineRun},
+    sla, Error,
+};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncidentIssuesConfig {
+    /// Where issues are opened, like "grantyap/sais-incidents".
+    pub repo: String,
+    /// Only outages at least this long get an issue.
+    #[serde(default = "default_after_mins")]
+    pub after_mins: u64,
+    #[serde(default = "default_labels")]
+    pub labels: Vec<String>,
+    /// GitHub's REST API, or anything answering like it.
+    #[serde(default = "default_api_url")]
+    pub api_url: String,
+}
+
+fn default_after_mins() -> u64 {
+    30
+}
+
+fn default_labels() -> Vec<String> {
+    vec!["incident".to_string()]
+}
+
+fn default_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedIssue {
+    number: u64,
+}
+
+pub struct IncidentIssues {
+    config: IncidentIssuesConfig,
+    /// Can write issues in the repo, from `GITHUB_TOKEN`.
+    token: String,
+    http_client: reqwest::Client,
+}
+
+impl IncidentIssues {
+    pub fn new(config: IncidentIssuesConfig, token: String) -> IncidentIssues {
+        IncidentIssues {
+            config,
+            token,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Whether the outage since `since` has gone on long enough for an
+    /// issue at `now`.
+    pub fn due(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        now - since >= Duration::minutes(self.config.after_mins as i64)
+    }
+
+    /// Opens an issue for the outage since `since`, returning its number.
+    pub async fn open(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> Result<u64, Error> {
+        let issue = self
+            .send(self.http_client.post(self.url("issues")).json(&json!({
+                "title": issue_title(since),
+                "body": opening_body(since, now),
+                "labels": self.config.labels,
+            })))
+            .await?
+            .json::<CreatedIssue>()
+            .await?;
+        Ok(issue.number)
+    }
+
+    /// Comments `comment` on issue `number` and closes it.
+    pub async fn close(&self, number: u64, comment: &str) -> Result<(), Error> {
+        self.send(
+            self.http_client
+                .post(self.url(&format!("issues/{}/comments", number)))
+                .json(&json!({ "body": comment })),
+        )
+        .await?;
+        self.send(
+            self.http_client
+                .patch(self.url(&format!("issues/{}", number)))
+                .json(&json!({ "state": "closed", "state_reason": "completed" })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/repos/{}/{}",
+            self.config.api_url.trim_end_matches('/'),
+            self.config.repo,
+            path
+        )
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, Error> {
+        let response = request
+            .bearer_auth(&self.token)
+            // GitHub turns away requests without one.
+            .header(
+                "User-Agent",
+                concat!("is-sais-down-bot/", env!("CARGO_PKG_VERSION")),
+            )
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response)
+    }
+}
+
+fn pht(time: DateTime<Utc>) -> String {
+    time.with_timezone(&FixedOffset::east_opt(3600 * 8).unwrap())
+        .format("%b %-d %H:%M PHT")
+        .to_string()
+}
+
+pub fn issue_title(since: DateTime<Utc>) -> String {
+    format!("UP SAIS outage since {}", pht(since))
+}
+
+pub fn opening_body(since: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    format!(
+        "UP SAIS has been down since {}, for {} so far. This issue is closed with a summary \
+         once it works again.",
+        pht(since),
+        sla::format_duration(now - since)
+    )
+}
+
+/// Sums up the outage for the closing comment, with the timeline of checks
+/// around it when there's a history.
+pub fn closing_comment(summary: Option<&OutageSummary>, timeline: &[TimelineRun]) -> String {
+    let summary = match summary {
+        Some(summary) => summary,
+        None => return "UP SAIS works again.".to_string(),
+    };
+    let reasons = summary
+        .reasons
+        .iter()
+        .map(|(reason, count)| format!("{} {}", reason, count))
+        .collect::<Vec<_>>();
+    format!(
+        "UP SAIS works again. It was down for {}, from {} to {}, with {} failed checks ({}).\n\n\
+         ## Timeline (PHT)\n\n{}",
+        sla::format_duration(summary.downtime()),
+        pht(summary.started_at),
+        pht(summary.ended_at),
+        summary.failed_checks,
+        reasons.join(", "),
+        postmortem::timeline_markdown(timeline)
+    )
+}
@@ -0,0 +1,90 @@
+//! Check results written to InfluxDB as line protocol.
+
+use crate::{exporters::CheckExporter, routes::RouteResult, sla, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InfluxConfig {
+    /// The whole write URL, e.g.
+    /// "http://localhost:8086/api/v2/write?org=up&bucket=sais" for InfluxDB 2
+    /// or "http://localhost:8086/write?db=sais" for InfluxDB 1.
+    pub write_url: String,
+    #[serde(default = "default_measurement")]
+    pub measurement: String,
+}
+
+fn default_measurement() -> String {
+    "sais_check".to_string()
+}
+
+pub struct InfluxExporter {
+    config: InfluxConfig,
+    /// Sent as `Authorization: Token <token>`, when set.
+    token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+impl InfluxExporter {
+    pub fn new(config: InfluxConfig, token: Option<String>) -> InfluxExporter {
+        InfluxExporter {
+            config,
+            token,
+            http_client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl CheckExporter for InfluxExporter {
+    async fn export(
+        &self,
+        checked_at: DateTime<Utc>,
+        route_results: &[RouteResult],
+    ) -> Result<(), Error> {
+        let body = route_results
+            .iter()
+            .map(|route_result| line(&self.config.measurement, checked_at, route_result))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut request = self.http_client.post(&self.config.write_url).body(body);
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Token {}", token));
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// One point, tagged with the route, with a nanosecond timestamp.
+pub fn line(measurement: &str, checked_at: DateTime<Utc>, route_result: &RouteResult) -> String {
+    let status = route_result.status.kind();
+    let mut fields = vec![
+        format!("up={}i", u8::from(!sla::failed_check(status))),
+        format!("status=\"{}\"", escape_string(status)),
+        format!("latency_ms={}i", route_result.latency.as_millis()),
+    ];
+    if let Some(reason) = route_result.status.reason() {
+        fields.push(format!("reason=\"{}\"", escape_string(&reason)));
+    }
+    format!(
+        "{},route={} {} {}",
+        escape_key(measurement),
+        escape_key(&route_result.route),
+        fields.join(","),
+        checked_at.timestamp_nanos_opt().unwrap_or_default()
+    )
+}
+
+// Measurement names, tag keys and tag values.
+fn escape_key(key: &str) -> String {
+    key.replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn escape_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,236 @@
+//! A rough look at the structure of the login page, both to log in the way
+//! the page's own form does, and to notice when SAIS changes it enough that
+//! the login heuristics might need updating.
+
+use std::collections::BTreeSet;
+
+/// A start tag found in a page, with lowercased tag and attribute names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Tag {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Finds every start tag in `html`. This is not a real HTML parser, but the
+/// PeopleSoft login page is simple enough for it.
+pub fn start_tags(html: &str) -> Vec<Tag> {
+    let mut tags = vec![];
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if rest.starts_with("!--") {
+            match rest.find("-->") {
+                Some(end) => rest = &rest[end + 3..],
+                None => break,
+            }
+            continue;
+        }
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        if let Some(tag) = parse_tag(&rest[..end]) {
+            tags.push(tag);
+        }
+        rest = &rest[end + 1..];
+    }
+    tags
+}
+
+fn parse_tag(inside: &str) -> Option<Tag> {
+    let inside = inside.trim_end_matches('/');
+    let name_end = inside
+        .find(|c: char| c.is_whitespace())
+        .unwrap_or(inside.len());
+    let name = &inside[..name_end];
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    let mut attributes = vec![];
+    let mut rest = inside[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_lowercase();
+        rest = rest[key_end..].trim_start();
+        let value = if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (value, remaining) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let quoted = &after_equals[1..];
+                    let end = quoted.find(quote).unwrap_or(quoted.len());
+                    (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+            rest = remaining.trim_start();
+            decode_entities(value)
+        } else {
+            String::new()
+        };
+        if !key.is_empty() {
+            attributes.push((key, value));
+        }
+    }
+
+    Some(Tag {
+        name: name.to_lowercase(),
+        attributes,
+    })
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// The form on the login page that has a password field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginForm {
+    pub action: Option<String>,
+    /// Hidden inputs, like PeopleSoft's `ICSID` and state tokens, that have
+    /// to be posted back along with the credentials.
+    pub hidden_fields: Vec<(String, String)>,
+}
+
+impl LoginForm {
+    pub fn from_html(html: &str) -> Option<LoginForm> {
+        let mut forms: Vec<(LoginForm, bool)> = vec![];
+        for tag in start_tags(html) {
+            if tag.name == "form" {
+                let action = tag
+                    .attribute("action")
+                    .filter(|action| !action.is_empty())
+                    .map(str::to_string);
+                forms.push((
+                    LoginForm {
+                        action,
+                        hidden_fields: vec![],
+                    },
+                    false,
+                ));
+                continue;
+            }
+            // Closing tags aren't tracked, so fields belong to the latest
+            // form that was opened.
+            let (form, has_password) = match (tag.name.as_str(), forms.last_mut()) {
+                ("input", Some(form)) => form,
+                _ => continue,
+            };
+            match tag.attribute("type").map(str::to_lowercase).as_deref() {
+                Some("password") => *has_password = true,
+                Some("hidden") => {
+                    if let Some(name) = tag.attribute("name") {
+                        let value = tag.attribute("value").unwrap_or_default();
+                        form.hidden_fields
+                            .push((name.to_string(), value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        forms
+            .into_iter()
+            .find(|(_, has_password)| *has_password)
+            .map(|(form, _)| form)
+    }
+}
+
+/// The parts of the login page the checker depends on. Text and attribute
+/// values are left out, since they change between loads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLayout {
+    pub title: Option<String>,
+    pub forms: usize,
+    pub fields: BTreeSet<String>,
+}
+
+impl PageLayout {
+    pub fn from_html(html: &str) -> PageLayout {
+        let tags = start_tags(html);
+        let fields = tags
+            .iter()
+            .filter(|tag| matches!(tag.name.as_str(), "input" | "select" | "textarea"))
+            .filter_map(|tag| tag.attribute("name"))
+            .map(str::to_string)
+            .collect();
+        PageLayout {
+            title: page_title(html),
+            forms: tags.iter().filter(|tag| tag.name == "form").count(),
+            fields,
+        }
+    }
+
+    /// Describes how `new` differs from this layout, or `None` if they are
+    /// the same.
+    pub fn changes_to(&self, new: &PageLayout) -> Option<String> {
+        let mut changes = vec![];
+        if self.title != new.title {
+            changes.push(format!("title {:?} -> {:?}", self.title, new.title));
+        }
+        if self.forms != new.forms {
+            changes.push(format!("forms {} -> {}", self.forms, new.forms));
+        }
+        let added = new.fields.difference(&self.fields).collect::<Vec<_>>();
+        if !added.is_empty() {
+            changes.push(format!("new fields {:?}", added));
+        }
+        let removed = self.fields.difference(&new.fields).collect::<Vec<_>>();
+        if !removed.is_empty() {
+            changes.push(format!("removed fields {:?}", removed));
+        }
+        if changes.is_empty() {
+            None
+        } else {
+            Some(changes.join(", "))
+        }
+    }
+}
+
+fn page_title(html: &str) -> Option<String> {
+    let lowercase = html.to_lowercase();
+    let start = lowercase.find("<title")?;
+    let start = start + lowercase[start..].find('>')? + 1;
+    let end = start + lowercase[start..].find("</title")?;
+    Some(html[start..end].trim().to_string())
+}
+
+/// Remembers the first layout seen, and reports when a later one differs.
+#[derive(Debug, Default)]
+pub struct LayoutWatcher {
+    known: Option<PageLayout>,
+}
+
+impl LayoutWatcher {
+    /// Returns a description of the change when `layout` differs from the
+    /// last one. The new layout is remembered either way, so each change is
+    /// only reported once.
+    pub fn observe(&mut self, layout: PageLayout) -> Option<String> {
+        let changes = self
+            .known
+            .as_ref()
+            .and_then(|known| known.changes_to(&layout));
+        self.known = Some(layout);
+        changes
+    }
+}
@@ -0,0 +1,108 @@
+//! Lease-based leader election over the shared history database, so two
+//! instances of the bot can run at once with only one checking SAIS and
+//! announcing, and the other taking over if it dies.
+
+use crate::{history::HistoryStore, Error};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// The name of the lease in the database.
+pub const LEASE_NAME: &str = "leader";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaderElectionConfig {
+    /// Tells the instances apart. Defaults to the host name and process ID.
+    #[serde(default)]
+    pub instance_id: Option<String>,
+    /// How long the leader can go without renewing its lease before the
+    /// standby takes over. It's renewed three times as often.
+    #[serde(default = "default_lease_secs")]
+    pub lease_secs: u64,
+}
+
+fn default_lease_secs() -> u64 {
+    30
+}
+
+#[derive(Debug)]
+pub struct Leadership {
+    instance_id: String,
+    lease: Duration,
+    is_leader: AtomicBool,
+}
+
+impl Leadership {
+    pub fn new(config: &LeaderElectionConfig) -> Leadership {
+        Leadership {
+            instance_id: config
+                .instance_id
+                .clone()
+                .unwrap_or_else(default_instance_id),
+            lease: Duration::seconds(config.lease_secs as i64),
+            is_leader: AtomicBool::new(false),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::Relaxed)
+    }
+
+    /// Takes or renews the lease as of `now`, returning whether this
+    /// instance leads. Not being able to reach the database counts as not
+    /// leading, since the other instance may have taken over meanwhile.
+    pub async fn renew(
+        &self,
+        history: &dyn HistoryStore,
+        now: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        let leads = history
+            .try_lease(LEASE_NAME, &self.instance_id, now, now + self.lease)
+            .await;
+        let is_leader = *leads.as_ref().unwrap_or(&false);
+        let was_leader = self.is_leader.swap(is_leader, Ordering::Relaxed);
+        if is_leader && !was_leader {
+            tracing::info!("{} is now the leader", self.instance_id);
+        } else if !is_leader && was_leader {
+            tracing::warn!("{} is no longer the leader", self.instance_id);
+        }
+        leads
+    }
+
+    /// Renews the lease until the bot exits.
+    pub async fn hold(self: Arc<Self>, history: Arc<dyn HistoryStore>) {
+        let every = (self.lease / 3).to_std().unwrap_or_default();
+        let mut interval = tokio::time::interval(every.max(std::time::Duration::from_secs(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(why) = self.renew(history.as_ref(), Utc::now()).await {
+                tracing::error!("Could not renew the leader lease: {:?}", why);
+            }
+        }
+    }
+}
+
+/// Whether this instance should check and announce: always, unless leader
+/// election is on and another instance leads.
+pub fn leads(leadership: Option<&Leadership>) -> bool {
+    leadership.is_none_or(Leadership::is_leader)
+}
+
+/// The host name and process ID.
+pub fn default_instance_id() -> String {
+    let host = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "localhost".to_string());
+    format!("{}-{}", host, std::process::id())
+}
@@ -0,0 +1,106 @@
+//! The SAIS checking logic behind the bot, kept separate from the Discord
+//! side so it can be exercised on its own, and reused by other projects.
+//!
+//! [`SaisClient::check`] logs in to UP SAIS once and says how it went as a
+//! [`ServiceStatus`]. [`routes::RouteChecker`] runs that check through every
+//! configured route at once, and [`monitor::Monitor`] turns a series of
+//! checks into confirmed status changes.
+//!
+//! ```no_run
+//! use is_sais_down_bot::{LoginDetails, SaisClient, SaisConfig, ServiceStatus};
+//!
+//! # async fn run() -> Result<(), is_sais_down_bot::Error> {
+//! let sais_config = SaisConfig::get().expect("Could not read config/sais.ron");
+//! let mut client = SaisClient::with_config(sais_config, LoginDetails::get());
+//! match client.check().await? {
+//!     ServiceStatus::Up => println!("SAIS is up"),
+//!     status => println!("SAIS is {}", status),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See `examples/check.rs` for a complete program.
+
+pub mod anomaly;
+#[cfg(feature = "web")]
+pub mod api_keys;
+pub mod backup;
+pub mod broker;
+pub mod calendar;
+pub mod capture;
+pub mod config;
+pub mod cookies;
+#[cfg(feature = "web")]
+pub mod dashboard;
+pub mod diagnostics;
+pub mod digest;
+#[cfg(feature = "redb")]
+pub mod embedded;
+pub mod escalation;
+pub mod exporters;
+pub mod forecast;
+pub mod gateway;
+#[cfg(feature = "web")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod guilds;
+pub mod har;
+pub mod health;
+pub mod heartbeat;
+pub mod heatmap;
+pub mod history;
+pub mod hooks;
+pub mod hostinfo;
+pub mod incidents;
+pub mod influx;
+pub mod layout;
+pub mod leader;
+pub mod maintenance;
+pub mod metrics;
+#[cfg(feature = "sqlite")]
+pub mod migrations;
+pub mod monitor;
+pub mod oncall;
+pub mod outages;
+pub mod pager;
+pub mod phrases;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod postmortem;
+pub mod preferences;
+pub mod redact;
+pub mod redis_state;
+pub mod releases;
+pub mod reminders;
+pub mod report;
+pub mod routes;
+pub mod runtime_stats;
+pub mod sais;
+pub mod schema;
+pub mod screenshot;
+pub mod settings;
+pub mod sla;
+pub mod spam;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod state;
+pub mod statsd;
+pub mod status;
+pub mod tcp_probe;
+pub mod timing;
+pub mod trace;
+pub mod transaction;
+pub mod transport;
+pub mod uptime;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use config::{LoginDetails, SaisConfig};
+pub use sais::SaisClient;
+pub use status::{DownReason, ServiceStatus};
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -0,0 +1,148 @@
+use is_sais_down_bot::redact;
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{Builder, Rotation},
+};
+use tracing_subscriber::{fmt, fmt::MakeWriter, prelude::*, EnvFilter};
+
+// Keeps the bot's own logs, and only warnings from the Discord libraries,
+// unless `RUST_LOG` says otherwise.
+const DEFAULT_LOG_FILTER: &str = "warn,is_sais_down_bot=info";
+const LOG_FILE_SUFFIX: &str = "log";
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Deserialize)]
+pub struct LogFileConfig {
+    directory: String,
+    #[serde(default = "default_file_name_prefix")]
+    file_name_prefix: String,
+    // Once either limit is passed, the oldest days are deleted.
+    #[serde(default)]
+    max_files: Option<usize>,
+    #[serde(default)]
+    max_total_size_mb: Option<u64>,
+}
+
+fn default_file_name_prefix() -> String {
+    "is-sais-down-bot".to_string()
+}
+
+/// Logs to stdout, and also to a daily rotated file when `log_file` is set.
+///
+/// The returned guard flushes the file when dropped, so it has to be kept
+/// alive until the bot exits.
+pub fn init(log_file: Option<&LogFileConfig>) -> Option<WorkerGuard> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let stdout_layer = fmt::layer().with_writer(Redacting(io::stdout));
+
+    let (file_layer, guard) = match log_file {
+        Some(log_file) => {
+            let mut builder = Builder::new()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(&log_file.file_name_prefix)
+                .filename_suffix(LOG_FILE_SUFFIX);
+            if let Some(max_files) = log_file.max_files {
+                builder = builder.max_log_files(max_files);
+            }
+            let appender = builder
+                .build(&log_file.directory)
+                .expect("Could not open the log file");
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+
+            if let Some(max_total_size_mb) = log_file.max_total_size_mb {
+                spawn_size_pruning(
+                    log_file.directory.clone(),
+                    log_file.file_name_prefix.clone(),
+                    max_total_size_mb * 1024 * 1024,
+                );
+            }
+
+            (
+                Some(fmt::layer().with_ansi(false).with_writer(Redacting(writer))),
+                Some(guard),
+            )
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stdout_layer)
+        .with(file_layer)
+        .init();
+
+    guard
+}
+
+// Passes every formatted event through `redact` before it's written. Events
+// are written whole, so a secret is never split across writes.
+struct Redacting<W>(W);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = Redacting<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Redacting(self.0.make_writer())
+    }
+}
+
+impl<W: Write> Write for Redacting<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(redact::redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+fn spawn_size_pruning(directory: String, file_name_prefix: String, max_total_bytes: u64) {
+    thread::spawn(move || loop {
+        if let Err(why) = prune_by_size(Path::new(&directory), &file_name_prefix, max_total_bytes) {
+            tracing::warn!("Could not prune log files: {:?}", why);
+        }
+        thread::sleep(PRUNE_INTERVAL);
+    });
+}
+
+// Deletes the oldest log files until the rest fit in `max_total_bytes`. The
+// newest file is the one being written to, so it is always kept.
+fn prune_by_size(
+    directory: &Path,
+    file_name_prefix: &str,
+    max_total_bytes: u64,
+) -> std::io::Result<()> {
+    let prefix = format!("{}.", file_name_prefix);
+    let mut log_files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name.starts_with(&prefix) && file_name.ends_with(LOG_FILE_SUFFIX) {
+            log_files.push((file_name, entry.metadata()?.len()));
+        }
+    }
+    // File names end in the date, so they sort oldest first.
+    log_files.sort();
+
+    let mut total_bytes: u64 = log_files.iter().map(|(_, len)| len).sum();
+    for (file_name, len) in log_files.iter().take(log_files.len().saturating_sub(1)) {
+        if total_bytes <= max_total_bytes {
+            break;
+        }
+        fs::remove_file(directory.join(file_name))?;
+        tracing::info!("Pruned old log file {}", file_name);
+        total_bytes -= len;
+    }
+    Ok(())
+}
@@ -1,57 +1,156 @@
 // Authored by: Grant :^)
 
 use chrono::prelude::*;
-use serde::Deserialize;
-use serenity::{
-    framework::standard::{
-        macros::{command, group},
-        CommandResult, StandardFramework,
-    },
-    model::{channel::Message, gateway::Ready, id::EmojiId},
-    prelude::*,
-    utils::MessageBuilder,
+#[cfg(feature = "grpc")]
+use is_sais_down_bot::grpc::{self, GrpcConfig};
+use is_sais_down_bot::{
+    anomaly::AnomalyConfig,
+    backup::{self, Backup},
+    broker::{self, BrokerConfig},
+    calendar::CalendarConfig,
+    escalation::EscalationPolicy,
+    exporters::CheckExporter,
+    forecast::{self, PatternWatch, Patterns},
+    gateway::GatewayWatch,
+    guilds::{Caller, GuildSettingsStore, GUILD_SETTINGS_FILEPATH},
+    health::Readiness,
+    heartbeat::Heartbeat,
+    history::{HistoryConfig, HistoryStore, RetentionConfig},
+    hooks::{Hooks, HooksConfig},
+    hostinfo,
+    incidents::{IncidentIssues, IncidentIssuesConfig},
+    influx::{InfluxConfig, InfluxExporter},
+    leader::{self, LeaderElectionConfig, Leadership},
+    maintenance::{MaintenanceStore, MAINTENANCE_FILEPATH},
+    metrics::CommandMetrics,
+    monitor::{MonitorConfig, MonitorHealth},
+    oncall::{OnCall, OnCallRotation, ONCALL_FILEPATH},
+    outages::OutageLog,
+    pager::{Pager, PagerConfig},
+    phrases::DownPhrases,
+    preferences::{PreferencesStore, PREFERENCES_FILEPATH},
+    redact,
+    releases::{ReleaseCheckConfig, ReleaseWatch},
+    reminders::ReminderConfig,
+    routes::RouteChecker,
+    sais::SaisClient,
+    screenshot::ScreenshotConfig,
+    settings::{SettingsStore, RUNTIME_SETTINGS_FILEPATH},
+    sla::{self, SlaConfig},
+    spam::{SpamConfig, SpamGuard, Verdict},
+    state::{SharedState, StateBackend},
+    statsd::{StatsdConfig, StatsdExporter},
+    timing::TimingHistograms,
+    trace::TraceConfig,
+    transaction::{self, Transaction},
+    transport::ReqwestTransport,
+    Error,
+};
+#[cfg(feature = "web")]
+use is_sais_down_bot::{
+    api_keys::ApiKeys,
+    dashboard::{Dashboard, DashboardData},
+    web::{self, WebConfig, WebState},
 };
+use poise::serenity_prelude::{self as serenity, Emoji, EmojiId, GatewayIntents};
+use serde::Deserialize;
 use std::{collections::HashMap, env, fs::File, io::prelude::*, sync::Arc, time::Duration};
 
-const SAIS_CONFIG_FILEPATH: &str = "config/sais.ron";
-const DISCORD_CONFIG_FILEPATH: &str = "config/discord.ron";
+mod alerts;
+mod commands;
+mod logging;
+mod maintenance_events;
+mod monthly_report;
+mod release_check;
+mod sample_config;
+mod self_test;
+mod setup;
+mod supervisor;
+mod surfaces;
+mod systemd;
 
-#[derive(Debug)]
-#[allow(non_snake_case)]
-struct LoginDetails {
-    timezoneOffset: i32,
-    userid: String,
-    pwd: String,
-    request_id: u64,
-}
+const DISCORD_CONFIG_FILEPATH: &str = "config/discord.ron";
+const BOT_CONFIG_FILEPATH: &str = "config/bot.ron";
 
-impl LoginDetails {
-    fn get() -> Self {
-        LoginDetails {
-            timezoneOffset: env::var("TIMEZONE_OFFSET")
-                .expect("Expected TIMEZONE_OFFSET")
-                .parse::<i32>()
-                .expect("Could not parse TIMEZONE_OFFSET"),
-            userid: env::var("USER_ID").expect("Expected USER_ID"),
-            pwd: env::var("PASSWORD").expect("Expected PASSWORD"),
-            request_id: env::var("REQUEST_ID")
-                .expect("Expected REQUEST_ID")
-                .parse::<u64>()
-                .expect("Could not parse REQUEST_ID"),
-        }
-    }
+#[derive(Debug, Default, Deserialize)]
+struct BotConfig {
+    // Errors and panics are only reported to Sentry when this is set.
+    #[serde(default)]
+    sentry_dsn: Option<String>,
+    // Logs only go to stdout when this is not set.
+    #[serde(default)]
+    log_file: Option<logging::LogFileConfig>,
+    // How SAIS is monitored in the background.
+    #[serde(default)]
+    monitor: MonitorConfig,
+    // Watches SAIS more closely during the busy parts of the academic year.
+    #[serde(default)]
+    calendar: CalendarConfig,
+    // The daily login check whose failures only the owner hears about.
+    #[serde(default)]
+    credential_self_test: self_test::SelfTestConfig,
+    // How often to check GitHub for a newer release, telling the owner.
+    #[serde(default)]
+    release_check: ReleaseCheckConfig,
+    // Overrides how `&sais` words each way SAIS can be down.
+    #[serde(default)]
+    down_phrases: DownPhrases,
+    // Check results are only kept when this is set.
+    #[serde(default)]
+    history: Option<HistoryConfig>,
+    // Lets two instances share the history database, with only the leader
+    // checking and announcing. Needs an SQLite or PostgreSQL `history`.
+    #[serde(default)]
+    leader_election: Option<LeaderElectionConfig>,
+    // Where cooldowns, the latest status and the check lock are kept, so that
+    // several processes can share them.
+    #[serde(default)]
+    state_backend: StateBackend,
+    // How long the history is kept. Only used when `history` is set.
+    #[serde(default)]
+    retention: RetentionConfig,
+    // The uptime target tracked in the alerts channel. Only used when
+    // `history` is set, and alerts are set in the Discord config.
+    #[serde(default)]
+    sla: Option<SlaConfig>,
+    // The HTTP server for metrics and the dashboard only runs when this is
+    // set.
+    #[cfg(feature = "web")]
+    #[serde(default)]
+    web: Option<WebConfig>,
+    // Where `&hostinfo` looks up each address, with `{ip}` in place of it.
+    // ipinfo.io when not set.
+    #[serde(default)]
+    ip_lookup_url: Option<String>,
+    // Outage announcements come with a screenshot of SAIS when this is set.
+    #[serde(default)]
+    screenshot: Option<ScreenshotConfig>,
+    // Every check is also written to InfluxDB when this is set.
+    #[serde(default)]
+    influxdb: Option<InfluxConfig>,
+    // Every check is also sent to a StatsD agent when this is set.
+    #[serde(default)]
+    statsd: Option<StatsdConfig>,
+    // Status changes are also published to NATS or MQTT when this is set.
+    #[serde(default)]
+    broker: Option<BrokerConfig>,
+    // Scripts that reclassify checks, reword `&sais` and hold back
+    // announcements only run when this is set.
+    #[serde(default)]
+    hooks: Option<HooksConfig>,
+    // How `&trace` runs traceroute, and what counts as campus.
+    #[serde(default)]
+    trace: TraceConfig,
+    // The gRPC status service only runs when this is set.
+    #[cfg(feature = "grpc")]
+    #[serde(default)]
+    grpc: Option<GrpcConfig>,
 }
 
-#[derive(Debug, Deserialize)]
-struct SaisConfig {
-    login_url: String,
-    login_success_string: String,
-}
-
-impl SaisConfig {
-    fn get() -> Result<SaisConfig, Box<dyn std::error::Error>> {
-        let sais_config_file = File::open(SAIS_CONFIG_FILEPATH)?;
-        let mut buf_reader = std::io::BufReader::new(sais_config_file);
+impl BotConfig {
+    fn get() -> Result<BotConfig, Box<dyn std::error::Error>> {
+        let bot_config_file = File::open(BOT_CONFIG_FILEPATH)?;
+        let mut buf_reader = std::io::BufReader::new(bot_config_file);
         let mut contents = String::new();
         buf_reader.read_to_string(&mut contents)?;
         Ok(ron::de::from_str(&contents)?)
@@ -62,6 +161,107 @@ impl SaisConfig {
 struct DiscordConfig {
     up_cebu_discord_server_id: u64,
     emoji_ids: HashMap<String, u64>,
+    #[serde(default)]
+    intents: IntentsConfig,
+    // Status changes are announced here, as well as in the channels servers
+    // choose with /setup, when set.
+    #[serde(default)]
+    alerts: Option<AlertsConfig>,
+    // Latency well over the usual is posted here, without pinging anyone,
+    // when set.
+    #[serde(default)]
+    latency_alerts: Option<AnomalyConfig>,
+    // Sent a report at the end of each month, when history is set.
+    #[serde(default)]
+    maintainer_ids: Vec<u64>,
+    // Where else to show the status.
+    #[serde(default)]
+    status_surfaces: Option<surfaces::SurfacesConfig>,
+    // Once disconnected from the gateway for longer than this, announcements
+    // wait for the bot to reconnect, and checks are recorded as no data.
+    #[serde(default = "default_gateway_outage_after_secs")]
+    gateway_outage_after_secs: u64,
+    // Users sending too many commands are ignored for a while.
+    #[serde(default)]
+    spam: SpamConfig,
+    // How often long outages are brought up again, replying to their
+    // announcements.
+    #[serde(default)]
+    still_down_reminders: ReminderConfig,
+    // Drafts a post-mortem after each outage when set. Needs history.
+    #[serde(default)]
+    post_mortems: Option<PostMortemConfig>,
+    // Who else to tell as an outage goes on, besides what servers set with
+    // &escalation.
+    #[serde(default)]
+    escalation: EscalationPolicy,
+    // Paged by the escalation's pager steps, and for every outage with
+    // on_outage, when set. Needs PAGER_ROUTING_KEY.
+    #[serde(default)]
+    pager: Option<PagerConfig>,
+    // Maintainers escalations only DM whoever is on call, when set.
+    #[serde(default)]
+    oncall: Option<OnCallRotation>,
+    // Long outages get a GitHub issue, when set. Needs GITHUB_TOKEN.
+    #[serde(default)]
+    incident_issues: Option<IncidentIssuesConfig>,
+    // Maintenance windows get a Discord event in each server, when set.
+    #[serde(default)]
+    maintenance_events: Option<maintenance_events::MaintenanceEventsConfig>,
+}
+
+fn default_gateway_outage_after_secs() -> u64 {
+    120
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertsConfig {
+    channel_id: u64,
+    // Pinged with every announcement, if set.
+    #[serde(default)]
+    role_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PostMortemConfig {
+    // Drafts are posted here, or sent to the owner and `maintainer_ids` when
+    // not set.
+    #[serde(default)]
+    channel_id: Option<u64>,
+}
+
+// Privileged intents have to be enabled for the bot in the Discord developer
+// portal, so only ask for what the enabled features actually use.
+#[derive(Debug, Deserialize)]
+struct IntentsConfig {
+    // Needed to read `&` prefix commands outside of DMs and mentions.
+    message_content: bool,
+    reactions: bool,
+}
+
+impl Default for IntentsConfig {
+    fn default() -> Self {
+        IntentsConfig {
+            message_content: true,
+            reactions: false,
+        }
+    }
+}
+
+impl IntentsConfig {
+    fn gateway_intents(&self) -> GatewayIntents {
+        let mut intents = GatewayIntents::GUILDS
+            | GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::DIRECT_MESSAGES;
+        if self.message_content {
+            intents |= GatewayIntents::MESSAGE_CONTENT;
+        }
+        if self.reactions {
+            intents |=
+                GatewayIntents::GUILD_MESSAGE_REACTIONS | GatewayIntents::DIRECT_MESSAGE_REACTIONS;
+        }
+        intents
+    }
 }
 
 impl DiscordConfig {
@@ -74,236 +274,975 @@ impl DiscordConfig {
     }
 }
 
-struct SaisClient {
-    sais_config: SaisConfig,
-    http_client: reqwest::blocking::Client,
-    login_details: LoginDetails,
-    cookies: String,
-    emoji_cache: HashMap<String, serenity::model::guild::Emoji>,
+/// Data shared by every command invocation on every shard.
+pub struct Data {
+    // Shared with the background monitor.
+    route_checker: Arc<RouteChecker>,
+    // The bot can use custom emojis from any server it is in, so a single
+    // cache built from the configured server is shared by every guild.
+    emoji_cache: HashMap<String, Emoji>,
+    transactions: Vec<Transaction>,
+    probe_transport: ReqwestTransport,
+    history: Option<Arc<dyn HistoryStore>>,
+    command_metrics: Arc<CommandMetrics>,
+    guild_settings: Arc<GuildSettingsStore>,
+    // What each user chose with /notify.
+    preferences: Arc<PreferencesStore>,
+    maintenance: Arc<MaintenanceStore>,
+    // Told when the gateway connection comes and goes.
+    readiness: Arc<Readiness>,
+    // Overridden by what `&config set` changes.
+    down_phrases: DownPhrases,
+    ip_lookup_url: String,
+    trace: TraceConfig,
+    // A newer release, for `&about`.
+    releases: Arc<ReleaseWatch>,
+    // For `&botstats` to tell how far behind the monitor is.
+    monitor_heartbeat: Heartbeat,
+    // As in the config files, for `&config` to show under the settings.
+    monitor_config: MonitorConfig,
+    // Changes the monitor config by the time of year, for `&config` too.
+    calendar: CalendarConfig,
+    // Recurring problems found in the history, for `&forecast`.
+    pattern_watch: Arc<PatternWatch>,
+    // `&sais` counts toward the current outage's summary.
+    outage_log: Arc<OutageLog>,
+    alerts_config: Option<AlertsConfig>,
+    // What `&config set` changes.
+    settings: Arc<SettingsStore>,
+    // Holds back announcements while the bot is cut off from Discord.
+    gateway: Arc<GatewayWatch>,
+    // Commands are left to the leader, when set.
+    leadership: Option<Arc<Leadership>>,
+    // Cooldowns, the latest status and the check lock.
+    state: Arc<dyn SharedState>,
+    instance_id: String,
+    // Rewords `&sais`'s replies, when set.
+    hooks: Option<Arc<Hooks>>,
+    spam_guard: SpamGuard,
+    // Who answers escalations, for `&oncall`, when set.
+    oncall: Option<Arc<OnCall>>,
 }
 
-struct SaisClientContainer;
+pub type Context<'a> = poise::Context<'a, Data, Error>;
 
-impl TypeMapKey for SaisClientContainer {
-    type Value = Arc<Mutex<SaisClient>>;
+async fn cache_server_emojis(
+    ctx: &serenity::Context,
+    discord_config: &DiscordConfig,
+) -> Result<HashMap<String, Emoji>, Error> {
+    let server_emojis = ctx
+        .http
+        .get_guild(discord_config.up_cebu_discord_server_id)
+        .await?
+        .emojis;
+
+    let mut emoji_cache = HashMap::default();
+    for (k, v) in &discord_config.emoji_ids {
+        let emoji = server_emojis
+            .get(&EmojiId(*v))
+            .ok_or_else(|| format!("Could not find emoji with ID {:?}", v))?;
+        emoji_cache.insert(k.clone(), emoji.clone());
+    }
+
+    Ok(emoji_cache)
 }
 
-impl SaisClient {
-    fn new() -> SaisClient {
-        SaisClient {
-            sais_config: SaisConfig::get().expect("Could not get SaisConfig"),
-            http_client: reqwest::blocking::Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .unwrap(),
-            login_details: LoginDetails::get(),
-            cookies: String::new(),
-            emoji_cache: HashMap::default(),
+async fn event_handler(
+    ctx: &serenity::Context,
+    event: &poise::Event<'_>,
+    _framework: poise::FrameworkContext<'_, Data, Error>,
+    data: &Data,
+) -> Result<(), Error> {
+    if let poise::Event::ShardStageUpdate { update } = event {
+        tracing::info!("Shard {} is {}", update.shard_id, update.new);
+        let connected = update.new == serenity::gateway::ConnectionStage::Connected;
+        data.readiness.set_gateway_connected(connected);
+        if connected {
+            reconnected(ctx, &data.gateway).await;
+        } else {
+            data.gateway.disconnected(Utc::now());
         }
     }
-
-    fn get_response(&self) -> Result<reqwest::blocking::Response, impl std::error::Error> {
-        self.http_client.get(&self.sais_config.login_url).send()
+    if let poise::Event::Resume { .. } = event {
+        tracing::info!("Resumed the gateway session");
+        reconnected(ctx, &data.gateway).await;
     }
-
-    fn can_login(&self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        let params = [
-            (
-                "timezoneOffset",
-                format!("{}", self.login_details.timezoneOffset),
-            ),
-            ("userid", format!("{}", self.login_details.userid)),
-            ("pwd", format!("{}", self.login_details.pwd)),
-            ("request_id", format!("{}", self.login_details.request_id)),
-        ];
-
-        let response = self
-            .http_client
-            .post(&self.sais_config.login_url)
-            .form(&params)
-            .header(reqwest::header::USER_AGENT, "Is UP SAIS down?/1.0")
-            .header(reqwest::header::COOKIE, &self.cookies)
-            .send()?;
-
-        let result_text = response.text()?;
-        if result_text.contains(&self.sais_config.login_success_string) {
-            println!(
-                "Found {:?} in response body.\nLogin success",
-                &self.sais_config.login_success_string
+    // Called when a shard is booted, and a READY payload is sent by Discord.
+    if let poise::Event::Ready { data_about_bot } = event {
+        tracing::info!(
+            "{} is connected on shard {:?}!",
+            data_about_bot.user.name,
+            data_about_bot.shard
+        );
+    }
+    if let poise::Event::GuildCreate {
+        guild,
+        is_new: true,
+    } = event
+    {
+        tracing::info!("Joined guild {}", guild.id);
+        if let Err(why) = setup::welcome(ctx, guild).await {
+            tracing::warn!(
+                "Could not welcome the owner of guild {}: {:?}",
+                guild.id,
+                why
             );
-            Ok(true)
-        } else if result_text.contains("Your UP Email ID and/or Password are invalid.") {
-            println!("Login credentials are invalid");
-            Ok(false)
-        } else {
-            println!(
-                "Could not find {:?} in response body",
-                &self.sais_config.login_success_string
+        }
+    }
+    Ok(())
+}
+
+// Whether to run the command: only on the leader, since both instances see
+// every command, and only once the user's cooldown is over.
+async fn check_command(ctx: Context<'_>) -> Result<bool, Error> {
+    let data = ctx.data();
+    if !leader::leads(data.leadership.as_deref()) {
+        return Ok(false);
+    }
+    // Other bots and webhooks could trigger each other, or the bot, forever.
+    if let poise::Context::Prefix(prefix) = ctx {
+        if prefix.msg.author.bot || prefix.msg.webhook_id.is_some() {
+            return Ok(false);
+        }
+    }
+    let author_id = ctx.author().id;
+    if !ctx.framework().options().owners.contains(&author_id) {
+        match data
+            .spam_guard
+            .record(author_id.0, std::time::Instant::now())
+        {
+            Verdict::Allowed => {}
+            Verdict::NowIgnored(ignore_for) => {
+                tracing::warn!("Ignoring {} for sending too many commands", author_id);
+                let message = format!(
+                    "That's a lot of commands, so I'll ignore yours for the next {} minutes.",
+                    ignore_for.as_secs() / 60
+                );
+                ctx.send(|m| m.content(message).ephemeral(true)).await?;
+                return Ok(false);
+            }
+            Verdict::Ignored => return Ok(false),
+        }
+    }
+    if !has_access(ctx).await {
+        let message = format!(
+            "You can't use `{}` in this server.",
+            ctx.command().qualified_name
+        );
+        ctx.send(|m| m.content(message).ephemeral(true)).await?;
+        return Ok(false);
+    }
+    let command = &ctx.command().name;
+    let cooldown = match commands::user_cooldown(command) {
+        Some(cooldown) => cooldown,
+        None => return Ok(true),
+    };
+    let key = format!("{}:{}", command, ctx.author().id);
+    match data.state.take_cooldown(&key, cooldown).await {
+        Ok(None) => Ok(true),
+        Ok(Some(left)) => {
+            let message = format!(
+                "You're too fast. Please wait {} seconds before retrying",
+                left.as_secs()
             );
+            ctx.send(|m| m.content(message).ephemeral(true)).await?;
             Ok(false)
         }
+        // Better to let a command through than to lock everyone out.
+        Err(why) => {
+            tracing::warn!("Could not check the cooldown of `{}`: {:?}", command, why);
+            Ok(true)
+        }
     }
+}
 
-    fn save_cookies_from_response(&mut self, response: &reqwest::blocking::Response) {
-        let set_cookie_iter = response.headers().get_all(reqwest::header::SET_COOKIE);
+// Whether the server's `/access` rules let the author use the command.
+// Commands in DMs have none.
+async fn has_access(ctx: Context<'_>) -> bool {
+    let settings = match ctx
+        .guild_id()
+        .and_then(|guild_id| ctx.data().guild_settings.get(guild_id.0))
+    {
+        Some(settings) if !settings.command_access.is_empty() => settings,
+        _ => return true,
+    };
+    let author = ctx.author();
+    let mut caller = Caller {
+        user_id: author.id.0,
+        is_owner: ctx.framework().options().owners.contains(&author.id),
+        ..Caller::default()
+    };
+    if let Some(member) = ctx.author_member().await {
+        let cache = ctx.serenity_context();
+        caller.roles = member
+            .roles(cache)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|role| (role.id.0, role.name))
+            .collect();
+        // Named like "MANAGE_GUILD | SEND_MESSAGES".
+        caller.permissions = member
+            .permissions(cache)
+            .map(|permissions| {
+                format!("{:?}", permissions)
+                    .split(" | ")
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+    settings.allows(&ctx.command().qualified_name, &caller)
+}
 
-        for cookie in set_cookie_iter {
-            self.cookies = format!("{};{}", self.cookies, cookie.to_str().unwrap());
+// Logs how long the bot was cut off, if it was for long, and sends what was
+// held back meanwhile.
+async fn reconnected(ctx: &serenity::Context, gateway: &GatewayWatch) {
+    if let Some(gap) = gateway.connected(Utc::now()) {
+        tracing::warn!(
+            "Was disconnected from Discord for {}s, since {}",
+            gap.duration().num_seconds(),
+            gap.from
+        );
+    }
+    for queued in gateway.take_queued() {
+        let sent = serenity::ChannelId(queued.channel_id)
+            .say(
+                &ctx.http,
+                format!(
+                    "{}\n(Late, since I was disconnected from Discord.)",
+                    queued.content
+                ),
+            )
+            .await;
+        match sent {
+            Ok(message) => alerts::publish(&ctx.http, &message).await,
+            Err(why) => {
+                tracing::error!("Could not send a held back announcement: {:?}", why);
+                sentry::capture_error(&why);
+            }
         }
     }
+}
 
-    fn clear_cookies(&mut self) {
-        self.cookies.clear();
+async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    match error {
+        // Command errors can carry request details, so keep them in the logs
+        // and give the user a generic reply instead.
+        poise::FrameworkError::Command { error, ctx } => {
+            record_command(ctx, false).await;
+            tracing::error!("Error in command `{}`: {:?}", ctx.command().name, error);
+            sentry::capture_error(error.as_ref());
+            let _ = ctx.say("Something went wrong while checking.").await;
+        }
+        // The standby leaves commands to the leader.
+        poise::FrameworkError::CommandCheckFailed { error: None, .. } => {}
+        error => {
+            if let Err(why) = poise::builtins::on_error(error).await {
+                tracing::error!("Error while handling error: {:?}", why);
+            }
+        }
     }
 }
 
-struct Handler;
+// When the command being handled started, for its metrics.
+struct CommandStarted(std::time::Instant);
 
-impl EventHandler for Handler {
-    // Set a handler to be called on the `ready` event. This is called when a
-    // shard is booted, and a READY payload is sent by Discord. This payload
-    // contains data like the current user's guild Ids, current user data,
-    // private channels, and more.
-    //
-    // In this case, just print what the current user's username is.
-    fn ready(&self, ctx: Context, ready: Ready) {
-        println!("{} is connected!", ready.user.name);
-
-        let discord_config = DiscordConfig::get().expect("Could not get DiscordConfig");
-        let mut data = ctx.data.write();
-        let mut sais_client = data
-            .get_mut::<SaisClientContainer>()
-            .expect("Could not get SaisClientContainer")
-            .lock();
-        let server_emojis = &ctx
-            .http
-            .get_guild(discord_config.up_cebu_discord_server_id)
-            .expect("Could not get Discord server")
-            .emojis;
-
-        for (k, v) in discord_config.emoji_ids {
-            sais_client.emoji_cache.insert(
-                k,
-                server_emojis
-                    .get(&EmojiId(v))
-                    .expect(&format!("Could not find emoji with ID {:?}", v))
-                    .clone(),
-            );
-        }
-
-        println!("Cached server emojis");
+async fn record_command(ctx: Context<'_>, succeeded: bool) {
+    let started = ctx.invocation_data::<CommandStarted>().await;
+    if let Some(started) = started.as_deref() {
+        ctx.data().command_metrics.record(
+            &ctx.command().qualified_name,
+            started.0.elapsed(),
+            succeeded,
+        );
     }
 }
 
 fn main() {
-    // Configure the client with your Discord bot token in the environment.
-    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+    let args = parse_args().expect("Could not read the arguments");
+    if let Some(directory) = &args.generate_config {
+        sample_config::write(std::path::Path::new(directory))
+            .expect("Could not generate the config");
+        return;
+    }
+    // The config and data files come back before they are read, so the
+    // restored ones are used.
+    let restored_backup = args
+        .restore
+        .as_deref()
+        .map(restore_backup)
+        .transpose()
+        .expect("Could not restore the backup");
+    let bot_config = BotConfig::get().expect("Could not get BotConfig");
+    redact::add_secrets_from_env();
+    #[cfg(feature = "web")]
+    for api_key in bot_config.web.iter().flat_map(|web| &web.api_keys) {
+        redact::add_secret(&api_key.key);
+    }
+    let _log_file = logging::init(bot_config.log_file.as_ref());
+    if let Some(backup) = &restored_backup {
+        tracing::info!(
+            "Restored the config from a backup made {}",
+            backup.created_at
+        );
+    }
 
-    let framework = StandardFramework::new()
-        .configure(|c| c.with_whitespace(true).prefix("&"))
-        .bucket("sais", |b| b.delay(5))
-        .group(&GENERAL_GROUP);
+    // Sentry has to be set up before the runtime starts its threads. The
+    // guard flushes pending reports when it is dropped at the end of `main`.
+    supervisor::install_panic_hook();
+    let _sentry = bot_config.sentry_dsn.as_deref().map(|dsn| {
+        let mut options = sentry::ClientOptions::new();
+        options.release = sentry::release_name!();
+        options.before_send = Some(Arc::new(|event| Some(redact_event(event))));
+        options.before_breadcrumb = Some(Arc::new(|mut breadcrumb| {
+            breadcrumb.message = breadcrumb.message.map(|message| redact::redact(&message));
+            Some(breadcrumb)
+        }));
+        sentry::init((dsn, options))
+    });
 
-    // Create a new instance of the Client, logging in as a bot. This will
-    // automatically prepend your bot token with "Bot ", which is a requirement
-    // by Discord for bot users.
-    let mut client = serenity::Client::new(&token, Handler).expect("Error creating client");
-    client.with_framework(framework);
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Could not build the Tokio runtime")
+        .block_on(run(bot_config, restored_backup, args.webhook_only));
+}
 
-    let sais_client_container = Arc::new(Mutex::new(SaisClient::new()));
-    {
-        let mut data = client.data.write();
-        data.insert::<SaisClientContainer>(Arc::clone(&sais_client_container));
+// Restarts share the task's state, like its route checker.
+async fn supervise_monitor(monitor_task: alerts::MonitorTask, heartbeat: Heartbeat) {
+    let monitor_task = Arc::new(monitor_task);
+    supervisor::supervise(heartbeat, move || monitor_task.clone().run()).await;
+}
+
+#[cfg(feature = "web")]
+const WEB_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+// Serves until the server fails, beating the heartbeat whenever /healthz
+// answers. `listener` is only bound again when not given.
+#[cfg(feature = "web")]
+async fn serve_web(
+    address: std::net::SocketAddr,
+    listener: Option<std::net::TcpListener>,
+    state: WebState,
+    heartbeat: Heartbeat,
+) {
+    let listener = match listener.map_or_else(|| std::net::TcpListener::bind(address), Ok) {
+        Ok(listener) => listener,
+        Err(why) => {
+            tracing::error!("Could not listen for the web server: {:?}", why);
+            return;
+        }
+    };
+    let healthz_url = match address.ip() {
+        ip if ip.is_unspecified() && ip.is_ipv4() => {
+            format!("http://127.0.0.1:{}/healthz", address.port())
+        }
+        ip if ip.is_unspecified() => format!("http://[::1]:{}/healthz", address.port()),
+        _ => format!("http://{}/healthz", address),
+    };
+    let probes = async {
+        let http_client = reqwest::Client::new();
+        let mut interval = tokio::time::interval(WEB_HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let probe = http_client
+                .get(&healthz_url)
+                .timeout(Duration::from_secs(10))
+                .send()
+                .await;
+            if probe.is_ok_and(|response| response.status().is_success()) {
+                heartbeat.beat();
+            }
+        }
+    };
+    tokio::select! {
+        result = web::serve(listener, state) => {
+            if let Err(why) = result {
+                tracing::error!("Web server error: {:?}", why);
+            }
+        }
+        _ = probes => {}
     }
+}
 
-    // Finally, start a single shard, and start listening to events.
-    //
-    // Shards will automatically attempt to reconnect, and will perform
-    // exponential backoff until it reconnects.
-    if let Err(why) = client.start() {
-        println!("Client error: {:?}", why);
+fn redact_event(mut event: sentry::protocol::Event<'static>) -> sentry::protocol::Event<'static> {
+    event.message = event.message.map(|message| redact::redact(&message));
+    for exception in event.exception.values.iter_mut() {
+        exception.value = exception.value.as_deref().map(redact::redact);
+    }
+    for breadcrumb in event.breadcrumbs.values.iter_mut() {
+        breadcrumb.message = breadcrumb.message.as_deref().map(redact::redact);
     }
+    event
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    // `--restore <file>` puts back a backup made with `&backup`.
+    restore: Option<String>,
+    // `--webhook-only` skips the gateway and only announces status changes
+    // through `DISCORD_WEBHOOK_URL`.
+    webhook_only: bool,
+    // `--generate-config <directory>` writes the sample config files and
+    // their schemas there, and exits.
+    generate_config: Option<String>,
 }
 
-#[group]
-#[commands(sais)]
-struct General;
-
-#[command]
-#[bucket = "sais"]
-fn sais(ctx: &mut Context, msg: &Message) -> CommandResult {
-    let _ = msg.channel_id.say(&ctx.http, "Let me check... :thinking:");
-
-    let mut data = ctx.data.write();
-    let mut sais_client = match data.get_mut::<SaisClientContainer>() {
-        Some(v) => v.lock(),
-        None => {
-            let _ = msg.reply(&ctx, "Could not get the SAIS client.");
-            return Ok(());
+fn parse_args() -> Result<Args, Error> {
+    let mut parsed = Args::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--restore" => {
+                parsed.restore = Some(
+                    args.next()
+                        .ok_or("Expected a backup file after --restore")?,
+                )
+            }
+            "--webhook-only" => parsed.webhook_only = true,
+            "--generate-config" => {
+                parsed.generate_config = Some(
+                    args.next()
+                        .ok_or("Expected a directory after --generate-config")?,
+                )
+            }
+            _ => return Err(format!("Unknown argument {:?}", arg).into()),
         }
-    };
+    }
+    Ok(parsed)
+}
 
-    println!("Checking SAIS at '{}'", &sais_client.sais_config.login_url);
-
-    let mut reply_message = MessageBuilder::new();
-    let query_time_string = current_time_utc_plus_8().format("%H:%M:%S").to_string();
-    reply_message
-        .push("As of ")
-        .push(query_time_string)
-        .push(", ");
-
-    let response = sais_client.get_response();
-    if let Err(why) = response {
-        println!("Could not get response: {:?}", why);
-        reply_message
-            .push("dili na gyud muload ")
-            .emoji(sais_client.emoji_cache.get("response_fail").unwrap());
-        let _ = msg.reply(&ctx, reply_message.build());
-        return Ok(());
-    }
-    println!("Got a response");
-
-    let response = response.unwrap();
-    if !response.status().is_success() {
-        println!("Unsuccessful status code {:?}", response.status());
-        reply_message
-            .push("UP SAIS is down... ")
-            .emoji(sais_client.emoji_cache.get("status_code_fail").unwrap());
-        let _ = msg.reply(&ctx, reply_message.build());
-        return Ok(());
-    }
-    println!("Successful status code {:?}", response.status());
-
-    sais_client.clear_cookies();
-    sais_client.save_cookies_from_response(&response);
-    println!(
-        "Cookies size: {:?}, capacity: {:?}",
-        sais_client.cookies.len(),
-        sais_client.cookies.capacity()
-    );
+/// Puts back the backup at `path`. The passphrase is read from
+/// `BACKUP_PASSPHRASE`.
+fn restore_backup(path: &str) -> Result<Backup, Error> {
+    let passphrase = env::var("BACKUP_PASSPHRASE")
+        .map_err(|_| "Expected BACKUP_PASSPHRASE in the environment")?;
+    let backup = Backup::decrypt(&std::fs::read(path)?, &passphrase)?;
+    backup.restore_config(backup::CONFIG_DIRECTORY)?;
+    backup.restore_data(".")?;
+    Ok(backup)
+}
 
-    match sais_client.can_login() {
-        Ok(did_succeed) => {
-            if did_succeed {
-                reply_message
-                    .push("UP SAIS is up! ")
-                    .emoji(sais_client.emoji_cache.get("login_ok").unwrap());
-            } else {
-                reply_message
-                    .push("UP SAIS is up, but there are login problems. ")
-                    .emoji(sais_client.emoji_cache.get("login_fail").unwrap());
-            }
+/// Compacts the history now and then, for as long as the bot runs.
+async fn compact_history(history: Arc<dyn HistoryStore>, retention: RetentionConfig) {
+    let mut interval = tokio::time::interval(retention.compact_every());
+    loop {
+        interval.tick().await;
+        if let Err(why) = retention
+            .compact(history.as_ref(), chrono::Utc::now())
+            .await
+        {
+            tracing::error!("Could not compact the history: {:?}", why);
         }
-        Err(why) => {
-            return Err(why.into());
+    }
+}
+
+/// Mines the history for recurring problems now and then, for `&forecast`.
+async fn mine_patterns(
+    history: Arc<dyn HistoryStore>,
+    calendar: CalendarConfig,
+    pattern_watch: Arc<PatternWatch>,
+) {
+    let mut interval = tokio::time::interval(forecast::MINE_EVERY);
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+        match history
+            .since(now - chrono::Duration::days(forecast::LOOKBACK_DAYS))
+            .await
+        {
+            Ok(records) => pattern_watch.mined(Patterns::mine(&records, &calendar, now)),
+            Err(why) => tracing::error!("Could not mine the history: {:?}", why),
         }
     }
-    let _ = msg.reply(&ctx, reply_message.build());
+}
 
-    Ok(())
+fn new_route_checker(
+    hooks: Option<Arc<Hooks>>,
+    check_timings: Arc<TimingHistograms>,
+) -> RouteChecker {
+    let route_checker = RouteChecker::new(SaisClient::new()).with_timings(check_timings);
+    match hooks {
+        Some(hooks) => route_checker.with_hooks(hooks),
+        None => route_checker,
+    }
+}
+
+async fn run(bot_config: BotConfig, restored_backup: Option<Backup>, webhook_only: bool) {
+    let history = match &bot_config.history {
+        Some(history_config) => Some(
+            history_config
+                .open()
+                .await
+                .expect("Could not open the history database"),
+        ),
+        None => None,
+    };
+    if let (Some(backup), Some(history)) = (&restored_backup, &history) {
+        match backup.restore_history(history.as_ref()).await {
+            Ok(true) => tracing::info!(
+                "Restored {} checks and {} hourly rollups from the backup",
+                backup.history.len(),
+                backup.rollups.len()
+            ),
+            Ok(false) => tracing::warn!("Not restoring the backup's history over existing checks"),
+            Err(why) => panic!("Could not restore the backup's history: {:?}", why),
+        }
+    }
+    let pattern_watch = Arc::new(PatternWatch::default());
+    if let Some(history) = &history {
+        tokio::spawn(compact_history(history.clone(), bot_config.retention));
+        tokio::spawn(mine_patterns(
+            history.clone(),
+            bot_config.calendar.clone(),
+            pattern_watch.clone(),
+        ));
+    }
+    let leadership = match &bot_config.leader_election {
+        Some(leader_config) => {
+            let history = history
+                .clone()
+                .expect("Leader election needs the history to be set");
+            let leadership = Arc::new(Leadership::new(leader_config));
+            leadership
+                .renew(history.as_ref(), Utc::now())
+                .await
+                .expect("Could not take part in the leader election");
+            if !leadership.is_leader() {
+                tracing::info!("{} is on standby", leadership.instance_id());
+            }
+            tokio::spawn(leadership.clone().hold(history));
+            Some(leadership)
+        }
+        None => None,
+    };
+    let state = bot_config
+        .state_backend
+        .open()
+        .expect("Could not set up the state backend");
+    let instance_id = leadership
+        .as_ref()
+        .map_or_else(leader::default_instance_id, |leadership| {
+            leadership.instance_id().to_string()
+        });
+    let guild_settings = Arc::new(
+        GuildSettingsStore::open(GUILD_SETTINGS_FILEPATH)
+            .expect("Could not load the guild settings"),
+    );
+    let preferences = Arc::new(
+        PreferencesStore::open(PREFERENCES_FILEPATH)
+            .expect("Could not load the notification preferences"),
+    );
+    let maintenance = Arc::new(
+        MaintenanceStore::open(MAINTENANCE_FILEPATH)
+            .expect("Could not load the maintenance windows"),
+    );
+    let monitor_health = Arc::new(MonitorHealth::default());
+    let readiness = Arc::new(Readiness::new(
+        monitor_health.clone(),
+        bot_config.monitor.interval(),
+        !webhook_only,
+    ));
+    let command_metrics = Arc::new(CommandMetrics::default());
+    // Filled in by the monitor's route checker.
+    let check_timings = Arc::new(TimingHistograms::default());
+    let (confirmed_statuses, statuses) = tokio::sync::watch::channel(None);
+    #[cfg(feature = "web")]
+    if let Some(web_config) = &bot_config.web {
+        // Bound here too, so a taken address stops the bot from starting.
+        let listener = std::net::TcpListener::bind(web_config.address)
+            .expect("Could not listen for the web server");
+        let dashboard = web_config.dashboard.clone().map(|dashboard_config| {
+            let client_secret = env::var("DISCORD_CLIENT_SECRET")
+                .expect("Expected a client secret in the environment for the dashboard");
+            let data = DashboardData {
+                history: history.clone(),
+                guild_settings: guild_settings.clone(),
+                maintenance: maintenance.clone(),
+                monitor_health: monitor_health.clone(),
+            };
+            Arc::new(Dashboard::new(dashboard_config, client_secret, data))
+        });
+        let state = WebState {
+            command_metrics: command_metrics.clone(),
+            check_timings: check_timings.clone(),
+            readiness: readiness.clone(),
+            history: history.clone(),
+            statuses: statuses.clone(),
+            dashboard,
+            api_keys: (!web_config.api_keys.is_empty())
+                .then(|| Arc::new(ApiKeys::new(web_config.api_keys.clone()))),
+        };
+        let address = web_config.address;
+        let heartbeat = Heartbeat::new(
+            "web server",
+            WEB_HEARTBEAT_INTERVAL + supervisor::STUCK_AFTER,
+        );
+        let mut listener = Some(listener);
+        tokio::spawn(supervisor::supervise(heartbeat.clone(), move || {
+            serve_web(address, listener.take(), state.clone(), heartbeat.clone())
+        }));
+        tracing::info!("Serving the web server on {}", web_config.address);
+    }
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_config) = &bot_config.grpc {
+        let listener = tokio::net::TcpListener::bind(grpc_config.address)
+            .await
+            .expect("Could not listen for the gRPC service");
+        tokio::spawn(grpc::serve(listener, statuses.clone()));
+        tracing::info!("Serving the gRPC service on {}", grpc_config.address);
+    }
+    if let Some(broker_config) = bot_config.broker.clone() {
+        tokio::spawn(broker::publish_changes(broker_config, statuses.clone()));
+    }
+    let monitor_config = bot_config.monitor;
+    let calendar = bot_config.calendar;
+    let settings = Arc::new(
+        SettingsStore::open(RUNTIME_SETTINGS_FILEPATH, monitor_config.interval_secs)
+            .expect("Could not load the runtime settings"),
+    );
+    let monitor_heartbeat = Heartbeat::new(
+        "monitor",
+        monitor_config.interval() + supervisor::STUCK_AFTER,
+    );
+    let sla_config = bot_config.sla;
+    let self_test_config = bot_config.credential_self_test;
+    let release_watch = Arc::new(ReleaseWatch::default());
+    let release_check_config = bot_config.release_check;
+    let release_check = release_check_config
+        .enabled
+        .then(|| release_check::ReleaseCheckTask {
+            config: release_check_config,
+            watch: release_watch.clone(),
+        });
+    let down_phrases = bot_config.down_phrases;
+    let screenshot_config = bot_config.screenshot;
+    let trace_config = bot_config.trace;
+    let mut exporters: Vec<Box<dyn CheckExporter>> = vec![];
+    if let Some(influx_config) = bot_config.influxdb {
+        tracing::info!("Writing checks to InfluxDB at {}", influx_config.write_url);
+        let token = env::var("INFLUXDB_TOKEN").ok();
+        exporters.push(Box::new(InfluxExporter::new(influx_config, token)));
+    }
+    if let Some(statsd_config) = bot_config.statsd {
+        tracing::info!("Sending checks to StatsD at {}", statsd_config.address);
+        exporters.push(Box::new(StatsdExporter::new(statsd_config)));
+    }
+    let hooks = bot_config.hooks.map(|hooks_config| {
+        tracing::info!("Running scripts from {}", hooks_config.dir.display());
+        Arc::new(Hooks::new(hooks_config))
+    });
+    let ip_lookup_url = bot_config
+        .ip_lookup_url
+        .unwrap_or_else(|| hostinfo::DEFAULT_IP_LOOKUP_URL.to_string());
+
+    if webhook_only {
+        let webhook_url =
+            env::var("DISCORD_WEBHOOK_URL").expect("Expected a webhook URL in the environment");
+        // Executing a webhook only needs the token in its URL.
+        let http = Arc::new(serenity::Http::new(""));
+        let webhook = http
+            .get_webhook_from_url(&webhook_url)
+            .await
+            .expect("Could not get the webhook");
+        let monitor_task = alerts::MonitorTask {
+            http,
+            route_checker: Arc::new(new_route_checker(hooks.clone(), check_timings.clone())),
+            monitor_config,
+            calendar,
+            alerts_config: None,
+            outage_log: Arc::new(OutageLog::default()),
+            post_mortems: None,
+            // discord.ron isn't read, so there's nobody to escalate to.
+            escalation: EscalationPolicy::default(),
+            pager: None,
+            oncall: None,
+            incident_issues: None,
+            // Webhook messages can't be replied to.
+            reminders: ReminderConfig {
+                enabled: false,
+                ..ReminderConfig::default()
+            },
+            // Posting in a channel needs the gateway.
+            latency_alerts: None,
+            settings,
+            owner_id: None,
+            maintainer_ids: vec![],
+            webhook: Some(webhook),
+            history,
+            // The uptime target is reported in the alerts channel, which
+            // needs the gateway.
+            sla_config: None,
+            guild_settings,
+            preferences,
+            maintenance,
+            health: monitor_health,
+            confirmed_statuses,
+            status_message_channel_id: None,
+            screenshot: screenshot_config,
+            exporters,
+            heartbeat: monitor_heartbeat.clone(),
+            gateway: None,
+            leadership: leadership.clone(),
+            state: state.clone(),
+            instance_id: instance_id.clone(),
+            hooks,
+        };
+        tracing::info!("Monitoring SAIS, announcing through the webhook only");
+        systemd::ready();
+        supervise_monitor(monitor_task, monitor_heartbeat).await;
+        return;
+    }
+
+    // Configure the client with your Discord bot token in the environment.
+    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+    let discord_config = DiscordConfig::get().expect("Could not get DiscordConfig");
+    let intents = discord_config.intents.gateway_intents();
+    let gateway = Arc::new(GatewayWatch::new(chrono::Duration::seconds(
+        discord_config.gateway_outage_after_secs as i64,
+    )));
+    let transactions = transaction::get_transactions().expect("Could not get transactions");
+    let outage_log = Arc::new(OutageLog::default());
+    let oncall = discord_config.oncall.clone().map(|rotation| {
+        Arc::new(
+            OnCall::open(rotation, ONCALL_FILEPATH).expect("Could not load the on-call override"),
+        )
+    });
+
+    let framework = poise::Framework::builder()
+        .options(poise::FrameworkOptions {
+            commands: vec![
+                commands::sais(),
+                commands::check(),
+                commands::probe(),
+                commands::diagnose(),
+                commands::har(),
+                commands::lastresponse(),
+                commands::hostinfo(),
+                commands::trace(),
+                commands::export(),
+                commands::heatmap(),
+                commands::reliability(),
+                commands::forecast(),
+                commands::compare(),
+                commands::botstats(),
+                setup::setup(),
+                setup::access(),
+                setup::escalation(),
+                setup::notify(),
+                setup::mute(),
+                setup::mute_alerts(),
+                setup::mutes(),
+                commands::backup(),
+                commands::spam(),
+                commands::oncall(),
+                commands::config(),
+                commands::about(),
+                commands::help(),
+            ],
+            prefix_options: poise::PrefixFrameworkOptions {
+                prefix: Some("&".into()),
+                mention_as_prefix: true,
+                ..Default::default()
+            },
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(event_handler(ctx, event, framework, data))
+            },
+            on_error: |error| Box::pin(on_error(error)),
+            pre_command: |ctx| {
+                Box::pin(async move {
+                    ctx.set_invocation_data(CommandStarted(std::time::Instant::now()))
+                        .await;
+                })
+            },
+            post_command: |ctx| Box::pin(record_command(ctx, true)),
+            command_check: Some(|ctx| Box::pin(check_command(ctx))),
+            // Kept in the shared state instead, by `check_command`.
+            manual_cooldowns: true,
+            ..Default::default()
+        })
+        .token(token)
+        .intents(intents)
+        // The setup runs once, when the first shard is ready.
+        .setup(move |ctx, _ready, framework| {
+            Box::pin(async move {
+                // The setup runs on the first READY, so the gateway is up.
+                readiness.set_gateway_connected(true);
+                systemd::ready();
+                // Registers `/sais` and friends alongside the `&` prefix
+                // commands.
+                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                tracing::info!("Registered slash commands");
+
+                let emoji_cache = cache_server_emojis(ctx, &discord_config).await?;
+                tracing::info!("Cached server emojis");
+
+                let route_checker =
+                    Arc::new(new_route_checker(hooks.clone(), check_timings.clone()));
+                // SAIS is always monitored, since servers can choose to
+                // have changes announced with /setup at any time.
+                let owner_id = ctx.http.get_current_application_info().await?.owner.id;
+                supervisor::report_to_owner(ctx.http.clone(), owner_id);
+                if let Some(release_check) = release_check {
+                    tokio::spawn(release_check.run());
+                }
+                let maintainer_ids = discord_config
+                    .maintainer_ids
+                    .iter()
+                    .map(|&id| serenity::UserId(id))
+                    .collect::<Vec<_>>();
+                if let Some(surfaces_config) = discord_config.status_surfaces.clone() {
+                    let heartbeat = Heartbeat::new(
+                        "status surfaces",
+                        Duration::from_secs(surfaces_config.min_interval_secs)
+                            + surfaces::IDLE_HEARTBEAT_INTERVAL
+                            + supervisor::STUCK_AFTER,
+                    );
+                    let ctx = ctx.clone();
+                    tokio::spawn(supervisor::supervise(heartbeat.clone(), move || {
+                        surfaces::StatusSurfaces {
+                            ctx: ctx.clone(),
+                            config: surfaces_config.clone(),
+                            statuses: statuses.clone(),
+                            heartbeat: heartbeat.clone(),
+                        }
+                        .run()
+                    }));
+                }
+                let monitor_task = alerts::MonitorTask {
+                    http: ctx.http.clone(),
+                    route_checker: route_checker.clone(),
+                    monitor_config: monitor_config.clone(),
+                    calendar: calendar.clone(),
+                    alerts_config: discord_config.alerts.clone(),
+                    reminders: discord_config.still_down_reminders.clone(),
+                    outage_log: outage_log.clone(),
+                    post_mortems: discord_config.post_mortems.clone(),
+                    escalation: discord_config.escalation.clone(),
+                    pager: discord_config.pager.clone().map(|pager_config| {
+                        let routing_key = env::var("PAGER_ROUTING_KEY")
+                            .expect("Expected a pager routing key in the environment");
+                        Pager::new(pager_config, routing_key)
+                    }),
+                    oncall: oncall.clone(),
+                    incident_issues: discord_config.incident_issues.clone().map(|config| {
+                        let token = env::var("GITHUB_TOKEN")
+                            .expect("Expected a GitHub token in the environment");
+                        IncidentIssues::new(config, token)
+                    }),
+                    latency_alerts: discord_config.latency_alerts.clone(),
+                    settings: settings.clone(),
+                    owner_id: Some(owner_id),
+                    maintainer_ids: maintainer_ids.clone(),
+                    webhook: None,
+                    history: history.clone(),
+                    sla_config,
+                    guild_settings: guild_settings.clone(),
+                    preferences: preferences.clone(),
+                    maintenance: maintenance.clone(),
+                    health: monitor_health,
+                    confirmed_statuses,
+                    status_message_channel_id: discord_config
+                        .status_surfaces
+                        .as_ref()
+                        .and_then(|surfaces| surfaces.status_message_channel_id),
+                    screenshot: screenshot_config,
+                    exporters,
+                    heartbeat: monitor_heartbeat.clone(),
+                    gateway: Some(gateway.clone()),
+                    leadership: leadership.clone(),
+                    state: state.clone(),
+                    instance_id: instance_id.clone(),
+                    hooks: hooks.clone(),
+                };
+                tokio::spawn(supervise_monitor(monitor_task, monitor_heartbeat.clone()));
+                tracing::info!("Started monitoring SAIS");
+                if self_test_config.enabled {
+                    let mut maintainers = vec![owner_id];
+                    maintainers.extend(maintainer_ids.iter().filter(|&&id| id != owner_id));
+                    let self_test = self_test::CredentialSelfTest {
+                        http: ctx.http.clone(),
+                        maintainers,
+                        config: self_test_config,
+                        leadership: leadership.clone(),
+                    };
+                    tokio::spawn(self_test.run());
+                }
+                if let (Some(history), false) = (&history, discord_config.maintainer_ids.is_empty())
+                {
+                    let report_task = monthly_report::MonthlyReportTask {
+                        http: ctx.http.clone(),
+                        history: history.clone(),
+                        maintainer_ids,
+                        leadership: leadership.clone(),
+                    };
+                    tokio::spawn(report_task.run());
+                }
+                if let Some(events_config) = discord_config.maintenance_events.clone() {
+                    let events_task = maintenance_events::MaintenanceEventsTask {
+                        ctx: ctx.clone(),
+                        config: events_config,
+                        maintenance: maintenance.clone(),
+                        leadership: leadership.clone(),
+                    };
+                    tokio::spawn(events_task.run());
+                }
+
+                Ok(Data {
+                    route_checker,
+                    emoji_cache,
+                    transactions,
+                    probe_transport: ReqwestTransport::default(),
+                    history,
+                    command_metrics,
+                    guild_settings,
+                    preferences,
+                    maintenance,
+                    readiness,
+                    down_phrases,
+                    ip_lookup_url,
+                    trace: trace_config,
+                    releases: release_watch,
+                    monitor_heartbeat,
+                    monitor_config,
+                    calendar,
+                    pattern_watch,
+                    outage_log,
+                    alerts_config: discord_config.alerts.clone(),
+                    settings,
+                    gateway,
+                    leadership,
+                    state,
+                    instance_id,
+                    hooks,
+                    spam_guard: SpamGuard::new(discord_config.spam.clone()),
+                    oncall,
+                })
+            })
+        });
+
+    // Finally, start as many shards as Discord recommends for the number of
+    // servers the bot is in, and start listening to events.
+    //
+    // Shards will automatically attempt to reconnect, and will perform
+    // exponential backoff until it reconnects.
+    if let Err(why) = framework.run_autosharded().await {
+        tracing::error!("Client error: {:?}", why);
+        sentry::capture_error(&why);
+    }
 }
 
 fn current_time_utc_plus_8() -> DateTime<FixedOffset> {
-    let utc_plus_8_offset = &chrono::FixedOffset::east(3600 * 8);
+    let utc_plus_8_offset = &chrono::FixedOffset::east_opt(3600 * 8).unwrap();
     Utc::now().with_timezone(utc_plus_8_offset)
 }
+
+// Like "down for 2h 13m (since 11:50 PHT)".
+fn outage_duration_string(since: DateTime<Utc>) -> String {
+    let utc_plus_8_offset = &chrono::FixedOffset::east_opt(3600 * 8).unwrap();
+    format!(
+        "down for {} (since {} PHT)",
+        sla::format_duration(Utc::now() - since),
+        since.with_timezone(utc_plus_8_offset).format("%H:%M")
+    )
+}
@@ -0,0 +1,112 @@
+//! Planned maintenance declared by the operators, during which status
+//! changes aren't announced.
+
+use crate::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub const MAINTENANCE_FILEPATH: &str = "data/maintenance.ron";
+
+/// What the bot's Discord events for maintenance windows are named with, so
+/// they can be told apart from the server's own.
+pub const EVENT_NAME_PREFIX: &str = "SAIS maintenance: ";
+
+// Discord's limit on an event's name.
+const MAX_EVENT_NAME_CHARS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Shown to students, e.g. "Enlistment system upgrade".
+    pub note: String,
+}
+
+impl MaintenanceWindow {
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.starts_at <= time && time < self.ends_at
+    }
+
+    /// The name of the window's Discord event, cut short to fit.
+    pub fn event_name(&self) -> String {
+        format!("{}{}", EVENT_NAME_PREFIX, self.note)
+            .chars()
+            .take(MAX_EVENT_NAME_CHARS)
+            .collect()
+    }
+}
+
+/// Every declared window, saved to disk whenever they change.
+#[derive(Debug)]
+pub struct MaintenanceStore {
+    path: PathBuf,
+    windows: Mutex<Vec<MaintenanceWindow>>,
+}
+
+impl MaintenanceStore {
+    /// Loads the windows, starting with none if the file doesn't exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<MaintenanceStore, Error> {
+        let path = path.as_ref().to_path_buf();
+        let windows = match fs::read_to_string(&path) {
+            Ok(contents) => ron::de::from_str(&contents)?,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(why) => return Err(why.into()),
+        };
+        Ok(MaintenanceStore {
+            path,
+            windows: Mutex::new(windows),
+        })
+    }
+
+    /// Every window, soonest first.
+    pub fn all(&self) -> Vec<MaintenanceWindow> {
+        self.windows.lock().unwrap().clone()
+    }
+
+    /// The window `time` falls in, if any.
+    pub fn active(&self, time: DateTime<Utc>) -> Option<MaintenanceWindow> {
+        let windows = self.windows.lock().unwrap();
+        windows.iter().find(|window| window.contains(time)).cloned()
+    }
+
+    pub fn add(&self, window: MaintenanceWindow) -> Result<(), Error> {
+        let mut windows = self.windows.lock().unwrap();
+        windows.push(window);
+        windows.sort_by_key(|window| window.starts_at);
+        self.save(&windows)
+    }
+
+    /// Removes windows that ended before `time`.
+    pub fn remove_ended(&self, time: DateTime<Utc>) -> Result<(), Error> {
+        let mut windows = self.windows.lock().unwrap();
+        let count = windows.len();
+        windows.retain(|window| window.ends_at > time);
+        if windows.len() != count {
+            self.save(&windows)?;
+        }
+        Ok(())
+    }
+
+    pub fn remove(&self, index: usize) -> Result<(), Error> {
+        let mut windows = self.windows.lock().unwrap();
+        if index < windows.len() {
+            windows.remove(index);
+            self.save(&windows)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, windows: &[MaintenanceWindow]) -> Result<(), Error> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let contents = ron::ser::to_string_pretty(windows, ron::ser::PrettyConfig::default())?;
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
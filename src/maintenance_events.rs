@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use is_sais_down_bot::{
+    leader::{self, Leadership},
+    maintenance::{MaintenanceStore, MaintenanceWindow, EVENT_NAME_PREFIX},
+};
+use poise::serenity_prelude::{
+    self as serenity, GuildId, ScheduledEvent, ScheduledEventStatus, ScheduledEventType,
+    StatusCode, Timestamp,
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tokio::time::{self, MissedTickBehavior};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaintenanceEventsConfig {
+    /// Shown as where the event takes place.
+    #[serde(default = "default_location")]
+    pub location: String,
+    /// How often the events are brought in line with the windows.
+    #[serde(default = "default_interval_mins")]
+    pub interval_mins: u64,
+}
+
+fn default_location() -> String {
+    "UP SAIS (sais.up.edu.ph)".to_string()
+}
+
+fn default_interval_mins() -> u64 {
+    5
+}
+
+/// Keeps a Discord event for each upcoming maintenance window in every server
+/// the bot is in, so members can be reminded natively. Events for windows
+/// that were taken back are deleted.
+pub struct MaintenanceEventsTask {
+    pub ctx: serenity::Context,
+    pub config: MaintenanceEventsConfig,
+    pub maintenance: Arc<MaintenanceStore>,
+    // Only the leader makes events, when set.
+    pub leadership: Option<Arc<Leadership>>,
+}
+
+impl MaintenanceEventsTask {
+    /// Runs until the bot exits.
+    pub async fn run(self) {
+        let mut interval = time::interval(Duration::from_secs(self.config.interval_mins * 60));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if !leader::leads(self.leadership.as_deref()) {
+                continue;
+            }
+            let now = Utc::now();
+            let windows = self
+                .maintenance
+                .all()
+                .into_iter()
+                .filter(|window| window.ends_at > now)
+                .collect::<Vec<_>>();
+            for guild_id in self.ctx.cache.guilds() {
+                match self.sync(guild_id, &windows, now).await {
+                    Ok(()) => {}
+                    // The server didn't give the bot the Manage Events
+                    // permission, and likely doesn't want events.
+                    Err(serenity::Error::Http(why))
+                        if why.status_code() == Some(StatusCode::FORBIDDEN) =>
+                    {
+                        tracing::debug!("Not allowed to make events in {}", guild_id);
+                    }
+                    Err(why) => {
+                        tracing::warn!(
+                            "Could not sync maintenance events in {}: {:?}",
+                            guild_id,
+                            why
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    async fn sync(
+        &self,
+        guild_id: GuildId,
+        windows: &[MaintenanceWindow],
+        now: DateTime<Utc>,
+    ) -> Result<(), serenity::Error> {
+        let bot_id = self.ctx.cache.current_user_id();
+        let events = guild_id
+            .scheduled_events(&self.ctx.http, false)
+            .await?
+            .into_iter()
+            .filter(|event| {
+                event.creator_id == Some(bot_id)
+                    && matches!(event.status, ScheduledEventStatus::Scheduled)
+                    && event.name.starts_with(EVENT_NAME_PREFIX)
+            })
+            .collect::<Vec<_>>();
+
+        // Discord only takes events that haven't started yet.
+        for window in windows.iter().filter(|window| window.starts_at > now) {
+            if events.iter().any(|event| is_for(event, window)) {
+                continue;
+            }
+            tracing::info!("Making an event in {} for \"{}\"", guild_id, window.note);
+            guild_id
+                .create_scheduled_event(&self.ctx.http, |event| {
+                    event
+                        .name(window.event_name())
+                        .description(format!(
+                            "UP SAIS is expected to be down for planned maintenance: {}",
+                            window.note
+                        ))
+                        .kind(ScheduledEventType::External)
+                        .location(&self.config.location)
+                        .start_time(Timestamp::from(window.starts_at))
+                        .end_time(Timestamp::from(window.ends_at))
+                })
+                .await?;
+        }
+        for event in events
+            .iter()
+            .filter(|event| !windows.iter().any(|window| is_for(event, window)))
+        {
+            tracing::info!("Deleting event \"{}\" in {}", event.name, guild_id);
+            guild_id
+                .delete_scheduled_event(&self.ctx.http, event.id)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn is_for(event: &ScheduledEvent, window: &MaintenanceWindow) -> bool {
+    event.name == window.event_name()
+        && event.start_time.unix_timestamp() == window.starts_at.timestamp()
+        && event
+            .end_time
+            .is_some_and(|end_time| end_time.unix_timestamp() == window.ends_at.timestamp())
+}
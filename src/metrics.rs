@@ -0,0 +1,88 @@
+//! How often each command is used, and how long it takes to handle.
+
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandStats {
+    pub invocations: u64,
+    pub errors: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+}
+
+impl CommandStats {
+    pub fn mean_latency(&self) -> Duration {
+        if self.invocations == 0 {
+            return Duration::ZERO;
+        }
+        self.total_latency.div_f64(self.invocations as f64)
+    }
+}
+
+/// Counts kept since the bot started, by command name.
+#[derive(Debug, Default)]
+pub struct CommandMetrics {
+    commands: Mutex<BTreeMap<String, CommandStats>>,
+}
+
+impl CommandMetrics {
+    pub fn record(&self, command: &str, latency: Duration, succeeded: bool) {
+        let mut commands = self.commands.lock().unwrap();
+        let stats = commands.entry(command.to_string()).or_default();
+        stats.invocations += 1;
+        stats.errors += !succeeded as u64;
+        stats.total_latency += latency;
+        stats.max_latency = stats.max_latency.max(latency);
+    }
+
+    /// Every command used so far, by name.
+    pub fn snapshot(&self) -> Vec<(String, CommandStats)> {
+        let commands = self.commands.lock().unwrap();
+        commands
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.clone()))
+            .collect()
+    }
+
+    /// The metrics in the Prometheus text format.
+    pub fn to_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut text = String::new();
+        write_counter(
+            &mut text,
+            "sais_bot_command_invocations_total",
+            "Commands handled, including failed ones.",
+            &snapshot,
+            |stats| stats.invocations.to_string(),
+        );
+        write_counter(
+            &mut text,
+            "sais_bot_command_errors_total",
+            "Commands that failed.",
+            &snapshot,
+            |stats| stats.errors.to_string(),
+        );
+        write_counter(
+            &mut text,
+            "sais_bot_command_latency_seconds_total",
+            "Time spent handling commands.",
+            &snapshot,
+            |stats| stats.total_latency.as_secs_f64().to_string(),
+        );
+        text
+    }
+}
+
+fn write_counter(
+    text: &mut String,
+    name: &str,
+    help: &str,
+    snapshot: &[(String, CommandStats)],
+    value: impl Fn(&CommandStats) -> String,
+) {
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+    let _ = writeln!(text, "# TYPE {} counter", name);
+    for (command, stats) in snapshot {
+        let _ = writeln!(text, "{}{{command=\"{}\"}} {}", name, command, value(stats));
+    }
+}
@@ -0,0 +1,71 @@
+//! Schema changes to the SQLite database, applied in order at startup. The
+//! database's `user_version` records how many have been applied, so
+//! upgrading the bot never needs the file to be edited by hand.
+//!
+//! Migrations are only ever appended to, never edited, since databases out
+//! there have already applied the existing ones.
+
+use crate::Error;
+use rusqlite::Connection;
+
+const MIGRATIONS: &[&str] = &[
+    // 1: Check history. Uses IF NOT EXISTS since databases from before
+    // migrations were tracked already have it.
+    "CREATE TABLE IF NOT EXISTS checks (
+        id INTEGER PRIMARY KEY,
+        checked_at TEXT NOT NULL,
+        route TEXT NOT NULL,
+        status TEXT NOT NULL,
+        reason TEXT,
+        latency_ms INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS checks_checked_at ON checks (checked_at);",
+    // 2: Hourly rollups of compacted checks.
+    "CREATE TABLE hourly_rollups (
+        hour TEXT NOT NULL,
+        route TEXT NOT NULL,
+        status TEXT NOT NULL,
+        checks INTEGER NOT NULL,
+        total_latency_ms INTEGER NOT NULL,
+        PRIMARY KEY (hour, route, status)
+    );",
+    // 3: Leases, for leader election.
+    "CREATE TABLE leases (
+        name TEXT PRIMARY KEY,
+        holder TEXT NOT NULL,
+        expires_at TEXT NOT NULL
+    );",
+];
+
+/// The schema version this build of the bot expects.
+pub fn latest_version() -> usize {
+    MIGRATIONS.len()
+}
+
+pub fn schema_version(connection: &Connection) -> Result<usize, Error> {
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+/// Applies every migration the database doesn't have yet, each in its own
+/// transaction.
+pub fn run(connection: &mut Connection) -> Result<(), Error> {
+    let version = schema_version(connection)?;
+    if version > latest_version() {
+        return Err(format!(
+            "The database is at schema version {}, but this bot only knows up to {}",
+            version,
+            latest_version()
+        )
+        .into());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index as i64 + 1)?;
+        transaction.commit()?;
+        tracing::info!("Migrated the database to schema version {}", index + 1);
+    }
+    Ok(())
+}
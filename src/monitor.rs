@@ -0,0 +1,323 @@
+use crate::status::ServiceStatus;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::{collections::VecDeque, mem, sync::Mutex, time::Duration};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MonitorConfig {
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// How many checks in a row have to agree before a change is announced.
+    #[serde(default = "default_confirm_after")]
+    pub confirm_after: u32,
+    /// How many of the latest checks are looked at to tell if SAIS is
+    /// flapping.
+    #[serde(default = "default_flap_window")]
+    pub flap_window: usize,
+    /// How many state changes within the window count as flapping.
+    #[serde(default = "default_flap_threshold")]
+    pub flap_threshold: usize,
+}
+
+fn default_interval_secs() -> u64 {
+    300
+}
+
+fn default_confirm_after() -> u32 {
+    3
+}
+
+fn default_flap_window() -> usize {
+    12
+}
+
+fn default_flap_threshold() -> usize {
+    4
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        MonitorConfig {
+            interval_secs: default_interval_secs(),
+            confirm_after: default_confirm_after(),
+            flap_window: default_flap_window(),
+            flap_threshold: default_flap_threshold(),
+        }
+    }
+}
+
+impl MonitorConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+/// A confirmed change in what state SAIS is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub from: ServiceStatus,
+    pub to: ServiceStatus,
+}
+
+/// Turns a stream of single check results into confirmed transitions, so a
+/// single flaky check doesn't get announced.
+///
+/// Statuses only count as different states when their kind differs, e.g. two
+/// `Down` results with different reasons agree with each other.
+#[derive(Debug)]
+pub struct TransitionTracker {
+    confirm_after: u32,
+    confirmed: Option<ServiceStatus>,
+    pending: Option<(ServiceStatus, u32)>,
+}
+
+impl TransitionTracker {
+    pub fn new(confirm_after: u32) -> Self {
+        TransitionTracker {
+            confirm_after: confirm_after.max(1),
+            confirmed: None,
+            pending: None,
+        }
+    }
+
+    /// The last confirmed status, if there has been one yet.
+    pub fn confirmed(&self) -> Option<ServiceStatus> {
+        self.confirmed
+    }
+
+    /// Returns a transition once `confirm_after` checks in a row agree on a
+    /// new state. The first confirmed state is not a transition, since there
+    /// is nothing to compare it with.
+    pub fn observe(&mut self, status: ServiceStatus) -> Option<Transition> {
+        if let Some(confirmed) = self.confirmed {
+            if same_state(&confirmed, &status) {
+                self.pending = None;
+                return None;
+            }
+        }
+
+        let count = match self.pending {
+            Some((pending, count)) if same_state(&pending, &status) => count + 1,
+            _ => 1,
+        };
+        if count < self.confirm_after {
+            self.pending = Some((status, count));
+            return None;
+        }
+
+        self.pending = None;
+        let from = self.confirmed.replace(status);
+        from.map(|from| Transition { from, to: status })
+    }
+}
+
+/// Something worth telling the alerts channel about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alert {
+    Changed(Transition),
+    /// SAIS started flapping. Changes are not announced until it settles.
+    Unstable,
+    /// SAIS stopped flapping, and is now in this state.
+    Stable(ServiceStatus),
+}
+
+/// Debounces check results like [`TransitionTracker`], and also collapses
+/// rapid back and forth changes into a single `Unstable` alert.
+#[derive(Debug)]
+pub struct Monitor {
+    tracker: TransitionTracker,
+    recent: VecDeque<ServiceStatus>,
+    flap_window: usize,
+    flap_threshold: usize,
+    flapping: bool,
+}
+
+impl Monitor {
+    pub fn new(monitor_config: &MonitorConfig) -> Self {
+        Monitor {
+            tracker: TransitionTracker::new(monitor_config.confirm_after),
+            recent: VecDeque::new(),
+            flap_window: monitor_config.flap_window.max(2),
+            flap_threshold: monitor_config.flap_threshold.max(1),
+            flapping: false,
+        }
+    }
+
+    /// Switches to changed settings, keeping the confirmed state and the
+    /// latest checks.
+    pub fn reconfigure(&mut self, monitor_config: &MonitorConfig) {
+        self.tracker.confirm_after = monitor_config.confirm_after.max(1);
+        self.flap_window = monitor_config.flap_window.max(2);
+        self.flap_threshold = monitor_config.flap_threshold.max(1);
+        while self.recent.len() > self.flap_window {
+            self.recent.pop_front();
+        }
+    }
+
+    pub fn is_flapping(&self) -> bool {
+        self.flapping
+    }
+
+    /// See [`TransitionTracker::confirmed`].
+    pub fn confirmed(&self) -> Option<ServiceStatus> {
+        self.tracker.confirmed()
+    }
+
+    pub fn observe(&mut self, status: ServiceStatus) -> Option<Alert> {
+        if self.recent.len() == self.flap_window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(status);
+        let transition = self.tracker.observe(status);
+
+        let changes = self.recent_changes();
+        if !self.flapping && changes >= self.flap_threshold {
+            self.flapping = true;
+            return Some(Alert::Unstable);
+        }
+        if self.flapping {
+            // Settled once the window is at most half as busy as it was when
+            // flapping started, and the latest checks confirm a state.
+            let settled = changes <= self.flap_threshold / 2
+                && self
+                    .tracker
+                    .confirmed()
+                    .is_some_and(|confirmed| same_state(&confirmed, &status));
+            if !settled {
+                return None;
+            }
+            self.flapping = false;
+            return self.tracker.confirmed().map(Alert::Stable);
+        }
+        transition.map(Alert::Changed)
+    }
+
+    fn recent_changes(&self) -> usize {
+        self.recent
+            .iter()
+            .zip(self.recent.iter().skip(1))
+            .filter(|(a, b)| !same_state(a, b))
+            .count()
+    }
+}
+
+/// Remembers when the current outage started, going by the first failed
+/// check since SAIS last worked.
+#[derive(Debug, Default)]
+pub struct OutageStreak {
+    since: Option<DateTime<Utc>>,
+}
+
+impl OutageStreak {
+    pub fn observe(&mut self, status: ServiceStatus, checked_at: DateTime<Utc>) {
+        if is_outage(&status) {
+            self.since.get_or_insert(checked_at);
+        } else {
+            self.since = None;
+        }
+    }
+
+    /// When the current outage started, if SAIS is out.
+    pub fn since(&self) -> Option<DateTime<Utc>> {
+        self.since
+    }
+}
+
+/// When the current outage started, kept through working checks in the
+/// middle of it until the confirmed status leaves it. Unlike the streak, a
+/// single working check doesn't start the outage afresh.
+#[derive(Debug, Default)]
+pub struct ConfirmedOutage {
+    since: Option<DateTime<Utc>>,
+}
+
+impl ConfirmedOutage {
+    /// Takes the streak's start and the confirmed status after a check,
+    /// returning when the outage started, if it isn't over.
+    pub fn observe(
+        &mut self,
+        streak_since: Option<DateTime<Utc>>,
+        confirmed: Option<&ServiceStatus>,
+    ) -> Option<DateTime<Utc>> {
+        if streak_since.is_none() && !confirmed.is_some_and(is_outage) {
+            self.since = None;
+        } else if self.since.is_none() {
+            self.since = streak_since;
+        }
+        self.since
+    }
+}
+
+/// Counts checks in a row where SAIS rejected the bot's own login details,
+/// so maintainers are only told once it clearly isn't a fluke.
+#[derive(Debug)]
+pub struct CredentialWatch {
+    confirm_after: u32,
+    in_a_row: u32,
+}
+
+impl CredentialWatch {
+    pub fn new(confirm_after: u32) -> Self {
+        CredentialWatch {
+            confirm_after: confirm_after.max(1),
+            in_a_row: 0,
+        }
+    }
+
+    /// True once per run of rejections, on the `confirm_after`th in a row.
+    pub fn observe(&mut self, status: ServiceStatus) -> bool {
+        if status != ServiceStatus::CredentialProblem {
+            self.in_a_row = 0;
+            return false;
+        }
+        self.in_a_row += 1;
+        self.in_a_row == self.confirm_after
+    }
+}
+
+/// What the background monitor has been up to, for the dashboard.
+#[derive(Debug, Default)]
+pub struct MonitorHealth {
+    snapshot: Mutex<HealthSnapshot>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthSnapshot {
+    /// When the last round of checks ran, whether or not it worked.
+    pub last_cycle_at: Option<DateTime<Utc>>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub last_status: Option<ServiceStatus>,
+    pub checks: u64,
+    /// Checks that couldn't be run at all, as opposed to finding SAIS down.
+    pub errors: u64,
+}
+
+impl MonitorHealth {
+    pub fn checked(&self, checked_at: DateTime<Utc>, status: ServiceStatus) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.last_cycle_at = Some(checked_at);
+        snapshot.last_checked_at = Some(checked_at);
+        snapshot.last_status = Some(status);
+        snapshot.checks += 1;
+    }
+
+    pub fn errored(&self, at: DateTime<Utc>) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.last_cycle_at = Some(at);
+        snapshot.errors += 1;
+    }
+
+    pub fn snapshot(&self) -> HealthSnapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+}
+
+/// Whether SAIS isn't working for students, as opposed to being slow, under
+/// maintenance, or rejecting the bot's own login details.
+pub fn is_outage(status: &ServiceStatus) -> bool {
+    matches!(status, ServiceStatus::Down(_) | ServiceStatus::PagesFailing)
+}
+
+fn same_state(a: &ServiceStatus, b: &ServiceStatus) -> bool {
+    mem::discriminant(a) == mem::discriminant(b)
+}
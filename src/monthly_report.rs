@@ -0,0 +1,133 @@
+use crate::current_time_utc_plus_8;
+use chrono::{FixedOffset, Utc};
+use is_sais_down_bot::{
+    history::{to_csv, HistoryStore},
+    leader::{self, Leadership},
+    report::MonthlyReport,
+    sla::{format_duration, month_bounds},
+};
+use poise::serenity_prelude::{AttachmentType, CreateEmbed, Http, UserId};
+use std::sync::Arc;
+
+// Outages past this many are only counted, to keep within embed limits.
+const MAX_LISTED_OUTAGES: usize = 10;
+
+/// DMs the maintainers a report on each month once it ends.
+pub struct MonthlyReportTask {
+    pub http: Arc<Http>,
+    pub history: Arc<dyn HistoryStore>,
+    pub maintainer_ids: Vec<UserId>,
+    // Only the leader sends the report, when set.
+    pub leadership: Option<Arc<Leadership>>,
+}
+
+impl MonthlyReportTask {
+    /// Runs until the bot exits.
+    pub async fn run(self) {
+        loop {
+            let (month_start, month_end) = month_bounds(current_time_utc_plus_8());
+            let until_month_end = (month_end - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(until_month_end).await;
+            if !leader::leads(self.leadership.as_deref()) {
+                continue;
+            }
+
+            if let Err(why) = self.send_report(month_start, month_end).await {
+                tracing::error!("Could not send the monthly report: {:?}", why);
+                sentry::capture_error(why.as_ref());
+            }
+        }
+    }
+
+    async fn send_report(
+        &self,
+        month_start: chrono::DateTime<Utc>,
+        month_end: chrono::DateTime<Utc>,
+    ) -> Result<(), is_sais_down_bot::Error> {
+        let report =
+            MonthlyReport::for_month(self.history.as_ref(), month_start, month_end).await?;
+        let embed = report_embed(&report);
+        let csv = to_csv(&report.records);
+        let month = month_start
+            .with_timezone(&FixedOffset::east_opt(3600 * 8).unwrap())
+            .format("%Y-%m");
+        for maintainer_id in &self.maintainer_ids {
+            let dm_channel = maintainer_id.create_dm_channel(&self.http).await?;
+            dm_channel
+                .send_message(&self.http, |m| {
+                    m.set_embed(embed.clone()).add_file(AttachmentType::Bytes {
+                        data: csv.clone().into_bytes().into(),
+                        filename: format!("sais-history-{}.csv", month),
+                    })
+                })
+                .await?;
+        }
+        tracing::info!("Sent the monthly report for {}", month);
+        Ok(())
+    }
+}
+
+fn report_embed(report: &MonthlyReport) -> CreateEmbed {
+    let utc_plus_8 = FixedOffset::east_opt(3600 * 8).unwrap();
+    let mut outages = report
+        .outages
+        .iter()
+        .take(MAX_LISTED_OUTAGES)
+        .map(|outage| {
+            format!(
+                "{} for {} ({})",
+                outage
+                    .started_at
+                    .with_timezone(&utc_plus_8)
+                    .format("%b %-d, %H:%M"),
+                format_duration(outage.duration()),
+                outage.cause
+            )
+        })
+        .collect::<Vec<_>>();
+    if report.outages.len() > MAX_LISTED_OUTAGES {
+        outages.push(format!(
+            "and {} more",
+            report.outages.len() - MAX_LISTED_OUTAGES
+        ));
+    }
+    let latency = report
+        .weekly_latency_ms
+        .iter()
+        .enumerate()
+        .map(|(week, latency_ms)| format!("Week {}: {} ms", week + 1, latency_ms))
+        .collect::<Vec<_>>();
+    let causes = report
+        .causes
+        .iter()
+        .take(5)
+        .map(|(cause, count)| format!("{}: {} checks", cause, count))
+        .collect::<Vec<_>>();
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!(
+            "UP SAIS in {}",
+            report
+                .month_start
+                .with_timezone(&utc_plus_8)
+                .format("%B %Y")
+        ))
+        .field(
+            "Uptime",
+            format!("{:.2}% of {} checks", report.uptime_percent, report.checks),
+            false,
+        )
+        .field("Outages", or_none(outages), false)
+        .field("Average latency", or_none(latency), false)
+        .field("Most common causes", or_none(causes), false);
+    embed
+}
+
+fn or_none(lines: Vec<String>) -> String {
+    if lines.is_empty() {
+        "None".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
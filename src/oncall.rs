@@ -0,0 +1,114 @@
+//! Which maintainer answers escalations, taking turns by the week, with swaps
+//! made through `&oncall override` kept in a file the bot writes.
+
+use crate::Error;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub const ONCALL_FILEPATH: &str = "data/oncall.ron";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OnCallRotation {
+    /// Take turns in this order, starting over after the last.
+    pub user_ids: Vec<u64>,
+    /// The first turn starts at 08:00 PHT on this day.
+    pub starts_on: NaiveDate,
+    #[serde(default = "default_shift_days")]
+    pub shift_days: u64,
+}
+
+fn default_shift_days() -> u64 {
+    7
+}
+
+impl OnCallRotation {
+    /// Whose turn it is at `time`, if the rotation has started.
+    pub fn scheduled(&self, time: DateTime<Utc>) -> Option<u64> {
+        let shift = self.shift_at(time)?;
+        Some(self.user_ids[shift as usize % self.user_ids.len()])
+    }
+
+    /// When the turn going on at `time` hands over to the next person.
+    pub fn shift_ends(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let shift = self.shift_at(time)?;
+        Some(self.start() + self.shift_length() * (shift + 1) as i32)
+    }
+
+    fn shift_at(&self, time: DateTime<Utc>) -> Option<i64> {
+        if self.user_ids.is_empty() || self.shift_days == 0 || time < self.start() {
+            return None;
+        }
+        Some((time - self.start()).num_seconds() / self.shift_length().num_seconds())
+    }
+
+    // 08:00 PHT is midnight in UTC.
+    fn start(&self) -> DateTime<Utc> {
+        self.starts_on.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+
+    fn shift_length(&self) -> Duration {
+        Duration::days(self.shift_days as i64)
+    }
+}
+
+/// Someone covering for whoever is scheduled, until `until`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnCallOverride {
+    pub user_id: u64,
+    pub until: DateTime<Utc>,
+}
+
+/// The rotation, with the override saved to disk whenever it changes.
+#[derive(Debug)]
+pub struct OnCall {
+    pub rotation: OnCallRotation,
+    path: PathBuf,
+    cover: Mutex<Option<OnCallOverride>>,
+}
+
+impl OnCall {
+    /// Loads the override, starting with none if the file doesn't exist yet.
+    pub fn open(rotation: OnCallRotation, path: impl AsRef<Path>) -> Result<OnCall, Error> {
+        let path = path.as_ref().to_path_buf();
+        let cover = match fs::read_to_string(&path) {
+            Ok(contents) => ron::de::from_str(&contents)?,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => None,
+            Err(why) => return Err(why.into()),
+        };
+        Ok(OnCall {
+            rotation,
+            path,
+            cover: Mutex::new(cover),
+        })
+    }
+
+    /// Who is on call at `time`, with an override over the rotation.
+    pub fn current(&self, time: DateTime<Utc>) -> Option<u64> {
+        self.cover_at(time)
+            .map(|cover| cover.user_id)
+            .or_else(|| self.rotation.scheduled(time))
+    }
+
+    /// The override going on at `time`, if any.
+    pub fn cover_at(&self, time: DateTime<Utc>) -> Option<OnCallOverride> {
+        let cover = *self.cover.lock().unwrap();
+        cover.filter(|cover| time < cover.until)
+    }
+
+    /// Puts someone else on call, or goes back to the rotation with `None`.
+    pub fn set_override(&self, cover: Option<OnCallOverride>) -> Result<(), Error> {
+        let mut current = self.cover.lock().unwrap();
+        *current = cover;
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let contents = ron::ser::to_string_pretty(&*current, ron::ser::PrettyConfig::default())?;
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
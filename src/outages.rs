@@ -0,0 +1,94 @@
+//! What happened during the current outage, for summing it up once SAIS is
+//! back.
+
+use crate::{monitor::is_outage, status::ServiceStatus};
+use chrono::{DateTime, Duration, Utc};
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// A finished outage, from its first failed check to the first working one
+/// after its last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutageSummary {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub failed_checks: u32,
+    /// How many failed checks had each reason, the most common first.
+    pub reasons: Vec<(String, u32)>,
+    /// How many times people checked SAIS themselves during it.
+    pub user_checks: u32,
+}
+
+impl OutageSummary {
+    pub fn downtime(&self) -> Duration {
+        self.ended_at - self.started_at
+    }
+}
+
+#[derive(Debug, Default)]
+struct Log {
+    started_at: Option<DateTime<Utc>>,
+    // The first working check since the last failed one.
+    recovered_at: Option<DateTime<Utc>>,
+    failed_checks: u32,
+    reasons: BTreeMap<String, u32>,
+    user_checks: u32,
+}
+
+/// The main route's failed checks since it last worked, shared with the
+/// commands people check SAIS with.
+#[derive(Debug, Default)]
+pub struct OutageLog {
+    log: Mutex<Log>,
+}
+
+impl OutageLog {
+    /// Notes a check of the main route. Statuses that are neither working
+    /// nor an outage, like maintenance, are left out.
+    pub fn observe(&self, checked_at: DateTime<Utc>, status: &ServiceStatus) {
+        let mut log = self.log.lock().unwrap();
+        if is_outage(status) {
+            log.started_at.get_or_insert(checked_at);
+            log.recovered_at = None;
+            log.failed_checks += 1;
+            let reason = status.reason().unwrap_or_else(|| status.kind().to_string());
+            *log.reasons.entry(reason).or_default() += 1;
+        } else if matches!(status, ServiceStatus::Up | ServiceStatus::Degraded(_)) {
+            log.recovered_at.get_or_insert(checked_at);
+        }
+    }
+
+    /// Counts someone checking SAIS, if it's out.
+    pub fn user_checked(&self) {
+        let mut log = self.log.lock().unwrap();
+        if log.started_at.is_some() && log.recovered_at.is_none() {
+            log.user_checks += 1;
+        }
+    }
+
+    /// The outage so far, if SAIS has worked since.
+    pub fn summary(&self) -> Option<OutageSummary> {
+        let log = self.log.lock().unwrap();
+        let mut reasons = log
+            .reasons
+            .iter()
+            .map(|(reason, count)| (reason.clone(), *count))
+            .collect::<Vec<_>>();
+        reasons.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        Some(OutageSummary {
+            started_at: log.started_at?,
+            ended_at: log.recovered_at?,
+            failed_checks: log.failed_checks,
+            reasons,
+            user_checks: log.user_checks,
+        })
+    }
+
+    /// Starts afresh once an outage is over, or failed checks turn out to be
+    /// a blip. Kept while SAIS is still failing.
+    pub fn clear_if_working(&self) {
+        let mut log = self.log.lock().unwrap();
+        if log.recovered_at.is_some() {
+            *log = Log::default();
+        }
+    }
+}
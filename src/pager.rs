@@ -0,0 +1,156 @@
+//! Pages whoever is on call through PagerDuty's Events API or Opsgenie's
+//! Alert API, or anything answering like either.
+
+use crate::Error;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum PagerService {
+    #[default]
+    PagerDuty,
+    Opsgenie,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PagerConfig {
+    #[serde(default)]
+    pub service: PagerService,
+    /// The service's own, when not set.
+    #[serde(default)]
+    pub events_url: Option<String>,
+    /// One of "critical", "error", "warning" or "info". Opsgenie gets P1, P2,
+    /// P3 and P5.
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    /// Pages as soon as an outage is announced, as well as for Pager
+    /// escalation steps.
+    #[serde(default)]
+    pub on_outage: bool,
+}
+
+fn default_severity() -> String {
+    "critical".to_string()
+}
+
+impl PagerConfig {
+    fn events_url(&self) -> &str {
+        match (&self.events_url, self.service) {
+            (Some(events_url), _) => events_url,
+            (None, PagerService::PagerDuty) => "https://events.pagerduty.com/v2/enqueue",
+            (None, PagerService::Opsgenie) => "https://api.opsgenie.com/v2/alerts",
+        }
+    }
+}
+
+pub struct Pager {
+    config: PagerConfig,
+    /// Picks the service to page, from `PAGER_ROUTING_KEY`. Opsgenie's is the
+    /// integration's API key.
+    routing_key: String,
+    http_client: reqwest::Client,
+}
+
+impl Pager {
+    pub fn new(config: PagerConfig, routing_key: String) -> Pager {
+        Pager {
+            config,
+            routing_key,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Whether every announced outage is paged, not only escalated ones.
+    pub fn pages_on_outage(&self) -> bool {
+        self.config.on_outage
+    }
+
+    /// Opens an incident for the outage since `since`. Paging again for the
+    /// same outage doesn't open another.
+    pub async fn trigger(&self, since: DateTime<Utc>, summary: &str) -> Result<(), Error> {
+        match self.config.service {
+            PagerService::PagerDuty => {
+                self.send(
+                    self.config.events_url(),
+                    json!({
+                        "routing_key": self.routing_key,
+                        "event_action": "trigger",
+                        "dedup_key": dedup_key(since),
+                        "payload": {
+                            "summary": summary,
+                            "source": "is-sais-down-bot",
+                            "severity": self.config.severity,
+                            "timestamp": since.to_rfc3339(),
+                        },
+                    }),
+                )
+                .await
+            }
+            PagerService::Opsgenie => {
+                self.send(
+                    self.config.events_url(),
+                    json!({
+                        "message": summary.chars().take(130).collect::<String>(),
+                        "alias": dedup_key(since),
+                        "description": summary,
+                        "source": "is-sais-down-bot",
+                        "priority": opsgenie_priority(&self.config.severity),
+                    }),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Closes the incident for the outage since `since`, once SAIS is back.
+    pub async fn resolve(&self, since: DateTime<Utc>) -> Result<(), Error> {
+        match self.config.service {
+            PagerService::PagerDuty => {
+                self.send(
+                    self.config.events_url(),
+                    json!({
+                        "routing_key": self.routing_key,
+                        "event_action": "resolve",
+                        "dedup_key": dedup_key(since),
+                    }),
+                )
+                .await
+            }
+            PagerService::Opsgenie => {
+                let url = format!(
+                    "{}/{}/close?identifierType=alias",
+                    self.config.events_url().trim_end_matches('/'),
+                    dedup_key(since)
+                );
+                self.send(&url, json!({ "source": "is-sais-down-bot" }))
+                    .await
+            }
+        }
+    }
+
+    async fn send(&self, url: &str, event: serde_json::Value) -> Result<(), Error> {
+        let mut request = self.http_client.post(url).json(&event);
+        if self.config.service == PagerService::Opsgenie {
+            request = request.header(
+                reqwest::header::AUTHORIZATION,
+                format!("GenieKey {}", self.routing_key),
+            );
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+fn dedup_key(since: DateTime<Utc>) -> String {
+    format!("sais-outage-{}", since.timestamp())
+}
+
+fn opsgenie_priority(severity: &str) -> &'static str {
+    match severity {
+        "critical" => "P1",
+        "error" => "P2",
+        "warning" => "P3",
+        _ => "P5",
+    }
+}
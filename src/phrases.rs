@@ -0,0 +1,55 @@
+//! How replies put each way SAIS can be down into words.
+
+use crate::status::{DownReason, ServiceStatus};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Phrasing keyed by [`ServiceStatus::reason`], e.g. "http_502" or
+/// "connection_refused", overriding the built-in ones. Status codes can also
+/// be phrased by class, e.g. "http_5xx". `{code}` is replaced with the status
+/// code and its name, e.g. "502 Bad Gateway".
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct DownPhrases(pub HashMap<String, String>);
+
+impl DownPhrases {
+    pub fn describe(&self, reason: DownReason) -> String {
+        let key = ServiceStatus::Down(reason)
+            .reason()
+            .expect("Down statuses always have a reason");
+        let class_key = match reason {
+            DownReason::HttpStatus(status) => Some(format!("http_{}xx", status.as_u16() / 100)),
+            _ => None,
+        };
+        let phrase = self
+            .0
+            .get(&key)
+            .or_else(|| class_key.as_ref().and_then(|key| self.0.get(key)))
+            .map(String::as_str)
+            .or_else(|| default_phrase(&key))
+            .or_else(|| class_key.as_deref().and_then(default_phrase))
+            .unwrap_or("UP SAIS is down... ({code})");
+        match reason {
+            DownReason::HttpStatus(status) => phrase.replace("{code}", &status.to_string()),
+            _ => phrase.to_string(),
+        }
+    }
+}
+
+fn default_phrase(key: &str) -> Option<&'static str> {
+    Some(match key {
+        "no_response" => "dili na gyud muload",
+        "connect_timeout" => "UP SAIS could not even be reached",
+        "read_timeout" => "UP SAIS connected, but stopped responding",
+        "login_timeout" => "UP SAIS loads, but the login response never came",
+        "check_timeout" => "UP SAIS is too slow to finish logging in",
+        "connection_refused" => "UP SAIS refused the connection, so nothing is taking requests",
+        "login_failed" => "UP SAIS is up, but there are login problems",
+        "http_502" => "UP SAIS is down. The proxy in front of it got a bad answer from SAIS itself ({code})",
+        "http_503" => "UP SAIS is down. It says it's unavailable, which usually means maintenance or too many users ({code})",
+        "http_504" => "UP SAIS is down. The proxy in front of it gave up waiting for SAIS itself ({code})",
+        "http_5xx" => "UP SAIS is down. Its server ran into an error ({code})",
+        "http_4xx" => "UP SAIS is turning requests away ({code})",
+        _ => return None,
+    })
+}
@@ -0,0 +1,143 @@
+//! Checker plugins: compiled WASM modules that a transaction's `Plugin`
+//! step runs on the last page, for systems whose pages take more than
+//! `Expect` to make sense of. Plugins run inside the bot, sandboxed: they
+//! can't reach the network or the filesystem, and only get so much memory
+//! and work per run.
+//!
+//! A plugin exports
+//!
+//! - `memory`,
+//! - `alloc(len: i32) -> i32`, returning where `len` bytes of input can be
+//!   written, and
+//! - `check(ptr: i32, len: i32) -> i32`, which reads the input and returns
+//!   0 when the system works, and anything else when it doesn't.
+//!
+//! The input is a JSON object with the last page's `status` (null after a
+//! step that wasn't a request) and `body`, the values extracted so far as
+//! `variables`, and the step's `args`. A plugin can import
+//! `report(ptr: i32, len: i32)` from the `bot` module to say what it found,
+//! and nothing else. The module is read again on every run, so plugins can
+//! be added or replaced without rebuilding or restarting the bot.
+
+use crate::Error;
+use std::{convert::TryFrom, io::ErrorKind, path::Path, sync::LazyLock};
+use wasmtime::{
+    Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+};
+
+/// The module plugins import host functions from.
+pub const HOST_MODULE: &str = "bot";
+/// How much work a run may do, in wasmtime fuel, which is roughly one unit
+/// per instruction.
+pub const FUEL: u64 = 1_000_000_000;
+pub const MAX_MEMORY_BYTES: usize = 64 << 20;
+pub const MAX_REPORT_BYTES: usize = 1 << 20;
+// A running plugin gives the transaction's timeout a chance to stop it
+// after this much fuel.
+const YIELD_INTERVAL: u64 = 100_000;
+
+static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).expect("Plugin engine config should be valid")
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginOutcome {
+    /// What `check` returned. 0 means the system works.
+    pub code: i32,
+    /// Everything the plugin reported.
+    pub report: String,
+}
+
+impl PluginOutcome {
+    pub fn passed(&self) -> bool {
+        self.code == 0
+    }
+}
+
+struct Run {
+    limits: StoreLimits,
+    report: String,
+}
+
+/// Loads the plugin at `path` and runs its `check` on `input`.
+pub async fn run(path: &Path, input: &serde_json::Value) -> Result<PluginOutcome, Error> {
+    let bytes = tokio::fs::read(path).await.map_err(|why| -> Error {
+        if why.kind() == ErrorKind::NotFound {
+            format!("there is no plugin at {}", path.display()).into()
+        } else {
+            why.into()
+        }
+    })?;
+    // Compiling can take a while for big modules.
+    let module = tokio::task::spawn_blocking(move || Module::new(&ENGINE, bytes))
+        .await?
+        .map_err(|why| format!("{} isn't a WASM module: {:#}", path.display(), why))?;
+    call(&module, input).await.map_err(describe)
+}
+
+async fn call(module: &Module, input: &serde_json::Value) -> wasmtime::Result<PluginOutcome> {
+    let mut linker = Linker::new(&ENGINE);
+    linker.func_wrap(HOST_MODULE, "report", report)?;
+    let mut store = Store::new(
+        &ENGINE,
+        Run {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_BYTES)
+                .instances(1)
+                .build(),
+            report: String::new(),
+        },
+    );
+    store.limiter(|run| &mut run.limits);
+    store.set_fuel(FUEL)?;
+    store.fuel_async_yield_interval(Some(YIELD_INTERVAL))?;
+
+    let instance = linker.instantiate_async(&mut store, module).await?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("the plugin doesn't export its memory"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+    let check = instance.get_typed_func::<(i32, i32), i32>(&mut store, "check")?;
+
+    let input = input.to_string();
+    let len = i32::try_from(input.len())?;
+    let ptr = alloc.call_async(&mut store, len).await?;
+    memory.write(&mut store, ptr as u32 as usize, input.as_bytes())?;
+    let code = check.call_async(&mut store, (ptr, len)).await?;
+    Ok(PluginOutcome {
+        code,
+        report: store.into_data().report,
+    })
+}
+
+fn report(mut caller: Caller<'_, Run>, ptr: i32, len: i32) -> wasmtime::Result<()> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| wasmtime::Error::msg("the plugin doesn't export its memory"))?;
+    let start = ptr as u32 as usize;
+    let bytes = memory
+        .data(&caller)
+        .get(start..start + len as u32 as usize)
+        .ok_or_else(|| wasmtime::Error::msg("the report is outside the plugin's memory"))?;
+    let text = String::from_utf8_lossy(bytes).into_owned();
+    let report = &mut caller.data_mut().report;
+    if report.len() + text.len() > MAX_REPORT_BYTES {
+        return Err(wasmtime::Error::msg(format!(
+            "the plugin reported over {} bytes",
+            MAX_REPORT_BYTES
+        )));
+    }
+    report.push_str(&text);
+    Ok(())
+}
+
+fn describe(why: wasmtime::Error) -> Error {
+    match why.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => "the plugin did too much work and was stopped".into(),
+        Some(trap) => format!("the plugin crashed: {}", trap).into(),
+        None => format!("{:#}", why).into(),
+    }
+}
@@ -0,0 +1,259 @@
+//! Keeps the history in PostgreSQL, for operators who already run it and
+//! want more than one process to use the history at once.
+
+use crate::{
+    history::{CheckRecord, HistoryStore, HourlyRollup},
+    Error,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio_postgres::{Client, NoTls};
+
+/// Like the SQLite [`migrations`](crate::migrations), only appended to. The
+/// applied version is kept in the `schema_version` table.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE checks (
+        id BIGSERIAL PRIMARY KEY,
+        checked_at TIMESTAMPTZ NOT NULL,
+        route TEXT NOT NULL,
+        status TEXT NOT NULL,
+        reason TEXT,
+        latency_ms BIGINT NOT NULL
+    );
+    CREATE INDEX checks_checked_at ON checks (checked_at);",
+    "CREATE TABLE hourly_rollups (
+        hour TIMESTAMPTZ NOT NULL,
+        route TEXT NOT NULL,
+        status TEXT NOT NULL,
+        checks BIGINT NOT NULL,
+        total_latency_ms BIGINT NOT NULL,
+        PRIMARY KEY (hour, route, status)
+    );",
+    "CREATE TABLE leases (
+        name TEXT PRIMARY KEY,
+        holder TEXT NOT NULL,
+        expires_at TIMESTAMPTZ NOT NULL
+    );",
+];
+
+pub struct PostgresHistory {
+    client: Client,
+}
+
+impl PostgresHistory {
+    pub async fn connect(config: &str) -> Result<PostgresHistory, Error> {
+        let (mut client, connection) = tokio_postgres::connect(config, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(why) = connection.await {
+                tracing::error!("PostgreSQL connection error: {:?}", why);
+            }
+        });
+        migrate(&mut client).await?;
+        Ok(PostgresHistory { client })
+    }
+}
+
+async fn migrate(client: &mut Client) -> Result<(), Error> {
+    client
+        .batch_execute("CREATE TABLE IF NOT EXISTS schema_version (version BIGINT NOT NULL)")
+        .await?;
+    let version = client
+        .query_opt("SELECT version FROM schema_version", &[])
+        .await?
+        .map(|row| row.get::<_, i64>(0) as usize)
+        .unwrap_or(0);
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "The database is at schema version {}, but this bot only knows up to {}",
+            version,
+            MIGRATIONS.len()
+        )
+        .into());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = client.transaction().await?;
+        transaction.batch_execute(migration).await?;
+        transaction
+            .execute("DELETE FROM schema_version", &[])
+            .await?;
+        transaction
+            .execute(
+                "INSERT INTO schema_version (version) VALUES ($1)",
+                &[&(index as i64 + 1)],
+            )
+            .await?;
+        transaction.commit().await?;
+        tracing::info!("Migrated the database to schema version {}", index + 1);
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl HistoryStore for PostgresHistory {
+    async fn record(&self, records: &[CheckRecord]) -> Result<(), Error> {
+        let statement = self
+            .client
+            .prepare(
+                "INSERT INTO checks (checked_at, route, status, reason, latency_ms)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .await?;
+        for record in records {
+            self.client
+                .execute(
+                    &statement,
+                    &[
+                        &record.checked_at,
+                        &record.route,
+                        &record.status,
+                        &record.reason,
+                        &(record.latency_ms as i64),
+                    ],
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn since(&self, since: DateTime<Utc>) -> Result<Vec<CheckRecord>, Error> {
+        let rows = self
+            .client
+            .query(
+                "SELECT checked_at, route, status, reason, latency_ms FROM checks
+                 WHERE checked_at >= $1 ORDER BY checked_at, id",
+                &[&since],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| CheckRecord {
+                checked_at: row.get(0),
+                route: row.get(1),
+                status: row.get(2),
+                reason: row.get(3),
+                latency_ms: row.get::<_, i64>(4) as u64,
+            })
+            .collect())
+    }
+
+    async fn compact(
+        &self,
+        raw_before: DateTime<Utc>,
+        rollups_before: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        self.client.execute("BEGIN", &[]).await?;
+        let result = async {
+            self.client
+                .execute(
+                    "INSERT INTO hourly_rollups (hour, route, status, checks, total_latency_ms)
+                     SELECT date_trunc('hour', checked_at AT TIME ZONE 'UTC') AT TIME ZONE 'UTC',
+                        route, status, COUNT(*), SUM(latency_ms)
+                     FROM checks WHERE checked_at < $1
+                     GROUP BY 1, 2, 3
+                     ON CONFLICT (hour, route, status) DO UPDATE SET
+                        checks = hourly_rollups.checks + excluded.checks,
+                        total_latency_ms =
+                            hourly_rollups.total_latency_ms + excluded.total_latency_ms",
+                    &[&raw_before],
+                )
+                .await?;
+            self.client
+                .execute("DELETE FROM checks WHERE checked_at < $1", &[&raw_before])
+                .await?;
+            self.client
+                .execute(
+                    "DELETE FROM hourly_rollups WHERE hour < $1",
+                    &[&rollups_before],
+                )
+                .await?;
+            Ok::<_, Error>(())
+        }
+        .await;
+        match result {
+            Ok(()) => self.client.execute("COMMIT", &[]).await?,
+            Err(why) => {
+                self.client.execute("ROLLBACK", &[]).await?;
+                return Err(why);
+            }
+        };
+        Ok(())
+    }
+
+    async fn record_rollups(&self, rollups: &[HourlyRollup]) -> Result<(), Error> {
+        for rollup in rollups {
+            self.client
+                .execute(
+                    "INSERT INTO hourly_rollups (hour, route, status, checks, total_latency_ms)
+                     VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (hour, route, status) DO UPDATE SET
+                        checks = hourly_rollups.checks + excluded.checks,
+                        total_latency_ms =
+                            hourly_rollups.total_latency_ms + excluded.total_latency_ms",
+                    &[
+                        &rollup.hour,
+                        &rollup.route,
+                        &rollup.status,
+                        &(rollup.checks as i64),
+                        &(rollup.total_latency_ms as i64),
+                    ],
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn rollups_since(&self, since: DateTime<Utc>) -> Result<Vec<HourlyRollup>, Error> {
+        let rows = self
+            .client
+            .query(
+                "SELECT hour, route, status, checks, total_latency_ms FROM hourly_rollups
+                 WHERE hour >= $1 ORDER BY hour, route, status",
+                &[&since],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| HourlyRollup {
+                hour: row.get(0),
+                route: row.get(1),
+                status: row.get(2),
+                checks: row.get::<_, i64>(3) as u64,
+                total_latency_ms: row.get::<_, i64>(4) as u64,
+            })
+            .collect())
+    }
+
+    // Only the bot's own tables, since the database may be shared.
+    async fn size_bytes(&self) -> Result<u64, Error> {
+        let row = self
+            .client
+            .query_one(
+                "SELECT pg_total_relation_size('checks') + pg_total_relation_size('hourly_rollups')",
+                &[],
+            )
+            .await?;
+        Ok(row.get::<_, i64>(0) as u64)
+    }
+
+    async fn try_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        let changed = self
+            .client
+            .execute(
+                "INSERT INTO leases (name, holder, expires_at) VALUES ($1, $2, $3)
+                 ON CONFLICT (name) DO UPDATE SET
+                    holder = excluded.holder,
+                    expires_at = excluded.expires_at
+                 WHERE leases.holder = excluded.holder OR leases.expires_at < $4",
+                &[&name, &holder, &expires_at, &now],
+            )
+            .await?;
+        Ok(changed == 1)
+    }
+}
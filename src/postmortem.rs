@@ -0,0 +1,233 @@
+//! A post-mortem draft for an outage, from the checks around it, for the
+//! maintainers to fill in.
+
+use crate::{
+    history::CheckRecord,
+    outages::OutageSummary,
+    routes::MAIN_ROUTE_NAME,
+    sla::{self, failed_check},
+    Error,
+};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use image::{ImageOutputFormat, RgbImage};
+use plotters::prelude::*;
+use std::{fmt::Write as _, io::Cursor};
+
+// The latency graph's size in pixels.
+const GRAPH_WIDTH: u32 = 960;
+const GRAPH_HEIGHT: u32 = 320;
+
+fn utc_plus_8() -> FixedOffset {
+    FixedOffset::east_opt(3600 * 8).unwrap()
+}
+
+/// How long before and after the outage the timeline and graph cover.
+pub fn margin() -> Duration {
+    Duration::minutes(30)
+}
+
+/// Checks in a row that had the same status and reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineRun {
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
+    pub status: String,
+    pub reason: Option<String>,
+    pub checks: u32,
+    pub mean_latency_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostMortem {
+    pub summary: OutageSummary,
+    pub timeline: Vec<TimelineRun>,
+    // The main route's checks within the margin, oldest first.
+    records: Vec<CheckRecord>,
+}
+
+impl PostMortem {
+    /// Drafts from `records`, which should cover the outage and the
+    /// [`margin`] around it. Other routes and checks outside it are left
+    /// out.
+    pub fn new(summary: OutageSummary, records: &[CheckRecord]) -> PostMortem {
+        let (since, until) = (summary.started_at - margin(), summary.ended_at + margin());
+        let mut records = records
+            .iter()
+            .filter(|record| {
+                record.route == MAIN_ROUTE_NAME
+                    && since <= record.checked_at
+                    && record.checked_at <= until
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        records.sort_by_key(|record| record.checked_at);
+
+        let mut timeline = Vec::<TimelineRun>::new();
+        let mut latency_sums = Vec::<u64>::new();
+        for record in &records {
+            match timeline.last_mut() {
+                Some(run) if run.status == record.status && run.reason == record.reason => {
+                    run.last_at = record.checked_at;
+                    run.checks += 1;
+                    *latency_sums.last_mut().unwrap() += record.latency_ms;
+                }
+                _ => {
+                    timeline.push(TimelineRun {
+                        first_at: record.checked_at,
+                        last_at: record.checked_at,
+                        status: record.status.clone(),
+                        reason: record.reason.clone(),
+                        checks: 1,
+                        mean_latency_ms: 0,
+                    });
+                    latency_sums.push(record.latency_ms);
+                }
+            }
+        }
+        for (run, sum) in timeline.iter_mut().zip(latency_sums) {
+            run.mean_latency_ms = sum / run.checks as u64;
+        }
+        PostMortem {
+            summary,
+            timeline,
+            records,
+        }
+    }
+
+    /// The draft as Markdown, with the parts only people can write in
+    /// italics.
+    pub fn to_markdown(&self) -> String {
+        let pht = |time: DateTime<Utc>| time.with_timezone(&utc_plus_8());
+        let summary = &self.summary;
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "# Post-mortem: UP SAIS outage, {} to {} PHT\n",
+            pht(summary.started_at).format("%b %-d %H:%M"),
+            pht(summary.ended_at).format("%b %-d %H:%M")
+        );
+        let _ = writeln!(
+            text,
+            "_A draft from the bot's checks. Fill in the parts in italics._\n"
+        );
+
+        let _ = writeln!(text, "## Summary\n");
+        let reasons = summary
+            .reasons
+            .iter()
+            .map(|(reason, count)| format!("{} {}", reason, count))
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            text,
+            "UP SAIS was down for {}. {} checks failed: {}. People checked with &sais {} times meanwhile.\n",
+            sla::format_duration(summary.downtime()),
+            summary.failed_checks,
+            reasons.join(", "),
+            summary.user_checks
+        );
+        let _ = writeln!(text, "_What happened, in a sentence or two._\n");
+
+        let _ = writeln!(text, "## Timeline (PHT)\n");
+        text.push_str(&timeline_markdown(&self.timeline));
+
+        let _ = writeln!(text, "\n## Cause\n");
+        let _ = writeln!(text, "_What went wrong, and how we know._\n");
+        let _ = writeln!(text, "## Follow-up\n");
+        let _ = writeln!(text, "_What could catch or prevent this sooner next time._");
+        text
+    }
+
+    /// Draws each check's latency over time as a PNG, up to the latest
+    /// check. The outage is shaded red, and failed checks are red dots.
+    pub fn latency_png(&self) -> Result<Vec<u8>, Error> {
+        let since = self.summary.started_at - margin();
+        let until = self
+            .records
+            .last()
+            .map_or(self.summary.ended_at, |record| record.checked_at)
+            .max(self.summary.ended_at);
+        let span = (until - since).num_milliseconds().max(1) as f64;
+        let max_latency = self
+            .records
+            .iter()
+            .map(|record| record.latency_ms)
+            .max()
+            .unwrap_or(0)
+            .max(1) as f64
+            * 1.1;
+        let x = |time: DateTime<Utc>| {
+            ((time - since).num_milliseconds() as f64 / span * (GRAPH_WIDTH - 1) as f64) as i32
+        };
+        let y = |latency_ms: u64| {
+            let height = (GRAPH_HEIGHT - 1) as f64;
+            (height - latency_ms as f64 / max_latency * height) as i32
+        };
+
+        let mut buffer = vec![0; (GRAPH_WIDTH * GRAPH_HEIGHT * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut buffer, (GRAPH_WIDTH, GRAPH_HEIGHT))
+                .into_drawing_area();
+            root.fill(&WHITE).map_err(|why| why.to_string())?;
+            let outage = [
+                (x(self.summary.started_at), 0),
+                (x(self.summary.ended_at), GRAPH_HEIGHT as i32),
+            ];
+            root.draw(&Rectangle::new(outage, RGBColor(255, 220, 220).filled()))
+                .map_err(|why| why.to_string())?;
+            let points = self
+                .records
+                .iter()
+                .map(|record| (x(record.checked_at), y(record.latency_ms)))
+                .collect::<Vec<_>>();
+            root.draw(&PathElement::new(points, BLUE.stroke_width(2)))
+                .map_err(|why| why.to_string())?;
+            for record in self
+                .records
+                .iter()
+                .filter(|record| failed_check(&record.status))
+            {
+                let point = (x(record.checked_at), y(record.latency_ms));
+                root.draw(&Circle::new(point, 4, RED.filled()))
+                    .map_err(|why| why.to_string())?;
+            }
+            root.present().map_err(|why| why.to_string())?;
+        }
+
+        let image = RgbImage::from_raw(GRAPH_WIDTH, GRAPH_HEIGHT, buffer)
+            .ok_or("Latency graph buffer is too small")?;
+        let mut png = Cursor::new(vec![]);
+        image.write_to(&mut png, ImageOutputFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
+
+/// Each run of the timeline as a Markdown list item, in PHT.
+pub fn timeline_markdown(timeline: &[TimelineRun]) -> String {
+    let pht = |time: DateTime<Utc>| time.with_timezone(&utc_plus_8());
+    let mut text = String::new();
+    if timeline.is_empty() {
+        let _ = writeln!(text, "No checks were kept in the history for it.");
+    }
+    for run in timeline {
+        let when = if run.checks == 1 {
+            pht(run.first_at).format("%H:%M").to_string()
+        } else {
+            format!(
+                "{} to {}",
+                pht(run.first_at).format("%H:%M"),
+                pht(run.last_at).format("%H:%M")
+            )
+        };
+        let status = match &run.reason {
+            Some(reason) => format!("{} ({})", run.status, reason),
+            None => run.status.clone(),
+        };
+        let checks = if run.checks == 1 { "check" } else { "checks" };
+        let _ = writeln!(
+            text,
+            "- {}: {}, {} {}, {} ms on average",
+            when, status, run.checks, checks, run.mean_latency_ms
+        );
+    }
+    text
+}
@@ -0,0 +1,203 @@
+//! What each user wants to be told about, and how, chosen with `/notify` and
+//! kept in a file the bot writes.
+
+use crate::{
+    digest::DigestPeriod,
+    monitor::{Alert, Transition},
+    status::ServiceStatus,
+    Error,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub const PREFERENCES_FILEPATH: &str = "data/preferences.ron";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum NotificationKind {
+    /// SAIS went down, failed some pages, or started going up and down.
+    Down,
+    /// SAIS works again.
+    Up,
+    /// SAIS works, but slowly.
+    Degraded,
+    /// SAIS is under maintenance, or declared maintenance started.
+    Maintenance,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 4] = [
+        NotificationKind::Down,
+        NotificationKind::Up,
+        NotificationKind::Degraded,
+        NotificationKind::Maintenance,
+    ];
+
+    /// What an announced alert is about.
+    pub fn of(alert: Alert) -> NotificationKind {
+        let status = match alert {
+            Alert::Changed(Transition { to, .. }) => to,
+            Alert::Stable(status) => status,
+            Alert::Unstable => return NotificationKind::Down,
+        };
+        match status {
+            ServiceStatus::Up | ServiceStatus::CredentialProblem => NotificationKind::Up,
+            ServiceStatus::Degraded(_) => NotificationKind::Degraded,
+            ServiceStatus::Down(_) | ServiceStatus::PagesFailing => NotificationKind::Down,
+            ServiceStatus::Maintenance => NotificationKind::Maintenance,
+        }
+    }
+}
+
+impl fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NotificationKind::Down => "when it goes down",
+            NotificationKind::Up => "when it's back up",
+            NotificationKind::Degraded => "when it's slow",
+            NotificationKind::Maintenance => "about maintenance",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Delivery {
+    /// Sent to the user directly.
+    Dm,
+    /// Mentioned in the announcements of the server they chose this in.
+    Ping { guild_id: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    pub kinds: BTreeSet<NotificationKind>,
+    pub delivery: Delivery,
+    /// Nothing is sent until then, set with `&mute`.
+    #[serde(default)]
+    pub muted_until: Option<DateTime<Utc>>,
+    /// Changes are rolled up and sent by DM this often instead, when set.
+    #[serde(default)]
+    pub digest: Option<DigestPeriod>,
+}
+
+impl NotificationPreferences {
+    pub fn is_muted(&self, time: DateTime<Utc>) -> bool {
+        self.muted_until.is_some_and(|until| time < until)
+    }
+}
+
+/// Every user's preferences by user ID, saved to disk whenever one changes.
+#[derive(Debug)]
+pub struct PreferencesStore {
+    path: PathBuf,
+    users: Mutex<BTreeMap<u64, NotificationPreferences>>,
+}
+
+impl PreferencesStore {
+    /// Loads the preferences, starting with none if the file doesn't exist
+    /// yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<PreferencesStore, Error> {
+        let path = path.as_ref().to_path_buf();
+        let users = match fs::read_to_string(&path) {
+            Ok(contents) => ron::de::from_str(&contents)?,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(why) => return Err(why.into()),
+        };
+        Ok(PreferencesStore {
+            path,
+            users: Mutex::new(users),
+        })
+    }
+
+    pub fn get(&self, user_id: u64) -> Option<NotificationPreferences> {
+        self.users.lock().unwrap().get(&user_id).cloned()
+    }
+
+    /// Saves `preferences` for the user, or forgets them if they chose
+    /// nothing.
+    pub fn set(&self, user_id: u64, preferences: NotificationPreferences) -> Result<(), Error> {
+        let mut users = self.users.lock().unwrap();
+        if preferences.kinds.is_empty() {
+            users.remove(&user_id);
+        } else {
+            users.insert(user_id, preferences);
+        }
+        self.save(&users)
+    }
+
+    /// Mutes the user until `until`, or unmutes them with `None`. Returns
+    /// whether they had any preferences to mute.
+    pub fn mute(&self, user_id: u64, until: Option<DateTime<Utc>>) -> Result<bool, Error> {
+        let mut users = self.users.lock().unwrap();
+        match users.get_mut(&user_id) {
+            Some(preferences) => preferences.muted_until = until,
+            None => return Ok(false),
+        }
+        self.save(&users)?;
+        Ok(true)
+    }
+
+    /// The users who want `kind` sent to them directly as it happens, and
+    /// haven't muted it at `time`.
+    pub fn dms(&self, kind: NotificationKind, time: DateTime<Utc>) -> Vec<u64> {
+        self.matching(kind, time, |delivery| delivery == Delivery::Dm)
+    }
+
+    /// The users who want to be mentioned in the server's announcements about
+    /// `kind` as it happens, and haven't muted it at `time`.
+    pub fn pings(&self, guild_id: u64, kind: NotificationKind, time: DateTime<Utc>) -> Vec<u64> {
+        self.matching(kind, time, |delivery| {
+            delivery == Delivery::Ping { guild_id }
+        })
+    }
+
+    fn matching(
+        &self,
+        kind: NotificationKind,
+        time: DateTime<Utc>,
+        delivery: impl Fn(Delivery) -> bool,
+    ) -> Vec<u64> {
+        let users = self.users.lock().unwrap();
+        users
+            .iter()
+            .filter(|(_, preferences)| {
+                preferences.kinds.contains(&kind)
+                    && delivery(preferences.delivery)
+                    && preferences.digest.is_none()
+                    && !preferences.is_muted(time)
+            })
+            .map(|(user_id, _)| *user_id)
+            .collect()
+    }
+
+    /// The users who want a digest this often, and haven't muted it at
+    /// `time`, with what they want to hear about.
+    pub fn digests(
+        &self,
+        period: DigestPeriod,
+        time: DateTime<Utc>,
+    ) -> Vec<(u64, BTreeSet<NotificationKind>)> {
+        let users = self.users.lock().unwrap();
+        users
+            .iter()
+            .filter(|(_, preferences)| {
+                preferences.digest == Some(period) && !preferences.is_muted(time)
+            })
+            .map(|(user_id, preferences)| (*user_id, preferences.kinds.clone()))
+            .collect()
+    }
+
+    fn save(&self, users: &BTreeMap<u64, NotificationPreferences>) -> Result<(), Error> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let contents = ron::ser::to_string_pretty(users, ron::ser::PrettyConfig::default())?;
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
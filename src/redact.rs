@@ -0,0 +1,87 @@
+//! Scrubs secrets out of anything the bot logs, replies with, exports or
+//! reports, so that new log lines and error messages can't leak the login
+//! details, session cookies or tokens.
+//!
+//! Known secrets, like the SAIS password, are added with [`add_secret`] and
+//! replaced wherever they appear. Cookies, `Authorization` headers and
+//! password-like parameters are recognized by their shape.
+
+use regex::Regex;
+use std::{
+    borrow::Cow,
+    sync::{LazyLock, RwLock},
+};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// The environment variables that hold secrets, added by
+/// [`add_secrets_from_env`].
+pub const SECRET_ENV_VARS: &[&str] = &[
+    "USER_ID",
+    "PASSWORD",
+    "DISCORD_TOKEN",
+    "DISCORD_CLIENT_SECRET",
+    "DISCORD_WEBHOOK_URL",
+    "BACKUP_PASSPHRASE",
+    "INFLUXDB_TOKEN",
+    "PAGER_ROUTING_KEY",
+    "NATS_TOKEN",
+    "MQTT_PASSWORD",
+    "GITHUB_TOKEN",
+];
+
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// Each keeps its first group and redacts the rest of the match.
+static PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // Headers as written in HTTP, e.g. "Cookie: PS_TOKEN=..."
+        r"(?i)((?:set-)?cookie:\s*)[^\r\n]+",
+        r"(?i)(authorization:\s*)[^\r\n]+",
+        // Headers as printed by `{:?}`, e.g. `"cookie": "PS_TOKEN=..."`
+        r#"(?i)("(?:set-)?cookie":\s*")[^"]*"#,
+        r#"(?i)("authorization":\s*")[^"]*"#,
+        r"(?i)\b((?:bearer|token|basic|geniekey)\s+)[A-Za-z0-9._~+/=-]{8,}",
+        r"(?i)\b((?:pwd|password|passwd|passphrase|token|api_?key|secret)=)[^&\s;,]+",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("The redaction patterns are valid"))
+    .collect()
+});
+
+/// Redacts `secret` from now on, wherever it appears.
+pub fn add_secret(secret: &str) {
+    if secret.is_empty() {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap();
+    if !secrets.iter().any(|known| known == secret) {
+        secrets.push(secret.to_string());
+        // Longer secrets first, in case one contains another.
+        secrets.sort_by_key(|known| std::cmp::Reverse(known.len()));
+    }
+}
+
+/// Adds the values of [`SECRET_ENV_VARS`] that are set.
+pub fn add_secrets_from_env() {
+    for name in SECRET_ENV_VARS {
+        if let Ok(value) = std::env::var(name) {
+            add_secret(&value);
+        }
+    }
+}
+
+pub fn redact(text: &str) -> String {
+    let mut text = Cow::Borrowed(text);
+    for secret in SECRETS.read().unwrap().iter() {
+        if text.contains(secret.as_str()) {
+            text = Cow::Owned(text.replace(secret.as_str(), REDACTED));
+        }
+    }
+    for pattern in PATTERNS.iter() {
+        if let Cow::Owned(replaced) = pattern.replace_all(&text, format!("${{1}}{}", REDACTED)) {
+            text = Cow::Owned(replaced);
+        }
+    }
+    text.into_owned()
+}
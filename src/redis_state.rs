@@ -0,0 +1,210 @@
+//! Keeps the [shared state](crate::state) in Redis, speaking just enough of
+//! its protocol (RESP) for the handful of commands needed.
+
+use crate::{history::CheckRecord, redact, state::SharedState, Error};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use std::{convert::TryFrom, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+/// Every key the bot uses starts with this, so the Redis server can be
+/// shared with other applications.
+pub const KEY_PREFIX: &str = "is-sais-down-bot:";
+
+// Deletes the lock only if it's still held by the one letting go of it.
+const UNLOCK_SCRIPT: &str =
+    "if redis.call('get', KEYS[1]) == ARGV[1] then return redis.call('del', KEYS[1]) else return 0 end";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Status(String),
+    Integer(i64),
+    /// `None` is Redis's nil.
+    Bulk(Option<String>),
+    Array(Option<Vec<Reply>>),
+}
+
+pub struct RedisState {
+    address: String,
+    username: Option<String>,
+    password: Option<String>,
+    database: u32,
+    // Connected on first use, and again after an error.
+    connection: Mutex<Option<BufReader<TcpStream>>>,
+}
+
+impl RedisState {
+    /// Takes a URL like `redis://:password@localhost:6379/0`.
+    pub fn new(url: &str) -> Result<RedisState, Error> {
+        let url = reqwest::Url::parse(url)?;
+        if url.scheme() != "redis" {
+            return Err(format!("Expected a redis:// URL, not {}://", url.scheme()).into());
+        }
+        let password = url.password().map(str::to_string);
+        if let Some(password) = &password {
+            redact::add_secret(password);
+        }
+        let database = match url.path().trim_start_matches('/') {
+            "" => 0,
+            database => database
+                .parse()
+                .map_err(|_| format!("{:?} isn't a Redis database number", database))?,
+        };
+        Ok(RedisState {
+            address: format!(
+                "{}:{}",
+                url.host_str().unwrap_or("127.0.0.1"),
+                url.port().unwrap_or(6379)
+            ),
+            username: Some(url.username().to_string()).filter(|username| !username.is_empty()),
+            password,
+            database,
+            connection: Mutex::default(),
+        })
+    }
+
+    /// Sends one command, returning Redis's reply. Error replies are
+    /// returned as errors.
+    pub async fn command(&self, args: &[&str]) -> Result<Reply, Error> {
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            *connection = Some(self.connect().await?);
+        }
+        let stream = connection.as_mut().expect("Connected above");
+        let reply = send(stream, args).await;
+        // The connection can't be trusted to be in step after an I/O error.
+        if reply.as_ref().is_err_and(|why| why.is::<std::io::Error>()) {
+            *connection = None;
+        }
+        reply
+    }
+
+    async fn connect(&self) -> Result<BufReader<TcpStream>, Error> {
+        let mut stream = BufReader::new(TcpStream::connect(&self.address).await?);
+        if let Some(password) = &self.password {
+            match &self.username {
+                Some(username) => send(&mut stream, &["AUTH", username, password]).await?,
+                None => send(&mut stream, &["AUTH", password]).await?,
+            };
+        }
+        if self.database != 0 {
+            send(&mut stream, &["SELECT", &self.database.to_string()]).await?;
+        }
+        Ok(stream)
+    }
+}
+
+/// A command as RESP, an array of bulk strings.
+pub fn encode(args: &[&str]) -> Vec<u8> {
+    let mut encoded = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        encoded.extend(format!("${}\r\n", arg.len()).into_bytes());
+        encoded.extend(arg.as_bytes());
+        encoded.extend(b"\r\n");
+    }
+    encoded
+}
+
+async fn send(stream: &mut BufReader<TcpStream>, args: &[&str]) -> Result<Reply, Error> {
+    stream.get_mut().write_all(&encode(args)).await?;
+    read_reply(stream).await
+}
+
+fn read_reply(stream: &mut BufReader<TcpStream>) -> BoxFuture<'_, Result<Reply, Error>> {
+    Box::pin(async move {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await? == 0 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        let line = line.trim_end_matches("\r\n");
+        let (kind, rest) = line.split_at(line.len().min(1));
+        let length = || {
+            rest.parse::<i64>()
+                .map_err(|_| format!("Bad reply from Redis: {:?}", line))
+        };
+        match kind {
+            "+" => Ok(Reply::Status(rest.to_string())),
+            "-" => Err(format!("Redis replied with an error: {}", rest).into()),
+            ":" => Ok(Reply::Integer(length()?)),
+            "$" => match usize::try_from(length()?) {
+                Ok(length) => {
+                    let mut bulk = vec![0; length + 2];
+                    stream.read_exact(&mut bulk).await?;
+                    bulk.truncate(length);
+                    Ok(Reply::Bulk(Some(String::from_utf8(bulk)?)))
+                }
+                Err(_) => Ok(Reply::Bulk(None)),
+            },
+            "*" => match usize::try_from(length()?) {
+                Ok(length) => {
+                    let mut replies = Vec::with_capacity(length);
+                    for _ in 0..length {
+                        replies.push(read_reply(stream).await?);
+                    }
+                    Ok(Reply::Array(Some(replies)))
+                }
+                Err(_) => Ok(Reply::Array(None)),
+            },
+            _ => Err(format!("Bad reply from Redis: {:?}", line).into()),
+        }
+    })
+}
+
+fn key(name: &str) -> String {
+    format!("{}{}", KEY_PREFIX, name)
+}
+
+#[async_trait]
+impl SharedState for RedisState {
+    async fn take_cooldown(
+        &self,
+        key_name: &str,
+        cooldown: Duration,
+    ) -> Result<Option<Duration>, Error> {
+        let key = key(&format!("cooldown:{}", key_name));
+        let millis = cooldown.as_millis().max(1).to_string();
+        match self
+            .command(&["SET", &key, "1", "PX", &millis, "NX"])
+            .await?
+        {
+            Reply::Status(_) => Ok(None),
+            _ => match self.command(&["PTTL", &key]).await? {
+                Reply::Integer(left) => Ok(Some(Duration::from_millis(left.max(0) as u64))),
+                reply => Err(format!("Unexpected reply to PTTL: {:?}", reply).into()),
+            },
+        }
+    }
+
+    async fn latest_status(&self) -> Result<Option<CheckRecord>, Error> {
+        match self.command(&["GET", &key("latest_status")]).await? {
+            Reply::Bulk(Some(json)) => Ok(Some(serde_json::from_str(&json)?)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn set_latest_status(&self, record: &CheckRecord) -> Result<(), Error> {
+        let json = serde_json::to_string(record)?;
+        self.command(&["SET", &key("latest_status"), &json]).await?;
+        Ok(())
+    }
+
+    async fn try_lock(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool, Error> {
+        let key = key(&format!("lock:{}", name));
+        let millis = ttl.as_millis().max(1).to_string();
+        let reply = self
+            .command(&["SET", &key, holder, "PX", &millis, "NX"])
+            .await?;
+        Ok(matches!(reply, Reply::Status(_)))
+    }
+
+    async fn unlock(&self, name: &str, holder: &str) -> Result<(), Error> {
+        let key = key(&format!("lock:{}", name));
+        self.command(&["EVAL", UNLOCK_SCRIPT, "1", &key, holder])
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use crate::supervisor;
+use is_sais_down_bot::releases::{
+    self, Release, ReleaseCheckConfig, ReleaseWatch, CURRENT_VERSION,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::time::{self, MissedTickBehavior};
+
+// Release notes past this many characters are cut short in the owner's DM.
+const MAX_CHANGELOG_CHARS: usize = 1000;
+
+/// Checks GitHub for a newer release every `interval_hours`, telling the
+/// owner once about each one found.
+pub struct ReleaseCheckTask {
+    pub config: ReleaseCheckConfig,
+    /// Shown in `&about`.
+    pub watch: Arc<ReleaseWatch>,
+}
+
+impl ReleaseCheckTask {
+    /// Runs until the bot exits.
+    pub async fn run(self) {
+        let mut interval = time::interval(Duration::from_secs(self.config.interval_hours * 3600));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match releases::latest_release(&self.config.releases_url).await {
+                Ok(release) => {
+                    if self.watch.found(release.clone()) {
+                        tracing::info!(
+                            "is-sais-down-bot {} is out, running {}",
+                            release.tag_name,
+                            CURRENT_VERSION
+                        );
+                        supervisor::tell_owner(self.notice(&release));
+                    }
+                }
+                Err(why) => tracing::warn!("Could not check for new releases: {:?}", why),
+            }
+        }
+    }
+
+    fn notice(&self, release: &Release) -> String {
+        let mut notice = format!(
+            "is-sais-down-bot {} is out, and I'm running {}: {}",
+            release.tag_name, CURRENT_VERSION, release.html_url
+        );
+        if self.config.changelog {
+            if let Some(changelog) = release.changelog_excerpt(MAX_CHANGELOG_CHARS) {
+                notice.push_str(&format!("\n>>> {}", changelog));
+            }
+        }
+        notice
+    }
+}
@@ -0,0 +1,139 @@
+//! Checks GitHub for releases newer than the running version, so long-running
+//! deployments don't silently fall behind.
+
+use crate::Error;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const DEFAULT_RELEASES_URL: &str =
+    "https://api.github.com/repos/grantyap/is-sais-down-bot/releases/latest";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseCheckConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_hours")]
+    pub interval_hours: u64,
+    /// GitHub's latest release endpoint, or anything answering like it.
+    #[serde(default = "default_releases_url")]
+    pub releases_url: String,
+    /// Include the start of the release notes when telling the owner.
+    #[serde(default = "default_enabled")]
+    pub changelog: bool,
+}
+
+impl Default for ReleaseCheckConfig {
+    fn default() -> Self {
+        ReleaseCheckConfig {
+            enabled: default_enabled(),
+            interval_hours: default_interval_hours(),
+            releases_url: default_releases_url(),
+            changelog: default_enabled(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_hours() -> u64 {
+    24
+}
+
+fn default_releases_url() -> String {
+    DEFAULT_RELEASES_URL.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+    /// The release notes, in Markdown.
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+impl Release {
+    pub fn is_newer_than(&self, version: &str) -> bool {
+        version_numbers(&self.tag_name) > version_numbers(version)
+    }
+
+    /// The release notes, cut short at a line break after at most
+    /// `max_chars` characters.
+    pub fn changelog_excerpt(&self, max_chars: usize) -> Option<String> {
+        let body = self.body.as_deref()?.trim();
+        if body.is_empty() {
+            return None;
+        }
+        if body.chars().count() <= max_chars {
+            return Some(body.to_string());
+        }
+        let cut = body
+            .char_indices()
+            .nth(max_chars)
+            .map_or(body.len(), |(index, _)| index);
+        let end = body[..cut].rfind('\n').unwrap_or(cut);
+        Some(format!("{}\n...", body[..end].trim_end()))
+    }
+}
+
+// "v1.10.0" is [1, 10, 0]. Anything after the numbers, like "-beta", is
+// ignored.
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map_while(|part| {
+            let digits = part
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+pub async fn latest_release(releases_url: &str) -> Result<Release, Error> {
+    let release = reqwest::Client::new()
+        .get(releases_url)
+        // GitHub turns away requests without one.
+        .header(
+            "User-Agent",
+            concat!("is-sais-down-bot/", env!("CARGO_PKG_VERSION")),
+        )
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(release)
+}
+
+/// The newest release found that is newer than this version, if any.
+#[derive(Debug, Default)]
+pub struct ReleaseWatch {
+    newer: Mutex<Option<Release>>,
+}
+
+impl ReleaseWatch {
+    pub fn newer_release(&self) -> Option<Release> {
+        self.newer.lock().unwrap().clone()
+    }
+
+    /// Keeps `release` if it's newer than this version, returning whether it
+    /// wasn't already known.
+    pub fn found(&self, release: Release) -> bool {
+        if !release.is_newer_than(CURRENT_VERSION) {
+            return false;
+        }
+        let mut newer = self.newer.lock().unwrap();
+        let is_new = newer
+            .as_ref()
+            .is_none_or(|known| known.tag_name != release.tag_name);
+        *newer = Some(release);
+        is_new
+    }
+}
@@ -0,0 +1,79 @@
+//! "Still down" reminders during long outages, coming less often the longer
+//! it goes on, so a channel isn't flooded with them.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReminderConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// How long after the outage announcement the first reminder comes.
+    /// Each one after that waits twice as long as the one before.
+    #[serde(default = "default_first_after_mins")]
+    pub first_after_mins: u64,
+    /// Reminders never wait longer than this.
+    #[serde(default = "default_max_gap_mins")]
+    pub max_gap_mins: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_first_after_mins() -> u64 {
+    30
+}
+
+fn default_max_gap_mins() -> u64 {
+    8 * 60
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        ReminderConfig {
+            enabled: default_enabled(),
+            first_after_mins: default_first_after_mins(),
+            max_gap_mins: default_max_gap_mins(),
+        }
+    }
+}
+
+/// When the next reminder about an outage is due.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReminderSchedule {
+    next_at: DateTime<Utc>,
+    gap: Duration,
+    max_gap: Duration,
+}
+
+impl ReminderSchedule {
+    /// Starts from an outage announced at `announced_at`, or `None` when
+    /// reminders are turned off.
+    pub fn new(config: &ReminderConfig, announced_at: DateTime<Utc>) -> Option<ReminderSchedule> {
+        if !config.enabled {
+            return None;
+        }
+        let first_after = Duration::minutes(config.first_after_mins.max(1) as i64);
+        Some(ReminderSchedule {
+            next_at: announced_at + first_after,
+            gap: first_after,
+            max_gap: Duration::minutes(config.max_gap_mins as i64).max(first_after),
+        })
+    }
+
+    pub fn next_at(&self) -> DateTime<Utc> {
+        self.next_at
+    }
+
+    /// Whether a reminder is due at `now`, moving on to the next one if so.
+    /// Reminders missed while the bot was busy or away only come once.
+    pub fn due(&mut self, now: DateTime<Utc>) -> bool {
+        if now < self.next_at {
+            return false;
+        }
+        self.gap = (self.gap * 2).min(self.max_gap);
+        self.next_at = now + self.gap;
+        true
+    }
+}
@@ -0,0 +1,242 @@
+//! Summaries of how SAIS did over a month, from the history.
+
+use crate::{
+    history::{CheckRecord, HistoryStore, HourlyRollup},
+    routes::MAIN_ROUTE_NAME,
+    sla::{counts, failed_check},
+    Error,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+
+/// A run of failed checks on the main route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outage {
+    pub started_at: DateTime<Utc>,
+    /// When SAIS was next seen working, or the last failed check if it
+    /// still wasn't by the end of the month.
+    pub ended_at: DateTime<Utc>,
+    /// The first failed check's reason, or its status kind if it had none.
+    pub cause: String,
+}
+
+impl Outage {
+    pub fn duration(&self) -> Duration {
+        self.ended_at - self.started_at
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyReport {
+    pub month_start: DateTime<Utc>,
+    /// Counted the same way as the [error budget](crate::sla::ErrorBudget).
+    pub uptime_percent: f64,
+    pub checks: u64,
+    pub outages: Vec<Outage>,
+    /// The main route's average latency in each week of the month, counting
+    /// weeks from the first.
+    pub weekly_latency_ms: Vec<u64>,
+    /// How many failed checks had each cause, most common first.
+    pub causes: Vec<(String, u64)>,
+    /// Every check from the month that hasn't been compacted yet.
+    pub records: Vec<CheckRecord>,
+}
+
+impl MonthlyReport {
+    pub async fn for_month(
+        history: &dyn HistoryStore,
+        month_start: DateTime<Utc>,
+        month_end: DateTime<Utc>,
+    ) -> Result<MonthlyReport, Error> {
+        let mut records = history.since(month_start).await?;
+        records.retain(|record| record.checked_at < month_end);
+        let mut rollups = history.rollups_since(month_start).await?;
+        rollups.retain(|rollup| rollup.hour < month_end);
+        Ok(MonthlyReport::from_history(month_start, records, &rollups))
+    }
+
+    /// Outages and their causes can only be found in the checks that
+    /// haven't been rolled up, but everything else also counts the rollups.
+    pub fn from_history(
+        month_start: DateTime<Utc>,
+        records: Vec<CheckRecord>,
+        rollups: &[HourlyRollup],
+    ) -> MonthlyReport {
+        let mut checks = 0;
+        let mut failed = 0;
+        let mut weekly_latency = BTreeMap::<i64, (u64, u64)>::new();
+        let mut causes = BTreeMap::<String, u64>::new();
+
+        let main_route_records = records
+            .iter()
+            .filter(|record| record.route == MAIN_ROUTE_NAME);
+        for record in main_route_records {
+            let week = (record.checked_at - month_start).num_days() / 7;
+            let (count, total) = weekly_latency.entry(week).or_default();
+            *count += 1;
+            *total += record.latency_ms;
+            if counts(&record.status) {
+                checks += 1;
+                if failed_check(&record.status) {
+                    failed += 1;
+                    *causes.entry(cause(record)).or_default() += 1;
+                }
+            }
+        }
+
+        for rollup in rollups
+            .iter()
+            .filter(|rollup| rollup.route == MAIN_ROUTE_NAME)
+        {
+            let week = (rollup.hour - month_start).num_days() / 7;
+            let (count, total) = weekly_latency.entry(week).or_default();
+            *count += rollup.checks;
+            *total += rollup.total_latency_ms;
+            if counts(&rollup.status) {
+                checks += rollup.checks;
+                if failed_check(&rollup.status) {
+                    failed += rollup.checks;
+                }
+            }
+        }
+
+        let uptime_percent = if checks == 0 {
+            100.0
+        } else {
+            100.0 * (1.0 - failed as f64 / checks as f64)
+        };
+        let weeks = weekly_latency.keys().last().map_or(0, |week| week + 1);
+        let weekly_latency_ms = (0..weeks)
+            .map(|week| match weekly_latency.get(&week) {
+                Some((count, total)) if *count > 0 => total / count,
+                _ => 0,
+            })
+            .collect();
+        let mut causes = causes.into_iter().collect::<Vec<_>>();
+        causes.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        MonthlyReport {
+            month_start,
+            uptime_percent,
+            checks,
+            outages: outages(&records),
+            weekly_latency_ms,
+            causes,
+            records,
+        }
+    }
+}
+
+/// Finds the outages in the main route's `records`, which have to be oldest
+/// first.
+pub fn outages(records: &[CheckRecord]) -> Vec<Outage> {
+    let mut outages = vec![];
+    let mut ongoing: Option<Outage> = None;
+    let main_route_records = records
+        .iter()
+        .filter(|record| record.route == MAIN_ROUTE_NAME && counts(&record.status));
+    for record in main_route_records {
+        if failed_check(&record.status) {
+            match &mut ongoing {
+                Some(outage) => outage.ended_at = record.checked_at,
+                None => {
+                    ongoing = Some(Outage {
+                        started_at: record.checked_at,
+                        ended_at: record.checked_at,
+                        cause: cause(record),
+                    })
+                }
+            }
+        } else if let Some(mut outage) = ongoing.take() {
+            outage.ended_at = record.checked_at;
+            outages.push(outage);
+        }
+    }
+    outages.extend(ongoing);
+    outages
+}
+
+fn cause(record: &CheckRecord) -> String {
+    record
+        .reason
+        .clone()
+        .unwrap_or_else(|| record.status.clone())
+}
+
+/// One route at a glance, over some checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceSummary {
+    /// `None` without any checks that count.
+    pub uptime_percent: Option<f64>,
+    pub median_latency_ms: Option<u64>,
+    /// The last check's status kind.
+    pub latest_status: Option<String>,
+}
+
+impl ServiceSummary {
+    /// A summary of the main route.
+    pub fn from_records(records: &[CheckRecord]) -> ServiceSummary {
+        ServiceSummary::for_route(records, MAIN_ROUTE_NAME)
+    }
+
+    pub fn for_route(records: &[CheckRecord], route: &str) -> ServiceSummary {
+        let route_records = records
+            .iter()
+            .filter(|record| record.route == route)
+            .collect::<Vec<_>>();
+        let counted = route_records
+            .iter()
+            .filter(|record| counts(&record.status))
+            .collect::<Vec<_>>();
+        let failed = counted
+            .iter()
+            .filter(|record| failed_check(&record.status))
+            .count();
+        let uptime_percent =
+            (!counted.is_empty()).then(|| 100.0 * (1.0 - failed as f64 / counted.len() as f64));
+
+        let mut latencies = route_records
+            .iter()
+            .map(|record| record.latency_ms)
+            .collect::<Vec<_>>();
+        latencies.sort_unstable();
+        ServiceSummary {
+            uptime_percent,
+            median_latency_ms: latencies.get(latencies.len() / 2).copied(),
+            latest_status: route_records.last().map(|record| record.status.clone()),
+        }
+    }
+}
+
+/// Mean time to recovery and between failures, over a period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reliability {
+    pub outages: usize,
+    /// How long outages lasted on average, or `None` without any.
+    pub mean_time_to_recovery: Option<Duration>,
+    /// How long SAIS stayed up between outages on average, or `None`
+    /// without any.
+    pub mean_time_between_failures: Option<Duration>,
+}
+
+impl Reliability {
+    /// `period` is how long the outages were looked for in.
+    pub fn from_outages(outages: &[Outage], period: Duration) -> Reliability {
+        let count = outages.len() as i32;
+        if count == 0 {
+            return Reliability {
+                outages: 0,
+                mean_time_to_recovery: None,
+                mean_time_between_failures: None,
+            };
+        }
+        let downtime = outages
+            .iter()
+            .fold(Duration::zero(), |total, outage| total + outage.duration());
+        Reliability {
+            outages: outages.len(),
+            mean_time_to_recovery: Some(downtime / count),
+            mean_time_between_failures: Some((period - downtime) / count),
+        }
+    }
+}
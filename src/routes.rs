@@ -0,0 +1,208 @@
+use crate::{
+    config::{IpFamily, Route},
+    hooks::Hooks,
+    layout::PageLayout,
+    monitor::OutageStreak,
+    sais::{ResponseSnippet, SaisClient},
+    status::ServiceStatus,
+    tcp_probe::TcpProbe,
+    timing::{TimingHistograms, TimingProbe},
+    Error,
+};
+use chrono::{DateTime, Utc};
+use futures::future::try_join_all;
+use std::{
+    sync::{Arc, Mutex as SyncMutex},
+    time::Duration,
+};
+use tokio::sync::Mutex;
+
+/// What the route that isn't listed in `routes` is called.
+pub const MAIN_ROUTE_NAME: &str = "here";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteResult {
+    pub route: String,
+    pub status: ServiceStatus,
+    /// See [`SaisClient::suspicious_redirect`].
+    pub suspicious_redirect: Option<String>,
+    /// See [`SaisClient::failing_pages`].
+    pub failing_pages: Vec<String>,
+    /// See [`SaisClient::error_page`].
+    pub error_page: Option<String>,
+    pub latency: Duration,
+}
+
+/// Checks SAIS through the main route and every configured extra route at
+/// the same time, so their results can be compared.
+pub struct RouteChecker {
+    clients: Vec<(Route, Mutex<SaisClient>)>,
+    // Follows the main route.
+    outage_streak: SyncMutex<OutageStreak>,
+    // Set when `tcp_probe` is.
+    tcp_probe: Option<TcpProbe>,
+    // Set when `timing_probe` is.
+    timing_probe: Option<TimingProbe>,
+    timings: Arc<TimingHistograms>,
+    // Gets the last word on every result, when set.
+    hooks: Option<Arc<Hooks>>,
+}
+
+impl RouteChecker {
+    pub fn new(main_client: SaisClient) -> Self {
+        let sais_config = &main_client.sais_config;
+        let mut routes = sais_config.routes.clone();
+        if sais_config.dual_stack {
+            for ip_family in [IpFamily::V4, IpFamily::V6] {
+                routes.push(Route {
+                    name: ip_family.to_string(),
+                    proxy: sais_config.proxy.clone(),
+                    ip_family: Some(ip_family),
+                });
+            }
+        }
+
+        let tcp_probe = if sais_config.tcp_probe {
+            TcpProbe::new(sais_config)
+        } else {
+            None
+        };
+
+        let timing_probe = if sais_config.timing_probe {
+            TimingProbe::new(sais_config)
+        } else {
+            None
+        };
+
+        let mut clients = routes
+            .into_iter()
+            .map(|route| {
+                let client = main_client.for_route(&route);
+                (route, Mutex::new(client))
+            })
+            .collect::<Vec<_>>();
+        let main_route = Route {
+            name: MAIN_ROUTE_NAME.to_string(),
+            proxy: main_client.sais_config.proxy.clone(),
+            ip_family: main_client.sais_config.ip_family,
+        };
+        clients.insert(0, (main_route, Mutex::new(main_client)));
+        RouteChecker {
+            clients,
+            outage_streak: SyncMutex::default(),
+            tcp_probe,
+            timing_probe,
+            timings: Arc::default(),
+            hooks: None,
+        }
+    }
+
+    /// Records the phases [`Self::probe_timings`] times in `timings`
+    /// instead of histograms of its own.
+    pub fn with_timings(mut self, timings: Arc<TimingHistograms>) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Has the classify hook reclassify every result.
+    pub fn with_hooks(mut self, hooks: Arc<Hooks>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// The main route's result always comes first. Routes pinned to an IP
+    /// family the SAIS host has no addresses for, or that can't be looked up
+    /// within the connect timeout, are left out.
+    pub async fn check_all(&self) -> Result<Vec<RouteResult>, Error> {
+        let checked_at = Utc::now();
+        let results = try_join_all(self.clients.iter().map(|(route, client)| async move {
+            let mut client = client.lock().await;
+            if let Some(ip_family) = route.ip_family {
+                // Looking up the host isn't covered by the check's timeouts.
+                let connect_timeout = client.sais_config.timeouts.connect();
+                let resolves =
+                    tokio::time::timeout(connect_timeout, client.resolves_to(ip_family)).await;
+                if resolves != Ok(true) {
+                    tracing::info!(
+                        "Skipping route {}, SAIS has no {} address",
+                        route.name,
+                        ip_family
+                    );
+                    return Ok(None);
+                }
+            }
+
+            let status = client.check().await?;
+            Ok::<_, Error>(Some(RouteResult {
+                route: route.name.clone(),
+                status,
+                suspicious_redirect: client.suspicious_redirect().map(str::to_string),
+                failing_pages: client.failing_pages().to_vec(),
+                error_page: client.error_page().map(str::to_string),
+                latency: client.last_latency(),
+            }))
+        }))
+        .await?;
+        let mut results = results.into_iter().flatten().collect::<Vec<_>>();
+        if let Some(hooks) = &self.hooks {
+            for result in results.iter_mut() {
+                result.status = hooks.classify(result);
+            }
+        }
+        self.outage_streak
+            .lock()
+            .unwrap()
+            .observe(results[0].status, checked_at);
+        Ok(results)
+    }
+
+    /// Times a plain TCP connection to SAIS, if `tcp_probe` is set. Kept
+    /// apart from [`Self::check_all`], since it isn't a route students use.
+    pub async fn probe_tcp(&self) -> Option<RouteResult> {
+        match &self.tcp_probe {
+            Some(tcp_probe) => Some(tcp_probe.run().await),
+            None => None,
+        }
+    }
+
+    /// Times each phase of loading the login page, if `timing_probe` is
+    /// set, and records them. Failures are only logged.
+    pub async fn probe_timings(&self) {
+        let timing_probe = match &self.timing_probe {
+            Some(timing_probe) => timing_probe,
+            None => return,
+        };
+        match timing_probe.run().await {
+            Ok(timings) => {
+                tracing::debug!("Check phases: {}", timings);
+                self.timings.record(&timings);
+            }
+            Err(why) => tracing::info!("Could not time the check's phases: {}", why),
+        }
+    }
+
+    /// When the main route's current outage started, if it's out.
+    pub fn outage_since(&self) -> Option<DateTime<Utc>> {
+        self.outage_streak.lock().unwrap().since()
+    }
+
+    pub async fn login_url(&self) -> String {
+        self.clients[0].1.lock().await.sais_config.login_url.clone()
+    }
+
+    /// The last response SAIS sent the main route.
+    pub async fn last_response(&self) -> Option<ResponseSnippet> {
+        self.clients[0].1.lock().await.last_response()
+    }
+
+    /// The main route's last normally loaded login page layout.
+    pub async fn login_page_layout(&self) -> Option<PageLayout> {
+        self.clients[0].1.lock().await.login_page_layout().cloned()
+    }
+}
+
+pub fn routes_disagree(results: &[RouteResult]) -> bool {
+    results
+        .windows(2)
+        .any(|pair| pair[0].status != pair[1].status)
+}
@@ -0,0 +1,59 @@
+//! How the bot's own process is doing, for `&botstats`.
+
+use crate::heartbeat::Heartbeat;
+use std::time::{Duration, Instant};
+
+/// The process's resident memory, on Linux.
+pub fn resident_memory_bytes() -> Option<u64> {
+    parse_resident_memory(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// Reads `VmRSS` out of the contents of `/proc/<pid>/status`.
+pub fn parse_resident_memory(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskCounts {
+    /// Spawned tasks that haven't finished yet.
+    pub alive: usize,
+    pub workers: usize,
+}
+
+/// Counts the tasks on the current tokio runtime. Panics outside one.
+pub fn task_counts() -> TaskCounts {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    TaskCounts {
+        alive: metrics.num_alive_tasks(),
+        workers: metrics.num_workers(),
+    }
+}
+
+/// How far past its next check a loop beating `heartbeat` every `interval`
+/// is, as of `now`.
+pub fn loop_lag(heartbeat: &Heartbeat, interval: Duration, now: Instant) -> Duration {
+    heartbeat.silent_for(now).saturating_sub(interval)
+}
+
+/// Like "12.3 MiB".
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
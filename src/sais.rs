@@ -0,0 +1,674 @@
+use crate::{
+    capture::{RecordingTransport, ReplayTransport},
+    config::{IpFamily, LoginDetails, Route, SaisConfig},
+    cookies::CookieJar,
+    har::truncate,
+    layout::{LoginForm, PageLayout},
+    redact::{self, REDACTED},
+    status::{DownReason, ServiceStatus},
+    transport::{
+        HttpRequest, HttpResponse, HttpTransport, ReqwestTransport, TimeoutError, TimeoutKind,
+    },
+    Error,
+};
+use chrono::{DateTime, Utc};
+use reqwest::{
+    header::{HeaderValue, COOKIE, SET_COOKIE},
+    Method, StatusCode,
+};
+use std::{
+    env,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const INVALID_CREDENTIALS_STRING: &str = "Your UP Email ID and/or Password are invalid.";
+
+/// Checks whether SAIS works by loading the login page and logging in, like
+/// a student would.
+///
+/// ```no_run
+/// use is_sais_down_bot::{LoginDetails, SaisClient, SaisConfig};
+///
+/// # async fn run() -> Result<(), is_sais_down_bot::Error> {
+/// let sais_config = SaisConfig::new(
+///     "https://sais.up.edu.ph/psp/ps/?cmd=login&languageCd=ENG",
+///     "<title>Employee-facing registry content</title>",
+/// );
+/// let mut client = SaisClient::with_config(sais_config, LoginDetails::get());
+/// let status = client.check().await?;
+/// println!("SAIS is {} ({:?})", status, client.last_latency());
+/// # Ok(())
+/// # }
+/// ```
+pub struct SaisClient {
+    pub sais_config: SaisConfig,
+    transport: Box<dyn HttpTransport>,
+    login_details: LoginDetails,
+    cookies: CookieJar,
+    login_page_layout: Option<PageLayout>,
+    login_form: Option<LoginForm>,
+    redirects: Vec<String>,
+    failing_pages: Vec<String>,
+    page_results: Vec<PageResult>,
+    error_page: Option<String>,
+    // When the reusable session was logged in, if there is one.
+    session_started: Option<Instant>,
+    // The same for every request of a check.
+    user_agent: String,
+    last_latency: Duration,
+    last_response: Mutex<Option<ResponseSnippet>>,
+}
+
+/// The start of a response SAIS sent, with the login details redacted.
+#[derive(Debug, Clone)]
+pub struct ResponseSnippet {
+    pub received_at: DateTime<Utc>,
+    pub method: Method,
+    pub url: String,
+    pub status: StatusCode,
+    /// Cut short after [`SNIPPET_LIMIT`] bytes.
+    pub body: String,
+}
+
+pub const SNIPPET_LIMIT: usize = 16 * 1024;
+
+/// How one of the `deep_checks` pages did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageResult {
+    pub name: String,
+    pub latency: Duration,
+    /// Why the page didn't work, or `None` if it did.
+    pub problem: Option<String>,
+}
+
+impl SaisClient {
+    /// Reads `config/sais.ron` and the login details from the environment,
+    /// panicking if either is missing. Responses are replayed from
+    /// `SAIS_REPLAY_DIR` or recorded to `SAIS_RECORD_DIR` when set.
+    // Not `Default`, since this reads the config file and the environment.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> SaisClient {
+        let sais_config = SaisConfig::get().expect("Could not get SaisConfig");
+        let login_details = LoginDetails::get();
+        let transport: Box<dyn HttpTransport> = if let Ok(dir) = env::var("SAIS_REPLAY_DIR") {
+            tracing::info!("Replaying SAIS responses from '{}'", dir);
+            Box::new(ReplayTransport::from_dir(dir).expect("Could not load SAIS_REPLAY_DIR"))
+        } else if let Ok(dir) = env::var("SAIS_RECORD_DIR") {
+            tracing::info!("Recording SAIS responses to '{}'", dir);
+            let secrets = vec![login_details.userid.clone(), login_details.pwd.clone()];
+            Box::new(
+                RecordingTransport::new(reqwest_transport(&sais_config), dir, secrets)
+                    .expect("Could not set up SAIS_RECORD_DIR"),
+            )
+        } else {
+            Box::new(reqwest_transport(&sais_config))
+        };
+
+        SaisClient::with_transport(sais_config, login_details, transport)
+    }
+
+    pub fn with_config(sais_config: SaisConfig, login_details: LoginDetails) -> SaisClient {
+        let transport = reqwest_transport(&sais_config);
+        SaisClient::with_transport(sais_config, login_details, transport)
+    }
+
+    /// Uses `transport` for every request instead of a plain reqwest client,
+    /// e.g. to route checks differently or to serve canned responses.
+    pub fn with_transport(
+        sais_config: SaisConfig,
+        login_details: LoginDetails,
+        transport: impl HttpTransport + 'static,
+    ) -> SaisClient {
+        SaisClient {
+            sais_config,
+            transport: Box::new(transport),
+            login_details,
+            cookies: CookieJar::new(),
+            login_page_layout: None,
+            login_form: None,
+            redirects: vec![],
+            failing_pages: vec![],
+            page_results: vec![],
+            error_page: None,
+            session_started: None,
+            user_agent: String::new(),
+            last_latency: Duration::ZERO,
+            last_response: Mutex::default(),
+        }
+    }
+
+    /// A client with the same config and login details that goes out through
+    /// `route` instead.
+    pub fn for_route(&self, route: &Route) -> SaisClient {
+        let mut sais_config = self.sais_config.clone();
+        sais_config.proxy = route.proxy.clone();
+        sais_config.ip_family = route.ip_family;
+        sais_config.dual_stack = false;
+        sais_config.routes.clear();
+        SaisClient::with_config(sais_config, self.login_details.clone())
+    }
+
+    /// The layout of the last login page that loaded normally.
+    pub fn login_page_layout(&self) -> Option<&PageLayout> {
+        self.login_page_layout.as_ref()
+    }
+
+    /// Whether the SAIS host has any addresses of `ip_family`. Routes through
+    /// a proxy can't tell, so they always count as having one.
+    pub async fn resolves_to(&self, ip_family: IpFamily) -> bool {
+        if self.sais_config.proxy.is_some() {
+            return true;
+        }
+        let url = match reqwest::Url::parse(&self.sais_config.login_url) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+        let (host, port) = match (url.host_str(), url.port_or_known_default()) {
+            (Some(host), Some(port)) => (host.to_string(), port),
+            _ => return false,
+        };
+        match tokio::net::lookup_host((host, port)).await {
+            Ok(mut addrs) => addrs.any(|addr| ip_family.matches(&addr.ip())),
+            Err(why) => {
+                tracing::warn!("Could not resolve SAIS host: {:?}", why);
+                false
+            }
+        }
+    }
+
+    /// Loads the login page, then tries to log in with the cookies it set.
+    /// SAIS being down is a status, not an error: errors are only for
+    /// checks that couldn't be made properly.
+    pub async fn check(&mut self) -> Result<ServiceStatus, Error> {
+        log_step(format!(
+            "Checking SAIS at '{}'",
+            &self.sais_config.login_url
+        ));
+
+        self.redirects.clear();
+        self.failing_pages.clear();
+        self.page_results.clear();
+        self.error_page = None;
+        self.user_agent = self.sais_config.headers.next_user_agent();
+        let check_timeout = self.sais_config.timeouts.check();
+        let start = Instant::now();
+        let result = match tokio::time::timeout(check_timeout, self.check_steps()).await {
+            Ok(result) => result,
+            Err(_) => Err(TimeoutError(TimeoutKind::Check).into()),
+        };
+        let latency = start.elapsed();
+        self.last_latency = latency;
+        log_step(format!("Check took {:?}", latency));
+        // Kept sessions are logged out of when they expire instead.
+        if self.sais_config.session.is_none() {
+            self.log_out().await;
+        }
+
+        match result {
+            Err(why) => match why.downcast_ref::<TimeoutError>() {
+                Some(TimeoutError(kind)) => {
+                    log_step(format!("Check failed: {}", why));
+                    Ok(ServiceStatus::Down(DownReason::TimedOut(*kind)))
+                }
+                None => Err(why),
+            },
+            Ok(ServiceStatus::Up) => match self.sais_config.degraded_after_secs {
+                Some(secs) if latency > Duration::from_secs(secs) => {
+                    Ok(ServiceStatus::Degraded(latency))
+                }
+                _ => Ok(ServiceStatus::Up),
+            },
+            result => result,
+        }
+    }
+
+    async fn check_steps(&mut self) -> Result<ServiceStatus, Error> {
+        let status = if self.session_is_alive().await {
+            ServiceStatus::Up
+        } else {
+            self.session_started = None;
+            self.full_login().await?
+        };
+        if status == ServiceStatus::Up && !self.sais_config.deep_checks.is_empty() {
+            self.run_deep_checks().await?;
+            if !self.failing_pages.is_empty() {
+                return Ok(ServiceStatus::PagesFailing);
+            }
+        }
+        Ok(status)
+    }
+
+    /// Whether the session from an earlier login still works, so the login
+    /// can be skipped this time.
+    async fn session_is_alive(&mut self) -> bool {
+        let (session_config, session_started) =
+            match (&self.sais_config.session, self.session_started) {
+                (Some(session_config), Some(session_started)) => {
+                    (session_config.clone(), session_started)
+                }
+                _ => return false,
+            };
+        if session_started.elapsed() > Duration::from_secs(session_config.max_age_secs) {
+            log_step("Session is too old, logging in again".to_string());
+            return false;
+        }
+        if self.cookies.remove_expired(Utc::now()) {
+            log_step("Session cookies expired, logging in again".to_string());
+            return false;
+        }
+
+        let request = match self.authenticated_request(Method::GET, &session_config.keepalive_url) {
+            Ok(request) => request,
+            Err(_) => return false,
+        };
+        match self.send(request).await {
+            Ok(response)
+                if response.status.is_success()
+                    && response.body.contains(&session_config.expect) =>
+            {
+                log_step("Session is still alive, skipping the login".to_string());
+                self.note_redirects(&response);
+                true
+            }
+            Ok(response) => {
+                log_step(format!(
+                    "Session expired ({}), logging in again",
+                    response.status
+                ));
+                self.cookies.clear();
+                false
+            }
+            Err(why) => {
+                log_step(format!("Could not check the session: {}", why));
+                false
+            }
+        }
+    }
+
+    async fn full_login(&mut self) -> Result<ServiceStatus, Error> {
+        let response = match self.get_response().await {
+            Ok(response) => response,
+            Err(why) if why.is::<TimeoutError>() => return Err(why),
+            Err(why) if was_refused(&*why) => {
+                log_step(format!("Connection refused: {:?}", why));
+                return Ok(ServiceStatus::Down(DownReason::ConnectionRefused));
+            }
+            Err(why) => {
+                log_step(format!("Could not get response: {:?}", why));
+                return Ok(ServiceStatus::Down(DownReason::NoResponse));
+            }
+        };
+        log_step("Got a response".to_string());
+        self.note_redirects(&response);
+
+        if self.is_maintenance_page(&response.body) {
+            log_step("Found a maintenance notice on the login page".to_string());
+            return Ok(ServiceStatus::Maintenance);
+        }
+        if !response.status.is_success() {
+            log_step(format!("Unsuccessful status code {:?}", response.status));
+            self.note_error_page(&response.body);
+            return Ok(ServiceStatus::Down(DownReason::HttpStatus(response.status)));
+        }
+        log_step(format!("Successful status code {:?}", response.status));
+        self.login_page_layout = Some(PageLayout::from_html(&response.body));
+        self.login_form = LoginForm::from_html(&response.body);
+        match &self.login_form {
+            Some(login_form) => log_step(format!(
+                "Found a login form with hidden fields {:?}",
+                login_form
+                    .hidden_fields
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>()
+            )),
+            None => log_step("Found no login form, posting to the login page".to_string()),
+        }
+
+        // Cookies from the login page replace any of the same name, and
+        // ones that have expired since are dropped.
+        self.cookies.remove_expired(Utc::now());
+        self.save_cookies_from_response(&response);
+        tracing::debug!("Cookies stored: {}", self.cookies.len());
+
+        let status = self.log_in().await?;
+        if status == ServiceStatus::Up {
+            self.session_started = Some(Instant::now());
+        }
+        Ok(status)
+    }
+
+    /// Ends the session of this check's login, if it had one, so it isn't
+    /// left dangling on the SAIS side. Failing to doesn't affect the result.
+    async fn log_out(&mut self) {
+        if self.session_started.take().is_none() {
+            return;
+        }
+        let logout_url = match &self.sais_config.logout_url {
+            Some(logout_url) => logout_url.clone(),
+            None => return,
+        };
+        let result = match self.authenticated_request(Method::GET, &logout_url) {
+            Ok(request) => self.send(request).await.map(|_| ()),
+            Err(why) => Err(why),
+        };
+        // The session is over either way.
+        self.cookies.clear();
+        match result {
+            Ok(()) => log_step("Logged out".to_string()),
+            Err(why) => tracing::warn!("Could not log out of SAIS: {}", why),
+        }
+    }
+
+    async fn run_deep_checks(&mut self) -> Result<(), Error> {
+        for deep_check in self.sais_config.deep_checks.clone() {
+            let request = self.authenticated_request(Method::GET, &deep_check.url)?;
+            let start = Instant::now();
+            let problem = match self.send(request).await {
+                Ok(response) => {
+                    self.note_redirects(&response);
+                    if !response.status.is_success() {
+                        Some(format!("it answered with {}", response.status))
+                    } else if !response.body.contains(&deep_check.expect) {
+                        Some(format!("it didn't show {:?}", deep_check.expect))
+                    } else {
+                        None
+                    }
+                }
+                Err(why) => {
+                    log_step(format!("Could not load {}: {}", deep_check.name, why));
+                    Some(why.to_string())
+                }
+            };
+            log_step(format!(
+                "Deep check {}: {}",
+                deep_check.name,
+                problem.as_deref().unwrap_or("ok")
+            ));
+            if problem.is_some() {
+                self.failing_pages.push(deep_check.name.clone());
+            }
+            self.page_results.push(PageResult {
+                name: deep_check.name,
+                latency: start.elapsed(),
+                problem,
+            });
+        }
+        Ok(())
+    }
+
+    /// How long the last check took.
+    pub fn last_latency(&self) -> Duration {
+        self.last_latency
+    }
+
+    /// The deep check pages that failed during the last check.
+    pub fn failing_pages(&self) -> &[String] {
+        &self.failing_pages
+    }
+
+    /// How each deep check page did during the last check. Empty when the
+    /// login didn't work, since they are only loaded after one that did.
+    pub fn page_results(&self) -> &[PageResult] {
+        &self.page_results
+    }
+
+    /// The last response SAIS sent, if any request got one.
+    pub fn last_response(&self) -> Option<ResponseSnippet> {
+        self.last_response.lock().unwrap().clone()
+    }
+
+    // Every request goes through here, keeping the response for
+    // `last_response`.
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let method = request.method.clone();
+        let url = request.url.clone();
+        let response = self.transport.send(request).await?;
+        let redact = |text: &str| {
+            let text = [&self.login_details.userid, &self.login_details.pwd]
+                .iter()
+                .filter(|secret| !secret.is_empty())
+                .fold(text.to_string(), |text, secret| {
+                    text.replace(*secret, REDACTED)
+                });
+            redact::redact(&text)
+        };
+        *self.last_response.lock().unwrap() = Some(ResponseSnippet {
+            received_at: Utc::now(),
+            method,
+            url: redact(&url),
+            status: response.status,
+            body: truncate(&redact(&response.body), SNIPPET_LIMIT),
+        });
+        Ok(response)
+    }
+
+    /// The name of the known error page the last check failed with, if it
+    /// was one of the configured `error_pages`.
+    pub fn error_page(&self) -> Option<&str> {
+        self.error_page.as_deref()
+    }
+
+    fn note_error_page(&mut self, body: &str) {
+        self.error_page = self
+            .sais_config
+            .error_pages
+            .iter()
+            .find(|error_page| error_page.matches(body))
+            .map(|error_page| error_page.name.clone());
+        if let Some(name) = &self.error_page {
+            log_step(format!("Recognized the page as: {}", name));
+        }
+    }
+
+    fn request(&self, method: Method, url: &str) -> Result<HttpRequest, Error> {
+        let mut request = HttpRequest::new(method, url);
+        self.sais_config
+            .headers
+            .apply(&self.user_agent, &mut request.headers)?;
+        Ok(request)
+    }
+
+    fn authenticated_request(&self, method: Method, url: &str) -> Result<HttpRequest, Error> {
+        let mut request = self.request(method, url)?;
+        let cookies = self.cookies.header(Utc::now());
+        if !cookies.is_empty() {
+            request
+                .headers
+                .insert(COOKIE, HeaderValue::from_str(&cookies)?);
+        }
+        Ok(request)
+    }
+
+    /// Where the login form posts to, relative to where the login page ended
+    /// up after redirects. The login details are only sent where SAIS could
+    /// redirect to, so a tampered page can't have them posted elsewhere.
+    fn login_form_url(&self) -> Result<String, Error> {
+        let login_page_url = self.redirects.last().unwrap_or(&self.sais_config.login_url);
+        match self
+            .login_form
+            .as_ref()
+            .and_then(|login_form| login_form.action.as_deref())
+        {
+            Some(action) => {
+                let url = reqwest::Url::parse(login_page_url)?.join(action)?;
+                if !self.is_trusted(&url) {
+                    return Err(format!(
+                        "The login form posts to {}, which isn't trusted",
+                        url.host_str().unwrap_or(url.as_str())
+                    )
+                    .into());
+                }
+                Ok(url.to_string())
+            }
+            None => Ok(login_page_url.clone()),
+        }
+    }
+
+    fn note_redirects(&mut self, response: &HttpResponse) {
+        if !response.redirects.is_empty() {
+            log_step(format!("Redirected through {:?}", response.redirects));
+            self.redirects.extend(response.redirects.iter().cloned());
+        }
+    }
+
+    /// Every redirect followed during the last check, in order.
+    pub fn redirects(&self) -> &[String] {
+        &self.redirects
+    }
+
+    /// The first redirect of the last check that went somewhere SAIS
+    /// shouldn't send people: another host that isn't trusted, or from HTTPS
+    /// down to plain HTTP.
+    pub fn suspicious_redirect(&self) -> Option<&str> {
+        reqwest::Url::parse(&self.sais_config.login_url).ok()?;
+        self.redirects
+            .iter()
+            .find(|redirect| match reqwest::Url::parse(redirect) {
+                Ok(redirect) => !self.is_trusted(&redirect),
+                Err(_) => true,
+            })
+            .map(String::as_str)
+    }
+
+    // On the login URL's host or a trusted one, and not down from HTTPS to
+    // plain HTTP.
+    fn is_trusted(&self, url: &reqwest::Url) -> bool {
+        let login_url = match reqwest::Url::parse(&self.sais_config.login_url) {
+            Ok(login_url) => login_url,
+            Err(_) => return false,
+        };
+        let trusted_host = url.host_str() == login_url.host_str()
+            || self
+                .sais_config
+                .trusted_redirect_hosts
+                .iter()
+                .any(|host| Some(host.as_str()) == url.host_str());
+        let downgraded = login_url.scheme() == "https" && url.scheme() != "https";
+        trusted_host && !downgraded
+    }
+
+    fn is_maintenance_page(&self, body: &str) -> bool {
+        let body = body.to_lowercase();
+        self.sais_config
+            .maintenance_strings
+            .iter()
+            .any(|maintenance_string| body.contains(&maintenance_string.to_lowercase()))
+    }
+
+    async fn get_response(&self) -> Result<HttpResponse, Error> {
+        let request = self.request(Method::GET, &self.sais_config.login_url)?;
+        self.send(request).await
+    }
+
+    async fn log_in(&mut self) -> Result<ServiceStatus, Error> {
+        let mut request = self.authenticated_request(Method::POST, &self.login_form_url()?)?;
+        let credentials = vec![
+            (
+                "timezoneOffset".to_string(),
+                format!("{}", self.login_details.timezoneOffset),
+            ),
+            ("userid".to_string(), self.login_details.userid.clone()),
+            ("pwd".to_string(), self.login_details.pwd.clone()),
+            (
+                "request_id".to_string(),
+                format!("{}", self.login_details.request_id),
+            ),
+        ];
+        // The page's own hidden fields go first, except where they would
+        // clash with the credentials.
+        if let Some(login_form) = &self.login_form {
+            request.form = login_form
+                .hidden_fields
+                .iter()
+                .filter(|(name, _)| credentials.iter().all(|(field, _)| field != name))
+                .cloned()
+                .collect();
+        }
+        request.form.extend(credentials);
+
+        let response = match self.send(request).await {
+            Ok(response) => response,
+            // SAIS served the login page, so it is reachable but overloaded.
+            Err(why) if matches!(why.downcast_ref(), Some(TimeoutError(TimeoutKind::Read))) => {
+                return Err(TimeoutError(TimeoutKind::Login).into());
+            }
+            Err(why) => return Err(why),
+        };
+        self.note_redirects(&response);
+        // Deep checks need the session cookies the login hands out.
+        self.save_cookies_from_response(&response);
+        let result_text = response.body;
+        if result_text.contains(&self.sais_config.login_success_string) {
+            log_step(format!(
+                "Found {:?} in response body.\nLogin success",
+                &self.sais_config.login_success_string
+            ));
+            Ok(ServiceStatus::Up)
+        } else if result_text.contains(INVALID_CREDENTIALS_STRING) {
+            log_step("Login credentials are invalid".to_string());
+            Ok(ServiceStatus::CredentialProblem)
+        } else if self.is_maintenance_page(&result_text) {
+            log_step("Found a maintenance notice after logging in".to_string());
+            Ok(ServiceStatus::Maintenance)
+        } else {
+            log_step(format!(
+                "Could not find {:?} in response body",
+                &self.sais_config.login_success_string
+            ));
+            self.note_error_page(&result_text);
+            Ok(ServiceStatus::Down(DownReason::LoginFailed))
+        }
+    }
+
+    fn save_cookies_from_response(&mut self, response: &HttpResponse) {
+        let now = Utc::now();
+        for cookie in response.headers.get_all(SET_COOKIE) {
+            match cookie.to_str() {
+                Ok(cookie) => self.cookies.store(cookie, now),
+                Err(_) => tracing::warn!("Skipping a cookie that isn't valid text"),
+            }
+        }
+    }
+}
+
+// Whether anything in the error's chain is the server refusing the
+// connection.
+fn was_refused(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+            if io_error.kind() == std::io::ErrorKind::ConnectionRefused {
+                return true;
+            }
+        }
+        source = error.source();
+    }
+    false
+}
+
+fn reqwest_transport(sais_config: &SaisConfig) -> ReqwestTransport {
+    if sais_config.proxy.is_some() {
+        tracing::info!("Sending SAIS checks through the configured proxy");
+    }
+    ReqwestTransport::new(
+        &sais_config.timeouts,
+        sais_config.proxy.as_deref(),
+        sais_config.ip_family,
+        sais_config.max_redirects,
+    )
+    .expect("Could not set up the SAIS HTTP client")
+}
+
+// Logs a step of a check, and leaves it as a Sentry breadcrumb so that a
+// reported error shows how far the check got. Breadcrumbs are dropped when
+// Sentry is not set up.
+fn log_step(message: String) {
+    tracing::info!("{}", message);
+    sentry::add_breadcrumb(sentry::Breadcrumb {
+        category: Some("check".to_string()),
+        message: Some(message),
+        ..Default::default()
+    });
+}
@@ -0,0 +1,65 @@
+//! The sample config files, as shipped with this build, and a JSON Schema
+//! for each, for `--generate-config` and `&config generate`.
+
+use crate::{BotConfig, DiscordConfig};
+use is_sais_down_bot::{config::SaisConfig, schema, transaction::Transaction, Error};
+use serde::de::DeserializeOwned;
+use std::{fs, path::Path};
+
+// Every file in `config/`, with all of their options commented.
+const SAMPLES: [(&str, &str); 4] = [
+    ("bot.ron", include_str!("../config/bot.ron")),
+    ("discord.ron", include_str!("../config/discord.ron")),
+    ("sais.ron", include_str!("../config/sais.ron")),
+    (
+        "transactions.ron",
+        include_str!("../config/transactions.ron"),
+    ),
+];
+
+/// Each sample, followed by its schema, e.g. `bot.ron` then
+/// `bot.schema.json`, as file names and contents.
+pub fn files() -> Result<Vec<(String, String)>, Error> {
+    let schemas = [
+        schema_of::<BotConfig>("bot.ron")?,
+        schema_of::<DiscordConfig>("discord.ron")?,
+        schema_of::<SaisConfig>("sais.ron")?,
+        schema_of::<Vec<Transaction>>("transactions.ron")?,
+    ];
+    Ok(SAMPLES
+        .iter()
+        .zip(schemas)
+        .flat_map(|((name, sample), schema)| {
+            [
+                (name.to_string(), sample.to_string()),
+                (schema_file_name(name), schema),
+            ]
+        })
+        .collect())
+}
+
+fn schema_of<T: DeserializeOwned>(name: &str) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(&schema::json_schema::<T>(
+        name,
+    )?)?)
+}
+
+fn schema_file_name(name: &str) -> String {
+    format!("{}.schema.json", name.trim_end_matches(".ron"))
+}
+
+/// Writes every file to `directory`, refusing to overwrite any.
+pub fn write(directory: &Path) -> Result<(), Error> {
+    let files = files()?;
+    fs::create_dir_all(directory)?;
+    for (name, _) in &files {
+        if directory.join(name).exists() {
+            return Err(format!("{} already exists", directory.join(name).display()).into());
+        }
+    }
+    for (name, contents) in files {
+        fs::write(directory.join(&name), contents)?;
+        println!("Wrote {}", directory.join(&name).display());
+    }
+    Ok(())
+}
@@ -0,0 +1,618 @@
+//! JSON Schemas for the config files, worked out from the config types'
+//! `Deserialize` implementations so they can't drift from what is read.
+//!
+//! The types are deserialized from a stand-in that records what they ask
+//! for. Enums are deserialized again until every variant has been seen.
+//! Whether a field has a default can't be seen this way, so no field is
+//! marked as required.
+
+use crate::Error;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// The JSON Schema draft the schemas follow.
+pub const SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+// Deserializing enum variants that hold other enums can take a few rounds.
+const MAX_PASSES: usize = 100;
+
+// Stands in for every string. Plain strings take anything, and this also
+// parses as a socket address, which a few fields are.
+const PLACEHOLDER_STRING: &str = "127.0.0.1:0";
+
+/// The schema for `T`, in the form its values take as JSON. Each enum is
+/// described once under `definitions`.
+pub fn json_schema<T: DeserializeOwned>(title: &str) -> Result<Value, Error> {
+    let mut enums = Enums::default();
+    for pass in 0..MAX_PASSES {
+        enums.pass = pass;
+        let mut root = Value::Null;
+        T::deserialize(Tracer {
+            enums: &mut enums,
+            schema: &mut root,
+        })?;
+        if enums.complete() {
+            let mut schema = json!({ "$schema": SCHEMA_DRAFT });
+            if let Value::Object(root) = root {
+                schema.as_object_mut().unwrap().extend(root);
+            }
+            schema["title"] = json!(title);
+            if !enums.variant_names.is_empty() {
+                schema["definitions"] = enums.definitions();
+            }
+            return Ok(schema);
+        }
+    }
+    Err(format!("could not see every variant of {}", title).into())
+}
+
+#[derive(Default)]
+struct Enums {
+    pass: usize,
+    variant_names: BTreeMap<&'static str, &'static [&'static str]>,
+    // By enum, then variant index.
+    variants: BTreeMap<&'static str, BTreeMap<usize, Value>>,
+}
+
+impl Enums {
+    // The first variant not seen yet, or else a different one each pass, to
+    // reach enums only some variants hold.
+    fn next_variant(
+        &mut self,
+        name: &'static str,
+        variant_names: &'static [&'static str],
+    ) -> usize {
+        self.variant_names.insert(name, variant_names);
+        let seen = self.variants.entry(name).or_default();
+        (0..variant_names.len())
+            .find(|index| !seen.contains_key(index))
+            .unwrap_or(self.pass % variant_names.len().max(1))
+    }
+
+    fn complete(&self) -> bool {
+        self.variant_names.iter().all(|(name, variant_names)| {
+            self.variants.get(name).map_or(0, BTreeMap::len) == variant_names.len()
+        })
+    }
+
+    fn definitions(&self) -> Value {
+        let mut definitions = Map::new();
+        for (name, variants) in &self.variants {
+            definitions.insert(
+                name.to_string(),
+                json!({ "oneOf": variants.values().cloned().collect::<Vec<_>>() }),
+            );
+        }
+        Value::Object(definitions)
+    }
+}
+
+#[derive(Debug)]
+struct TraceError(String);
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl de::Error for TraceError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        TraceError(msg.to_string())
+    }
+}
+
+// Writes the schema of whatever is deserialized from it to `schema`.
+struct Tracer<'a> {
+    enums: &'a mut Enums,
+    schema: &'a mut Value,
+}
+
+macro_rules! trace_primitive {
+    ($method:ident, $visit:ident, $value:expr, $schema:expr) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+            *self.schema = $schema;
+            visitor.$visit($value)
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Tracer<'_> {
+    type Error = TraceError;
+
+    trace_primitive!(
+        deserialize_bool,
+        visit_bool,
+        false,
+        json!({ "type": "boolean" })
+    );
+    trace_primitive!(deserialize_i8, visit_i8, 0, json!({ "type": "integer" }));
+    trace_primitive!(deserialize_i16, visit_i16, 0, json!({ "type": "integer" }));
+    trace_primitive!(deserialize_i32, visit_i32, 0, json!({ "type": "integer" }));
+    trace_primitive!(deserialize_i64, visit_i64, 0, json!({ "type": "integer" }));
+    trace_primitive!(
+        deserialize_u8,
+        visit_u8,
+        0,
+        json!({ "type": "integer", "minimum": 0 })
+    );
+    trace_primitive!(
+        deserialize_u16,
+        visit_u16,
+        0,
+        json!({ "type": "integer", "minimum": 0 })
+    );
+    trace_primitive!(
+        deserialize_u32,
+        visit_u32,
+        0,
+        json!({ "type": "integer", "minimum": 0 })
+    );
+    trace_primitive!(
+        deserialize_u64,
+        visit_u64,
+        0,
+        json!({ "type": "integer", "minimum": 0 })
+    );
+    trace_primitive!(deserialize_f32, visit_f32, 0.0, json!({ "type": "number" }));
+    trace_primitive!(deserialize_f64, visit_f64, 0.0, json!({ "type": "number" }));
+    trace_primitive!(
+        deserialize_char,
+        visit_char,
+        'a',
+        json!({ "type": "string", "minLength": 1, "maxLength": 1 })
+    );
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        let (placeholder, schema) = string_placeholder(&visitor);
+        *self.schema = schema;
+        visitor.visit_str(placeholder)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        self.deserialize_str(visitor)
+    }
+
+    trace_primitive!(
+        deserialize_identifier,
+        visit_str,
+        PLACEHOLDER_STRING,
+        json!({ "type": "string" })
+    );
+    trace_primitive!(
+        deserialize_bytes,
+        visit_bytes,
+        &[],
+        json!({ "type": "string" })
+    );
+    trace_primitive!(
+        deserialize_byte_buf,
+        visit_bytes,
+        &[],
+        json!({ "type": "string" })
+    );
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        *self.schema = json!({});
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        *self.schema = json!({ "type": "null" });
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        let mut inner = Value::Null;
+        let value = visitor.visit_some(Tracer {
+            enums: self.enums,
+            schema: &mut inner,
+        })?;
+        *self.schema = json!({ "anyOf": [inner, { "type": "null" }] });
+        Ok(value)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        let mut items = vec![];
+        let value = visitor.visit_seq(SeqTracer {
+            enums: self.enums,
+            items: &mut items,
+            len: 1,
+        })?;
+        let items = items.pop().unwrap_or_else(|| json!({}));
+        *self.schema = json!({ "type": "array", "items": items });
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        let mut items = vec![];
+        let value = visitor.visit_seq(SeqTracer {
+            enums: self.enums,
+            items: &mut items,
+            len,
+        })?;
+        *self.schema = tuple_schema(items);
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        let mut values = Map::new();
+        let value = visitor.visit_map(MapTracer {
+            enums: self.enums,
+            fields: &[""],
+            next: 0,
+            properties: &mut values,
+        })?;
+        let values = values
+            .into_iter()
+            .next()
+            .map_or_else(|| json!({}), |(_, v)| v);
+        *self.schema = json!({ "type": "object", "additionalProperties": values });
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        let mut properties = Map::new();
+        let value = visitor.visit_map(MapTracer {
+            enums: self.enums,
+            fields,
+            next: 0,
+            properties: &mut properties,
+        })?;
+        *self.schema = struct_schema(Some(name), properties);
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        let index = self.enums.next_variant(name, variants);
+        *self.schema = json!({ "$ref": format!("#/definitions/{}", name) });
+        visitor.visit_enum(EnumTracer {
+            enums: self.enums,
+            name,
+            variant: variants
+                .get(index)
+                .copied()
+                .ok_or_else(|| TraceError(format!("{} has no variants", name)))?,
+            index,
+        })
+    }
+}
+
+// Dates are read from strings too, but not from the usual placeholder, so
+// they're told apart by what their visitor expects.
+fn string_placeholder<'de, V: Visitor<'de>>(visitor: &V) -> (&'static str, Value) {
+    struct Expecting<'a, V>(&'a V);
+
+    impl<'de, V: Visitor<'de>> fmt::Display for Expecting<'_, V> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.expecting(f)
+        }
+    }
+
+    let expecting = Expecting(visitor).to_string();
+    if expecting.contains("date and time") {
+        (
+            "1970-01-01T00:00:00Z",
+            json!({ "type": "string", "format": "date-time" }),
+        )
+    } else if expecting.contains("date") {
+        ("1970-01-01", json!({ "type": "string", "format": "date" }))
+    } else {
+        (PLACEHOLDER_STRING, json!({ "type": "string" }))
+    }
+}
+
+fn tuple_schema(items: Vec<Value>) -> Value {
+    json!({
+        "type": "array",
+        "minItems": items.len(),
+        "maxItems": items.len(),
+        "items": items,
+    })
+}
+
+fn struct_schema(name: Option<&str>, properties: Map<String, Value>) -> Value {
+    let mut schema = json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    });
+    if let Some(name) = name {
+        schema["title"] = json!(name);
+    }
+    schema
+}
+
+// Hands out `len` elements, or fewer if one can't be deserialized from the
+// placeholders, keeping what its schema was found to be.
+struct SeqTracer<'a> {
+    enums: &'a mut Enums,
+    items: &'a mut Vec<Value>,
+    len: usize,
+}
+
+impl<'de> SeqAccess<'de> for SeqTracer<'_> {
+    type Error = TraceError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, TraceError> {
+        if self.items.len() == self.len {
+            return Ok(None);
+        }
+        let mut item = Value::Null;
+        let element = seed.deserialize(Tracer {
+            enums: self.enums,
+            schema: &mut item,
+        });
+        self.items.push(item);
+        match element {
+            Ok(element) => Ok(Some(element)),
+            // Like an IP network parsed from a string. Its schema is known,
+            // and an empty list doesn't need a value.
+            Err(_) if self.len == 1 => Ok(None),
+            Err(why) => Err(why),
+        }
+    }
+}
+
+// Hands out every field once. Maps get one entry.
+struct MapTracer<'a> {
+    enums: &'a mut Enums,
+    fields: &'static [&'static str],
+    next: usize,
+    properties: &'a mut Map<String, Value>,
+}
+
+impl<'de> MapAccess<'de> for MapTracer<'_> {
+    type Error = TraceError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, TraceError> {
+        let field = match self.fields.get(self.next) {
+            Some(field) => *field,
+            None => return Ok(None),
+        };
+        if field.is_empty() {
+            // A map's key, which can be a string or a number.
+            let mut key = Value::Null;
+            return seed
+                .deserialize(Tracer {
+                    enums: self.enums,
+                    schema: &mut key,
+                })
+                .map(Some);
+        }
+        seed.deserialize(field.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, TraceError> {
+        let field = self.fields[self.next];
+        self.next += 1;
+        let mut property = Value::Null;
+        let value = seed.deserialize(Tracer {
+            enums: self.enums,
+            schema: &mut property,
+        })?;
+        self.properties.insert(field.to_string(), property);
+        Ok(value)
+    }
+}
+
+struct EnumTracer<'a> {
+    enums: &'a mut Enums,
+    name: &'static str,
+    variant: &'static str,
+    index: usize,
+}
+
+impl<'de, 'a> EnumAccess<'de> for EnumTracer<'a> {
+    type Error = TraceError;
+    type Variant = EnumTracer<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, EnumTracer<'a>), TraceError> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl EnumTracer<'_> {
+    // Variants other than unit ones are written as an object with the
+    // variant's name as its only key.
+    fn record(self, contents: Option<Value>) {
+        let schema = match contents {
+            None => json!({ "const": self.variant }),
+            Some(contents) => {
+                let mut properties = Map::new();
+                properties.insert(self.variant.to_string(), contents);
+                let mut schema = struct_schema(None, properties);
+                schema["required"] = json!([self.variant]);
+                schema
+            }
+        };
+        self.enums
+            .variants
+            .entry(self.name)
+            .or_default()
+            .insert(self.index, schema);
+    }
+}
+
+impl<'de> VariantAccess<'de> for EnumTracer<'_> {
+    type Error = TraceError;
+
+    fn unit_variant(self) -> Result<(), TraceError> {
+        self.record(None);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, TraceError> {
+        let mut contents = Value::Null;
+        let value = seed.deserialize(Tracer {
+            enums: &mut *self.enums,
+            schema: &mut contents,
+        })?;
+        self.record(Some(contents));
+        Ok(value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        let mut items = vec![];
+        let value = visitor.visit_seq(SeqTracer {
+            enums: &mut *self.enums,
+            items: &mut items,
+            len,
+        })?;
+        self.record(Some(tuple_schema(items)));
+        Ok(value)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        let mut properties = Map::new();
+        let value = visitor.visit_map(MapTracer {
+            enums: &mut *self.enums,
+            fields,
+            next: 0,
+            properties: &mut properties,
+        })?;
+        self.record(Some(struct_schema(None, properties)));
+        Ok(value)
+    }
+}
+
+/// The field paths `schema` describes, like `monitor.interval_secs`, for
+/// checking that a sample config mentions all of them. Enum variants' fields
+/// are included, and map values and list items don't add to the path.
+pub fn field_paths(schema: &Value) -> BTreeSet<String> {
+    let mut paths = BTreeSet::new();
+    collect_paths(schema, schema, "", &mut paths, 0);
+    paths
+}
+
+fn collect_paths(
+    root: &Value,
+    schema: &Value,
+    prefix: &str,
+    paths: &mut BTreeSet<String>,
+    depth: usize,
+) {
+    // Guards against enums that hold themselves.
+    if depth > 16 {
+        return;
+    }
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/definitions/");
+        if let Some(definition) = root["definitions"].get(name) {
+            collect_paths(root, definition, prefix, paths, depth + 1);
+        }
+        return;
+    }
+    for key in ["anyOf", "oneOf"] {
+        for alternative in schema[key].as_array().into_iter().flatten() {
+            collect_paths(root, alternative, prefix, paths, depth + 1);
+        }
+    }
+    let is_variant = schema["required"].is_array();
+    for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+        // A variant's name isn't a field.
+        let path = match (is_variant, prefix.is_empty()) {
+            (true, _) => prefix.to_string(),
+            (false, true) => name.clone(),
+            (false, false) => format!("{}.{}", prefix, name),
+        };
+        if !is_variant {
+            paths.insert(path.clone());
+        }
+        collect_paths(root, property, &path, paths, depth + 1);
+    }
+    match &schema["items"] {
+        Value::Array(items) => {
+            for item in items {
+                collect_paths(root, item, prefix, paths, depth + 1);
+            }
+        }
+        items @ Value::Object(_) => collect_paths(root, items, prefix, paths, depth + 1),
+        _ => {}
+    }
+    if schema["additionalProperties"].is_object() {
+        collect_paths(
+            root,
+            &schema["additionalProperties"],
+            prefix,
+            paths,
+            depth + 1,
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Screenshots of what SAIS is serving, taken with a headless Chrome or
+//! Chromium, so students can see an error page without trying themselves.
+
+use crate::Error;
+use serde::Deserialize;
+use std::{env, process, time::Duration};
+use tokio::{fs, process::Command};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScreenshotConfig {
+    /// The Chrome or Chromium executable, by path or by name on `PATH`.
+    #[serde(default = "default_browser")]
+    pub browser: String,
+    #[serde(default = "default_width")]
+    pub width: u32,
+    #[serde(default = "default_height")]
+    pub height: u32,
+    /// How long the browser gets to load the page and save the screenshot.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_browser() -> String {
+    "chromium".to_string()
+}
+
+fn default_width() -> u32 {
+    1280
+}
+
+fn default_height() -> u32 {
+    800
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// Loads `url` in the browser and returns the screenshot as a PNG.
+pub async fn capture(config: &ScreenshotConfig, url: &str) -> Result<Vec<u8>, Error> {
+    let path = env::temp_dir().join(format!("sais-screenshot-{}.png", process::id()));
+    let mut browser = Command::new(&config.browser)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--hide-scrollbars")
+        .arg(format!("--window-size={},{}", config.width, config.height))
+        .arg(format!("--screenshot={}", path.display()))
+        .arg(url)
+        .kill_on_drop(true)
+        .spawn()?;
+    let exit_status =
+        tokio::time::timeout(Duration::from_secs(config.timeout_secs), browser.wait())
+            .await
+            .map_err(|_| format!("The browser took over {}s", config.timeout_secs))??;
+    if !exit_status.success() {
+        return Err(format!("The browser exited with {}", exit_status).into());
+    }
+
+    let png = fs::read(&path).await?;
+    fs::remove_file(&path).await?;
+    Ok(png)
+}
@@ -0,0 +1,110 @@
+use crate::current_time_utc_plus_8;
+use chrono::{DateTime, Duration as ChronoDuration, FixedOffset, Timelike};
+use is_sais_down_bot::{
+    leader::{self, Leadership},
+    sais::SaisClient,
+    status::ServiceStatus,
+};
+use poise::serenity_prelude::{Http, UserId};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SelfTestConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// The hour of the day, in PHT, to log in at. Early morning by default,
+    /// when hardly anyone else is using SAIS.
+    #[serde(default = "default_hour")]
+    pub hour: u32,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        SelfTestConfig {
+            enabled: default_enabled(),
+            hour: default_hour(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_hour() -> u32 {
+    4
+}
+
+/// Logs in with a fresh session once a day, so the bot's own login details
+/// going bad are caught even while the monitor reuses a session, and tells
+/// the maintainers privately instead of the servers.
+pub struct CredentialSelfTest {
+    pub http: Arc<Http>,
+    /// The owner and the configured maintainers.
+    pub maintainers: Vec<UserId>,
+    pub config: SelfTestConfig,
+    /// Only the leader runs the test, when set.
+    pub leadership: Option<Arc<Leadership>>,
+}
+
+impl CredentialSelfTest {
+    /// Runs until the bot exits.
+    pub async fn run(self) {
+        loop {
+            let wait = until_hour(self.config.hour, current_time_utc_plus_8());
+            tokio::time::sleep(wait.to_std().unwrap_or_default()).await;
+            if !leader::leads(self.leadership.as_deref()) {
+                continue;
+            }
+
+            match SaisClient::new().check().await {
+                Ok(ServiceStatus::CredentialProblem) => {
+                    tracing::warn!("The credential self-test was rejected");
+                    if let Err(why) = warn_maintainers(&self.http, &self.maintainers).await {
+                        tracing::error!("Could not message the maintainers: {:?}", why);
+                        sentry::capture_error(&why);
+                    }
+                }
+                Ok(status) => tracing::info!("Credential self-test: SAIS is {}", status),
+                Err(why) => tracing::error!("Could not run the credential self-test: {:?}", why),
+            }
+        }
+    }
+}
+
+// How long from `now` until the next time it's `hour` o'clock.
+fn until_hour(hour: u32, now: DateTime<FixedOffset>) -> ChronoDuration {
+    let today = now
+        .with_hour(hour)
+        .and_then(|time| time.with_minute(0))
+        .and_then(|time| time.with_second(0))
+        .and_then(|time| time.with_nanosecond(0))
+        .unwrap_or(now);
+    let next = if today > now {
+        today
+    } else {
+        today + ChronoDuration::days(1)
+    };
+    next - now
+}
+
+/// Tells the maintainers the bot's own UP SAIS login was rejected while
+/// SAIS itself worked.
+pub async fn warn_maintainers(
+    http: &Http,
+    maintainers: &[UserId],
+) -> Result<(), poise::serenity_prelude::Error> {
+    for maintainer in maintainers {
+        let dm_channel = maintainer.create_dm_channel(http).await?;
+        dm_channel
+            .say(
+                http,
+                "UP SAIS is up, but it rejected my login details. The password likely \
+                 expired, so update `PASSWORD` (and `USER_ID` if that changed too). \
+                 Until then, I can't tell whether logging in works for students.",
+            )
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! Settings the owner can change with `&config set` while the bot runs,
+//! kept in a file the bot writes. Each one overrides the config files until
+//! it is set back to `default`.
+
+use crate::{monitor::MonitorConfig, phrases::DownPhrases, Error};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub const RUNTIME_SETTINGS_FILEPATH: &str = "data/settings.ron";
+
+/// Every key `&config set` takes, with what it takes. None are secrets.
+pub const KEYS: &[(&str, &str)] = &[
+    (
+        "monitor.interval_secs",
+        "seconds between checks, from 30 up to the interval in bot.ron",
+    ),
+    (
+        "monitor.confirm_after",
+        "checks in a row that have to agree, 1 to 20",
+    ),
+    (
+        "monitor.flap_window",
+        "latest checks looked at for flapping, 2 to 100",
+    ),
+    (
+        "monitor.flap_threshold",
+        "changes in the window that count as flapping, 1 to 100",
+    ),
+    ("alerts.channel_id", "the alerts channel's ID"),
+    (
+        "alerts.role_id",
+        "the role pinged with announcements, or none",
+    ),
+    (
+        "down_phrases.<reason>",
+        "how &sais words a reason, e.g. down_phrases.http_503 or down_phrases.http_5xx",
+    ),
+];
+
+/// Sets a key back to what the config files say.
+pub const DEFAULT_VALUE: &str = "default";
+
+const MAX_PHRASE_CHARS: usize = 300;
+
+/// The settings changed at runtime. Unset ones come from the config files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Overrides {
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    #[serde(default)]
+    pub confirm_after: Option<u32>,
+    #[serde(default)]
+    pub flap_window: Option<usize>,
+    #[serde(default)]
+    pub flap_threshold: Option<usize>,
+    #[serde(default)]
+    pub alerts_channel_id: Option<u64>,
+    /// `Some(None)` pings nobody.
+    #[serde(default)]
+    pub alerts_role_id: Option<Option<u64>>,
+    #[serde(default)]
+    pub down_phrases: BTreeMap<String, String>,
+}
+
+/// The overrides, saved to disk whenever one changes.
+#[derive(Debug)]
+pub struct SettingsStore {
+    path: PathBuf,
+    // The interval in bot.ron, which the monitor's watchdogs are set up for.
+    max_interval_secs: u64,
+    overrides: Mutex<Overrides>,
+}
+
+impl SettingsStore {
+    /// Loads the overrides, starting with none if the file doesn't exist
+    /// yet.
+    pub fn open(path: impl AsRef<Path>, max_interval_secs: u64) -> Result<SettingsStore, Error> {
+        let path = path.as_ref().to_path_buf();
+        let overrides = match fs::read_to_string(&path) {
+            Ok(contents) => ron::de::from_str(&contents)?,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => Overrides::default(),
+            Err(why) => return Err(why.into()),
+        };
+        Ok(SettingsStore {
+            path,
+            max_interval_secs,
+            overrides: Mutex::new(overrides),
+        })
+    }
+
+    pub fn overrides(&self) -> Overrides {
+        self.overrides.lock().unwrap().clone()
+    }
+
+    /// Validates `value` for `key` and saves it, or removes the override
+    /// when it's [`DEFAULT_VALUE`]. Errors are meant for the owner to read.
+    pub fn set(&self, key: &str, value: &str) -> Result<(), Error> {
+        let mut overrides = self.overrides.lock().unwrap();
+        let mut changed = overrides.clone();
+        let reset = value.eq_ignore_ascii_case(DEFAULT_VALUE);
+        match key {
+            "monitor.interval_secs" => {
+                changed.interval_secs = parse_unless(reset, value, 30, self.max_interval_secs)?;
+            }
+            "monitor.confirm_after" => changed.confirm_after = parse_unless(reset, value, 1, 20)?,
+            "monitor.flap_window" => changed.flap_window = parse_unless(reset, value, 2, 100)?,
+            "monitor.flap_threshold" => {
+                changed.flap_threshold = parse_unless(reset, value, 1, 100)?;
+            }
+            "alerts.channel_id" if reset => changed.alerts_channel_id = None,
+            "alerts.channel_id" => changed.alerts_channel_id = Some(parse_id(value)?),
+            "alerts.role_id" => {
+                changed.alerts_role_id = match value {
+                    _ if reset => None,
+                    "none" => Some(None),
+                    _ => Some(Some(parse_id(value)?)),
+                };
+            }
+            _ => {
+                let reason = key
+                    .strip_prefix("down_phrases.")
+                    .ok_or_else(|| format!("{} isn't a setting", key))?;
+                if !is_reason(reason) {
+                    return Err(format!("{} isn't a reason SAIS can be down for", reason).into());
+                }
+                if reset {
+                    changed.down_phrases.remove(reason);
+                } else if value.trim().is_empty() || value.chars().count() > MAX_PHRASE_CHARS {
+                    return Err(
+                        format!("phrases have to be 1 to {} characters", MAX_PHRASE_CHARS).into(),
+                    );
+                } else {
+                    changed
+                        .down_phrases
+                        .insert(reason.to_string(), value.to_string());
+                }
+            }
+        }
+
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let contents = ron::ser::to_string_pretty(&changed, ron::ser::PrettyConfig::default())?;
+        fs::write(&self.path, contents)?;
+        *overrides = changed;
+        Ok(())
+    }
+
+    /// `base` from bot.ron, with the monitor overrides.
+    pub fn monitor_config(&self, base: &MonitorConfig) -> MonitorConfig {
+        let overrides = self.overrides.lock().unwrap();
+        MonitorConfig {
+            interval_secs: overrides.interval_secs.unwrap_or(base.interval_secs),
+            confirm_after: overrides.confirm_after.unwrap_or(base.confirm_after),
+            flap_window: overrides.flap_window.unwrap_or(base.flap_window),
+            flap_threshold: overrides.flap_threshold.unwrap_or(base.flap_threshold),
+        }
+    }
+
+    /// `base` from bot.ron, with the phrases set at runtime taking
+    /// precedence.
+    pub fn down_phrases(&self, base: &DownPhrases) -> DownPhrases {
+        let mut phrases = base.clone();
+        phrases
+            .0
+            .extend(self.overrides.lock().unwrap().down_phrases.clone());
+        phrases
+    }
+
+    /// The alerts channel and role to ping, from `base` in discord.ron and
+    /// the overrides. Setting the channel at runtime works without one in
+    /// discord.ron.
+    pub fn alerts_target(&self, base: Option<(u64, Option<u64>)>) -> Option<(u64, Option<u64>)> {
+        let overrides = self.overrides.lock().unwrap();
+        let channel_id = overrides
+            .alerts_channel_id
+            .or_else(|| base.map(|(channel_id, _)| channel_id))?;
+        let role_id = match overrides.alerts_role_id {
+            Some(role_id) => role_id,
+            None => base.and_then(|(_, role_id)| role_id),
+        };
+        Some((channel_id, role_id))
+    }
+}
+
+fn parse_unless<T>(reset: bool, value: &str, min: T, max: T) -> Result<Option<T>, Error>
+where
+    T: std::str::FromStr + PartialOrd + std::fmt::Display,
+{
+    if reset {
+        return Ok(None);
+    }
+    match value.parse::<T>() {
+        Ok(parsed) if min <= parsed && parsed <= max => Ok(Some(parsed)),
+        _ => Err(format!("expected a number from {} to {}", min, max).into()),
+    }
+}
+
+// Discord IDs, which can be copied with a mention's brackets around them.
+fn parse_id(value: &str) -> Result<u64, Error> {
+    value
+        .trim_start_matches("<#")
+        .trim_start_matches("<@&")
+        .trim_end_matches('>')
+        .parse()
+        .ok()
+        .filter(|&id| id > 0)
+        .ok_or_else(|| format!("{} isn't a Discord ID", value).into())
+}
+
+// What `DownPhrases` is keyed by.
+fn is_reason(reason: &str) -> bool {
+    const REASONS: &[&str] = &[
+        "no_response",
+        "connection_refused",
+        "connect_timeout",
+        "read_timeout",
+        "login_timeout",
+        "check_timeout",
+        "login_failed",
+    ];
+    if REASONS.contains(&reason) {
+        return true;
+    }
+    match reason.strip_prefix("http_") {
+        Some(class) if class.len() == 3 && class.ends_with("xx") => {
+            matches!(class.as_bytes()[0], b'1'..=b'5')
+        }
+        Some(code) => code
+            .parse::<u16>()
+            .is_ok_and(|code| (100..600).contains(&code)),
+        None => false,
+    }
+}
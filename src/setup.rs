@@ -0,0 +1,681 @@
+use crate::{Context, Error};
+use chrono::{DateTime, FixedOffset, Utc};
+use is_sais_down_bot::{
+    digest::DigestPeriod,
+    escalation::EscalationStep,
+    guilds::{Access, EmojiStyle, Language},
+    history,
+    preferences::{Delivery, NotificationKind, NotificationPreferences},
+};
+use poise::serenity_prelude::{self as serenity, ChannelType, InteractionResponseType};
+use std::{collections::BTreeSet, time::Duration};
+
+// How long each step waits for a choice before giving up.
+const CHOICE_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Discord select menus can't have more options than this.
+const MAX_OPTIONS: usize = 25;
+
+// The longest a mute can be, so nobody forgets they muted the bot.
+const MAX_MUTE_DAYS: i64 = 14;
+
+/// Sets up announcements and how the bot talks in this server.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
+    let guild = match ctx.guild() {
+        Some(guild) => guild,
+        None => return Ok(()),
+    };
+
+    let mut channels = guild
+        .channels
+        .values()
+        .filter_map(|channel| channel.clone().guild())
+        .filter(|channel| matches!(channel.kind, ChannelType::Text | ChannelType::News))
+        .collect::<Vec<_>>();
+    channels.sort_by_key(|channel| channel.position);
+    let mut channel_options = vec![("Don't announce".to_string(), "none".to_string())];
+    channel_options.extend(
+        channels
+            .iter()
+            .take(MAX_OPTIONS - 1)
+            .map(|channel| (format!("#{}", channel.name), channel.id.0.to_string())),
+    );
+
+    let mut roles = guild
+        .roles
+        .values()
+        .filter(|role| role.id.0 != guild.id.0 && !role.managed)
+        .collect::<Vec<_>>();
+    roles.sort_by_key(|role| std::cmp::Reverse(role.position));
+    let mut role_options = vec![("Don't ping anyone".to_string(), "none".to_string())];
+    role_options.extend(
+        roles
+            .iter()
+            .take(MAX_OPTIONS - 1)
+            .map(|role| (format!("@{}", role.name), role.id.0.to_string())),
+    );
+
+    let mut settings = ctx
+        .data()
+        .guild_settings
+        .get(guild.id.0)
+        .unwrap_or_default();
+    let steps_done = async {
+        let channel = choose(
+            ctx,
+            "Where should I announce status changes?",
+            channel_options,
+        )
+        .await?;
+        settings.announcement_channel_id = match channel {
+            Some(channel) => channel.parse().ok(),
+            None => return Ok::<_, Error>(false),
+        };
+        let role = choose(ctx, "Who should I ping with announcements?", role_options).await?;
+        settings.alert_role_id = match role {
+            Some(role) => role.parse().ok(),
+            None => return Ok(false),
+        };
+        let mut digest_options = vec![("Right away".to_string(), "now".to_string())];
+        for period in DigestPeriod::ALL {
+            digest_options.push((
+                format!("In {} digest, without pinging", digest_label(period)),
+                period.to_string(),
+            ));
+        }
+        let digest = choose(ctx, "When should I announce changes?", digest_options).await?;
+        settings.digest = match digest.as_deref() {
+            Some(choice) => digest_period(choice),
+            None => return Ok(false),
+        };
+        let language = choose(
+            ctx,
+            "Which language should I use?",
+            vec![
+                ("English".to_string(), "english".to_string()),
+                ("Cebuano".to_string(), "cebuano".to_string()),
+            ],
+        )
+        .await?;
+        settings.language = match language.as_deref() {
+            Some("cebuano") => Language::Cebuano,
+            Some(_) => Language::English,
+            None => return Ok(false),
+        };
+        let emoji_style = choose(
+            ctx,
+            "Which emojis should I use?",
+            vec![
+                ("Custom server emojis".to_string(), "custom".to_string()),
+                ("Standard emojis".to_string(), "unicode".to_string()),
+            ],
+        )
+        .await?;
+        settings.emoji_style = match emoji_style.as_deref() {
+            Some("unicode") => EmojiStyle::Unicode,
+            Some(_) => EmojiStyle::Custom,
+            None => return Ok(false),
+        };
+        Ok(true)
+    }
+    .await?;
+
+    if !steps_done {
+        ctx.say("Setup timed out, nothing was changed.").await?;
+        return Ok(());
+    }
+    ctx.data().guild_settings.set(guild.id.0, settings)?;
+    tracing::info!("Saved the settings for guild {}", guild.id);
+    ctx.say("All set!").await?;
+    Ok(())
+}
+
+/// Shows or changes who may use a command in this server.
+///
+/// For example, `access diagnose role:Techs` or `access config set owner`.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn access(
+    ctx: Context<'_>,
+    #[description = "A command, then rules like role:Techs, user:1234 or permission:MANAGE_GUILD"]
+    #[rest]
+    arguments: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.0,
+        None => return Ok(()),
+    };
+    let mut settings = ctx.data().guild_settings.get(guild_id).unwrap_or_default();
+    let arguments = arguments.unwrap_or_default();
+    let words = arguments.split_whitespace().collect::<Vec<_>>();
+    // The command's name can be several words, up to the first rule.
+    let name_length = words
+        .iter()
+        .position(|word| word.parse::<Access>().is_ok() || *word == "default")
+        .unwrap_or(words.len());
+    let (name, rules) = words.split_at(name_length);
+    let name = name.join(" ");
+
+    if name.is_empty() && rules.is_empty() {
+        let lines = settings
+            .command_access
+            .iter()
+            .map(|(command, rules)| format!("{}: {}", command, rules_text(rules)))
+            .collect::<Vec<_>>();
+        ctx.say(if lines.is_empty() {
+            "Every command can be used by anyone its own checks allow.".to_string()
+        } else {
+            format!("```\n{}\n```", lines.join("\n"))
+        })
+        .await?;
+        return Ok(());
+    }
+    if !command_names(ctx).contains(&name) {
+        ctx.say(format!("There's no `{}` command.", name)).await?;
+        return Ok(());
+    }
+    if name == "access" {
+        ctx.say("Who may use `access` can't be changed, so nobody gets locked out of it.")
+            .await?;
+        return Ok(());
+    }
+    if rules.is_empty() {
+        let rules = settings
+            .command_access
+            .get(&name)
+            .map_or("no rules".to_string(), |rules| rules_text(rules));
+        ctx.say(format!("`{}`: {}", name, rules)).await?;
+        return Ok(());
+    }
+
+    if rules == ["default"] {
+        settings.command_access.remove(&name);
+    } else {
+        let rules = match rules
+            .iter()
+            .map(|rule| rule.parse::<Access>())
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(rules) => rules,
+            Err(why) => {
+                ctx.say(why).await?;
+                return Ok(());
+            }
+        };
+        settings.command_access.insert(name.clone(), rules);
+    }
+    ctx.data().guild_settings.set(guild_id, settings)?;
+    tracing::info!("Changed who may use `{}` in guild {}", name, guild_id);
+    ctx.say(format!("Changed who may use `{}`.", name)).await?;
+    Ok(())
+}
+
+/// Chooses what you're told about when SAIS changes, and how.
+#[poise::command(prefix_command, slash_command)]
+pub async fn notify(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.0;
+    let current = ctx.data().preferences.get(user_id);
+    let kind_options = NotificationKind::ALL
+        .iter()
+        .map(|kind| (capitalize(&kind.to_string()), format!("{:?}", kind)))
+        .collect::<Vec<_>>();
+    let selected = current.as_ref().map_or(vec![], |preferences| {
+        preferences
+            .kinds
+            .iter()
+            .map(|kind| format!("{:?}", kind))
+            .collect()
+    });
+    let kinds = match choose_many(
+        ctx,
+        "What should I tell you about? Pick nothing to stop.",
+        kind_options,
+        NotificationKind::ALL.len() as u64,
+        &selected,
+    )
+    .await?
+    {
+        Some(kinds) => NotificationKind::ALL
+            .iter()
+            .filter(|kind| kinds.contains(&format!("{:?}", kind)))
+            .copied()
+            .collect::<BTreeSet<_>>(),
+        None => {
+            ctx.say("Timed out, nothing was changed.").await?;
+            return Ok(());
+        }
+    };
+    if kinds.is_empty() {
+        ctx.data().preferences.set(
+            user_id,
+            NotificationPreferences {
+                kinds,
+                delivery: Delivery::Dm,
+                muted_until: None,
+                digest: None,
+            },
+        )?;
+        ctx.say("I won't notify you anymore.").await?;
+        return Ok(());
+    }
+
+    let mut delivery_options = vec![("Send me a DM as it happens".to_string(), "dm".to_string())];
+    // Pings only reach people in servers that get announcements.
+    let announces_here = ctx.guild_id().filter(|guild_id| {
+        ctx.data()
+            .guild_settings
+            .get(guild_id.0)
+            .is_some_and(|settings| settings.announcement_channel_id.is_some())
+    });
+    if announces_here.is_some() {
+        delivery_options.push((
+            "Ping me in this server's announcements".to_string(),
+            "ping".to_string(),
+        ));
+    }
+    for period in DigestPeriod::ALL {
+        delivery_options.push((
+            format!("Send me {} digest by DM", digest_label(period)),
+            period.to_string(),
+        ));
+    }
+    let (delivery, digest) = match choose(ctx, "How should I tell you?", delivery_options)
+        .await?
+        .as_deref()
+    {
+        Some("ping") => match announces_here {
+            Some(guild_id) => (
+                Delivery::Ping {
+                    guild_id: guild_id.0,
+                },
+                None,
+            ),
+            None => (Delivery::Dm, None),
+        },
+        Some(choice) => (Delivery::Dm, digest_period(choice)),
+        None => {
+            ctx.say("Timed out, nothing was changed.").await?;
+            return Ok(());
+        }
+    };
+    ctx.data().preferences.set(
+        user_id,
+        NotificationPreferences {
+            kinds,
+            delivery,
+            // Choosing again doesn't undo &mute.
+            muted_until: current.and_then(|preferences| preferences.muted_until),
+            digest,
+        },
+    )?;
+    tracing::info!("Saved the notification preferences of user {}", user_id);
+    ctx.say(match (delivery, digest) {
+        (_, Some(period)) => format!("All set! I'll DM you {} digest.", digest_label(period)),
+        (Delivery::Dm, None) => "All set! I'll DM you.".to_string(),
+        (Delivery::Ping { .. }, None) => {
+            "All set! I'll ping you in this server's announcements.".to_string()
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+// "an hourly" or "a daily", for choices.
+fn digest_label(period: DigestPeriod) -> &'static str {
+    match period {
+        DigestPeriod::Hourly => "an hourly",
+        DigestPeriod::Daily => "a daily",
+    }
+}
+
+// The digest picked as `choice`, if any.
+fn digest_period(choice: &str) -> Option<DigestPeriod> {
+    DigestPeriod::ALL
+        .iter()
+        .find(|period| period.to_string() == choice)
+        .copied()
+}
+
+/// Stops your notifications for a while, like `mute 6h`, or `mute off`.
+#[poise::command(prefix_command, slash_command)]
+pub async fn mute(
+    ctx: Context<'_>,
+    #[description = "How long, like 30m, 6h or 2d, or off"] duration: String,
+) -> Result<(), Error> {
+    let until = match mute_until(&duration) {
+        Ok(until) => until,
+        Err(why) => {
+            ctx.say(why).await?;
+            return Ok(());
+        }
+    };
+    if !ctx.data().preferences.mute(ctx.author().id.0, until)? {
+        ctx.say("You don't get any notifications. Choose some with /notify.")
+            .await?;
+        return Ok(());
+    }
+    ctx.say(match until {
+        Some(until) => format!("Muted your notifications until {}.", pht(until)),
+        None => "Unmuted your notifications.".to_string(),
+    })
+    .await?;
+    Ok(())
+}
+
+/// Stops pinging the role with announcements here for a while, like `mute-alerts 2h`.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    rename = "mute-alerts"
+)]
+pub async fn mute_alerts(
+    ctx: Context<'_>,
+    #[description = "How long, like 30m, 6h or 2d, or off"] duration: String,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.0,
+        None => return Ok(()),
+    };
+    let until = match mute_until(&duration) {
+        Ok(until) => until,
+        Err(why) => {
+            ctx.say(why).await?;
+            return Ok(());
+        }
+    };
+    let mut settings = ctx.data().guild_settings.get(guild_id).unwrap_or_default();
+    if settings.alert_role_id.is_none() {
+        ctx.say("Announcements here don't ping a role. Choose one with /setup.")
+            .await?;
+        return Ok(());
+    }
+    settings.role_muted_until = until;
+    ctx.data().guild_settings.set(guild_id, settings)?;
+    tracing::info!(
+        "Muted the alert role of guild {} until {:?}",
+        guild_id,
+        until
+    );
+    ctx.say(match until {
+        Some(until) => format!(
+            "Announcements here won't ping the role until {}. They're still posted.",
+            pht(until)
+        ),
+        None => "Announcements here ping the role again.".to_string(),
+    })
+    .await?;
+    Ok(())
+}
+
+/// Shows what's muted, for you and in this server.
+#[poise::command(prefix_command, slash_command)]
+pub async fn mutes(ctx: Context<'_>) -> Result<(), Error> {
+    let now = Utc::now();
+    let mut lines = vec![];
+    if let Some(until) = ctx
+        .data()
+        .preferences
+        .get(ctx.author().id.0)
+        .and_then(|preferences| preferences.muted_until)
+        .filter(|until| now < *until)
+    {
+        lines.push(format!(
+            "Your notifications are muted until {}.",
+            pht(until)
+        ));
+    }
+    if let Some(until) = ctx
+        .guild_id()
+        .and_then(|guild_id| ctx.data().guild_settings.get(guild_id.0))
+        .and_then(|settings| settings.role_muted_until)
+        .filter(|until| now < *until)
+    {
+        lines.push(format!(
+            "This server's announcements won't ping the role until {}.",
+            pht(until)
+        ));
+    }
+    ctx.say(if lines.is_empty() {
+        "Nothing is muted.".to_string()
+    } else {
+        lines.join("\n")
+    })
+    .await?;
+    Ok(())
+}
+
+// When a mute for `duration` would end, or `None` for "off".
+fn mute_until(duration: &str) -> Result<Option<DateTime<Utc>>, String> {
+    if duration.eq_ignore_ascii_case("off") {
+        return Ok(None);
+    }
+    match history::parse_range(duration) {
+        Some(duration) if duration <= chrono::Duration::days(MAX_MUTE_DAYS) => {
+            Ok(Some(Utc::now() + duration))
+        }
+        Some(_) => Err(format!("Mutes can be up to {} days long.", MAX_MUTE_DAYS)),
+        None => Err(format!(
+            "I don't understand {:?}. Try 30m, 6h, 2d or off.",
+            duration
+        )),
+    }
+}
+
+pub fn pht(time: DateTime<Utc>) -> String {
+    time.with_timezone(&FixedOffset::east_opt(3600 * 8).unwrap())
+        .format("%b %-d %H:%M PHT")
+        .to_string()
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Shows or changes who else to tell as an outage goes on in this server.
+///
+/// For example, `escalation 15m:role 1h:@Techs 4h:@Someone`, or `escalation off`.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn escalation(
+    ctx: Context<'_>,
+    #[description = "Steps like 15m:role, 1h:@Techs or 4h:@Someone, or off"]
+    #[rest]
+    steps: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.0,
+        None => return Ok(()),
+    };
+    let mut settings = ctx.data().guild_settings.get(guild_id).unwrap_or_default();
+    let steps = match steps.as_deref().map(str::trim) {
+        None | Some("") => {
+            ctx.say(if settings.escalation.steps.is_empty() {
+                "Nobody else is told as outages go on.".to_string()
+            } else {
+                format!("Escalation: {}", steps_text(&settings.escalation.steps))
+            })
+            .await?;
+            return Ok(());
+        }
+        Some("off") => vec![],
+        Some(steps) => {
+            let steps = match steps
+                .split_whitespace()
+                .map(|step| step.parse::<EscalationStep>())
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(steps) => steps,
+                Err(why) => {
+                    ctx.say(why).await?;
+                    return Ok(());
+                }
+            };
+            if let Some(step) = steps.iter().find(|step| !step.target.allowed_in_guilds()) {
+                ctx.say(format!("{} is only for the bot's own config.", step))
+                    .await?;
+                return Ok(());
+            }
+            steps
+        }
+    };
+    settings.escalation.steps = steps;
+    settings
+        .escalation
+        .steps
+        .sort_by_key(|step| step.after_mins);
+    ctx.data().guild_settings.set(guild_id, settings)?;
+    tracing::info!("Changed the escalation in guild {}", guild_id);
+    ctx.say("Changed who else is told as outages go on.")
+        .await?;
+    Ok(())
+}
+
+fn steps_text(steps: &[EscalationStep]) -> String {
+    steps
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn rules_text(rules: &[Access]) -> String {
+    rules
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Every command's name, with subcommands after their parent's, like
+// "config set".
+fn command_names(ctx: Context<'_>) -> Vec<String> {
+    fn add(names: &mut Vec<String>, prefix: &str, commands: &[poise::Command<crate::Data, Error>]) {
+        for command in commands {
+            let name = format!("{}{}", prefix, command.name);
+            add(names, &format!("{} ", name), &command.subcommands);
+            names.push(name);
+        }
+    }
+    let mut names = Vec::new();
+    add(&mut names, "", &ctx.framework().options().commands);
+    names
+}
+
+// Asks the invoking user to pick one of `options`, as (label, value) pairs,
+// and returns the value. `None` means they didn't answer in time.
+async fn choose(
+    ctx: Context<'_>,
+    prompt: &str,
+    options: Vec<(String, String)>,
+) -> Result<Option<String>, Error> {
+    let values = choose_many(ctx, prompt, options, 1, &[]).await?;
+    Ok(values.and_then(|values| values.into_iter().next()))
+}
+
+// Like `choose`, but lets the user pick any number of `options`, up to
+// `max_values`, with `selected` picked to begin with. Picking none is an
+// answer too.
+async fn choose_many(
+    ctx: Context<'_>,
+    prompt: &str,
+    options: Vec<(String, String)>,
+    max_values: u64,
+    selected: &[String],
+) -> Result<Option<Vec<String>>, Error> {
+    let custom_id = format!("setup-{}", ctx.id());
+    let handle = ctx
+        .send(|m| {
+            m.content(prompt).components(|components| {
+                components.create_action_row(|row| {
+                    row.create_select_menu(|menu| {
+                        if max_values > 1 {
+                            menu.min_values(0).max_values(max_values);
+                        }
+                        menu.custom_id(&custom_id).options(|menu_options| {
+                            for (label, value) in &options {
+                                menu_options.create_option(|option| {
+                                    option
+                                        .label(label)
+                                        .value(value)
+                                        .default_selection(selected.contains(value))
+                                });
+                            }
+                            menu_options
+                        })
+                    })
+                })
+            })
+        })
+        .await?;
+    let message = handle.message().await?;
+
+    let interaction = message
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter(move |interaction| interaction.data.custom_id == custom_id)
+        .timeout(CHOICE_TIMEOUT)
+        .await;
+    let interaction = match interaction {
+        Some(interaction) => interaction,
+        None => return Ok(None),
+    };
+    let values = interaction.data.values.clone();
+    let labels = options
+        .iter()
+        .filter(|(_, value)| values.contains(value))
+        .map(|(label, _)| label.as_str())
+        .collect::<Vec<_>>();
+    let labels = if labels.is_empty() {
+        "nothing".to_string()
+    } else {
+        labels.join(", ")
+    };
+    interaction
+        .create_interaction_response(ctx.serenity_context(), |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.content(format!("{} {}", prompt, labels))
+                        .components(|components| components)
+                })
+        })
+        .await?;
+    Ok(Some(values))
+}
+
+/// Points the owner of a server the bot was just added to at `/setup`.
+/// Discord doesn't say who added the bot, so the owner is the best guess.
+pub async fn welcome(ctx: &serenity::Context, guild: &serenity::Guild) -> Result<(), Error> {
+    let dm_channel = guild.owner_id.create_dm_channel(ctx).await?;
+    dm_channel
+        .say(
+            ctx,
+            format!(
+                "Thanks for adding me to {}! Use /setup there to choose where I announce \
+                 when UP SAIS goes down, who to ping, and how I talk.",
+                guild.name
+            ),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Monthly uptime targets, and how much downtime a month has left before
+//! its target is missed.
+
+use crate::{
+    history::{HistoryStore, NO_DATA},
+    routes::MAIN_ROUTE_NAME,
+    Error,
+};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, TimeZone, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlaConfig {
+    /// How much of each month SAIS should be up, e.g. 99.0.
+    pub monthly_target_percent: f64,
+}
+
+/// How a month is going against its uptime target, judged by the main
+/// route's checks. Checks made during maintenance, while the bot's own
+/// credentials were rejected, or while the bot was cut off from Discord
+/// don't count either way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorBudget {
+    pub target_percent: f64,
+    pub uptime_percent: f64,
+    /// The month's downtime so far, estimated from the share of failed
+    /// checks.
+    pub downtime: Duration,
+    /// How much downtime the whole month is allowed.
+    pub budget: Duration,
+}
+
+impl ErrorBudget {
+    /// Goes negative once the budget is exceeded.
+    pub fn remaining(&self) -> Duration {
+        self.budget - self.downtime
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.downtime > self.budget
+    }
+
+    /// The budget for the month `now` is in, in `now`'s time zone, from the
+    /// checks in `history`.
+    pub async fn for_month(
+        history: &dyn HistoryStore,
+        target_percent: f64,
+        now: DateTime<FixedOffset>,
+    ) -> Result<ErrorBudget, Error> {
+        let (month_start, month_end) = month_bounds(now);
+        let mut counted = 0;
+        let mut failed = 0;
+        for record in history.since(month_start).await? {
+            if record.route == MAIN_ROUTE_NAME && counts(&record.status) {
+                counted += 1;
+                failed += failed_check(&record.status) as u64;
+            }
+        }
+        for rollup in history.rollups_since(month_start).await? {
+            if rollup.route == MAIN_ROUTE_NAME && counts(&rollup.status) {
+                counted += rollup.checks;
+                if failed_check(&rollup.status) {
+                    failed += rollup.checks;
+                }
+            }
+        }
+
+        let failed_share = if counted == 0 {
+            0.0
+        } else {
+            failed as f64 / counted as f64
+        };
+        let elapsed = now.with_timezone(&Utc) - month_start;
+        let month_length = month_end - month_start;
+        Ok(ErrorBudget {
+            target_percent,
+            uptime_percent: 100.0 * (1.0 - failed_share),
+            downtime: scale(elapsed, failed_share),
+            budget: scale(month_length, 1.0 - target_percent / 100.0),
+        })
+    }
+}
+
+pub(crate) fn counts(status: &str) -> bool {
+    !matches!(status, "maintenance" | "credential_problem" | NO_DATA)
+}
+
+pub(crate) fn failed_check(status: &str) -> bool {
+    matches!(status, "down" | "pages_failing")
+}
+
+fn scale(duration: Duration, factor: f64) -> Duration {
+    Duration::seconds((duration.num_seconds() as f64 * factor).round() as i64)
+}
+
+/// When the month `now` is in starts and ends, in `now`'s time zone.
+pub fn month_bounds(now: DateTime<FixedOffset>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let timezone = now.timezone();
+    let start = timezone
+        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .unwrap();
+    let (next_year, next_month) = match now.month() {
+        12 => (now.year() + 1, 1),
+        month => (now.year(), month + 1),
+    };
+    let end = timezone
+        .with_ymd_and_hms(next_year, next_month, 1, 0, 0, 0)
+        .unwrap();
+    (start.with_timezone(&Utc), end.with_timezone(&Utc))
+}
+
+/// Like "3h 20m", for showing budgets in messages.
+pub fn format_duration(duration: Duration) -> String {
+    let sign = if duration < Duration::zero() { "-" } else { "" };
+    let minutes = duration.num_minutes().abs();
+    if minutes >= 60 {
+        format!("{}{}h {}m", sign, minutes / 60, minutes % 60)
+    } else {
+        format!("{}{}m", sign, minutes)
+    }
+}
@@ -0,0 +1,133 @@
+//! Notices users sending commands far faster than anyone needs to and
+//! ignores them for a while, so they can't use up the bot's SAIS logins.
+
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpamConfig {
+    /// More commands than this within the window count as spam.
+    #[serde(default = "default_max_commands")]
+    pub max_commands: usize,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// How long spammers are ignored for.
+    #[serde(default = "default_ignore_secs")]
+    pub ignore_secs: u64,
+}
+
+fn default_max_commands() -> usize {
+    8
+}
+
+fn default_window_secs() -> u64 {
+    60
+}
+
+fn default_ignore_secs() -> u64 {
+    900
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        SpamConfig {
+            max_commands: default_max_commands(),
+            window_secs: default_window_secs(),
+            ignore_secs: default_ignore_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allowed,
+    /// Just started being ignored, for this long. Worth telling them once.
+    NowIgnored(Duration),
+    Ignored,
+}
+
+#[derive(Debug, Default)]
+struct Activity {
+    recent: VecDeque<Instant>,
+    ignored_until: Option<Instant>,
+}
+
+/// Every user's recent commands, kept in memory.
+#[derive(Debug)]
+pub struct SpamGuard {
+    config: SpamConfig,
+    users: Mutex<HashMap<u64, Activity>>,
+}
+
+impl SpamGuard {
+    pub fn new(config: SpamConfig) -> Self {
+        SpamGuard {
+            config,
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a command from `user_id` at `now`, including ones a cooldown
+    /// will turn away.
+    pub fn record(&self, user_id: u64, now: Instant) -> Verdict {
+        let window = Duration::from_secs(self.config.window_secs);
+        let mut users = self.users.lock().unwrap();
+        users.retain(|_, activity| {
+            activity
+                .recent
+                .back()
+                .is_some_and(|last| now.saturating_duration_since(*last) < window)
+                || activity.ignored_until.is_some_and(|until| until > now)
+        });
+
+        let activity = users.entry(user_id).or_default();
+        if activity.ignored_until.is_some_and(|until| until > now) {
+            return Verdict::Ignored;
+        }
+        activity.ignored_until = None;
+        while activity
+            .recent
+            .front()
+            .is_some_and(|first| now.saturating_duration_since(*first) >= window)
+        {
+            activity.recent.pop_front();
+        }
+        activity.recent.push_back(now);
+        if activity.recent.len() <= self.config.max_commands {
+            return Verdict::Allowed;
+        }
+        let ignore_for = Duration::from_secs(self.config.ignore_secs);
+        activity.recent.clear();
+        activity.ignored_until = Some(now + ignore_for);
+        Verdict::NowIgnored(ignore_for)
+    }
+
+    /// Stops ignoring `user_id` and forgets their recent commands. Returns
+    /// whether they were being ignored.
+    pub fn pardon(&self, user_id: u64, now: Instant) -> bool {
+        self.users
+            .lock()
+            .unwrap()
+            .remove(&user_id)
+            .and_then(|activity| activity.ignored_until)
+            .is_some_and(|until| until > now)
+    }
+
+    /// The users being ignored, with how long is left, longest first.
+    pub fn ignored(&self, now: Instant) -> Vec<(u64, Duration)> {
+        let users = self.users.lock().unwrap();
+        let mut ignored = users
+            .iter()
+            .filter_map(|(user_id, activity)| {
+                let until = activity.ignored_until?;
+                (until > now).then(|| (*user_id, until - now))
+            })
+            .collect::<Vec<_>>();
+        ignored.sort_by_key(|(user_id, left)| (std::cmp::Reverse(*left), *user_id));
+        ignored
+    }
+}
@@ -0,0 +1,204 @@
+//! Keeps the history in SQLite, the default store.
+
+use crate::{
+    history::{start_of_hour, CheckRecord, HistoryStore, HourlyRollup},
+    migrations, Error,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::{path::Path, sync::Mutex};
+
+pub struct SqliteHistory {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteHistory {
+    /// Opens the history database, creating it and its directory if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<SqliteHistory, Error> {
+        if let Some(directory) = path.as_ref().parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        SqliteHistory::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<SqliteHistory, Error> {
+        SqliteHistory::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut connection: Connection) -> Result<SqliteHistory, Error> {
+        migrations::run(&mut connection)?;
+        Ok(SqliteHistory {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+// The queries are quick, so they run on the calling task instead of being
+// handed to a blocking thread.
+#[async_trait]
+impl HistoryStore for SqliteHistory {
+    async fn record(&self, records: &[CheckRecord]) -> Result<(), Error> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        for record in records {
+            transaction.execute(
+                "INSERT INTO checks (checked_at, route, status, reason, latency_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    record.checked_at,
+                    record.route,
+                    record.status,
+                    record.reason,
+                    record.latency_ms
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn since(&self, since: DateTime<Utc>) -> Result<Vec<CheckRecord>, Error> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT checked_at, route, status, reason, latency_ms FROM checks
+             WHERE checked_at >= ?1 ORDER BY checked_at, id",
+        )?;
+        let records = statement
+            .query_map(params![since], |row| {
+                Ok(CheckRecord {
+                    checked_at: row.get(0)?,
+                    route: row.get(1)?,
+                    status: row.get(2)?,
+                    reason: row.get(3)?,
+                    latency_ms: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    async fn compact(
+        &self,
+        raw_before: DateTime<Utc>,
+        rollups_before: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        let mut rollups = vec![];
+        {
+            let mut statement = transaction.prepare(
+                "SELECT checked_at, route, status, latency_ms FROM checks WHERE checked_at < ?1",
+            )?;
+            let mut rows = statement.query(params![raw_before])?;
+            while let Some(row) = rows.next()? {
+                let checked_at: DateTime<Utc> = row.get(0)?;
+                let latency_ms: u64 = row.get(3)?;
+                rollups.push(HourlyRollup {
+                    hour: start_of_hour(checked_at),
+                    route: row.get(1)?,
+                    status: row.get(2)?,
+                    checks: 1,
+                    total_latency_ms: latency_ms,
+                });
+            }
+        }
+        add_rollups(&transaction, &rollups)?;
+        let compacted = transaction.execute(
+            "DELETE FROM checks WHERE checked_at < ?1",
+            params![raw_before],
+        )?;
+        let expired = transaction.execute(
+            "DELETE FROM hourly_rollups WHERE hour < ?1",
+            params![rollups_before],
+        )?;
+        transaction.commit()?;
+        tracing::info!(
+            "Rolled up {} checks, and deleted {} old rollups",
+            compacted,
+            expired
+        );
+        Ok(())
+    }
+
+    async fn record_rollups(&self, rollups: &[HourlyRollup]) -> Result<(), Error> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        add_rollups(&transaction, rollups)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn rollups_since(&self, since: DateTime<Utc>) -> Result<Vec<HourlyRollup>, Error> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT hour, route, status, checks, total_latency_ms FROM hourly_rollups
+             WHERE hour >= ?1 ORDER BY hour, route, status",
+        )?;
+        let rollups = statement
+            .query_map(params![since], |row| {
+                Ok(HourlyRollup {
+                    hour: row.get(0)?,
+                    route: row.get(1)?,
+                    status: row.get(2)?,
+                    checks: row.get(3)?,
+                    total_latency_ms: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rollups)
+    }
+
+    async fn size_bytes(&self) -> Result<u64, Error> {
+        let connection = self.connection.lock().unwrap();
+        let size = connection.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(size)
+    }
+
+    async fn try_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        let connection = self.connection.lock().unwrap();
+        let changed = connection.execute(
+            "INSERT INTO leases (name, holder, expires_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (name) DO UPDATE SET
+                holder = excluded.holder,
+                expires_at = excluded.expires_at
+             WHERE leases.holder = excluded.holder OR leases.expires_at < ?4",
+            params![name, holder, expires_at, now],
+        )?;
+        Ok(changed == 1)
+    }
+}
+
+// Adds to the rollups already kept for the same hour, route and status.
+fn add_rollups(
+    transaction: &rusqlite::Transaction<'_>,
+    rollups: &[HourlyRollup],
+) -> Result<(), Error> {
+    for rollup in rollups {
+        transaction.execute(
+            "INSERT INTO hourly_rollups (hour, route, status, checks, total_latency_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (hour, route, status) DO UPDATE SET
+                checks = checks + excluded.checks,
+                total_latency_ms = total_latency_ms + excluded.total_latency_ms",
+            params![
+                rollup.hour,
+                rollup.route,
+                rollup.status,
+                rollup.checks,
+                rollup.total_latency_ms
+            ],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! State that several processes may need to agree on: command cooldowns, the
+//! latest status, and the lock that keeps checks of SAIS from overlapping.
+//! Kept in memory by default, or in Redis so that the bot, the web server and
+//! `sais-check` running as separate processes see the same state.
+
+use crate::{history::CheckRecord, redis_state::RedisState, Error};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The name of the lock held while SAIS is being checked.
+pub const CHECK_LOCK: &str = "check";
+
+/// How long a check can hold [`CHECK_LOCK`] before it's let go anyway.
+pub const CHECK_LOCK_TTL: Duration = Duration::from_secs(60);
+
+// How often a check waiting for the lock tries again.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub enum StateBackend {
+    /// Only this process sees the state.
+    #[default]
+    Memory,
+    /// A Redis URL, e.g. `Redis("redis://127.0.0.1:6379/0")`.
+    Redis(String),
+}
+
+impl StateBackend {
+    pub fn open(&self) -> Result<Arc<dyn SharedState>, Error> {
+        match self {
+            StateBackend::Memory => Ok(Arc::new(MemoryState::default())),
+            StateBackend::Redis(url) => Ok(Arc::new(RedisState::new(url)?)),
+        }
+    }
+}
+
+#[async_trait]
+pub trait SharedState: Send + Sync {
+    /// Starts the cooldown called `key` if it isn't running yet. Otherwise
+    /// returns how much of it is left.
+    async fn take_cooldown(&self, key: &str, cooldown: Duration)
+        -> Result<Option<Duration>, Error>;
+
+    /// The main route's latest check, whichever process made it.
+    async fn latest_status(&self) -> Result<Option<CheckRecord>, Error>;
+
+    async fn set_latest_status(&self, record: &CheckRecord) -> Result<(), Error>;
+
+    /// Takes the lock called `name` for `holder`, if it's free. It's let go
+    /// after `ttl` in case the holder dies.
+    async fn try_lock(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool, Error>;
+
+    /// Lets go of the lock, if `holder` still has it.
+    async fn unlock(&self, name: &str, holder: &str) -> Result<(), Error>;
+}
+
+/// Runs `check` while holding [`CHECK_LOCK`], waiting up to `ttl` for
+/// another process's check to finish first. The check still runs if the
+/// state can't be reached.
+pub async fn with_check_lock<T>(
+    state: &dyn SharedState,
+    holder: &str,
+    ttl: Duration,
+    check: impl Future<Output = T>,
+) -> T {
+    let started = Instant::now();
+    let locked = loop {
+        match state.try_lock(CHECK_LOCK, holder, ttl).await {
+            Ok(true) => break true,
+            Ok(false) if started.elapsed() < ttl => tokio::time::sleep(LOCK_RETRY_INTERVAL).await,
+            Ok(false) => break false,
+            Err(why) => {
+                tracing::warn!("Could not take the check lock: {:?}", why);
+                break false;
+            }
+        }
+    };
+    let output = check.await;
+    if locked {
+        if let Err(why) = state.unlock(CHECK_LOCK, holder).await {
+            tracing::warn!("Could not let go of the check lock: {:?}", why);
+        }
+    }
+    output
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryState {
+    cooldowns: Mutex<HashMap<String, Instant>>,
+    latest_status: Mutex<Option<CheckRecord>>,
+    // The holder of each lock, and when it expires.
+    locks: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+#[async_trait]
+impl SharedState for MemoryState {
+    async fn take_cooldown(
+        &self,
+        key: &str,
+        cooldown: Duration,
+    ) -> Result<Option<Duration>, Error> {
+        let now = Instant::now();
+        let mut cooldowns = self.cooldowns.lock().unwrap();
+        cooldowns.retain(|_, ends_at| *ends_at > now);
+        if let Some(ends_at) = cooldowns.get(key) {
+            return Ok(Some(*ends_at - now));
+        }
+        cooldowns.insert(key.to_string(), now + cooldown);
+        Ok(None)
+    }
+
+    async fn latest_status(&self) -> Result<Option<CheckRecord>, Error> {
+        Ok(self.latest_status.lock().unwrap().clone())
+    }
+
+    async fn set_latest_status(&self, record: &CheckRecord) -> Result<(), Error> {
+        *self.latest_status.lock().unwrap() = Some(record.clone());
+        Ok(())
+    }
+
+    async fn try_lock(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool, Error> {
+        let now = Instant::now();
+        let mut locks = self.locks.lock().unwrap();
+        match locks.get(name) {
+            Some((_, expires_at)) if *expires_at > now => Ok(false),
+            _ => {
+                locks.insert(name.to_string(), (holder.to_string(), now + ttl));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn unlock(&self, name: &str, holder: &str) -> Result<(), Error> {
+        let mut locks = self.locks.lock().unwrap();
+        if locks
+            .get(name)
+            .is_some_and(|(held_by, _)| held_by == holder)
+        {
+            locks.remove(name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! Check results sent over UDP as StatsD metrics, for hosts running a
+//! Datadog or Telegraf agent.
+
+use crate::{exporters::CheckExporter, routes::RouteResult, sla, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::net::{lookup_host, UdpSocket};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatsdConfig {
+    /// Where the agent listens, e.g. "127.0.0.1:8125".
+    pub address: String,
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// Puts the route and status in DogStatsD tags instead of the metric
+    /// names.
+    #[serde(default)]
+    pub tags: bool,
+}
+
+fn default_prefix() -> String {
+    "sais".to_string()
+}
+
+pub struct StatsdExporter {
+    config: StatsdConfig,
+}
+
+impl StatsdExporter {
+    pub fn new(config: StatsdConfig) -> StatsdExporter {
+        StatsdExporter { config }
+    }
+}
+
+#[async_trait]
+impl CheckExporter for StatsdExporter {
+    async fn export(
+        &self,
+        _checked_at: DateTime<Utc>,
+        route_results: &[RouteResult],
+    ) -> Result<(), Error> {
+        let address = lookup_host(&self.config.address)
+            .await?
+            .next()
+            .ok_or("The StatsD address doesn't resolve")?;
+        let local_address: SocketAddr = match address {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let packet = route_results
+            .iter()
+            .flat_map(|route_result| metrics(&self.config, route_result))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let socket = UdpSocket::bind(local_address).await?;
+        socket.send_to(packet.as_bytes(), address).await?;
+        Ok(())
+    }
+}
+
+/// A count of the check's status, its latency as a timer, and whether SAIS
+/// was up as a gauge.
+pub fn metrics(config: &StatsdConfig, route_result: &RouteResult) -> Vec<String> {
+    let prefix = &config.prefix;
+    let route = metric_name(&route_result.route);
+    let status = route_result.status.kind();
+    let up = u8::from(!sla::failed_check(status));
+    let latency_ms = route_result.latency.as_millis();
+    if config.tags {
+        vec![
+            format!("{}.check:1|c|#route:{},status:{}", prefix, route, status),
+            format!("{}.latency_ms:{}|ms|#route:{}", prefix, latency_ms, route),
+            format!("{}.up:{}|g|#route:{}", prefix, up, route),
+        ]
+    } else {
+        vec![
+            format!("{}.check.{}.{}:1|c", prefix, route, status),
+            format!("{}.latency_ms.{}:{}|ms", prefix, route, latency_ms),
+            format!("{}.up.{}:{}|g", prefix, route, up),
+        ]
+    }
+}
+
+// Route names are free-form, but StatsD metric names and tags aren't.
+fn metric_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+            _ => '_',
+        })
+        .collect()
+}
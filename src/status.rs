@@ -0,0 +1,117 @@
+use crate::transport::TimeoutKind;
+use reqwest::StatusCode;
+use std::{fmt, time::Duration};
+
+/// What a check found out about SAIS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceStatus {
+    /// Logging in worked.
+    Up,
+    /// Logging in worked, but the check took longer than
+    /// `degraded_after_secs`.
+    Degraded(Duration),
+    Down(DownReason),
+    /// Logging in worked, but some of the configured deep check pages did
+    /// not.
+    PagesFailing,
+    /// SAIS is serving a maintenance notice.
+    Maintenance,
+    /// SAIS works, but rejected the bot's own login details, so this says
+    /// nothing about whether students can log in.
+    CredentialProblem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownReason {
+    /// The login page could not be loaded at all.
+    NoResponse,
+    /// SAIS's server refused the connection, so nothing was listening.
+    ConnectionRefused,
+    /// SAIS took longer than one of the configured timeouts.
+    TimedOut(TimeoutKind),
+    /// The login page loaded, but with an unsuccessful status code.
+    HttpStatus(StatusCode),
+    /// The login page loaded, but logging in did not work.
+    LoginFailed,
+}
+
+impl fmt::Display for ServiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceStatus::Up => write!(f, "up"),
+            ServiceStatus::Degraded(latency) => write!(f, "up, but slow ({}s)", latency.as_secs()),
+            ServiceStatus::Down(DownReason::NoResponse) => write!(f, "not loading"),
+            ServiceStatus::Down(DownReason::ConnectionRefused) => {
+                write!(f, "refusing connections")
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Connect)) => {
+                write!(f, "unreachable")
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Read)) => {
+                write!(f, "not responding")
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Login)) => {
+                write!(f, "not finishing logins")
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Check)) => write!(f, "too slow"),
+            ServiceStatus::Down(DownReason::HttpStatus(status)) => write!(f, "down ({})", status),
+            ServiceStatus::Down(DownReason::LoginFailed) => {
+                write!(f, "up, but with login problems")
+            }
+            ServiceStatus::PagesFailing => write!(f, "up, but with some pages failing"),
+            ServiceStatus::Maintenance => write!(f, "under maintenance"),
+            ServiceStatus::CredentialProblem => write!(f, "up, but rejecting the bot's login"),
+        }
+    }
+}
+
+impl ServiceStatus {
+    /// A short name for the kind of status that stays the same between
+    /// versions, as stored in the history.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ServiceStatus::Up => "up",
+            ServiceStatus::Degraded(_) => "degraded",
+            ServiceStatus::Down(_) => "down",
+            ServiceStatus::PagesFailing => "pages_failing",
+            ServiceStatus::Maintenance => "maintenance",
+            ServiceStatus::CredentialProblem => "credential_problem",
+        }
+    }
+
+    /// What to tell everyone else. SAIS rejecting the bot's own login
+    /// details still means it's reachable, so that counts as up.
+    pub fn public(self) -> ServiceStatus {
+        match self {
+            ServiceStatus::CredentialProblem => ServiceStatus::Up,
+            status => status,
+        }
+    }
+
+    /// Why SAIS counted as down, in the same stable form as [`Self::kind`].
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            ServiceStatus::Down(DownReason::NoResponse) => Some("no_response".to_string()),
+            ServiceStatus::Down(DownReason::ConnectionRefused) => {
+                Some("connection_refused".to_string())
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Connect)) => {
+                Some("connect_timeout".to_string())
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Read)) => {
+                Some("read_timeout".to_string())
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Login)) => {
+                Some("login_timeout".to_string())
+            }
+            ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Check)) => {
+                Some("check_timeout".to_string())
+            }
+            ServiceStatus::Down(DownReason::HttpStatus(status)) => {
+                Some(format!("http_{}", status.as_u16()))
+            }
+            ServiceStatus::Down(DownReason::LoginFailed) => Some("login_failed".to_string()),
+            _ => None,
+        }
+    }
+}
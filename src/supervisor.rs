@@ -0,0 +1,103 @@
+//! Keeps the bot going when part of it panics or gets stuck. Tasks that do
+//! are started again, and the owner is told.
+
+use is_sais_down_bot::{heartbeat::Heartbeat, redact};
+use poise::serenity_prelude::{Http, UserId};
+use std::{
+    future::Future,
+    panic,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+use tokio::{sync::mpsc, time};
+
+/// How much longer than its usual gap between heartbeats a task gets before
+/// it counts as stuck.
+pub const STUCK_AFTER: Duration = Duration::from_secs(5 * 60);
+// How long to wait before starting a panicked task again, so a panic on
+// every run doesn't turn into a busy loop.
+const RESTART_DELAY: Duration = Duration::from_secs(30);
+const HEARTBEAT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// Messages waiting to be sent to the owner, once the bot knows who that is.
+static OWNER_NOTICES: OnceLock<mpsc::UnboundedSender<String>> = OnceLock::new();
+
+/// Prints panics redacted, and tells the owner about them. Sentry sets up
+/// its own hook after this one, which reports the panic first, then calls
+/// this.
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let message = redact::redact(&info.to_string());
+        eprintln!("{}", message);
+        tell_owner(format!("I panicked:\n```\n{}\n```", message));
+    }));
+}
+
+/// DMs the owner every notice from now on. Only the first call does
+/// anything.
+pub fn report_to_owner(http: Arc<Http>, owner_id: UserId) {
+    let (sender, mut notices) = mpsc::unbounded_channel();
+    if OWNER_NOTICES.set(sender).is_err() {
+        return;
+    }
+    tokio::spawn(async move {
+        while let Some(notice) = notices.recv().await {
+            let dm = async {
+                let dm_channel = owner_id.create_dm_channel(&http).await?;
+                dm_channel.say(&http, notice).await
+            };
+            if let Err(why) = dm.await {
+                tracing::error!("Could not DM the owner: {:?}", why);
+            }
+        }
+    });
+}
+
+// Dropped when the owner isn't known yet, like in webhook-only mode.
+pub fn tell_owner(notice: String) {
+    if let Some(notices) = OWNER_NOTICES.get() {
+        let _ = notices.send(notice);
+    }
+}
+
+/// Runs the task made by `start`, making a new one whenever it panics or
+/// goes past its heartbeat's threshold without a beat.
+pub async fn supervise<F, Fut>(heartbeat: Heartbeat, mut start: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    loop {
+        heartbeat.beat();
+        let mut task = tokio::spawn(start());
+        let mut checks = time::interval(HEARTBEAT_CHECK_INTERVAL);
+        let notice = loop {
+            tokio::select! {
+                result = &mut task => match result {
+                    Ok(()) => return,
+                    Err(why) if why.is_panic() => {
+                        time::sleep(RESTART_DELAY).await;
+                        break format!("The {} panicked, so I started it again.", heartbeat.name());
+                    }
+                    Err(why) => {
+                        tracing::error!("The {} stopped: {:?}", heartbeat.name(), why);
+                        return;
+                    }
+                },
+                _ = checks.tick() => {
+                    let now = Instant::now();
+                    if heartbeat.is_stuck(now) {
+                        task.abort();
+                        break format!(
+                            "The {} made no progress in {}s, so I started it again.",
+                            heartbeat.name(),
+                            heartbeat.silent_for(now).as_secs()
+                        );
+                    }
+                }
+            }
+        };
+        tracing::warn!("{}", notice);
+        tell_owner(notice);
+    }
+}
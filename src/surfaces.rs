@@ -0,0 +1,253 @@
+use crate::{current_time_utc_plus_8, outage_duration_string};
+use chrono::{DateTime, Utc};
+use is_sais_down_bot::{
+    heartbeat::Heartbeat, monitor, status::ServiceStatus, uptime::HourlyUptime,
+};
+use poise::serenity_prelude::{
+    self as serenity, Activity, ChannelId, CreateEmbed, Http, MessageId, Timestamp,
+};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::{sync::watch, time::Instant};
+
+/// Places outside of messages that show SAIS's status.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SurfacesConfig {
+    /// Show the status as the bot's activity.
+    #[serde(default = "default_presence")]
+    pub presence: bool,
+    /// A channel whose topic is set to the status.
+    #[serde(default)]
+    pub topic_channel_id: Option<u64>,
+    /// A channel renamed to the status, like "sais-down".
+    #[serde(default)]
+    pub rename_channel_id: Option<u64>,
+    /// A channel with a single pinned message showing the status, edited
+    /// after every check rather than only when the status changes.
+    /// Announcements aren't posted there.
+    #[serde(default)]
+    pub status_message_channel_id: Option<u64>,
+    /// The least time between two updates. Discord only allows a channel's
+    /// name or topic to change twice every ten minutes.
+    #[serde(default = "default_min_interval_secs")]
+    pub min_interval_secs: u64,
+}
+
+fn default_presence() -> bool {
+    true
+}
+
+fn default_min_interval_secs() -> u64 {
+    300
+}
+
+/// Updates every status surface from one place, so the updates can be
+/// spaced out to stay within Discord's rate limits. Statuses that come in
+/// while waiting are batched, and only the latest one is shown.
+pub struct StatusSurfaces {
+    pub ctx: serenity::Context,
+    pub config: SurfacesConfig,
+    pub statuses: watch::Receiver<Option<ServiceStatus>>,
+    // Beaten while waiting for statuses, and after each update.
+    pub heartbeat: Heartbeat,
+}
+
+// How often the heartbeat is beaten while there's nothing to update.
+pub const IDLE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+impl StatusSurfaces {
+    /// Runs until the monitor stops sending statuses.
+    pub async fn run(mut self) {
+        let min_interval = Duration::from_secs(self.config.min_interval_secs);
+        let mut last_update: Option<Instant> = None;
+        let mut shown: Option<&'static str> = None;
+
+        loop {
+            let changed = tokio::select! {
+                changed = self.statuses.changed() => changed,
+                _ = tokio::time::sleep(IDLE_HEARTBEAT_INTERVAL) => {
+                    self.heartbeat.beat();
+                    continue;
+                }
+            };
+            if changed.is_err() {
+                return;
+            }
+            if let Some(last_update) = last_update {
+                tokio::time::sleep_until(last_update + min_interval).await;
+            }
+            let status = match *self.statuses.borrow_and_update() {
+                Some(status) => status,
+                None => continue,
+            };
+            if shown == Some(status.kind()) {
+                continue;
+            }
+
+            tracing::info!("Updating status surfaces to {}", status);
+            last_update = Some(Instant::now());
+            shown = Some(status.kind());
+            if let Err(why) = self.update(status).await {
+                tracing::error!("Could not update the status surfaces: {:?}", why);
+                sentry::capture_error(&why);
+            }
+            self.heartbeat.beat();
+        }
+    }
+
+    async fn update(&self, status: ServiceStatus) -> Result<(), serenity::Error> {
+        if self.config.presence {
+            self.ctx
+                .set_activity(Activity::watching(format!("SAIS: {}", status)))
+                .await;
+        }
+        if let Some(channel_id) = self.config.topic_channel_id {
+            let topic = format!(
+                "UP SAIS is {} (as of {})",
+                status,
+                current_time_utc_plus_8().format("%H:%M")
+            );
+            ChannelId(channel_id)
+                .edit(&self.ctx.http, |channel| channel.topic(topic))
+                .await?;
+        }
+        if let Some(channel_id) = self.config.rename_channel_id {
+            let name = format!("sais-{}", status.kind().replace('_', "-"));
+            ChannelId(channel_id)
+                .edit(&self.ctx.http, |channel| channel.name(name))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// How the bot finds its own pinned message again after restarting.
+const STATUS_MESSAGE_TITLE: &str = "UP SAIS status";
+
+/// What the pinned status message shows.
+pub struct StatusMessageContent {
+    pub confirmed: ServiceStatus,
+    pub latest: ServiceStatus,
+    pub latency: Duration,
+    pub checked_at: DateTime<Utc>,
+    pub outage_since: Option<DateTime<Utc>>,
+    // Only known with a history.
+    pub last_day: Option<HourlyUptime>,
+}
+
+/// A single pinned message showing the status, edited after every check.
+/// Not a surface updated by [`StatusSurfaces`], since those only change
+/// with the confirmed status.
+pub struct StatusMessage {
+    channel_id: ChannelId,
+    message_id: Option<MessageId>,
+}
+
+impl StatusMessage {
+    pub fn new(channel_id: u64) -> Self {
+        StatusMessage {
+            channel_id: ChannelId(channel_id),
+            message_id: None,
+        }
+    }
+
+    /// Edits the pinned message, or posts and pins one if it can't be found.
+    pub async fn update(
+        &mut self,
+        http: &Http,
+        content: &StatusMessageContent,
+    ) -> Result<(), serenity::Error> {
+        let embed = status_message_embed(content);
+        if self.message_id.is_none() {
+            self.message_id = self.find_pinned(http).await?;
+        }
+        match self.message_id {
+            Some(message_id) => {
+                let edited = self
+                    .channel_id
+                    .edit_message(http, message_id, |message| message.set_embed(embed))
+                    .await;
+                if edited.is_err() {
+                    // Looked up again next time, in case it was deleted.
+                    self.message_id = None;
+                }
+                edited?;
+            }
+            None => {
+                let message = self
+                    .channel_id
+                    .send_message(http, |message| message.set_embed(embed))
+                    .await?;
+                self.message_id = Some(message.id);
+                message.pin(http).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn find_pinned(&self, http: &Http) -> Result<Option<MessageId>, serenity::Error> {
+        let bot_id = http.get_current_user().await?.id;
+        Ok(self
+            .channel_id
+            .pins(http)
+            .await?
+            .into_iter()
+            .find(|message| {
+                message.author.id == bot_id
+                    && message
+                        .embeds
+                        .first()
+                        .is_some_and(|embed| embed.title.as_deref() == Some(STATUS_MESSAGE_TITLE))
+            })
+            .map(|message| message.id))
+    }
+}
+
+fn status_message_embed(content: &StatusMessageContent) -> CreateEmbed {
+    let colour = match content.confirmed {
+        ServiceStatus::Up | ServiceStatus::CredentialProblem => 0x2ecc71,
+        ServiceStatus::Down(_) => 0xe74c3c,
+        _ => 0xf1c40f,
+    };
+    let mut description = format!("UP SAIS is **{}**", content.confirmed);
+    match content.outage_since {
+        Some(since) if monitor::is_outage(&content.confirmed) => {
+            description.push_str(&format!(", {}.", outage_duration_string(since)));
+        }
+        _ => description.push('.'),
+    }
+    let last_check = format!(
+        "{} in {} ms, at {} PHT",
+        content.latest,
+        content.latency.as_millis(),
+        content
+            .checked_at
+            .with_timezone(&chrono::FixedOffset::east_opt(3600 * 8).unwrap())
+            .format("%H:%M:%S")
+    );
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(STATUS_MESSAGE_TITLE)
+        .description(description)
+        .colour(colour)
+        .field("Last check", last_check, false)
+        .footer(|footer| footer.text("Updated after every check"))
+        .timestamp(Timestamp::from(content.checked_at));
+    if let Some(last_day) = &content.last_day {
+        let uptime = match last_day.uptime_percent() {
+            Some(uptime) => format!("{:.2}% up", uptime),
+            None => "No checks yet".to_string(),
+        };
+        embed.field(
+            "Last 24 hours",
+            format!(
+                "{}\n{}, an hour a square, oldest first",
+                last_day.to_bar(),
+                uptime
+            ),
+            false,
+        );
+    }
+    embed
+}
@@ -0,0 +1,44 @@
+//! Tells systemd how the bot is doing when it runs as a `Type=notify`
+//! service. Everything here does nothing when the bot wasn't started by
+//! systemd, or on systems without it.
+
+use std::time::Duration;
+
+/// Says startup is done.
+pub fn ready() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Ready]);
+}
+
+/// Tells the watchdog the monitor is still going.
+pub fn watchdog() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Watchdog]);
+}
+
+/// Warns if the watchdog would fire before the monitor next checks in.
+pub fn check_watchdog_timeout(monitor_interval: Duration) {
+    #[cfg(unix)]
+    {
+        let mut usec = 0;
+        if sd_notify::watchdog_enabled(false, &mut usec)
+            && Duration::from_micros(usec) <= monitor_interval
+        {
+            tracing::warn!(
+                "WatchdogSec ({:?}) isn't longer than the monitor interval ({:?}), \
+                 so systemd will keep restarting the bot",
+                Duration::from_micros(usec),
+                monitor_interval
+            );
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = monitor_interval;
+}
+
+#[cfg(unix)]
+fn notify(state: &[sd_notify::NotifyState]) {
+    if let Err(why) = sd_notify::notify(false, state) {
+        tracing::warn!("Could not notify systemd: {:?}", why);
+    }
+}
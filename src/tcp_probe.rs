@@ -0,0 +1,90 @@
+//! A plain TCP connection to the SAIS host, timed apart from the HTTP check,
+//! so "connects fine but HTTP hangs" can be told apart from "can't even
+//! connect".
+
+use crate::{
+    config::{IpFamily, SaisConfig},
+    routes::RouteResult,
+    status::{DownReason, ServiceStatus},
+    transport::TimeoutKind,
+};
+use std::{
+    io::ErrorKind,
+    time::{Duration, Instant},
+};
+use tokio::net::{lookup_host, TcpStream};
+
+/// What the probe's results are recorded as in the history.
+pub const TCP_ROUTE_NAME: &str = "tcp";
+
+#[derive(Debug, Clone)]
+pub struct TcpProbe {
+    host: String,
+    port: u16,
+    ip_family: Option<IpFamily>,
+    timeout: Duration,
+}
+
+impl TcpProbe {
+    /// Probes the login URL's host and port. Checks through a proxy never
+    /// connect to SAIS directly, so there is nothing to compare them with.
+    pub fn new(sais_config: &SaisConfig) -> Option<TcpProbe> {
+        if sais_config.proxy.is_some() {
+            return None;
+        }
+        let url = reqwest::Url::parse(&sais_config.login_url).ok()?;
+        Some(TcpProbe {
+            host: url.host_str()?.to_string(),
+            port: url.port_or_known_default()?,
+            ip_family: sais_config.ip_family,
+            timeout: sais_config.timeouts.connect(),
+        })
+    }
+
+    /// Connects to each of the host's addresses in turn until one works. Only
+    /// connecting is timed, not resolving the host.
+    pub async fn run(&self) -> RouteResult {
+        let (status, latency) = self.connect().await;
+        RouteResult {
+            route: TCP_ROUTE_NAME.to_string(),
+            status,
+            suspicious_redirect: None,
+            failing_pages: vec![],
+            error_page: None,
+            latency,
+        }
+    }
+
+    async fn connect(&self) -> (ServiceStatus, Duration) {
+        let addrs = match lookup_host((self.host.as_str(), self.port)).await {
+            Ok(addrs) => addrs
+                .filter(|addr| {
+                    self.ip_family
+                        .is_none_or(|ip_family| ip_family.matches(&addr.ip()))
+                })
+                .collect::<Vec<_>>(),
+            Err(why) => {
+                tracing::info!("TCP probe could not resolve {}: {}", self.host, why);
+                return (ServiceStatus::Down(DownReason::NoResponse), Duration::ZERO);
+            }
+        };
+
+        let mut result = (ServiceStatus::Down(DownReason::NoResponse), Duration::ZERO);
+        for addr in addrs {
+            let start = Instant::now();
+            let reason = match tokio::time::timeout(self.timeout, TcpStream::connect(addr)).await {
+                Ok(Ok(_)) => return (ServiceStatus::Up, start.elapsed()),
+                Ok(Err(why)) if why.kind() == ErrorKind::ConnectionRefused => {
+                    DownReason::ConnectionRefused
+                }
+                Ok(Err(why)) => {
+                    tracing::info!("TCP probe could not connect to {}: {}", addr, why);
+                    DownReason::NoResponse
+                }
+                Err(_) => DownReason::TimedOut(TimeoutKind::Connect),
+            };
+            result = (ServiceStatus::Down(reason), start.elapsed());
+        }
+        result
+    }
+}
@@ -0,0 +1,262 @@
+//! How long each phase of loading the SAIS login page takes, like curl's
+//! `-w` timings: resolving the host, connecting, the TLS handshake and the
+//! first byte of the response. Slow DNS or connecting points at the
+//! network, and a slow first byte at SAIS itself.
+
+use crate::{
+    config::{IpFamily, SaisConfig},
+    Error,
+};
+use std::{
+    fmt,
+    fmt::Write as _,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{lookup_host, TcpStream},
+};
+use tokio_native_tls::{native_tls, TlsConnector};
+
+/// How long each phase took, each apart from the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    pub dns: Duration,
+    pub connect: Duration,
+    /// `None` for plain HTTP.
+    pub tls: Option<Duration>,
+    /// From sending the request to the first byte of the response.
+    pub first_byte: Duration,
+    /// From the first byte to the last.
+    pub download: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.dns + self.connect + self.tls.unwrap_or_default() + self.first_byte + self.download
+    }
+
+    /// Each phase by the name it has in the metrics.
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        let mut phases = vec![("dns", self.dns), ("connect", self.connect)];
+        phases.extend(self.tls.map(|tls| ("tls", tls)));
+        phases.extend([
+            ("first_byte", self.first_byte),
+            ("download", self.download),
+            ("total", self.total()),
+        ]);
+        phases
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DNS {} ms, connect {} ms",
+            self.dns.as_millis(),
+            self.connect.as_millis()
+        )?;
+        if let Some(tls) = self.tls {
+            write!(f, ", TLS {} ms", tls.as_millis())?;
+        }
+        write!(
+            f,
+            ", first byte {} ms, download {} ms, total {} ms",
+            self.first_byte.as_millis(),
+            self.download.as_millis(),
+            self.total().as_millis()
+        )
+    }
+}
+
+/// Loads the login page over a connection of its own, timing each phase.
+/// The HTTP client doesn't expose when its phases end, so this is a
+/// separate request.
+#[derive(Debug, Clone)]
+pub struct TimingProbe {
+    host: String,
+    port: u16,
+    https: bool,
+    path: String,
+    ip_family: Option<IpFamily>,
+    timeout: Duration,
+}
+
+impl TimingProbe {
+    /// Probes the login URL. Checks through a proxy never connect to SAIS
+    /// directly, so their phases can't be timed.
+    pub fn new(sais_config: &SaisConfig) -> Option<TimingProbe> {
+        if sais_config.proxy.is_some() {
+            return None;
+        }
+        let url = reqwest::Url::parse(&sais_config.login_url).ok()?;
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        Some(TimingProbe {
+            host: url.host_str()?.to_string(),
+            port: url.port_or_known_default()?,
+            https: url.scheme() == "https",
+            path,
+            ip_family: sais_config.ip_family,
+            timeout: sais_config.timeouts.check(),
+        })
+    }
+
+    /// Fails if any phase fails, or they take longer than the check timeout
+    /// altogether.
+    pub async fn run(&self) -> Result<Timings, Error> {
+        match tokio::time::timeout(self.timeout, self.phases()).await {
+            Ok(timings) => timings,
+            Err(_) => Err(format!("took over {}s", self.timeout.as_secs()).into()),
+        }
+    }
+
+    async fn phases(&self) -> Result<Timings, Error> {
+        let start = Instant::now();
+        let addr = lookup_host((self.host.as_str(), self.port))
+            .await?
+            .find(|addr| {
+                self.ip_family
+                    .is_none_or(|ip_family| ip_family.matches(&addr.ip()))
+            })
+            .ok_or_else(|| format!("{} has no addresses to connect to", self.host))?;
+        let dns = start.elapsed();
+
+        let start = Instant::now();
+        let stream = TcpStream::connect(addr).await?;
+        let connect = start.elapsed();
+
+        if !self.https {
+            let (first_byte, download) = self.request(stream).await?;
+            return Ok(Timings {
+                dns,
+                connect,
+                tls: None,
+                first_byte,
+                download,
+            });
+        }
+        let start = Instant::now();
+        let connector = TlsConnector::from(native_tls::TlsConnector::new()?);
+        let stream = connector.connect(&self.host, stream).await?;
+        let tls = start.elapsed();
+        let (first_byte, download) = self.request(stream).await?;
+        Ok(Timings {
+            dns,
+            connect,
+            tls: Some(tls),
+            first_byte,
+            download,
+        })
+    }
+
+    // Times the first byte of the response, then the rest of it.
+    async fn request(
+        &self,
+        mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    ) -> Result<(Duration, Duration), Error> {
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            self.path, self.host
+        );
+        let start = Instant::now();
+        stream.write_all(request.as_bytes()).await?;
+        let mut buffer = [0; 8192];
+        if stream.read(&mut buffer).await? == 0 {
+            return Err("the connection closed without a response".into());
+        }
+        let first_byte = start.elapsed();
+
+        let start = Instant::now();
+        while stream.read(&mut buffer).await? > 0 {}
+        Ok((first_byte, start.elapsed()))
+    }
+}
+
+/// Upper bounds of the histogram buckets, in seconds.
+const BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    // Cumulative, one per bucket.
+    counts: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+/// Every timed check's phases since the bot started, as Prometheus
+/// histograms.
+#[derive(Debug, Default)]
+pub struct TimingHistograms {
+    phases: Mutex<Vec<(&'static str, Histogram)>>,
+}
+
+impl TimingHistograms {
+    pub fn record(&self, timings: &Timings) {
+        let mut phases = self.phases.lock().unwrap();
+        for (phase, duration) in timings.phases() {
+            let index = match phases.iter().position(|(name, _)| *name == phase) {
+                Some(index) => index,
+                None => {
+                    phases.push((phase, Histogram::default()));
+                    phases.len() - 1
+                }
+            };
+            let histogram = &mut phases[index].1;
+            let secs = duration.as_secs_f64();
+            for (count, bound) in histogram.counts.iter_mut().zip(BUCKETS) {
+                *count += (secs <= bound) as u64;
+            }
+            histogram.count += 1;
+            histogram.sum += secs;
+        }
+    }
+
+    /// The histograms in the Prometheus text format, or nothing before the
+    /// first timed check.
+    pub fn to_prometheus(&self) -> String {
+        const NAME: &str = "sais_check_phase_seconds";
+        let phases = self.phases.lock().unwrap();
+        let mut text = String::new();
+        if phases.is_empty() {
+            return text;
+        }
+        let _ = writeln!(
+            text,
+            "# HELP {} How long each phase of loading the SAIS login page took.",
+            NAME
+        );
+        let _ = writeln!(text, "# TYPE {} histogram", NAME);
+        for (phase, histogram) in phases.iter() {
+            for (count, bound) in histogram.counts.iter().zip(BUCKETS) {
+                let _ = writeln!(
+                    text,
+                    "{}_bucket{{phase=\"{}\",le=\"{}\"}} {}",
+                    NAME, phase, bound, count
+                );
+            }
+            let _ = writeln!(
+                text,
+                "{}_bucket{{phase=\"{}\",le=\"+Inf\"}} {}",
+                NAME, phase, histogram.count
+            );
+            let _ = writeln!(
+                text,
+                "{}_sum{{phase=\"{}\"}} {}",
+                NAME, phase, histogram.sum
+            );
+            let _ = writeln!(
+                text,
+                "{}_count{{phase=\"{}\"}} {}",
+                NAME, phase, histogram.count
+            );
+        }
+        text
+    }
+}
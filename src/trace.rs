@@ -0,0 +1,239 @@
+//! The network path to SAIS, traced with the system's `traceroute`, for
+//! telling whether packets are lost inside or outside the campus network.
+
+use crate::Error;
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::{fmt, io::ErrorKind, net::IpAddr, time::Duration};
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TraceConfig {
+    /// The traceroute executable, by path or by name on `PATH`.
+    #[serde(default = "default_program")]
+    pub program: String,
+    #[serde(default = "default_max_hops")]
+    pub max_hops: u8,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Networks counted as part of campus, besides private addresses, e.g.
+    /// "203.0.113.0/24".
+    #[serde(default)]
+    pub campus_networks: Vec<IpNet>,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        TraceConfig {
+            program: default_program(),
+            max_hops: default_max_hops(),
+            timeout_secs: default_timeout_secs(),
+            campus_networks: vec![],
+        }
+    }
+}
+
+fn default_program() -> String {
+    "traceroute".to_string()
+}
+
+fn default_max_hops() -> u8 {
+    20
+}
+
+fn default_timeout_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub number: u8,
+    /// The first address that replied, if any did.
+    pub address: Option<IpAddr>,
+    pub round_trips: Vec<Duration>,
+    /// Probes sent to this hop, including those that went unanswered.
+    pub probes: usize,
+}
+
+impl Hop {
+    pub fn loss_percent(&self) -> usize {
+        if self.probes == 0 {
+            return 0;
+        }
+        (self.probes - self.round_trips.len()) * 100 / self.probes
+    }
+
+    fn mean_round_trip(&self) -> Option<Duration> {
+        let total = self.round_trips.iter().sum::<Duration>();
+        (!self.round_trips.is_empty()).then(|| total / self.round_trips.len() as u32)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub host: String,
+    /// The address traceroute resolved the host to.
+    pub target: Option<IpAddr>,
+    pub hops: Vec<Hop>,
+    pub campus_networks: Vec<IpNet>,
+}
+
+impl Trace {
+    /// Parses the output of `traceroute -n`.
+    pub fn parse(host: &str, output: &str, campus_networks: Vec<IpNet>) -> Trace {
+        let mut lines = output.lines();
+        let target = lines
+            .next()
+            .and_then(|header| Some(header.split_once('(')?.1.split_once(')')?.0))
+            .and_then(|target| target.parse().ok());
+
+        let mut hops = vec![];
+        for line in lines {
+            let mut fields = line.split_whitespace().peekable();
+            let number = match fields.next().and_then(|number| number.parse().ok()) {
+                Some(number) => number,
+                None => continue,
+            };
+            let mut hop = Hop {
+                number,
+                address: None,
+                round_trips: vec![],
+                probes: 0,
+            };
+            while let Some(field) = fields.next() {
+                if field == "*" {
+                    hop.probes += 1;
+                } else if let Ok(address) = field.parse::<IpAddr>() {
+                    hop.address.get_or_insert(address);
+                } else if let Ok(millis) = field.parse::<f64>() {
+                    if fields.peek() == Some(&"ms") {
+                        fields.next();
+                        hop.round_trips
+                            .push(Duration::from_secs_f64(millis / 1000.0));
+                        hop.probes += 1;
+                    }
+                }
+            }
+            hops.push(hop);
+        }
+
+        Trace {
+            host: host.to_string(),
+            target,
+            hops,
+            campus_networks,
+        }
+    }
+
+    pub fn is_on_campus(&self, address: IpAddr) -> bool {
+        let is_private = match address {
+            IpAddr::V4(address) => address.is_private() || address.is_link_local(),
+            // Unique local addresses, fc00::/7.
+            IpAddr::V6(address) => (address.segments()[0] & 0xfe00) == 0xfc00,
+        };
+        is_private
+            || self
+                .campus_networks
+                .iter()
+                .any(|network| network.contains(&address))
+    }
+
+    /// The last hop that replied at all.
+    pub fn last_reply(&self) -> Option<&Hop> {
+        self.hops.iter().rev().find(|hop| hop.address.is_some())
+    }
+
+    pub fn reached_target(&self) -> bool {
+        self.target.is_some() && self.last_reply().and_then(|hop| hop.address) == self.target
+    }
+}
+
+/// Traces the path to `host` with the configured traceroute.
+pub async fn run(config: &TraceConfig, host: &str) -> Result<Trace, Error> {
+    let traceroute = Command::new(&config.program)
+        .arg("-n")
+        .arg("-q")
+        .arg("3")
+        .arg("-w")
+        .arg("2")
+        .arg("-m")
+        .arg(config.max_hops.to_string())
+        .arg(host)
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(config.timeout_secs), traceroute)
+        .await
+        .map_err(|_| format!("traceroute took over {}s", config.timeout_secs))?
+        .map_err(|why| -> Error {
+            if why.kind() == ErrorKind::NotFound {
+                format!("{} isn't installed where the bot runs", config.program).into()
+            } else {
+                why.into()
+            }
+        })?;
+    if !output.status.success() {
+        return Err(format!(
+            "traceroute exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(Trace::parse(
+        host,
+        &String::from_utf8_lossy(&output.stdout),
+        config.campus_networks.clone(),
+    ))
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for hop in &self.hops {
+            write!(f, "{:>2}  ", hop.number)?;
+            let address = match hop.address {
+                Some(address) => address,
+                None => {
+                    writeln!(f, "no reply")?;
+                    continue;
+                }
+            };
+            write!(
+                f,
+                "{:<15}  {:<10}  {:>3}% loss",
+                address.to_string(),
+                if self.is_on_campus(address) {
+                    "campus"
+                } else {
+                    "outside"
+                },
+                hop.loss_percent()
+            )?;
+            if let Some(mean) = hop.mean_round_trip() {
+                write!(f, "  {:.1} ms", mean.as_secs_f64() * 1000.0)?;
+            }
+            writeln!(f)?;
+        }
+
+        if self.reached_target() {
+            return write!(f, "Reached {}.", self.host);
+        }
+        match self
+            .last_reply()
+            .and_then(|hop| Some((hop.number, hop.address?)))
+        {
+            Some((number, address)) => write!(
+                f,
+                "Replies stop after hop {} ({}), which is {} the campus network.",
+                number,
+                address,
+                if self.is_on_campus(address) {
+                    "inside"
+                } else {
+                    "outside"
+                }
+            ),
+            None => write!(f, "No hop replied on the way to {}.", self.host),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Small declarative scripts for probing UP systems other than SAIS, e.g.
+//! "load this page, pick out a token, post a form with it, expect a
+//! keyword", without writing new checking code for each one.
+
+use crate::{
+    config::RequestHeaders,
+    layout::start_tags,
+    transport::{HttpRequest, HttpTransport},
+    Error,
+};
+use futures::future::join_all;
+use reqwest::{
+    header::{HeaderValue, COOKIE, SET_COOKIE},
+    Method,
+};
+use serde::Deserialize;
+use std::{collections::HashMap, env, fmt, fs::File, io::prelude::*, time::Duration};
+
+pub const TRANSACTIONS_FILEPATH: &str = "config/transactions.ron";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transaction {
+    pub name: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    pub steps: Vec<Step>,
+    /// Loaded with the transaction's cookies after the steps, whether they
+    /// passed or not. Failing to log out doesn't fail the transaction.
+    #[serde(default)]
+    pub logout_url: Option<String>,
+    #[serde(default)]
+    pub headers: RequestHeaders,
+}
+
+fn default_timeout_secs() -> u64 {
+    60
+}
+
+/// URLs and form values can use `{name}` for a value extracted by an
+/// earlier step, and `{env:NAME}` for an environment variable, so secrets
+/// don't have to be in the config.
+#[derive(Debug, Clone, Deserialize)]
+pub enum Step {
+    Get(String),
+    Post {
+        url: String,
+        form: Vec<(String, String)>,
+    },
+    /// Saves the value of the hidden input `field` in the last page as
+    /// `{field}`.
+    ExtractHiddenField(String),
+    /// Saves the text between `before` and `after` in the last page as
+    /// `{name}`.
+    ExtractBetween {
+        name: String,
+        before: String,
+        after: String,
+    },
+    /// The last page has to contain this text.
+    Expect(String),
+    /// The last page has to have been served with this status code.
+    ExpectStatus(u16),
+    /// Runs the WASM checker plugin at `path` on the last page, see
+    /// [`crate::plugin`]. It fails when the plugin says the system doesn't
+    /// work, and what the plugin reports becomes the last page. `args` are
+    /// handed to the plugin.
+    Plugin {
+        path: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionOutcome {
+    Passed,
+    /// `step` counts from 1.
+    Failed {
+        step: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for TransactionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionOutcome::Passed => write!(f, "passed"),
+            TransactionOutcome::Failed { step, reason } => {
+                write!(f, "failed at step {}: {}", step, reason)
+            }
+        }
+    }
+}
+
+/// Reads the configured transactions. Having none configured is fine.
+pub fn get_transactions() -> Result<Vec<Transaction>, Box<dyn std::error::Error>> {
+    let transactions_file = match File::open(TRANSACTIONS_FILEPATH) {
+        Ok(file) => file,
+        Err(why) if why.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(why) => return Err(why.into()),
+    };
+    let mut buf_reader = std::io::BufReader::new(transactions_file);
+    let mut contents = String::new();
+    buf_reader.read_to_string(&mut contents)?;
+    Ok(ron::de::from_str(&contents)?)
+}
+
+/// Runs a transaction's steps in order, carrying cookies from one request
+/// to the next, and stops at the first step that fails.
+pub async fn run(transport: &dyn HttpTransport, transaction: &Transaction) -> TransactionOutcome {
+    let timeout = Duration::from_secs(transaction.timeout_secs);
+    match tokio::time::timeout(timeout, run_steps(transport, transaction)).await {
+        Ok(outcome) => outcome,
+        Err(_) => TransactionOutcome::Failed {
+            step: 0,
+            reason: format!("did not finish within {}s", transaction.timeout_secs),
+        },
+    }
+}
+
+/// Runs every transaction at the same time, each within its own timeout,
+/// so all of them take about as long as the slowest. The outcomes are in
+/// the same order as `transactions`.
+pub async fn run_all(
+    transport: &dyn HttpTransport,
+    transactions: &[Transaction],
+) -> Vec<TransactionOutcome> {
+    join_all(
+        transactions
+            .iter()
+            .map(|transaction| run(transport, transaction)),
+    )
+    .await
+}
+
+async fn run_steps(transport: &dyn HttpTransport, transaction: &Transaction) -> TransactionOutcome {
+    let mut state = State {
+        headers: transaction.headers.clone(),
+        user_agent: transaction.headers.next_user_agent(),
+        ..State::default()
+    };
+    for (index, step) in transaction.steps.iter().enumerate() {
+        tracing::debug!(
+            "Transaction {} step {}: {:?}",
+            transaction.name,
+            index + 1,
+            step
+        );
+        if let Err(why) = state.run_step(transport, step).await {
+            return TransactionOutcome::Failed {
+                step: index + 1,
+                reason: why.to_string(),
+            };
+        }
+    }
+    TransactionOutcome::Passed
+}
+
+#[derive(Default)]
+struct State {
+    headers: RequestHeaders,
+    user_agent: String,
+    variables: HashMap<String, String>,
+    cookies: Vec<String>,
+    status: Option<u16>,
+    body: String,
+}
+
+impl State {
+    async fn run_step(&mut self, transport: &dyn HttpTransport, step: &Step) -> Result<(), Error> {
+        match step {
+            Step::Get(url) => {
+                let request = self.request(Method::GET, url)?;
+                self.send(transport, request).await
+            }
+            Step::Post { url, form } => {
+                let mut request = self.request(Method::POST, url)?;
+                request.form = form
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), self.fill_in(value)?)))
+                    .collect::<Result<_, Error>>()?;
+                self.send(transport, request).await
+            }
+            Step::ExtractHiddenField(field) => {
+                let value = start_tags(&self.body)
+                    .into_iter()
+                    .find(|tag| {
+                        tag.name == "input"
+                            && tag.attribute("type") == Some("hidden")
+                            && tag.attribute("name") == Some(field.as_str())
+                    })
+                    .map(|tag| tag.attribute("value").unwrap_or_default().to_string())
+                    .ok_or_else(|| format!("no hidden field {:?}", field))?;
+                self.variables.insert(field.clone(), value);
+                Ok(())
+            }
+            Step::ExtractBetween {
+                name,
+                before,
+                after,
+            } => {
+                let start = self
+                    .body
+                    .find(before.as_str())
+                    .map(|start| start + before.len())
+                    .ok_or_else(|| format!("could not find {:?}", before))?;
+                let end = self.body[start..]
+                    .find(after.as_str())
+                    .ok_or_else(|| format!("could not find {:?} after {:?}", after, before))?;
+                let value = self.body[start..start + end].to_string();
+                self.variables.insert(name.clone(), value);
+                Ok(())
+            }
+            Step::Expect(text) => {
+                if self.body.contains(text.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("page does not contain {:?}", text).into())
+                }
+            }
+            Step::ExpectStatus(status) => match self.status {
+                Some(actual) if actual == *status => Ok(()),
+                actual => Err(format!("expected status {}, got {:?}", status, actual).into()),
+            },
+            Step::Plugin { path, args } => self.run_plugin(path, args).await,
+        }
+    }
+
+    #[cfg(feature = "plugins")]
+    async fn run_plugin(&mut self, path: &str, args: &[String]) -> Result<(), Error> {
+        let input = serde_json::json!({
+            "status": self.status,
+            "body": self.body,
+            "variables": self.variables,
+            "args": args
+                .iter()
+                .map(|arg| self.fill_in(arg))
+                .collect::<Result<Vec<_>, _>>()?,
+        });
+        let outcome =
+            crate::plugin::run(std::path::Path::new(&self.fill_in(path)?), &input).await?;
+        let passed = outcome.passed();
+        self.status = None;
+        self.body = outcome.report;
+        if passed {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} returned {}: {}",
+                path,
+                outcome.code,
+                self.body.lines().last().unwrap_or_default().trim()
+            )
+            .into())
+        }
+    }
+
+    #[cfg(not(feature = "plugins"))]
+    async fn run_plugin(&mut self, _path: &str, _args: &[String]) -> Result<(), Error> {
+        Err("The bot was built without the plugins feature".into())
+    }
+
+    fn request(&self, method: Method, url: &str) -> Result<HttpRequest, Error> {
+        let mut request = HttpRequest::new(method, &self.fill_in(url)?);
+        self.headers.apply(&self.user_agent, &mut request.headers)?;
+        if !self.cookies.is_empty() {
+            request
+                .headers
+                .insert(COOKIE, HeaderValue::from_str(&self.cookies.join("; "))?);
+        }
+        Ok(request)
+    }
+
+    async fn send(
+        &mut self,
+        transport: &dyn HttpTransport,
+        request: HttpRequest,
+    ) -> Result<(), Error> {
+        let response = transport.send(request).await?;
+        for cookie in response.headers.get_all(SET_COOKIE) {
+            // Only the name=value pair is sent back.
+            let pair = cookie.to_str()?.split(';').next().unwrap_or_default();
+            let name = pair.split('=').next().unwrap_or_default();
+            self.cookies
+                .retain(|cookie| cookie.split('=').next() != Some(name));
+            self.cookies.push(pair.trim().to_string());
+        }
+        self.status = Some(response.status.as_u16());
+        self.body = response.body;
+        Ok(())
+    }
+
+    fn fill_in(&self, template: &str) -> Result<String, Error> {
+        let mut filled_in = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed {{ in {:?}", template))?;
+            filled_in.push_str(&rest[..start]);
+            let name = &rest[start + 1..start + end];
+            let value = match name.strip_prefix("env:") {
+                Some(var) => env::var(var).map_err(|_| format!("{} is not set", var))?,
+                None => self
+                    .variables
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("nothing was extracted as {:?}", name))?,
+            };
+            filled_in.push_str(&value);
+            rest = &rest[start + end + 1..];
+        }
+        filled_in.push_str(rest);
+        Ok(filled_in)
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{
+    config::{IpFamily, Timeouts},
+    Error,
+};
+use async_trait::async_trait;
+use hyper::client::connect::HttpInfo;
+use reqwest::{
+    header::{HeaderMap, COOKIE, LOCATION},
+    redirect, Method, StatusCode, Url, Version,
+};
+use std::{
+    collections::HashSet,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time;
+
+/// A request made by the checker, independent of the HTTP library.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    /// Sent as an `application/x-www-form-urlencoded` body when not empty.
+    pub form: Vec<(String, String)>,
+}
+
+impl HttpRequest {
+    pub fn new(method: Method, url: &str) -> Self {
+        HttpRequest {
+            method,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            form: Vec::new(),
+        }
+    }
+}
+
+/// A fully read response.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+    /// Every URL the request was redirected to, in order. The response came
+    /// from the last one.
+    pub redirects: Vec<String>,
+    /// The HTTP version the response came over.
+    pub version: Version,
+    /// The connection the response came over, when the transport can tell.
+    pub connection: Option<ConnectionInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub remote_addr: SocketAddr,
+    pub local_addr: SocketAddr,
+    /// Whether an earlier response came over the same connection.
+    pub reused: bool,
+}
+
+/// Sends the checker's requests. Implemented over reqwest by
+/// [`ReqwestTransport`], and by canned responses in tests.
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error>;
+}
+
+/// Which of the configured [`Timeouts`] ran out, and while doing what.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutKind {
+    Connect,
+    /// The read timeout, while loading a page.
+    Read,
+    /// The read timeout, while waiting for the response to the login form,
+    /// after SAIS had already served the login page.
+    Login,
+    Check,
+}
+
+/// Returned by transports when a request runs out of time, so the checker
+/// can tell which timeout tripped.
+#[derive(Debug)]
+pub struct TimeoutError(pub TimeoutKind);
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            TimeoutKind::Connect => write!(f, "timed out connecting"),
+            TimeoutKind::Read => write!(f, "timed out waiting for a response"),
+            TimeoutKind::Login => write!(f, "timed out waiting for the login response"),
+            TimeoutKind::Check => write!(f, "timed out finishing the check"),
+        }
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+#[async_trait]
+impl<T: HttpTransport + ?Sized> HttpTransport for Box<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        (**self).send(request).await
+    }
+}
+
+#[async_trait]
+impl<T: HttpTransport + ?Sized> HttpTransport for Arc<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        (**self).send(request).await
+    }
+}
+
+/// Returned when a request is redirected more than `max_redirects` times.
+#[derive(Debug)]
+pub struct TooManyRedirects(pub Vec<String>);
+
+impl fmt::Display for TooManyRedirects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "too many redirects: {}", self.0.join(" -> "))
+    }
+}
+
+impl std::error::Error for TooManyRedirects {}
+
+pub struct ReqwestTransport {
+    http_client: reqwest::Client,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    max_redirects: usize,
+    // The local end of every connection a response came over. A connection
+    // is only ever reused for the same local address and port.
+    local_addrs: Mutex<HashSet<SocketAddr>>,
+}
+
+impl ReqwestTransport {
+    pub fn new(
+        timeouts: &Timeouts,
+        proxy: Option<&str>,
+        ip_family: Option<IpFamily>,
+        max_redirects: usize,
+    ) -> Result<Self, Error> {
+        // Redirects are followed by hand in `send`, so the chain can be
+        // reported.
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(timeouts.connect())
+            .redirect(redirect::Policy::none());
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        // Binding to an unspecified address of one family makes the
+        // connector skip the host's addresses of the other.
+        match ip_family {
+            Some(IpFamily::V4) => {
+                builder = builder.local_address(IpAddr::from(Ipv4Addr::UNSPECIFIED))
+            }
+            Some(IpFamily::V6) => {
+                builder = builder.local_address(IpAddr::from(Ipv6Addr::UNSPECIFIED))
+            }
+            None => {}
+        }
+
+        Ok(ReqwestTransport {
+            http_client: builder.build()?,
+            connect_timeout: timeouts.connect(),
+            read_timeout: timeouts.read(),
+            max_redirects,
+            local_addrs: Mutex::default(),
+        })
+    }
+
+    async fn send_once(&self, request: &HttpRequest) -> Result<reqwest::Response, Error> {
+        let mut builder = self
+            .http_client
+            .request(request.method.clone(), &request.url)
+            .headers(request.headers.clone());
+        if !request.form.is_empty() {
+            builder = builder.form(&request.form);
+        }
+
+        // reqwest has no read timeout, so it is enforced here. Connecting is
+        // bounded by the connect timeout, so running past both means the
+        // server connected but never answered.
+        time::timeout(self.connect_timeout + self.read_timeout, builder.send())
+            .await
+            .map_err(|_| TimeoutError(TimeoutKind::Read))?
+            .map_err(classify_error)
+    }
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        ReqwestTransport::new(&Timeouts::default(), None, None, DEFAULT_MAX_REDIRECTS).unwrap()
+    }
+}
+
+/// How many redirects are followed when nothing else is configured.
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, mut request: HttpRequest) -> Result<HttpResponse, Error> {
+        let mut redirects = vec![];
+        let mut response = loop {
+            let response = self.send_once(&request).await?;
+            let location = match response.headers().get(LOCATION) {
+                Some(location) if response.status().is_redirection() => location,
+                _ => break response,
+            };
+            if redirects.len() == self.max_redirects {
+                return Err(TooManyRedirects(redirects).into());
+            }
+
+            let next_url = Url::parse(&request.url)?.join(location.to_str()?)?;
+            // Like browsers, only 307 and 308 repeat a POST.
+            if !matches!(
+                response.status(),
+                StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+            ) {
+                request.method = Method::GET;
+                request.form.clear();
+            }
+            // Cookies are not sent on to other hosts.
+            if Url::parse(&request.url)?.host_str() != next_url.host_str() {
+                request.headers.remove(COOKIE);
+            }
+            request.url = next_url.to_string();
+            redirects.push(request.url.clone());
+        };
+
+        let mut body = Vec::new();
+        while let Some(chunk) = time::timeout(self.read_timeout, response.chunk())
+            .await
+            .map_err(|_| TimeoutError(TimeoutKind::Read))?
+            .map_err(classify_error)?
+        {
+            body.extend_from_slice(&chunk);
+        }
+
+        let connection = response.extensions().get::<HttpInfo>().map(|info| {
+            let mut local_addrs = self.local_addrs.lock().unwrap();
+            ConnectionInfo {
+                remote_addr: info.remote_addr(),
+                local_addr: info.local_addr(),
+                reused: !local_addrs.insert(info.local_addr()),
+            }
+        });
+        Ok(HttpResponse {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+            redirects,
+            version: response.version(),
+            connection,
+        })
+    }
+}
+
+fn classify_error(error: reqwest::Error) -> Error {
+    if error.is_connect() && error.is_timeout() {
+        TimeoutError(TimeoutKind::Connect).into()
+    } else {
+        error.into()
+    }
+}
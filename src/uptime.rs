@@ -0,0 +1,57 @@
+//! Uptime over the last day, hour by hour, for showing at a glance.
+
+use crate::{
+    history::CheckRecord,
+    routes::MAIN_ROUTE_NAME,
+    sla::{counts, failed_check},
+};
+use chrono::{DateTime, Duration, Utc};
+
+/// The main route's failed and counted checks in each of the last 24 hours,
+/// oldest first. The last hour ends now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HourlyUptime {
+    pub hours: [(u64, u64); 24],
+}
+
+impl HourlyUptime {
+    pub fn from_records(records: &[CheckRecord], now: DateTime<Utc>) -> HourlyUptime {
+        let mut hours = [(0, 0); 24];
+        for record in records {
+            if record.route != MAIN_ROUTE_NAME || !counts(&record.status) {
+                continue;
+            }
+            let age = now - record.checked_at;
+            if age < Duration::zero() || age >= Duration::hours(24) {
+                continue;
+            }
+            let (failed, total) = &mut hours[23 - age.num_hours() as usize];
+            *failed += failed_check(&record.status) as u64;
+            *total += 1;
+        }
+        HourlyUptime { hours }
+    }
+
+    /// The share of the day's checks that worked, or `None` if there were
+    /// none.
+    pub fn uptime_percent(&self) -> Option<f64> {
+        let (failed, total) = self.hours.iter().fold((0, 0), |(failed, total), hour| {
+            (failed + hour.0, total + hour.1)
+        });
+        (total > 0).then(|| 100.0 * (total - failed) as f64 / total as f64)
+    }
+
+    /// A square for each hour: green if every check worked, yellow if some
+    /// failed, red if most did, and white without checks.
+    pub fn to_bar(&self) -> String {
+        self.hours
+            .iter()
+            .map(|(failed, total)| match (failed, total) {
+                (_, 0) => '⬜',
+                (0, _) => '🟩',
+                (failed, total) if failed * 2 > *total => '🟥',
+                _ => '🟨',
+            })
+            .collect()
+    }
+}
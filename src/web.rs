@@ -0,0 +1,233 @@
+//! An HTTP server for things outside Discord, like metrics scrapers and
+//! the status page.
+
+use crate::{
+    api_keys::{ApiKey, ApiKeyCheck, ApiKeys},
+    dashboard::{self, Dashboard, DashboardConfig},
+    graphql::{self, GraphqlData, StatusSchema},
+    health::Readiness,
+    history::HistoryStore,
+    metrics::CommandMetrics,
+    status::ServiceStatus,
+    timing::TimingHistograms,
+    Error,
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{
+        header::{AUTHORIZATION, RETRY_AFTER},
+        HeaderMap, Request, StatusCode,
+    },
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+    time::Instant,
+};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebConfig {
+    /// Where to listen, e.g. "127.0.0.1:8080".
+    pub address: SocketAddr,
+    /// The admin dashboard is only served when this is set.
+    #[serde(default)]
+    pub dashboard: Option<DashboardConfig>,
+    /// When any are set, /graphql needs one of them, sent as
+    /// `Authorization: Bearer <key>` or `X-API-Key: <key>`.
+    #[serde(default)]
+    pub api_keys: Vec<ApiKey>,
+}
+
+/// What the handlers can read from the bot.
+#[derive(Clone)]
+pub struct WebState {
+    pub command_metrics: Arc<CommandMetrics>,
+    /// The phases of the monitor's checks, when `timing_probe` is set.
+    pub check_timings: Arc<TimingHistograms>,
+    pub readiness: Arc<Readiness>,
+    /// Queried through /graphql, when turned on.
+    pub history: Option<Arc<dyn HistoryStore>>,
+    /// The monitor's confirmed status, `None` until its first check.
+    pub statuses: watch::Receiver<Option<ServiceStatus>>,
+    pub dashboard: Option<Arc<Dashboard>>,
+    /// /graphql is open to anyone when this isn't set.
+    pub api_keys: Option<Arc<ApiKeys>>,
+}
+
+/// Sent to status page clients whenever the status changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEvent {
+    /// See [`ServiceStatus::kind`].
+    pub kind: String,
+    /// See [`ServiceStatus::reason`].
+    pub reason: Option<String>,
+    /// Like "down (503 Service Unavailable)".
+    pub description: String,
+}
+
+impl StatusEvent {
+    pub fn new(status: ServiceStatus) -> StatusEvent {
+        StatusEvent {
+            kind: status.kind().to_string(),
+            reason: status.reason(),
+            description: status.to_string(),
+        }
+    }
+}
+
+pub fn router(state: WebState) -> Router {
+    let mut router = Router::new()
+        .route("/", get(status_page))
+        .route("/ws", get(status_updates))
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(liveness))
+        .route("/readyz", get(readiness))
+        .with_state(state.clone());
+    let schema = graphql::schema(GraphqlData {
+        history: state.history.clone(),
+        statuses: state.statuses.clone(),
+    });
+    let mut api = Router::new()
+        .route("/graphql", post(graphql_query))
+        .with_state(schema);
+    if let Some(api_keys) = state.api_keys.clone() {
+        api = api.route_layer(middleware::from_fn_with_state(api_keys, require_api_key));
+    }
+    router = router.merge(api);
+    if let Some(dashboard) = state.dashboard {
+        router = router.merge(dashboard::router().with_state(dashboard));
+    }
+    router
+}
+
+/// Serves until the bot exits.
+pub async fn serve(listener: TcpListener, state: WebState) -> Result<(), Error> {
+    listener.set_nonblocking(true)?;
+    axum::Server::from_tcp(listener)?
+        .serve(router(state).into_make_service())
+        .await?;
+    Ok(())
+}
+
+async fn metrics(State(state): State<WebState>) -> String {
+    state.command_metrics.to_prometheus() + state.check_timings.to_prometheus().as_str()
+}
+
+// The process is up and serving requests, which is all liveness means.
+async fn liveness() -> &'static str {
+    "ok"
+}
+
+async fn readiness(State(state): State<WebState>) -> Response {
+    match state.readiness.not_ready_because(chrono::Utc::now()) {
+        None => "ready".into_response(),
+        Some(reason) => (StatusCode::SERVICE_UNAVAILABLE, reason).into_response(),
+    }
+}
+
+async fn require_api_key<B>(
+    State(api_keys): State<Arc<ApiKeys>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match api_keys.check(api_key(request.headers()), Instant::now()) {
+        ApiKeyCheck::Allowed => next.run(request).await,
+        ApiKeyCheck::Unauthorized => {
+            (StatusCode::UNAUTHORIZED, "Missing or unknown API key").into_response()
+        }
+        ApiKeyCheck::RateLimited { retry_after } => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, (retry_after.as_secs() + 1).to_string())],
+            "Too many requests with this API key",
+        )
+            .into_response(),
+    }
+}
+
+fn api_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or_else(|| headers.get("x-api-key")?.to_str().ok())
+}
+
+async fn graphql_query(
+    State(schema): State<StatusSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn status_page(State(state): State<WebState>) -> Html<String> {
+    let status = match *state.statuses.borrow() {
+        Some(status) => status.to_string(),
+        None => "not checked yet".to_string(),
+    };
+    Html(STATUS_PAGE.replace("{status}", &status))
+}
+
+// Kept up to date by /ws, reconnecting whenever the connection drops.
+const STATUS_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Is UP SAIS down?</title>
+<style>
+body { font-family: sans-serif; text-align: center; margin-top: 20vh; }
+</style>
+</head>
+<body>
+<h1>UP SAIS is <span id="status">{status}</span></h1>
+<p id="updated"></p>
+<script>
+function connect() {
+    const scheme = location.protocol === "https:" ? "wss:" : "ws:";
+    const socket = new WebSocket(scheme + "//" + location.host + "/ws");
+    socket.onmessage = (message) => {
+        const event = JSON.parse(message.data);
+        document.getElementById("status").textContent = event.description;
+        document.getElementById("updated").textContent =
+            "Updated " + new Date().toLocaleTimeString();
+    };
+    socket.onclose = () => setTimeout(connect, 5000);
+}
+connect();
+</script>
+</body>
+</html>
+"#;
+
+async fn status_updates(State(state): State<WebState>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(|socket| push_statuses(socket, state.statuses))
+}
+
+// Sends the current status, then every change, until the client leaves or
+// the monitor stops.
+async fn push_statuses(
+    mut socket: WebSocket,
+    mut statuses: watch::Receiver<Option<ServiceStatus>>,
+) {
+    loop {
+        let status = *statuses.borrow_and_update();
+        if let Some(status) = status {
+            let event = serde_json::to_string(&StatusEvent::new(status))
+                .expect("Status events can always be serialized");
+            if socket.send(Message::Text(event)).await.is_err() {
+                return;
+            }
+        }
+        if statuses.changed().await.is_err() {
+            return;
+        }
+    }
+}
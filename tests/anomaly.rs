@@ -0,0 +1,82 @@
+use is_sais_down_bot::{
+    anomaly::{AnomalyConfig, LatencyAnomaly, LatencyBaseline},
+    status::{DownReason, ServiceStatus},
+};
+use std::time::Duration;
+
+fn config() -> AnomalyConfig {
+    ron::de::from_str("(channel_id: 1, min_checks: 10, min_deviation_ms: 100)").unwrap()
+}
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+// Ten steady checks of around 400 ms.
+fn steady_baseline() -> LatencyBaseline {
+    let mut baseline = LatencyBaseline::new(config());
+    for millis in [380, 420, 400, 390, 410, 400, 385, 415, 400, 400] {
+        assert_eq!(baseline.observe(&ServiceStatus::Up, ms(millis)), None);
+    }
+    baseline
+}
+
+#[test]
+fn slowdowns_are_posted_once_until_they_end() {
+    let mut baseline = steady_baseline();
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(2000)), None);
+    match baseline.observe(&ServiceStatus::Up, ms(2500)) {
+        Some(LatencyAnomaly::Slow {
+            latency_ms,
+            mean_ms,
+            ..
+        }) => {
+            assert_eq!(latency_ms, 2500);
+            assert!(mean_ms > 400.0);
+        }
+        anomaly => panic!("expected a slowdown, got {:?}", anomaly),
+    }
+    assert_eq!(
+        baseline.observe(&ServiceStatus::Degraded(ms(9000)), ms(9000)),
+        None
+    );
+    assert!(matches!(
+        baseline.observe(&ServiceStatus::Up, ms(420)),
+        Some(LatencyAnomaly::Recovered {
+            latency_ms: 420,
+            ..
+        })
+    ));
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(400)), None);
+}
+
+#[test]
+fn small_or_lone_slow_checks_are_not_posted() {
+    let mut baseline = steady_baseline();
+    // Several deviations over, but under min_deviation_ms.
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(480)), None);
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(480)), None);
+    // One slow check alone.
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(3000)), None);
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(400)), None);
+}
+
+#[test]
+fn failed_checks_are_left_out() {
+    let mut baseline = steady_baseline();
+    let (mean_ms, _) = baseline.mean_and_std_dev().unwrap();
+    for _ in 0..5 {
+        assert_eq!(
+            baseline.observe(&ServiceStatus::Down(DownReason::NoResponse), ms(30000)),
+            None
+        );
+    }
+    assert_eq!(baseline.mean_and_std_dev().unwrap().0, mean_ms);
+
+    let mut baseline = LatencyBaseline::new(config());
+    assert_eq!(baseline.mean_and_std_dev(), None);
+    // Not enough checks yet to tell what's usual.
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(400)), None);
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(5000)), None);
+    assert_eq!(baseline.observe(&ServiceStatus::Up, ms(5000)), None);
+}
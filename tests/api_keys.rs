@@ -0,0 +1,44 @@
+#![cfg(feature = "web")]
+
+use is_sais_down_bot::api_keys::{ApiKey, ApiKeyCheck, ApiKeys};
+use std::time::{Duration, Instant};
+
+fn api_keys(requests_per_minute: u32) -> ApiKeys {
+    ApiKeys::new(vec![ApiKey {
+        name: "student council".to_string(),
+        key: "s3cret".to_string(),
+        requests_per_minute,
+    }])
+}
+
+#[test]
+fn unknown_keys_are_unauthorized() {
+    let api_keys = api_keys(60);
+    let now = Instant::now();
+
+    assert_eq!(api_keys.check(None, now), ApiKeyCheck::Unauthorized);
+    assert_eq!(
+        api_keys.check(Some("guess"), now),
+        ApiKeyCheck::Unauthorized
+    );
+    assert_eq!(api_keys.check(Some("s3cret"), now), ApiKeyCheck::Allowed);
+}
+
+#[test]
+fn keys_are_limited_until_their_allowance_refills() {
+    let api_keys = api_keys(2);
+    let now = Instant::now();
+
+    assert_eq!(api_keys.check(Some("s3cret"), now), ApiKeyCheck::Allowed);
+    assert_eq!(api_keys.check(Some("s3cret"), now), ApiKeyCheck::Allowed);
+    assert_eq!(
+        api_keys.check(Some("s3cret"), now),
+        ApiKeyCheck::RateLimited {
+            retry_after: Duration::from_secs(30)
+        }
+    );
+    assert_eq!(
+        api_keys.check(Some("s3cret"), now + Duration::from_secs(30)),
+        ApiKeyCheck::Allowed
+    );
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "sqlite")]
+
+use chrono::{TimeZone, Utc};
+use is_sais_down_bot::{
+    backup::{Backup, DATA_FILEPATHS},
+    guilds::GUILD_SETTINGS_FILEPATH,
+    history::{CheckRecord, HistoryStore, HourlyRollup},
+    preferences::PREFERENCES_FILEPATH,
+    sqlite::SqliteHistory,
+};
+use std::fs;
+
+fn record() -> CheckRecord {
+    CheckRecord {
+        checked_at: Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap(),
+        route: "here".to_string(),
+        status: "up".to_string(),
+        reason: None,
+        latency_ms: 100,
+    }
+}
+
+fn rollup_record() -> CheckRecord {
+    CheckRecord {
+        checked_at: Utc.with_ymd_and_hms(2026, 7, 1, 8, 30, 0).unwrap(),
+        route: "here".to_string(),
+        status: "down".to_string(),
+        reason: Some("read_timeout".to_string()),
+        latency_ms: 30000,
+    }
+}
+
+#[tokio::test]
+async fn backup_restores_config_and_history_on_another_host() {
+    let old_config = tempfile::tempdir().unwrap();
+    fs::write(old_config.path().join("bot.ron"), "(sentry_dsn: None)").unwrap();
+    let old_data = tempfile::tempdir().unwrap();
+    fs::create_dir(old_data.path().join("data")).unwrap();
+    fs::write(old_data.path().join(GUILD_SETTINGS_FILEPATH), "{}").unwrap();
+    fs::write(old_data.path().join(PREFERENCES_FILEPATH), "{1: ()}").unwrap();
+    let old_history = SqliteHistory::open_in_memory().unwrap();
+    old_history
+        .record(&[rollup_record(), record()])
+        .await
+        .unwrap();
+    // The older check is kept as a rollup.
+    old_history
+        .compact(record().checked_at, Utc.timestamp_opt(0, 0).unwrap())
+        .await
+        .unwrap();
+
+    let archive = Backup::collect(old_config.path(), old_data.path(), Some(&old_history))
+        .await
+        .unwrap()
+        .encrypt("correct horse")
+        .unwrap();
+
+    let new_config = tempfile::tempdir().unwrap();
+    let new_data = tempfile::tempdir().unwrap();
+    let new_history = SqliteHistory::open_in_memory().unwrap();
+    let backup = Backup::decrypt(&archive, "correct horse").unwrap();
+    backup.restore_config(new_config.path()).unwrap();
+    backup.restore_data(new_data.path()).unwrap();
+    assert!(backup.restore_history(&new_history).await.unwrap());
+
+    assert_eq!(
+        fs::read_to_string(new_config.path().join("bot.ron")).unwrap(),
+        "(sentry_dsn: None)"
+    );
+    assert_eq!(
+        fs::read_to_string(new_data.path().join(GUILD_SETTINGS_FILEPATH)).unwrap(),
+        "{}"
+    );
+    assert_eq!(
+        fs::read_to_string(new_data.path().join(PREFERENCES_FILEPATH)).unwrap(),
+        "{1: ()}"
+    );
+    // Files that weren't there aren't made up.
+    assert_eq!(backup.data_files.len(), 2);
+    assert!(DATA_FILEPATHS
+        .iter()
+        .filter(|path| ![GUILD_SETTINGS_FILEPATH, PREFERENCES_FILEPATH].contains(path))
+        .all(|path| !new_data.path().join(path).exists()));
+    assert_eq!(
+        new_history
+            .since(Utc.timestamp_opt(0, 0).unwrap())
+            .await
+            .unwrap(),
+        vec![record()]
+    );
+    assert_eq!(
+        new_history
+            .rollups_since(Utc.timestamp_opt(0, 0).unwrap())
+            .await
+            .unwrap(),
+        vec![HourlyRollup {
+            hour: Utc.with_ymd_and_hms(2026, 7, 1, 8, 0, 0).unwrap(),
+            route: "here".to_string(),
+            status: "down".to_string(),
+            checks: 1,
+            total_latency_ms: 30000,
+        }]
+    );
+    // Restoring again doesn't duplicate the history.
+    assert!(!backup.restore_history(&new_history).await.unwrap());
+}
+
+#[tokio::test]
+async fn backup_is_encrypted() {
+    let config = tempfile::tempdir().unwrap();
+    fs::write(config.path().join("sais.ron"), "secret-looking config").unwrap();
+    let archive = Backup::collect(config.path(), config.path(), None)
+        .await
+        .unwrap()
+        .encrypt("correct horse")
+        .unwrap();
+
+    assert!(!String::from_utf8_lossy(&archive).contains("secret-looking"));
+    assert!(Backup::decrypt(&archive, "wrong horse").is_err());
+    let mut damaged = archive.clone();
+    *damaged.last_mut().unwrap() ^= 1;
+    assert!(Backup::decrypt(&damaged, "correct horse").is_err());
+}
+
+#[tokio::test]
+async fn data_files_are_only_restored_where_they_belong() {
+    let config = tempfile::tempdir().unwrap();
+    let mut backup = Backup::collect(config.path(), config.path(), None)
+        .await
+        .unwrap();
+    backup.data_files = vec![("../escaped.ron".to_string(), "()".to_string())];
+
+    let data = tempfile::tempdir().unwrap();
+    assert!(backup.restore_data(data.path().join("root")).is_err());
+    assert!(!data.path().join("escaped.ron").exists());
+}
@@ -0,0 +1,209 @@
+use chrono::{TimeZone, Utc};
+use is_sais_down_bot::{
+    broker::{self, BrokerConfig, StatusChange},
+    status::{DownReason, ServiceStatus},
+};
+use reqwest::StatusCode;
+use std::time::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::{mpsc, watch},
+};
+
+fn change() -> StatusChange {
+    StatusChange::new(
+        ServiceStatus::Down(DownReason::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)),
+        Some(ServiceStatus::Up),
+        Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap(),
+    )
+}
+
+#[test]
+fn changes_say_what_the_status_was_and_is() {
+    assert_eq!(
+        serde_json::to_string(&change()).unwrap(),
+        r#"{"kind":"down","reason":"http_503","description":"down (503 Service Unavailable)","previous_kind":"up","changed_at":"2026-08-01T08:00:00Z"}"#
+    );
+}
+
+#[test]
+fn mqtt_packets_follow_3_1_1() {
+    assert_eq!(
+        broker::mqtt_connect_packet("bot", None, None),
+        vec![0x10, 15, 0, 4, b'M', b'Q', b'T', b'T', 4, 0x02, 0, 60, 0, 3, b'b', b'o', b't']
+    );
+    let with_login = broker::mqtt_connect_packet("bot", Some("u"), Some("p"));
+    assert_eq!(with_login[9], 0xc2);
+    assert_eq!(&with_login[17..], &[0, 1, b'u', 0, 1, b'p']);
+
+    assert_eq!(
+        broker::mqtt_publish_packet("a/b", b"hi", true),
+        vec![0x31, 7, 0, 3, b'a', b'/', b'b', b'h', b'i']
+    );
+    // Lengths over 127 take more than one byte.
+    let long = broker::mqtt_publish_packet("t", &[b'x'; 200], false);
+    assert_eq!(&long[..3], &[0x30, (203 % 128) | 0x80, 1]);
+    assert_eq!(long.len(), 3 + 203);
+}
+
+#[tokio::test]
+async fn changes_are_published_to_nats() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        stream
+            .get_mut()
+            .write_all(b"INFO {\"server_id\":\"test\"}\r\n")
+            .await
+            .unwrap();
+        let mut lines = vec![];
+        for _ in 0..4 {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            lines.push(line.trim_end().to_string());
+        }
+        stream.get_mut().write_all(b"PONG\r\n").await.unwrap();
+        lines
+    });
+    let config = BrokerConfig::Nats {
+        address,
+        subject: "sais.status".to_string(),
+    };
+
+    broker::publish(&config, &change()).await.unwrap();
+
+    let lines = server.await.unwrap();
+    assert!(lines[0].starts_with("CONNECT {"));
+    let payload = serde_json::to_string(&change()).unwrap();
+    assert_eq!(lines[1], format!("PUB sais.status {}", payload.len()));
+    assert_eq!(lines[2], payload);
+    assert_eq!(lines[3], "PING");
+}
+
+#[tokio::test]
+async fn nats_errors_fail_the_publish() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream.write_all(b"INFO {}\r\n").await.unwrap();
+        stream
+            .write_all(b"-ERR 'Authorization Violation'\r\n")
+            .await
+            .unwrap();
+        // Keeps the connection open until the client is done.
+        let _ = stream.read(&mut [0; 1024]).await;
+    });
+    let config = BrokerConfig::Nats {
+        address,
+        subject: "sais.status".to_string(),
+    };
+
+    let why = broker::publish(&config, &change()).await.unwrap_err();
+    assert!(why.to_string().contains("Authorization Violation"));
+}
+
+// Accepts MQTT connections, answering CONNACK with `code` and sending on
+// every PUBLISH packet it gets.
+async fn fake_mqtt(code: u8) -> (String, mpsc::UnboundedReceiver<Vec<u8>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let (published, received) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut bytes = vec![];
+            let mut buffer = [0; 1024];
+            let mut acked = false;
+            loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                bytes.extend(&buffer[..read]);
+                if !acked && bytes.first() == Some(&0x10) {
+                    acked = true;
+                    stream.write_all(&[0x20, 2, 0, code]).await.unwrap();
+                }
+            }
+            // Skip the CONNECT, which is short enough for a one byte length.
+            let publish = bytes.split_off(2 + bytes[1] as usize);
+            if !publish.is_empty() {
+                published.send(publish).unwrap();
+            }
+        }
+    });
+    (address, received)
+}
+
+#[tokio::test]
+async fn changes_are_published_to_mqtt() {
+    let (address, mut received) = fake_mqtt(0).await;
+    let config = BrokerConfig::Mqtt {
+        address,
+        topic: "sais/status".to_string(),
+        client_id: "is-sais-down-bot".to_string(),
+        username: None,
+        retain: true,
+    };
+
+    broker::publish(&config, &change()).await.unwrap();
+
+    let payload = serde_json::to_vec(&change()).unwrap();
+    let mut expected = broker::mqtt_publish_packet("sais/status", &payload, true);
+    // Then DISCONNECT.
+    expected.extend([0xe0, 0]);
+    assert_eq!(received.recv().await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn refused_mqtt_connections_fail_the_publish() {
+    let (address, _received) = fake_mqtt(5).await;
+    let config = BrokerConfig::Mqtt {
+        address,
+        topic: "sais/status".to_string(),
+        client_id: "is-sais-down-bot".to_string(),
+        username: None,
+        retain: false,
+    };
+
+    let why = broker::publish(&config, &change()).await.unwrap_err();
+    assert!(why.to_string().contains("refused"));
+}
+
+#[tokio::test]
+async fn only_changes_of_kind_are_published() {
+    let (address, mut received) = fake_mqtt(0).await;
+    let config = BrokerConfig::Mqtt {
+        address,
+        topic: "sais/status".to_string(),
+        client_id: "is-sais-down-bot".to_string(),
+        username: None,
+        retain: false,
+    };
+    let (statuses, receiver) = watch::channel(None);
+    tokio::spawn(broker::publish_changes(config, receiver));
+    let kind_published = |packet: Vec<u8>| {
+        // The JSON payload sits between the PUBLISH header and DISCONNECT.
+        let start = packet.iter().position(|&b| b == b'{').unwrap();
+        let end = packet.iter().rposition(|&b| b == b'}').unwrap();
+        serde_json::from_slice::<StatusChange>(&packet[start..=end])
+            .unwrap()
+            .kind
+    };
+
+    statuses.send(Some(ServiceStatus::Up)).unwrap();
+    assert_eq!(kind_published(received.recv().await.unwrap()), "up");
+    statuses
+        .send(Some(ServiceStatus::Degraded(Duration::from_secs(12))))
+        .unwrap();
+    assert_eq!(kind_published(received.recv().await.unwrap()), "degraded");
+    statuses
+        .send(Some(ServiceStatus::Degraded(Duration::from_secs(15))))
+        .unwrap();
+    statuses.send(Some(ServiceStatus::Up)).unwrap();
+    assert_eq!(kind_published(received.recv().await.unwrap()), "up");
+}
@@ -0,0 +1,125 @@
+use chrono::NaiveDate;
+use is_sais_down_bot::{
+    calendar::{CalendarConfig, Profile},
+    monitor::MonitorConfig,
+};
+
+fn base() -> MonitorConfig {
+    MonitorConfig {
+        interval_secs: 300,
+        confirm_after: 3,
+        flap_window: 12,
+        flap_threshold: 4,
+    }
+}
+
+fn date(date: &str) -> NaiveDate {
+    date.parse().unwrap()
+}
+
+fn calendar() -> CalendarConfig {
+    ron::de::from_str(
+        r#"(
+            periods: [
+                (name: "enrollment", start: "2026-08-10", end: "2026-08-21"),
+                (name: "semestral break", start: "2026-12-19", end: "2027-01-03", profile: Relaxed),
+            ],
+            relaxed: (confirm_after: Some(4)),
+            aggressive: (interval_secs: Some(60), confirm_after: Some(2)),
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn periods_choose_the_profile() {
+    let calendar = calendar();
+    assert_eq!(calendar.profile_on(date("2026-08-09")), Profile::Relaxed);
+    assert_eq!(calendar.profile_on(date("2026-08-10")), Profile::Aggressive);
+    assert_eq!(calendar.profile_on(date("2026-08-21")), Profile::Aggressive);
+    assert_eq!(calendar.profile_on(date("2026-08-22")), Profile::Relaxed);
+    assert_eq!(calendar.profile_on(date("2027-01-01")), Profile::Relaxed);
+
+    assert_eq!(
+        calendar.monitor_config(&base(), date("2026-08-15")),
+        MonitorConfig {
+            interval_secs: 60,
+            confirm_after: 2,
+            ..base()
+        }
+    );
+    assert_eq!(
+        calendar.monitor_config(&base(), date("2026-09-01")),
+        MonitorConfig {
+            confirm_after: 4,
+            ..base()
+        }
+    );
+}
+
+#[test]
+fn profiles_cant_check_less_often_than_bot_ron() {
+    let lazy = CalendarConfig {
+        relaxed: ron::de::from_str("(interval_secs: Some(3600))").unwrap(),
+        ..calendar()
+    };
+    assert_eq!(
+        lazy.monitor_config(&base(), date("2026-09-01"))
+            .interval_secs,
+        300
+    );
+}
+
+#[test]
+fn no_calendar_leaves_the_config_alone() {
+    let empty = CalendarConfig::default();
+    assert_eq!(empty.monitor_config(&base(), date("2026-08-15")), base());
+    assert_eq!(empty.describe(date("2026-08-15")), "Monitoring is relaxed.");
+    assert_eq!(
+        calendar().describe(date("2026-08-01")),
+        "Monitoring is relaxed until enrollment starts on Aug 10."
+    );
+    assert_eq!(
+        calendar().describe(date("2026-08-12")),
+        "Monitoring is aggressive for enrollment until Aug 21."
+    );
+}
+
+#[test]
+fn holidays_and_breaks_are_known() {
+    let calendar = CalendarConfig {
+        holidays: ron::de::from_str(
+            r#"[
+                (name: "the semestral break", start: "2026-12-19", end: Some("2027-01-03")),
+                (name: "Holy Week", start: "2027-03-25"),
+            ]"#,
+        )
+        .unwrap(),
+        ..calendar()
+    };
+    assert_eq!(
+        calendar.holiday_on(date("2026-06-12")),
+        Some("Independence Day")
+    );
+    assert_eq!(
+        calendar.holiday_on(date("2026-12-25")),
+        Some("the semestral break")
+    );
+    assert_eq!(
+        calendar.holiday_on(date("2027-01-03")),
+        Some("the semestral break")
+    );
+    assert_eq!(calendar.holiday_on(date("2027-03-25")), Some("Holy Week"));
+    assert_eq!(calendar.holiday_on(date("2027-03-26")), None);
+    assert_eq!(calendar.holiday_on(date("2026-08-12")), None);
+
+    let calendar = CalendarConfig {
+        ph_holidays: false,
+        ..calendar
+    };
+    assert_eq!(calendar.holiday_on(date("2026-06-12")), None);
+    assert_eq!(
+        calendar.holiday_on(date("2026-12-25")),
+        Some("the semestral break")
+    );
+}
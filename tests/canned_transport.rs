@@ -0,0 +1,538 @@
+use async_trait::async_trait;
+use is_sais_down_bot::{
+    config::{
+        DeepCheck, ErrorPage, LoginDetails, RequestHeaders, SaisConfig, SessionConfig, Timeouts,
+    },
+    sais::{SaisClient, SNIPPET_LIMIT},
+    status::{DownReason, ServiceStatus},
+    transport::{HttpRequest, HttpResponse, HttpTransport},
+    Error,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE, COOKIE, SET_COOKIE, USER_AGENT},
+    Method, StatusCode, Version,
+};
+use std::sync::{Arc, Mutex};
+
+const LOGIN_URL: &str = "https://sais.example/psp/ps/?cmd=login";
+const LOGIN_SUCCESS_STRING: &str = "<title>Employee-facing registry content</title>";
+
+/// Answers each request with the next canned response, and keeps every
+/// request it was sent.
+#[derive(Clone, Default)]
+struct CannedTransport {
+    responses: Arc<Mutex<Vec<Result<HttpResponse, String>>>>,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl CannedTransport {
+    fn new(mut responses: Vec<Result<HttpResponse, String>>) -> Self {
+        responses.reverse();
+        CannedTransport {
+            responses: Arc::new(Mutex::new(responses)),
+            requests: Arc::default(),
+        }
+    }
+
+    fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for CannedTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.requests.lock().unwrap().push(request);
+        self.responses
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("No canned response left")
+            .map_err(Into::into)
+    }
+}
+
+fn response(status: u16, set_cookies: &[&str], body: &str) -> Result<HttpResponse, String> {
+    let mut headers = HeaderMap::new();
+    for cookie in set_cookies {
+        headers.append(SET_COOKIE, HeaderValue::from_str(cookie).unwrap());
+    }
+    Ok(HttpResponse {
+        status: StatusCode::from_u16(status).unwrap(),
+        headers,
+        body: body.to_string(),
+        redirects: vec![],
+        version: Version::HTTP_11,
+        connection: None,
+    })
+}
+
+fn sais_client(transport: &CannedTransport) -> SaisClient {
+    SaisClient::with_transport(
+        SaisConfig {
+            login_url: LOGIN_URL.to_string(),
+            login_success_string: LOGIN_SUCCESS_STRING.to_string(),
+            maintenance_strings: vec![],
+            max_redirects: 10,
+            trusted_redirect_hosts: vec![],
+            timeouts: Timeouts::default(),
+            degraded_after_secs: None,
+            proxy: None,
+            ip_family: None,
+            dual_stack: false,
+            routes: vec![],
+            deep_checks: vec![],
+            tcp_probe: false,
+            timing_probe: false,
+            error_pages: vec![],
+            session: None,
+            logout_url: None,
+            headers: RequestHeaders::default(),
+        },
+        LoginDetails {
+            timezoneOffset: -480,
+            userid: "juan@up.edu.ph".to_string(),
+            pwd: "hunter2".to_string(),
+            request_id: 42,
+        },
+        transport.clone(),
+    )
+}
+
+#[tokio::test]
+async fn success_string_means_login_ok() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+    ]);
+
+    let result = sais_client(&transport).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Up);
+}
+
+#[tokio::test]
+async fn missing_success_string_means_login_failed() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &[], "<html><body>Something unexpected</body></html>"),
+    ]);
+
+    let result = sais_client(&transport).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Down(DownReason::LoginFailed));
+}
+
+#[tokio::test]
+async fn transport_error_means_response_failed() {
+    let transport = CannedTransport::new(vec![Err("connection refused".to_string())]);
+
+    let result = sais_client(&transport).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Down(DownReason::NoResponse));
+}
+
+#[tokio::test]
+async fn error_status_skips_login() {
+    let transport = CannedTransport::new(vec![response(503, &[], "Service Unavailable")]);
+
+    let result = sais_client(&transport).check().await.unwrap();
+    assert_eq!(
+        result,
+        ServiceStatus::Down(DownReason::HttpStatus(StatusCode::SERVICE_UNAVAILABLE))
+    );
+    assert_eq!(transport.requests().len(), 1);
+}
+
+fn error_pages() -> Vec<ErrorPage> {
+    vec![
+        ErrorPage {
+            name: "F5 firewall block page".to_string(),
+            matches: vec![
+                "The requested URL was rejected".to_string(),
+                "support ID".to_string(),
+            ],
+        },
+        ErrorPage {
+            name: "PeopleSoft application error".to_string(),
+            matches: vec![
+                "peoplesoft".to_string(),
+                "an error has occurred".to_string(),
+            ],
+        },
+    ]
+}
+
+#[tokio::test]
+async fn known_error_pages_are_named() {
+    let transport = CannedTransport::new(vec![
+        response(
+            403,
+            &[],
+            "The requested URL was rejected. Your support ID is: 1234",
+        ),
+        response(200, &[], ""),
+        response(200, &[], "<h1>PeopleSoft</h1><p>An error has occurred.</p>"),
+        response(500, &[], "The requested URL was rejected."),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.error_pages = error_pages();
+
+    sais_client.check().await.unwrap();
+    assert_eq!(sais_client.error_page(), Some("F5 firewall block page"));
+    assert_eq!(
+        sais_client.check().await.unwrap(),
+        ServiceStatus::Down(DownReason::LoginFailed)
+    );
+    assert_eq!(
+        sais_client.error_page(),
+        Some("PeopleSoft application error")
+    );
+    // Only part of the block page.
+    sais_client.check().await.unwrap();
+    assert_eq!(sais_client.error_page(), None);
+}
+
+#[tokio::test]
+async fn last_response_is_kept_redacted_and_truncated() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(
+            200,
+            &[],
+            &format!("Welcome, juan@up.edu.ph{}", "x".repeat(SNIPPET_LIMIT)),
+        ),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    assert!(sais_client.last_response().is_none());
+
+    sais_client.check().await.unwrap();
+
+    let snippet = sais_client.last_response().unwrap();
+    assert_eq!(snippet.method, Method::POST);
+    assert_eq!(snippet.status, StatusCode::OK);
+    assert!(snippet.body.starts_with("Welcome, [REDACTED]x"));
+    assert!(snippet.body.ends_with("[truncated]"));
+    assert!(snippet.body.len() < SNIPPET_LIMIT + 20);
+}
+
+#[tokio::test]
+async fn login_is_posted_with_credentials_and_cookies() {
+    let transport = CannedTransport::new(vec![
+        response(200, &["PS_TOKEN=abc123", "ExpirePage=1"], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+    ]);
+
+    sais_client(&transport).check().await.unwrap();
+
+    let requests = transport.requests();
+    assert_eq!(requests[0].method, Method::GET);
+    let login = &requests[1];
+    assert_eq!(login.method, Method::POST);
+    assert_eq!(login.url, LOGIN_URL);
+    assert!(login
+        .form
+        .contains(&("userid".to_string(), "juan@up.edu.ph".to_string())));
+    let cookie = login.headers.get(COOKIE).unwrap().to_str().unwrap();
+    assert!(cookie.contains("PS_TOKEN=abc123"));
+    assert!(cookie.contains("ExpirePage=1"));
+}
+
+#[tokio::test]
+async fn login_posts_hidden_fields_to_the_form_action() {
+    let login_page = r#"<form action="/psp/ps/EMPLOYEE/?cmd=login&amp;languageCd=ENG" method="post">
+        <input type="hidden" name="ICSID" value="s3ss10n">
+        <input type="hidden" name="userid" value="">
+        <input type="text" name="userid">
+        <input type="password" name="pwd">
+    </form>"#;
+    let transport = CannedTransport::new(vec![
+        response(200, &[], login_page),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+    ]);
+
+    let result = sais_client(&transport).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Up);
+
+    let login = &transport.requests()[1];
+    assert_eq!(
+        login.url,
+        "https://sais.example/psp/ps/EMPLOYEE/?cmd=login&languageCd=ENG"
+    );
+    assert_eq!(login.form[0], ("ICSID".to_string(), "s3ss10n".to_string()));
+    let userids = login
+        .form
+        .iter()
+        .filter(|(name, _)| name == "userid")
+        .collect::<Vec<_>>();
+    assert_eq!(
+        userids,
+        vec![&("userid".to_string(), "juan@up.edu.ph".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn login_details_are_not_posted_to_another_host() {
+    let login_page = r#"<form action="https://evil.example/collect" method="post">
+        <input type="text" name="userid">
+        <input type="password" name="pwd">
+    </form>"#;
+    let transport = CannedTransport::new(vec![response(200, &[], login_page)]);
+
+    let why = sais_client(&transport).check().await.unwrap_err();
+    assert!(why.to_string().contains("evil.example"));
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn login_form_may_post_to_a_trusted_host() {
+    let login_page = r#"<form action="https://sso.example/login" method="post">
+        <input type="text" name="userid">
+        <input type="password" name="pwd">
+    </form>"#;
+    let transport = CannedTransport::new(vec![
+        response(200, &[], login_page),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.trusted_redirect_hosts = vec!["sso.example".to_string()];
+
+    assert_eq!(sais_client.check().await.unwrap(), ServiceStatus::Up);
+    assert_eq!(transport.requests()[1].url, "https://sso.example/login");
+}
+
+fn enlistment_check() -> DeepCheck {
+    DeepCheck {
+        name: "enlistment".to_string(),
+        url: "https://sais.example/psc/ps/ENLISTMENT".to_string(),
+        expect: "Enlistment Cart".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn working_deep_checks_are_up_and_use_the_login_session() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &["PS_LOGIN=session"], LOGIN_SUCCESS_STRING),
+        response(200, &[], "<h1>Enlistment Cart</h1>"),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.deep_checks = vec![enlistment_check()];
+
+    assert_eq!(sais_client.check().await.unwrap(), ServiceStatus::Up);
+    let deep_check = &transport.requests()[2];
+    assert_eq!(deep_check.method, Method::GET);
+    assert!(deep_check
+        .headers
+        .get(COOKIE)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .contains("PS_LOGIN=session"));
+}
+
+#[tokio::test]
+async fn failing_deep_check_means_pages_failing() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+        response(500, &[], "Enlistment Cart"),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.deep_checks = vec![enlistment_check()];
+
+    assert_eq!(
+        sais_client.check().await.unwrap(),
+        ServiceStatus::PagesFailing
+    );
+    assert_eq!(sais_client.failing_pages(), &["enlistment".to_string()]);
+}
+
+#[tokio::test]
+async fn each_deep_check_says_why_it_failed() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+        response(200, &[], "<h1>Enlistment Cart</h1>"),
+        response(200, &[], "Something went wrong"),
+        response(503, &[], ""),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    let grades_check = DeepCheck {
+        name: "grades".to_string(),
+        expect: "Grades".to_string(),
+        ..enlistment_check()
+    };
+    let statement_check = DeepCheck {
+        name: "account statement".to_string(),
+        ..enlistment_check()
+    };
+    sais_client.sais_config.deep_checks = vec![enlistment_check(), grades_check, statement_check];
+
+    assert_eq!(
+        sais_client.check().await.unwrap(),
+        ServiceStatus::PagesFailing
+    );
+    let problems = sais_client
+        .page_results()
+        .iter()
+        .map(|page| (page.name.as_str(), page.problem.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        vec![
+            ("enlistment", None),
+            ("grades", Some("it didn't show \"Grades\"")),
+            (
+                "account statement",
+                Some("it answered with 503 Service Unavailable")
+            ),
+        ]
+    );
+    assert_eq!(
+        sais_client.failing_pages(),
+        &["grades".to_string(), "account statement".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn live_session_skips_the_login_until_it_expires() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &["PS_LOGIN=session"], LOGIN_SUCCESS_STRING),
+        response(200, &[], "Student Center"),
+        response(200, &[], "Your session has expired"),
+        response(200, &[], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.session = Some(SessionConfig {
+        keepalive_url: "https://sais.example/psc/ps/HOME".to_string(),
+        expect: "Student Center".to_string(),
+        max_age_secs: 1800,
+    });
+
+    for _ in 0..3 {
+        assert_eq!(sais_client.check().await.unwrap(), ServiceStatus::Up);
+    }
+
+    let requests = transport.requests();
+    let methods = requests
+        .iter()
+        .map(|request| request.method.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        methods,
+        vec![
+            Method::GET,
+            Method::POST,
+            Method::GET,
+            Method::GET,
+            Method::GET,
+            Method::POST
+        ]
+    );
+    assert!(requests[2]
+        .headers
+        .get(COOKIE)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .contains("PS_LOGIN=session"));
+}
+
+#[tokio::test]
+async fn expired_session_cookies_mean_a_fresh_login() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &["PS_LOGIN=session; Max-Age=1"], LOGIN_SUCCESS_STRING),
+        response(200, &["PS_LOGIN=fresh"], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.session = Some(SessionConfig {
+        keepalive_url: "https://sais.example/psc/ps/HOME".to_string(),
+        expect: "Student Center".to_string(),
+        max_age_secs: 1800,
+    });
+
+    assert_eq!(sais_client.check().await.unwrap(), ServiceStatus::Up);
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert_eq!(sais_client.check().await.unwrap(), ServiceStatus::Up);
+
+    let requests = transport.requests();
+    let methods = requests
+        .iter()
+        .map(|request| request.method.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        methods,
+        vec![Method::GET, Method::POST, Method::GET, Method::POST]
+    );
+    assert_eq!(requests[3].headers.get(COOKIE).unwrap(), "PS_LOGIN=fresh");
+}
+
+#[tokio::test]
+async fn each_check_takes_the_next_user_agent() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+        response(200, &[], ""),
+        response(200, &[], LOGIN_SUCCESS_STRING),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.headers.user_agents = vec!["first".to_string(), "second".to_string()];
+    sais_client.sais_config.headers.extra =
+        vec![("Accept-Language".to_string(), "en-US".to_string())];
+
+    for _ in 0..2 {
+        sais_client.check().await.unwrap();
+    }
+
+    let user_agents = transport
+        .requests()
+        .iter()
+        .map(|request| request.headers.get(USER_AGENT).unwrap().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(user_agents, vec!["first", "first", "second", "second"]);
+    assert!(transport.requests().iter().all(|request| request
+        .headers
+        .get(ACCEPT_LANGUAGE)
+        .unwrap()
+        == "en-US"));
+}
+
+#[tokio::test]
+async fn successful_login_is_logged_out_of() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &["PS_LOGIN=session"], LOGIN_SUCCESS_STRING),
+        Err("connection reset".to_string()),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.logout_url =
+        Some("https://sais.example/psp/ps/?cmd=logout".to_string());
+
+    // A failed logout doesn't change the result.
+    assert_eq!(sais_client.check().await.unwrap(), ServiceStatus::Up);
+    let logout = &transport.requests()[2];
+    assert_eq!(logout.url, "https://sais.example/psp/ps/?cmd=logout");
+    assert!(logout
+        .headers
+        .get(COOKIE)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .contains("PS_LOGIN=session"));
+}
+
+#[tokio::test]
+async fn failed_login_is_not_logged_out_of() {
+    let transport = CannedTransport::new(vec![
+        response(200, &[], ""),
+        response(200, &[], "Something unexpected"),
+    ]);
+    let mut sais_client = sais_client(&transport);
+    sais_client.sais_config.logout_url =
+        Some("https://sais.example/psp/ps/?cmd=logout".to_string());
+
+    sais_client.check().await.unwrap();
+    assert_eq!(transport.requests().len(), 2);
+}
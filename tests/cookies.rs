@@ -0,0 +1,44 @@
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::cookies::CookieJar;
+
+#[test]
+fn cookies_are_sent_without_their_attributes() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 1, 8, 0, 0).unwrap();
+    let mut jar = CookieJar::new();
+    jar.store("PS_LOGIN=session; Path=/; Secure; HttpOnly", now);
+    jar.store("PS_TOKEN=abc; Domain=sais.example", now);
+    jar.store("PS_LOGIN=renewed; Path=/", now);
+
+    assert_eq!(jar.header(now), "PS_TOKEN=abc; PS_LOGIN=renewed");
+}
+
+#[test]
+fn cookies_expire() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 1, 8, 0, 0).unwrap();
+    let mut jar = CookieJar::new();
+    jar.store("PS_LOGIN=session", now);
+    jar.store("PS_TOKEN=abc; Max-Age=600", now);
+    jar.store("ExpirePage=x; Expires=Thu, 01 Oct 2026 09:00:00 GMT", now);
+    jar.store(
+        "Old=x; Expires=Thu, 01-Oct-2026 08:20:00 GMT; Max-Age=3600",
+        now,
+    );
+
+    let later = now + Duration::minutes(15);
+    assert_eq!(jar.header(later), "PS_LOGIN=session; ExpirePage=x; Old=x");
+    assert!(jar.remove_expired(later));
+    assert!(!jar.remove_expired(later));
+    assert_eq!(jar.len(), 3);
+    assert!(jar.remove_expired(now + Duration::hours(2)));
+    assert_eq!(jar.header(now + Duration::hours(2)), "PS_LOGIN=session");
+}
+
+#[test]
+fn expired_cookies_delete_stored_ones() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 1, 8, 0, 0).unwrap();
+    let mut jar = CookieJar::new();
+    jar.store("PS_LOGIN=session", now);
+    jar.store("PS_LOGIN=; Max-Age=0", now);
+
+    assert!(jar.is_empty());
+}
@@ -0,0 +1,206 @@
+#![cfg(feature = "web")]
+
+use is_sais_down_bot::{
+    dashboard::{Dashboard, DashboardConfig, DashboardData},
+    guilds::GuildSettingsStore,
+    health::Readiness,
+    maintenance::MaintenanceStore,
+    metrics::CommandMetrics,
+    monitor::MonitorHealth,
+    web::{self, WebState},
+};
+use reqwest::{header, redirect::Policy, StatusCode};
+use std::{net::TcpListener, sync::Arc, time::Duration};
+use tempfile::TempDir;
+use tokio::sync::watch;
+use wiremock::{
+    matchers::{body_string_contains, header as header_is, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const ADMIN_ID: u64 = 42;
+
+struct TestDashboard {
+    address: String,
+    client: reqwest::Client,
+    maintenance: Arc<MaintenanceStore>,
+    _directory: TempDir,
+}
+
+// Serves a dashboard that logs in whoever Discord says `user_id` is.
+async fn serve_dashboard(discord: &MockServer, user_id: u64) -> TestDashboard {
+    Mock::given(method("POST"))
+        .and(path("/oauth2/token"))
+        .and(body_string_contains("code=the-code"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": "the-token",
+            "token_type": "Bearer",
+        })))
+        .mount(discord)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/users/@me"))
+        .and(header_is("authorization", "Bearer the-token"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "id": user_id.to_string() })),
+        )
+        .mount(discord)
+        .await;
+
+    let directory = tempfile::tempdir().unwrap();
+    let maintenance =
+        Arc::new(MaintenanceStore::open(directory.path().join("maintenance.ron")).unwrap());
+    let monitor_health = Arc::new(MonitorHealth::default());
+    let data = DashboardData {
+        history: None,
+        guild_settings: Arc::new(
+            GuildSettingsStore::open(directory.path().join("guilds.ron")).unwrap(),
+        ),
+        maintenance: maintenance.clone(),
+        monitor_health: monitor_health.clone(),
+    };
+    let config = DashboardConfig {
+        client_id: 1,
+        redirect_url: "http://localhost/admin/callback".to_string(),
+        admin_user_ids: vec![ADMIN_ID],
+    };
+    let dashboard = Dashboard::with_discord_api(config, "secret".to_string(), data, &discord.uri());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(web::serve(
+        listener,
+        WebState {
+            command_metrics: Arc::new(CommandMetrics::default()),
+            check_timings: Arc::default(),
+            readiness: Arc::new(Readiness::new(
+                monitor_health.clone(),
+                Duration::from_secs(60),
+                false,
+            )),
+            history: None,
+            statuses: watch::channel(None).1,
+            dashboard: Some(Arc::new(dashboard)),
+            api_keys: None,
+        },
+    ));
+    TestDashboard {
+        address,
+        client: reqwest::Client::builder()
+            .redirect(Policy::none())
+            .build()
+            .unwrap(),
+        maintenance,
+        _directory: directory,
+    }
+}
+
+impl TestDashboard {
+    // Goes through the Discord login and returns the session cookie.
+    async fn log_in(&self) -> reqwest::Response {
+        let login = self
+            .client
+            .get(format!("{}/admin/login", self.address))
+            .send()
+            .await
+            .unwrap();
+        let authorize_url =
+            reqwest::Url::parse(login.headers()[header::LOCATION].to_str().unwrap()).unwrap();
+        let state = authorize_url
+            .query_pairs()
+            .find(|(name, _)| name == "state")
+            .unwrap()
+            .1
+            .into_owned();
+        self.client
+            .get(format!("{}/admin/callback", self.address))
+            .query(&[("code", "the-code"), ("state", state.as_str())])
+            .send()
+            .await
+            .unwrap()
+    }
+}
+
+fn session_cookie(response: &reqwest::Response) -> String {
+    let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    set_cookie.split(';').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn logged_out_visitors_are_sent_to_log_in() {
+    let discord = MockServer::start().await;
+    let dashboard = serve_dashboard(&discord, ADMIN_ID).await;
+
+    let response = dashboard
+        .client
+        .get(format!("{}/admin", dashboard.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(response.headers()[header::LOCATION], "/admin/login");
+
+    let response = dashboard
+        .client
+        .post(format!("{}/admin/maintenance", dashboard.address))
+        .form(&[
+            ("starts_at", "2022-01-10T08:00"),
+            ("ends_at", "2022-01-10T12:00"),
+            ("note", "Upgrade"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(dashboard.maintenance.all().is_empty());
+}
+
+#[tokio::test]
+async fn admins_can_log_in_and_declare_maintenance() {
+    let discord = MockServer::start().await;
+    let dashboard = serve_dashboard(&discord, ADMIN_ID).await;
+
+    let callback = dashboard.log_in().await;
+    assert_eq!(callback.status(), StatusCode::SEE_OTHER);
+    let cookie = session_cookie(&callback);
+
+    let overview = dashboard
+        .client
+        .get(format!("{}/admin", dashboard.address))
+        .header(header::COOKIE, &cookie)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(overview.status(), StatusCode::OK);
+
+    dashboard
+        .client
+        .post(format!("{}/admin/maintenance", dashboard.address))
+        .header(header::COOKIE, &cookie)
+        .form(&[
+            ("starts_at", "2099-01-10T08:00"),
+            ("ends_at", "2099-01-10T12:00"),
+            ("note", "Upgrade"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    let windows = dashboard.maintenance.all();
+    assert_eq!(windows.len(), 1);
+    assert_eq!(
+        windows[0].starts_at.to_rfc3339(),
+        "2099-01-10T00:00:00+00:00"
+    );
+    assert_eq!(windows[0].note, "Upgrade");
+}
+
+#[tokio::test]
+async fn other_users_cannot_log_in() {
+    let discord = MockServer::start().await;
+    let dashboard = serve_dashboard(&discord, 7).await;
+
+    let callback = dashboard.log_in().await;
+    assert_eq!(callback.status(), StatusCode::FORBIDDEN);
+    assert!(callback.headers().get(header::SET_COOKIE).is_none());
+}
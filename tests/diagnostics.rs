@@ -0,0 +1,59 @@
+use is_sais_down_bot::{
+    diagnostics::ConnectionLog,
+    transport::{HttpRequest, HttpTransport, ReqwestTransport},
+};
+use reqwest::{Method, Version};
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn connection_reuse_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+        .mount(&server)
+        .await;
+    let log = ConnectionLog::new(ReqwestTransport::default());
+
+    for _ in 0..2 {
+        log.send(HttpRequest::new(Method::GET, &server.uri()))
+            .await
+            .unwrap();
+    }
+
+    let requests = log.requests();
+    let responses = requests
+        .iter()
+        .map(|request| request.outcome.clone().unwrap())
+        .collect::<Vec<_>>();
+    assert!(responses
+        .iter()
+        .all(|response| response.version == Version::HTTP_11));
+    let connections = responses
+        .iter()
+        .map(|response| response.connection.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(connections[0].remote_addr, *server.address());
+    assert!(!connections[0].reused);
+    assert!(connections[1].reused);
+    assert_eq!(
+        requests[1].to_string(),
+        format!(
+            "GET {}: 200 over HTTP/1.1, reused connection to {}",
+            server.uri(),
+            server.address()
+        )
+    );
+}
+
+#[tokio::test]
+async fn failed_requests_are_kept() {
+    let log = ConnectionLog::new(ReqwestTransport::default());
+
+    assert!(log
+        .send(HttpRequest::new(Method::GET, "http://127.0.0.1:1/"))
+        .await
+        .is_err());
+    assert!(log.requests()[0]
+        .to_string()
+        .starts_with("GET http://127.0.0.1:1/: failed ("));
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    digest::{digest_message, DigestLog, DigestPeriod},
+    preferences::NotificationKind,
+};
+use std::collections::BTreeSet;
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    // 09:00 PHT.
+    Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+#[test]
+fn digests_are_due_every_hour_or_every_morning() {
+    assert_eq!(DigestPeriod::Hourly.last_due(at(59)), at(0));
+    assert_eq!(DigestPeriod::Hourly.last_due(at(60)), at(60));
+    // 08:00 PHT.
+    assert_eq!(DigestPeriod::Daily.last_due(at(30)), at(-60));
+    assert_eq!(DigestPeriod::Daily.last_due(at(-61)), at(-60 - 24 * 60));
+}
+
+#[test]
+fn digests_roll_up_what_was_asked_for() {
+    let mut log = DigestLog::default();
+    log.record(at(-5), NotificationKind::Down, "Before".to_string());
+    log.record(at(5), NotificationKind::Down, "Down".to_string());
+    log.record(at(10), NotificationKind::Up, "Up".to_string());
+    log.record(at(20), NotificationKind::Down, "Down again".to_string());
+
+    let texts = |kinds: Option<&BTreeSet<NotificationKind>>| {
+        log.between(at(0), at(20), kinds)
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(texts(None), vec!["Down", "Up"]);
+    let up_only = BTreeSet::from([NotificationKind::Up]);
+    assert_eq!(texts(Some(&up_only)), vec!["Up"]);
+
+    assert_eq!(
+        digest_message(at(0), &log.between(at(0), at(20), None)).unwrap(),
+        "What happened with UP SAIS since Aug 10 09:00 PHT:\n- Down\n- Up"
+    );
+    assert_eq!(digest_message(at(0), &[]), None);
+}
+
+#[test]
+fn long_digests_are_cut_short() {
+    let mut log = DigestLog::default();
+    for minute in 0..100 {
+        log.record(at(minute), NotificationKind::Down, "x".repeat(50));
+    }
+    let message = digest_message(at(0), &log.between(at(0), at(100), None)).unwrap();
+    assert!(message.len() < 2000);
+    assert!(message.ends_with("more."));
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "redb")]
+
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    embedded::RedbHistory,
+    history::{CheckRecord, HistoryStore, HourlyRollup},
+};
+
+fn record(checked_at: chrono::DateTime<Utc>, route: &str) -> CheckRecord {
+    CheckRecord {
+        checked_at,
+        route: route.to_string(),
+        status: "up".to_string(),
+        reason: None,
+        latency_ms: 100,
+    }
+}
+
+#[tokio::test]
+async fn records_survive_reopening_and_come_back_in_order() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("history.redb");
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+
+    let history = RedbHistory::open(&path).unwrap();
+    history
+        .record(&[
+            record(start + Duration::hours(1), "here"),
+            record(start + Duration::hours(1), "campus"),
+            record(start, "here"),
+        ])
+        .await
+        .unwrap();
+    drop(history);
+
+    let history = RedbHistory::open(&path).unwrap();
+    history
+        .record(&[record(start + Duration::hours(2), "here")])
+        .await
+        .unwrap();
+
+    let records = history.since(start + Duration::minutes(30)).await.unwrap();
+    assert_eq!(
+        records,
+        vec![
+            record(start + Duration::hours(1), "here"),
+            record(start + Duration::hours(1), "campus"),
+            record(start + Duration::hours(2), "here"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn compaction_rolls_old_checks_up_by_the_hour() {
+    let directory = tempfile::tempdir().unwrap();
+    let history = RedbHistory::open(directory.path().join("history.redb")).unwrap();
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    history
+        .record(&[
+            record(start, "here"),
+            record(start + Duration::minutes(30), "here"),
+            record(start + Duration::minutes(30), "campus"),
+            record(start + Duration::minutes(90), "here"),
+        ])
+        .await
+        .unwrap();
+
+    history
+        .compact(start + Duration::hours(1), start - Duration::days(1))
+        .await
+        .unwrap();
+
+    let rollup = |route: &str, checks| HourlyRollup {
+        hour: start,
+        route: route.to_string(),
+        status: "up".to_string(),
+        checks,
+        total_latency_ms: checks * 100,
+    };
+    assert_eq!(
+        history.rollups_since(start).await.unwrap(),
+        vec![rollup("campus", 1), rollup("here", 2)]
+    );
+    assert_eq!(
+        history.since(start).await.unwrap(),
+        vec![record(start + Duration::minutes(90), "here")]
+    );
+
+    history
+        .compact(start + Duration::hours(1), start + Duration::hours(1))
+        .await
+        .unwrap();
+    assert!(history.rollups_since(start).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn recorded_rollups_add_to_those_kept() {
+    let directory = tempfile::tempdir().unwrap();
+    let history = RedbHistory::open(directory.path().join("history.redb")).unwrap();
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    let rollup = |checks| HourlyRollup {
+        hour: start,
+        route: "here".to_string(),
+        status: "up".to_string(),
+        checks,
+        total_latency_ms: checks * 100,
+    };
+
+    history.record_rollups(&[rollup(2)]).await.unwrap();
+    history.record_rollups(&[rollup(3)]).await.unwrap();
+    assert_eq!(history.rollups_since(start).await.unwrap(), vec![rollup(5)]);
+}
+
+#[tokio::test]
+async fn the_size_is_that_of_the_database_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("history.redb");
+    let history = RedbHistory::open(&path).unwrap();
+    history
+        .record(&[record(
+            Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap(),
+            "here",
+        )])
+        .await
+        .unwrap();
+
+    let size = history.size_bytes().await.unwrap();
+    assert!(size > 0);
+    assert_eq!(size, std::fs::metadata(&path).unwrap().len());
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    escalation::{EscalationPolicy, EscalationStep, EscalationTarget, EscalationTracker},
+    pager::{Pager, PagerConfig, PagerService},
+};
+use wiremock::{
+    matchers::{body_partial_json, header, method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn step(after_mins: u64, target: EscalationTarget) -> EscalationStep {
+    EscalationStep { after_mins, target }
+}
+
+#[test]
+fn steps_are_written_like_in_commands() {
+    let steps = ["15m:role", "1h:<@&12>", "4h:<@!34>", "90m:pager"]
+        .iter()
+        .map(|step| step.parse::<EscalationStep>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        vec![
+            step(15, EscalationTarget::AlertRole),
+            step(60, EscalationTarget::Role(12)),
+            step(240, EscalationTarget::User(34)),
+            step(90, EscalationTarget::Pager),
+        ]
+    );
+    assert_eq!(steps[1].to_string(), "1h:<@&12>");
+    assert_eq!(steps[2].to_string(), "4h:<@34>");
+    assert_eq!(steps[3].to_string(), "90m:pager");
+    assert!(!steps[3].target.allowed_in_guilds());
+    assert!("15m".parse::<EscalationStep>().is_err());
+    assert!("soon:role".parse::<EscalationStep>().is_err());
+    assert!("1h:someone".parse::<EscalationStep>().is_err());
+}
+
+#[test]
+fn each_step_is_taken_once_per_outage() {
+    let policy = EscalationPolicy {
+        steps: vec![
+            step(15, EscalationTarget::AlertRole),
+            step(60, EscalationTarget::Maintainers),
+        ],
+    };
+    let mut tracker = EscalationTracker::default();
+    assert!(tracker.due(None, &policy, at(0), at(14)).is_empty());
+    assert_eq!(
+        tracker.due(None, &policy, at(0), at(15)),
+        vec![&policy.steps[0]]
+    );
+    assert!(tracker.due(None, &policy, at(0), at(30)).is_empty());
+    // Servers keep their own track.
+    assert_eq!(
+        tracker.due(Some(1), &policy, at(0), at(30)),
+        vec![&policy.steps[0]]
+    );
+    assert_eq!(
+        tracker.due(None, &policy, at(0), at(90)),
+        vec![&policy.steps[1]]
+    );
+
+    tracker.reset();
+    assert_eq!(tracker.due(None, &policy, at(100), at(200)).len(), 2);
+}
+
+#[tokio::test]
+async fn outages_are_paged_and_resolved() {
+    let pagerduty = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/enqueue"))
+        .and(body_partial_json(serde_json::json!({
+            "routing_key": "r0uting",
+            "event_action": "trigger",
+            "dedup_key": "sais-outage-1786323600",
+            "payload": {"summary": "UP SAIS is down", "severity": "critical"},
+        })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&pagerduty)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v2/enqueue"))
+        .and(body_partial_json(serde_json::json!({
+            "event_action": "resolve",
+            "dedup_key": "sais-outage-1786323600",
+        })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&pagerduty)
+        .await;
+
+    let pager = Pager::new(
+        PagerConfig {
+            service: PagerService::PagerDuty,
+            events_url: Some(format!("{}/v2/enqueue", pagerduty.uri())),
+            severity: "critical".to_string(),
+            on_outage: false,
+        },
+        "r0uting".to_string(),
+    );
+    pager.trigger(at(0), "UP SAIS is down").await.unwrap();
+    pager.resolve(at(0)).await.unwrap();
+}
+
+#[tokio::test]
+async fn opsgenie_alerts_are_opened_and_closed() {
+    let opsgenie = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/alerts"))
+        .and(header("authorization", "GenieKey k3y"))
+        .and(body_partial_json(serde_json::json!({
+            "message": "UP SAIS is down",
+            "alias": "sais-outage-1786323600",
+            "priority": "P3",
+        })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&opsgenie)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v2/alerts/sais-outage-1786323600/close"))
+        .and(query_param("identifierType", "alias"))
+        .and(header("authorization", "GenieKey k3y"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&opsgenie)
+        .await;
+
+    let pager = Pager::new(
+        PagerConfig {
+            service: PagerService::Opsgenie,
+            events_url: Some(format!("{}/v2/alerts", opsgenie.uri())),
+            severity: "warning".to_string(),
+            on_outage: true,
+        },
+        "k3y".to_string(),
+    );
+    assert!(pager.pages_on_outage());
+    pager.trigger(at(0), "UP SAIS is down").await.unwrap();
+    pager.resolve(at(0)).await.unwrap();
+}
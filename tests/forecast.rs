@@ -0,0 +1,120 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    calendar::CalendarConfig,
+    forecast::{hour_string, DayCount, Patterns},
+    history::CheckRecord,
+};
+
+fn record(checked_at: DateTime<Utc>, status: &str) -> CheckRecord {
+    CheckRecord {
+        checked_at,
+        route: "here".to_string(),
+        status: status.to_string(),
+        reason: None,
+        latency_ms: 100,
+    }
+}
+
+fn calendar() -> CalendarConfig {
+    ron::de::from_str(
+        r#"(periods: [(name: "enrollment", start: "2026-09-08", end: "2026-09-10")])"#,
+    )
+    .unwrap()
+}
+
+// Midnight of Sep 1 in PHT.
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 8, 31, 16, 0, 0).unwrap()
+}
+
+// Two weeks of hourly checks, failing between 2 and 3 AM for the first
+// eight nights, and in the afternoon during enrollment.
+fn records() -> Vec<CheckRecord> {
+    let mut records = vec![];
+    for day in 0..14 {
+        for hour in 0..24 {
+            let checked_at = start() + Duration::days(day) + Duration::hours(hour);
+            let status = match (day, hour) {
+                (0..=7, 2) | (7..=9, 14) => "down",
+                (12, 14) => "maintenance",
+                _ => "up",
+            };
+            records.push(record(checked_at, status));
+        }
+    }
+    // Other routes aren't looked at.
+    records.push(CheckRecord {
+        route: "elsewhere".to_string(),
+        ..record(start() + Duration::hours(20), "down")
+    });
+    records
+}
+
+#[test]
+fn recurring_hours_and_busy_days_are_found() {
+    let patterns = Patterns::mine(&records(), &calendar(), start());
+    assert_eq!(patterns.days_checked(), 14);
+    assert_eq!(
+        patterns.busy_days,
+        DayCount {
+            failed: 3,
+            checked: 3
+        }
+    );
+    assert_eq!(
+        patterns.other_days,
+        DayCount {
+            failed: 7,
+            checked: 11
+        }
+    );
+
+    let recurring = patterns.recurring();
+    assert_eq!(recurring.len(), 1);
+    assert_eq!(recurring[0].hour, 2);
+    assert_eq!(
+        recurring[0].days,
+        DayCount {
+            failed: 8,
+            checked: 14
+        }
+    );
+}
+
+#[test]
+fn busy_periods_are_forecast_from_earlier_busy_days() {
+    let patterns = Patterns::mine(&records(), &calendar(), start());
+    let pht = |day: i64, hour| start() + Duration::days(day - 1) + Duration::hours(hour);
+
+    let forecast = patterns.forecast(&calendar(), pht(9, 6));
+    assert_eq!(forecast.level(), "high");
+    assert!((forecast.likelihood - 0.8).abs() < 1e-9);
+    assert!(forecast.reasons[0].starts_with("It's enrollment"));
+    assert_eq!(
+        forecast.reasons[1],
+        "Problems between 2 AM and 3 AM on 8 of the 14 days checked then."
+    );
+
+    let forecast = patterns.forecast(&calendar(), pht(7, 12));
+    assert!(forecast.reasons[0].starts_with("enrollment starts tomorrow"));
+
+    let forecast = patterns.forecast(&calendar(), pht(20, 12));
+    assert!((forecast.likelihood - 11.0 / 16.0).abs() < 1e-9);
+    assert_eq!(
+        forecast.reasons[0],
+        "SAIS had problems on 10 of the 14 days checked."
+    );
+}
+
+#[test]
+fn no_problems_is_a_low_chance() {
+    let records = (0..24 * 14)
+        .map(|hour| record(start() + Duration::hours(hour), "up"))
+        .collect::<Vec<_>>();
+    let patterns = Patterns::mine(&records, &CalendarConfig::default(), start());
+    assert!(patterns.recurring().is_empty());
+    let forecast = patterns.forecast(&CalendarConfig::default(), start());
+    assert_eq!(forecast.level(), "low");
+    assert_eq!(hour_string(0), "12 AM");
+    assert_eq!(hour_string(23), "11 PM");
+}
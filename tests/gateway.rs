@@ -0,0 +1,56 @@
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::gateway::{Gap, GatewayWatch, QueuedAnnouncement};
+
+#[test]
+fn short_disconnections_are_not_gaps() {
+    let gateway = GatewayWatch::new(Duration::minutes(2));
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+
+    gateway.disconnected(start);
+    assert!(!gateway.is_cut_off(start + Duration::seconds(90)));
+    assert_eq!(gateway.connected(start + Duration::seconds(90)), None);
+    assert!(!gateway.is_cut_off(start + Duration::minutes(10)));
+}
+
+#[test]
+fn long_disconnections_are_gaps_from_the_first_disconnect() {
+    let gateway = GatewayWatch::new(Duration::minutes(2));
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+
+    gateway.disconnected(start);
+    // Still reconnecting.
+    gateway.disconnected(start + Duration::minutes(1));
+    assert!(gateway.is_cut_off(start + Duration::minutes(3)));
+
+    let gap = gateway.connected(start + Duration::minutes(5)).unwrap();
+    assert_eq!(
+        gap,
+        Gap {
+            from: start,
+            to: start + Duration::minutes(5)
+        }
+    );
+    assert_eq!(gap.duration(), Duration::minutes(5));
+    assert!(!gateway.is_cut_off(start + Duration::minutes(6)));
+    assert_eq!(gateway.connected(start + Duration::minutes(6)), None);
+}
+
+#[test]
+fn queued_announcements_are_taken_once_in_order() {
+    let gateway = GatewayWatch::new(Duration::minutes(2));
+    let announcement = |content: &str| QueuedAnnouncement {
+        channel_id: 746697859818061847,
+        content: content.to_string(),
+    };
+    gateway.queue(announcement("UP SAIS is down."));
+    gateway.queue(announcement("UP SAIS is up."));
+
+    assert_eq!(
+        gateway.take_queued(),
+        vec![
+            announcement("UP SAIS is down."),
+            announcement("UP SAIS is up.")
+        ]
+    );
+    assert_eq!(gateway.take_queued(), vec![]);
+}
@@ -0,0 +1,94 @@
+#![cfg(all(feature = "web", feature = "sqlite"))]
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    graphql::{self, GraphqlData},
+    history::{CheckRecord, HistoryStore},
+    sqlite::SqliteHistory,
+    status::ServiceStatus,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+fn record(checked_at: DateTime<Utc>, route: &str, status: &str) -> CheckRecord {
+    CheckRecord {
+        checked_at,
+        route: route.to_string(),
+        status: status.to_string(),
+        reason: None,
+        latency_ms: 100,
+    }
+}
+
+#[tokio::test]
+async fn history_can_be_queried() {
+    let directory = tempfile::tempdir().unwrap();
+    let history = SqliteHistory::open(directory.path().join("history.sqlite3")).unwrap();
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    let at = |minutes| start + Duration::minutes(minutes);
+    history
+        .record(&[
+            record(at(0), "here", "up"),
+            record(at(0), "proxy", "down"),
+            record(at(5), "here", "down"),
+            record(at(5), "proxy", "down"),
+            record(at(10), "here", "up"),
+            record(at(10), "proxy", "up"),
+        ])
+        .await
+        .unwrap();
+    let (_statuses, receiver) = watch::channel(Some(ServiceStatus::Up));
+    let schema = graphql::schema(GraphqlData {
+        history: Some(Arc::new(history)),
+        statuses: receiver,
+    });
+
+    let response = schema
+        .execute(
+            r#"{
+                latestStatus { kind }
+                outages(from: "2026-08-01T00:00:00Z", to: "2026-08-02T00:00:00Z") {
+                    startedAt
+                    durationSecs
+                    cause
+                }
+                uptimeByService(since: "2026-08-01T00:00:00Z") {
+                    service
+                    uptimePercent
+                }
+            }"#,
+        )
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        json!({
+            "latestStatus": { "kind": "up" },
+            "outages": [{
+                "startedAt": "2026-08-01T08:05:00+00:00",
+                "durationSecs": 300,
+                "cause": "down",
+            }],
+            "uptimeByService": [
+                { "service": "here", "uptimePercent": 100.0 * 2.0 / 3.0 },
+                { "service": "proxy", "uptimePercent": 100.0 / 3.0 },
+            ],
+        })
+    );
+}
+
+#[tokio::test]
+async fn history_queries_fail_without_a_history() {
+    let (_statuses, receiver) = watch::channel(None);
+    let schema = graphql::schema(GraphqlData {
+        history: None,
+        statuses: receiver,
+    });
+
+    let response = schema
+        .execute(r#"{ latestStatus { kind } uptimeByService(since: "2026-08-01T00:00:00Z") { service } }"#)
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(response.errors[0].message, "The history is turned off");
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "grpc")]
+
+use futures::StreamExt;
+use is_sais_down_bot::{
+    grpc::{
+        self,
+        proto::{status_client::StatusClient, GetStatusRequest, StreamStatusChangesRequest},
+    },
+    status::{DownReason, ServiceStatus},
+};
+use tokio::{net::TcpListener, sync::watch};
+use tonic::{transport::Channel, Code};
+
+async fn serve_status(statuses: watch::Receiver<Option<ServiceStatus>>) -> StatusClient<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(grpc::serve(listener, statuses));
+    let channel = Channel::from_shared(format!("http://{}", address))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    StatusClient::new(channel)
+}
+
+#[tokio::test]
+async fn status_is_unavailable_before_the_first_check() {
+    let (statuses, receiver) = watch::channel(None);
+    let mut client = serve_status(receiver).await;
+
+    let error = client.get_status(GetStatusRequest {}).await.unwrap_err();
+    assert_eq!(error.code(), Code::Unavailable);
+
+    statuses.send(Some(ServiceStatus::Up)).unwrap();
+    let status = client.get_status(GetStatusRequest {}).await.unwrap();
+    assert_eq!(status.into_inner().kind, "up");
+}
+
+#[tokio::test]
+async fn status_changes_are_streamed() {
+    let (statuses, receiver) = watch::channel(Some(ServiceStatus::Up));
+    let mut client = serve_status(receiver).await;
+
+    let mut changes = client
+        .stream_status_changes(StreamStatusChangesRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(changes.next().await.unwrap().unwrap().kind, "up");
+
+    statuses
+        .send(Some(ServiceStatus::Down(DownReason::LoginFailed)))
+        .unwrap();
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!(change.kind, "down");
+    assert_eq!(change.reason.as_deref(), Some("login_failed"));
+}
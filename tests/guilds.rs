@@ -0,0 +1,133 @@
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    escalation::EscalationPolicy,
+    guilds::{Access, Caller, EmojiStyle, GuildSettings, GuildSettingsStore, Language},
+};
+use std::collections::BTreeMap;
+
+#[test]
+fn settings_are_saved_and_loaded_again() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("data").join("guilds.ron");
+
+    let store = GuildSettingsStore::open(&path).unwrap();
+    assert_eq!(store.get(1), None);
+    let settings = GuildSettings {
+        announcement_channel_id: Some(10),
+        alert_role_id: None,
+        role_muted_until: None,
+        digest: None,
+        escalation: EscalationPolicy::default(),
+        language: Language::Cebuano,
+        emoji_style: EmojiStyle::Unicode,
+        command_access: BTreeMap::from([(
+            "diagnose".to_string(),
+            vec![Access::Role("Techs".to_string()), Access::User(3)],
+        )]),
+    };
+    store.set(1, settings.clone()).unwrap();
+    store.set(2, GuildSettings::default()).unwrap();
+
+    let store = GuildSettingsStore::open(&path).unwrap();
+    assert_eq!(store.get(1), Some(settings));
+    assert_eq!(store.all().len(), 2);
+}
+
+#[test]
+fn missing_fields_get_defaults() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("guilds.ron");
+    std::fs::write(&path, "{ 5: (announcement_channel_id: Some(7)) }").unwrap();
+
+    let settings = GuildSettingsStore::open(&path).unwrap().get(5).unwrap();
+    assert_eq!(settings.announcement_channel_id, Some(7));
+    assert_eq!(settings.emoji_style, EmojiStyle::Custom);
+}
+
+#[test]
+fn access_rules_are_written_as_text() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("guilds.ron");
+    std::fs::write(
+        &path,
+        r#"{ 5: (command_access: { "sais": ["everyone"], "config set": ["owner", "permission:manage guild"] }) }"#,
+    )
+    .unwrap();
+
+    let settings = GuildSettingsStore::open(&path).unwrap().get(5).unwrap();
+    assert_eq!(settings.command_access["sais"], vec![Access::Everyone]);
+    assert_eq!(
+        settings.command_access["config set"],
+        vec![
+            Access::Owner,
+            Access::Permission("MANAGE_GUILD".to_string())
+        ]
+    );
+
+    assert_eq!("role:<@&12>".parse(), Ok(Access::Role("12".to_string())));
+    assert_eq!("user:<@!34>".parse(), Ok(Access::User(34)));
+    assert_eq!(Access::Role("Techs".to_string()).to_string(), "role:Techs");
+    for rule in ["anyone", "role:", "user:me", "owner:1"] {
+        assert!(rule.parse::<Access>().is_err(), "{}", rule);
+    }
+}
+
+#[test]
+fn commands_without_rules_are_left_to_their_own_checks() {
+    let settings = GuildSettings {
+        command_access: BTreeMap::from([
+            (
+                "diagnose".to_string(),
+                vec![Access::Role("techs".to_string())],
+            ),
+            ("config".to_string(), vec![Access::Owner]),
+            (
+                "export".to_string(),
+                vec![Access::Permission("MANAGE_MESSAGES".to_string())],
+            ),
+        ]),
+        ..GuildSettings::default()
+    };
+    let member = Caller {
+        user_id: 1,
+        roles: vec![(20, "Students".to_string())],
+        permissions: vec!["SEND_MESSAGES".to_string()],
+        ..Caller::default()
+    };
+    let tech = Caller {
+        user_id: 2,
+        roles: vec![(21, "Techs".to_string())],
+        ..Caller::default()
+    };
+    let admin = Caller {
+        user_id: 3,
+        permissions: vec!["ADMINISTRATOR".to_string()],
+        ..Caller::default()
+    };
+    let owner = Caller {
+        user_id: 4,
+        is_owner: true,
+        ..Caller::default()
+    };
+
+    assert!(settings.allows("sais", &member));
+    assert!(!settings.allows("diagnose", &member));
+    assert!(settings.allows("diagnose", &tech));
+    assert!(!settings.allows("config set", &tech));
+    assert!(settings.allows("config set", &owner));
+    assert!(settings.allows("diagnose", &owner));
+    assert!(!settings.allows("export", &member));
+    assert!(settings.allows("export", &admin));
+}
+
+#[test]
+fn a_muted_role_isnt_pinged_until_it_ends() {
+    let now = Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap();
+    let settings = GuildSettings {
+        alert_role_id: Some(5),
+        role_muted_until: Some(now + Duration::hours(2)),
+        ..GuildSettings::default()
+    };
+    assert_eq!(settings.alert_role(now), None);
+    assert_eq!(settings.alert_role(now + Duration::hours(2)), Some(5));
+}
@@ -0,0 +1,90 @@
+use is_sais_down_bot::{
+    har::{HarRecorder, BODY_LIMIT},
+    transport::{HttpRequest, HttpTransport, ReqwestTransport},
+};
+use reqwest::{header::COOKIE, Method};
+use serde_json::Value;
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+fn recorder() -> HarRecorder<ReqwestTransport> {
+    HarRecorder::new(
+        ReqwestTransport::default(),
+        vec!["juan@up.edu.ph".to_string(), "hunter2".to_string()],
+    )
+}
+
+#[tokio::test]
+async fn secrets_and_cookies_are_redacted() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("set-cookie", "PS_TOKEN=abc123; Path=/")
+                .set_body_string("Welcome, juan@up.edu.ph"),
+        )
+        .mount(&server)
+        .await;
+    let recorder = recorder();
+    let mut request = HttpRequest::new(Method::POST, &format!("{}/?cmd=login", server.uri()));
+    request
+        .headers
+        .insert(COOKIE, "PS_TOKEN=old; SignOnDefault=x".parse().unwrap());
+    request.form = vec![
+        ("userid".to_string(), "juan@up.edu.ph".to_string()),
+        ("pwd".to_string(), "hunter2".to_string()),
+    ];
+
+    recorder.send(request).await.unwrap();
+
+    let har = recorder.to_json().unwrap();
+    assert!(!har.contains("hunter2"), "{}", har);
+    assert!(!har.contains("juan@up.edu.ph"), "{}", har);
+    assert!(!har.contains("abc123"), "{}", har);
+    assert!(!har.contains("PS_TOKEN=old"), "{}", har);
+
+    let har: Value = serde_json::from_str(&har).unwrap();
+    let entry = &har["log"]["entries"][0];
+    assert_eq!(har["log"]["version"], "1.2");
+    assert_eq!(entry["request"]["method"], "POST");
+    assert_eq!(entry["request"]["queryString"][0]["value"], "login");
+    assert_eq!(
+        entry["request"]["postData"]["params"][1]["value"],
+        "[REDACTED]"
+    );
+    assert_eq!(entry["response"]["status"], 200);
+    assert_eq!(entry["response"]["content"]["text"], "Welcome, [REDACTED]");
+    assert_eq!(entry["serverIPAddress"], server.address().ip().to_string());
+}
+
+#[tokio::test]
+async fn long_bodies_are_truncated() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("x".repeat(BODY_LIMIT * 2)))
+        .mount(&server)
+        .await;
+    let recorder = recorder();
+
+    recorder
+        .send(HttpRequest::new(Method::GET, &server.uri()))
+        .await
+        .unwrap();
+
+    let content = &recorder.har().log.entries[0].response.content;
+    assert_eq!(content.size, BODY_LIMIT * 2);
+    assert!(content.text.len() < BODY_LIMIT + 20);
+    assert!(content.text.ends_with("[truncated]"));
+}
+
+#[tokio::test]
+async fn failed_requests_are_kept() {
+    let recorder = recorder();
+
+    assert!(recorder
+        .send(HttpRequest::new(Method::GET, "http://127.0.0.1:1/"))
+        .await
+        .is_err());
+    let entry = &recorder.har().log.entries[0];
+    assert_eq!(entry.response.status, 0);
+    assert!(entry.error.is_some());
+}
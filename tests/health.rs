@@ -0,0 +1,46 @@
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::{health::Readiness, monitor::MonitorHealth, status::ServiceStatus};
+use std::sync::Arc;
+
+#[test]
+fn ready_needs_the_gateway_and_a_recent_monitor_cycle() {
+    let monitor_health = Arc::new(MonitorHealth::default());
+    let readiness = Readiness::new(
+        monitor_health.clone(),
+        std::time::Duration::from_secs(60),
+        true,
+    );
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+
+    assert_eq!(
+        readiness.not_ready_because(start),
+        Some("not connected to the Discord gateway")
+    );
+    readiness.set_gateway_connected(true);
+    assert_eq!(
+        readiness.not_ready_because(start),
+        Some("the monitor hasn't run yet")
+    );
+
+    monitor_health.checked(start, ServiceStatus::Up);
+    assert_eq!(
+        readiness.not_ready_because(start + Duration::seconds(90)),
+        None
+    );
+    // Failed cycles still count as the monitor running.
+    monitor_health.errored(start + Duration::seconds(60));
+    assert_eq!(
+        readiness.not_ready_because(start + Duration::seconds(150)),
+        None
+    );
+    assert_eq!(
+        readiness.not_ready_because(start + Duration::seconds(181)),
+        Some("the monitor is stuck")
+    );
+
+    readiness.set_gateway_connected(false);
+    assert_eq!(
+        readiness.not_ready_because(start + Duration::seconds(61)),
+        Some("not connected to the Discord gateway")
+    );
+}
@@ -0,0 +1,27 @@
+use is_sais_down_bot::heartbeat::Heartbeat;
+use std::time::{Duration, Instant};
+
+#[test]
+fn tasks_are_stuck_once_silent_past_the_threshold() {
+    let heartbeat = Heartbeat::new("monitor", Duration::from_secs(60));
+    let start = Instant::now();
+    heartbeat.beat_at(start);
+
+    assert!(!heartbeat.is_stuck(start + Duration::from_secs(60)));
+    assert!(heartbeat.is_stuck(start + Duration::from_secs(61)));
+    assert_eq!(
+        heartbeat.silent_for(start + Duration::from_secs(61)),
+        Duration::from_secs(61)
+    );
+}
+
+#[test]
+fn clones_share_beats() {
+    let heartbeat = Heartbeat::new("web server", Duration::from_secs(60));
+    let start = Instant::now();
+    heartbeat.beat_at(start);
+
+    heartbeat.clone().beat_at(start + Duration::from_secs(50));
+
+    assert!(!heartbeat.is_stuck(start + Duration::from_secs(100)));
+}
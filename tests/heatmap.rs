@@ -0,0 +1,65 @@
+use chrono::{FixedOffset, TimeZone, Utc};
+use is_sais_down_bot::{
+    heatmap::OutageHeatmap,
+    history::{CheckRecord, HourlyRollup},
+};
+
+fn record(checked_at: chrono::DateTime<Utc>, route: &str, status: &str) -> CheckRecord {
+    CheckRecord {
+        checked_at,
+        route: route.to_string(),
+        status: status.to_string(),
+        reason: None,
+        latency_ms: 100,
+    }
+}
+
+#[test]
+fn checks_are_bucketed_by_local_weekday_and_hour() {
+    // Sunday night in UTC is Monday morning in the Philippines.
+    let checked_at = Utc.with_ymd_and_hms(2026, 9, 6, 23, 30, 0).unwrap();
+    let records = vec![
+        record(checked_at, "here", "down"),
+        record(checked_at, "here", "up"),
+        record(checked_at, "here", "maintenance"),
+        record(checked_at, "campus", "down"),
+    ];
+
+    let heatmap = OutageHeatmap::from_records(&records, FixedOffset::east_opt(8 * 3600).unwrap());
+    assert_eq!(heatmap.cells[0][7], (1, 2));
+    assert_eq!(heatmap.failure_rate(0, 7), Some(0.5));
+    assert_eq!(heatmap.failure_rate(6, 23), None);
+}
+
+#[test]
+fn compacted_checks_are_counted_from_their_rollups() {
+    let utc_plus_8 = FixedOffset::east_opt(8 * 3600).unwrap();
+    let hour = Utc.with_ymd_and_hms(2026, 9, 6, 23, 0, 0).unwrap();
+    let rollup = |route: &str, status: &str, checks| HourlyRollup {
+        hour,
+        route: route.to_string(),
+        status: status.to_string(),
+        checks,
+        total_latency_ms: 100 * checks,
+    };
+    let records = vec![record(hour, "here", "up")];
+
+    let heatmap = OutageHeatmap::from_records(&records, utc_plus_8).with_rollups(
+        &[
+            rollup("here", "down", 3),
+            rollup("here", "up", 8),
+            rollup("here", "maintenance", 2),
+            rollup("campus", "down", 12),
+        ],
+        utc_plus_8,
+    );
+    assert_eq!(heatmap.cells[0][7], (3, 12));
+}
+
+#[test]
+fn renders_a_png() {
+    let png = OutageHeatmap::from_records(&[], FixedOffset::east_opt(0).unwrap())
+        .to_png()
+        .unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+}
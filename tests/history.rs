@@ -0,0 +1,232 @@
+#![cfg(feature = "sqlite")]
+
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    history::{
+        parse_range, to_csv, to_json, CheckRecord, HistoryStore, HourlyRollup, RetentionConfig,
+    },
+    routes::RouteResult,
+    sqlite::SqliteHistory,
+    status::{DownReason, ServiceStatus},
+};
+use reqwest::StatusCode;
+
+fn route_result(route: &str, status: ServiceStatus) -> RouteResult {
+    RouteResult {
+        route: route.to_string(),
+        status,
+        suspicious_redirect: None,
+        failing_pages: vec![],
+        error_page: None,
+        latency: std::time::Duration::from_millis(1234),
+    }
+}
+
+#[tokio::test]
+async fn records_since_a_time_come_back_oldest_first() {
+    let history = SqliteHistory::open_in_memory().unwrap();
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    for hour in [2, 0, 1] {
+        history
+            .record_results(
+                start + Duration::hours(hour),
+                &[route_result("here", ServiceStatus::Up)],
+            )
+            .await
+            .unwrap();
+    }
+
+    let records = history.since(start + Duration::minutes(30)).await.unwrap();
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.checked_at)
+            .collect::<Vec<_>>(),
+        vec![start + Duration::hours(1), start + Duration::hours(2)]
+    );
+}
+
+#[tokio::test]
+async fn status_kind_and_reason_are_recorded() {
+    let history = SqliteHistory::open_in_memory().unwrap();
+    let checked_at = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    history
+        .record_results(
+            checked_at,
+            &[route_result(
+                "campus",
+                ServiceStatus::Down(DownReason::HttpStatus(StatusCode::BAD_GATEWAY)),
+            )],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        history.since(checked_at).await.unwrap(),
+        vec![CheckRecord {
+            checked_at,
+            route: "campus".to_string(),
+            status: "down".to_string(),
+            reason: Some("http_502".to_string()),
+            latency_ms: 1234,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn history_is_kept_on_disk() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("data").join("history.sqlite3");
+    let checked_at = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    SqliteHistory::open(&path)
+        .unwrap()
+        .record_results(checked_at, &[route_result("here", ServiceStatus::Up)])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        SqliteHistory::open(&path)
+            .unwrap()
+            .since(checked_at)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn ranges_are_minutes_hours_or_days() {
+    assert_eq!(parse_range("30m"), Some(Duration::minutes(30)));
+    assert_eq!(parse_range("24h"), Some(Duration::hours(24)));
+    assert_eq!(parse_range("7d"), Some(Duration::days(7)));
+    assert_eq!(parse_range("7"), None);
+    assert_eq!(parse_range("0d"), None);
+    assert_eq!(parse_range("d"), None);
+    assert_eq!(parse_range(""), None);
+}
+
+#[test]
+fn exports_have_one_row_per_record() {
+    let records = vec![CheckRecord {
+        checked_at: Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap(),
+        route: "outside, PH".to_string(),
+        status: "down".to_string(),
+        reason: Some("no_response".to_string()),
+        latency_ms: 10,
+    }];
+
+    assert_eq!(
+        to_csv(&records),
+        "checked_at,route,status,reason,latency_ms\n\
+         2026-08-01T08:00:00+00:00,\"outside, PH\",down,no_response,10\n"
+    );
+    let json: serde_json::Value = serde_json::from_str(&to_json(&records).unwrap()).unwrap();
+    assert_eq!(json[0]["reason"], "no_response");
+}
+
+#[tokio::test]
+async fn compaction_rolls_old_checks_up_by_the_hour() {
+    let history = SqliteHistory::open_in_memory().unwrap();
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    for minutes in [0, 20, 40, 70] {
+        history
+            .record_results(
+                start + Duration::minutes(minutes),
+                &[route_result("here", ServiceStatus::Up)],
+            )
+            .await
+            .unwrap();
+    }
+
+    let retention = RetentionConfig {
+        raw_days: 1,
+        rollup_days: 30,
+        compact_every_hours: 24,
+    };
+    retention
+        .compact(&history, start + Duration::days(1) + Duration::minutes(75))
+        .await
+        .unwrap();
+    // Run twice, to make sure the second run doesn't count anything again.
+    retention
+        .compact(&history, start + Duration::days(1) + Duration::minutes(75))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        history.rollups_since(start).await.unwrap(),
+        vec![HourlyRollup {
+            hour: start,
+            route: "here".to_string(),
+            status: "up".to_string(),
+            checks: 3,
+            total_latency_ms: 3 * 1234,
+        }]
+    );
+    let records = history.since(start).await.unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].checked_at, start + Duration::minutes(70));
+}
+
+#[tokio::test]
+async fn compaction_deletes_expired_rollups() {
+    let history = SqliteHistory::open_in_memory().unwrap();
+    let start = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    history
+        .record_results(start, &[route_result("here", ServiceStatus::Up)])
+        .await
+        .unwrap();
+
+    let retention = RetentionConfig::default();
+    retention
+        .compact(&history, start + Duration::days(31))
+        .await
+        .unwrap();
+    assert_eq!(history.rollups_since(start).await.unwrap().len(), 1);
+
+    retention
+        .compact(&history, start + Duration::days(366))
+        .await
+        .unwrap();
+    assert!(history.rollups_since(start).await.unwrap().is_empty());
+    assert!(history.since(start).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn the_size_is_that_of_the_database_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("history.sqlite3");
+    let history = SqliteHistory::open(&path).unwrap();
+    history
+        .record_results(
+            Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap(),
+            &[route_result("here", ServiceStatus::Up)],
+        )
+        .await
+        .unwrap();
+
+    let size = history.size_bytes().await.unwrap();
+    assert!(size > 0);
+    assert_eq!(size, std::fs::metadata(&path).unwrap().len());
+}
+
+#[test]
+fn untrusted_checks_are_recorded_as_no_data() {
+    let checked_at = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    let result = route_result(
+        "here",
+        ServiceStatus::Down(DownReason::HttpStatus(StatusCode::BAD_GATEWAY)),
+    );
+
+    assert_eq!(
+        CheckRecord::no_data(checked_at, &result, "gateway_disconnected"),
+        CheckRecord {
+            checked_at,
+            route: "here".to_string(),
+            status: "no_data".to_string(),
+            reason: Some("gateway_disconnected".to_string()),
+            latency_ms: 1234,
+        }
+    );
+}
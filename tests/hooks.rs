@@ -0,0 +1,184 @@
+use is_sais_down_bot::{
+    hooks::{self, Hooks, HooksConfig},
+    monitor::{Alert, Transition},
+    routes::RouteResult,
+    status::{DownReason, ServiceStatus},
+    transport::TimeoutKind,
+};
+use reqwest::StatusCode;
+use std::{fs, path::Path, time::Duration};
+use tempfile::TempDir;
+
+fn write_script(dir: &Path, name: &str, body: &str) {
+    fs::write(dir.join(name), body).unwrap();
+}
+
+fn hooks_in(dir: &TempDir) -> Hooks {
+    Hooks::new(HooksConfig {
+        dir: dir.path().to_path_buf(),
+        max_operations: 10_000,
+    })
+}
+
+fn result(status: ServiceStatus) -> RouteResult {
+    RouteResult {
+        route: "here".to_string(),
+        status,
+        suspicious_redirect: None,
+        failing_pages: vec![],
+        error_page: None,
+        latency: Duration::from_millis(1500),
+    }
+}
+
+const DOWN_503: ServiceStatus =
+    ServiceStatus::Down(DownReason::HttpStatus(StatusCode::SERVICE_UNAVAILABLE));
+
+#[test]
+fn statuses_are_read_from_kinds_and_reasons() {
+    let latency = Duration::from_secs(12);
+    assert_eq!(hooks::parse_status("up", latency), Some(ServiceStatus::Up));
+    assert_eq!(
+        hooks::parse_status("degraded\n", latency),
+        Some(ServiceStatus::Degraded(latency))
+    );
+    assert_eq!(
+        hooks::parse_status("maintenance", latency),
+        Some(ServiceStatus::Maintenance)
+    );
+    assert_eq!(
+        hooks::parse_status("down http_503", latency),
+        Some(DOWN_503)
+    );
+    assert_eq!(
+        hooks::parse_status("down read_timeout", latency),
+        Some(ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Read)))
+    );
+    assert_eq!(
+        hooks::parse_status("down", latency),
+        Some(ServiceStatus::Down(DownReason::NoResponse))
+    );
+    assert_eq!(hooks::parse_status("sideways", latency), None);
+    assert_eq!(hooks::parse_status("up later", latency), None);
+    assert_eq!(hooks::parse_status("down http_1000", latency), None);
+}
+
+#[test]
+fn classify_scripts_replace_statuses() {
+    let dir = tempfile::tempdir().unwrap();
+    let hooks = hooks_in(&dir);
+    // Without a script, results are kept.
+    assert_eq!(hooks.classify(&result(DOWN_503)), DOWN_503);
+
+    write_script(
+        dir.path(),
+        "classify.rhai",
+        r#"if result.reason == "http_503" && result.route == "here" { "maintenance" }"#,
+    );
+    assert_eq!(
+        hooks.classify(&result(DOWN_503)),
+        ServiceStatus::Maintenance
+    );
+    // Returning nothing keeps the result.
+    assert_eq!(
+        hooks.classify(&result(ServiceStatus::Up)),
+        ServiceStatus::Up
+    );
+}
+
+#[test]
+fn broken_classify_scripts_are_ignored() {
+    let dir = tempfile::tempdir().unwrap();
+    let hooks = hooks_in(&dir);
+
+    for script in [
+        r#""sideways""#,
+        "42",
+        r#"if result.kind == "#,
+        r#"throw "no idea""#,
+        r#"loop { }"#,
+    ] {
+        write_script(dir.path(), "classify.rhai", script);
+        assert_eq!(hooks.classify(&result(DOWN_503)), DOWN_503, "{}", script);
+    }
+}
+
+#[test]
+fn reply_scripts_get_the_reply() {
+    let dir = tempfile::tempdir().unwrap();
+    let hooks = hooks_in(&dir);
+    write_script(
+        dir.path(),
+        "reply.rhai",
+        r#"if status.kind == "up" { text + ", mga bai" }"#,
+    );
+
+    assert_eq!(
+        hooks.reply(ServiceStatus::Up, "UP SAIS is up!".to_string()),
+        "UP SAIS is up!, mga bai"
+    );
+    assert_eq!(
+        hooks.reply(DOWN_503, "UP SAIS is down.".to_string()),
+        "UP SAIS is down."
+    );
+}
+
+#[test]
+fn alert_scripts_hold_back_announcements() {
+    let dir = tempfile::tempdir().unwrap();
+    let hooks = hooks_in(&dir);
+    let recovered = Alert::Changed(Transition {
+        from: DOWN_503,
+        to: ServiceStatus::Up,
+    });
+    assert!(hooks.should_announce(recovered));
+
+    write_script(
+        dir.path(),
+        "alert.rhai",
+        r#"alert.alert != "changed" || alert.previous_kind != "down""#,
+    );
+    assert!(!hooks.should_announce(recovered));
+    assert!(hooks.should_announce(Alert::Unstable));
+
+    // Scripts that don't finish in time don't hold anything back.
+    write_script(dir.path(), "alert.rhai", "loop { } false");
+    assert!(hooks.should_announce(recovered));
+}
+
+#[test]
+fn scripts_reload_when_they_change() {
+    let dir = tempfile::tempdir().unwrap();
+    let hooks = hooks_in(&dir);
+    assert_eq!(hooks.script_path("reply"), dir.path().join("reply.rhai"));
+
+    write_script(dir.path(), "reply.rhai", r#""first""#);
+    assert_eq!(hooks.reply(ServiceStatus::Up, String::new()), "first");
+    write_script(dir.path(), "reply.rhai", r#""and second""#);
+    assert_eq!(hooks.reply(ServiceStatus::Up, String::new()), "and second");
+
+    // Shared code is read from the same directory, on every run.
+    write_script(
+        dir.path(),
+        "words.rhai",
+        r#"export const SIGN_OFF = "bai";"#,
+    );
+    write_script(
+        dir.path(),
+        "reply.rhai",
+        r#"import "words" as words; text + words::SIGN_OFF"#,
+    );
+    assert_eq!(hooks.reply(ServiceStatus::Up, "ok ".to_string()), "ok bai");
+    write_script(
+        dir.path(),
+        "words.rhai",
+        r#"export const SIGN_OFF = "mga bai";"#,
+    );
+    assert_eq!(
+        hooks.reply(ServiceStatus::Up, "ok ".to_string()),
+        "ok mga bai"
+    );
+
+    fs::remove_file(dir.path().join("reply.rhai")).unwrap();
+    assert_eq!(hooks.reply(ServiceStatus::Up, "ok".to_string()), "ok");
+}
@@ -0,0 +1,53 @@
+use is_sais_down_bot::hostinfo::{self, IpDetails};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+#[tokio::test]
+async fn addresses_are_looked_up() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/127.0.0.1/json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "ip": "127.0.0.1",
+            "hostname": "localhost",
+            "org": "AS9821 University of the Philippines",
+            "city": "Cebu City",
+            "country": "PH",
+        })))
+        .mount(&server)
+        .await;
+
+    let host_info = hostinfo::look_up("127.0.0.1", &format!("{}/{{ip}}/json", server.uri()))
+        .await
+        .unwrap();
+    assert_eq!(
+        host_info.addresses,
+        vec![(
+            "127.0.0.1".parse().unwrap(),
+            Ok(IpDetails {
+                hostname: Some("localhost".to_string()),
+                org: Some("AS9821 University of the Philippines".to_string()),
+                city: Some("Cebu City".to_string()),
+                country: Some("PH".to_string()),
+            })
+        )]
+    );
+    assert_eq!(
+        host_info.to_string(),
+        "127.0.0.1 resolves to:\n127.0.0.1\n  localhost\n  AS9821 University of the Philippines\n  Cebu City, PH"
+    );
+}
+
+#[tokio::test]
+async fn failed_lookups_still_list_the_address() {
+    let server = MockServer::start().await;
+
+    let host_info = hostinfo::look_up("127.0.0.1", &format!("{}/{{ip}}/json", server.uri()))
+        .await
+        .unwrap();
+    assert!(host_info
+        .to_string()
+        .starts_with("127.0.0.1 resolves to:\n127.0.0.1 (could not look it up: "));
+}
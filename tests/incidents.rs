@@ -0,0 +1,95 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    incidents::{self, IncidentIssues, IncidentIssuesConfig},
+    outages::OutageSummary,
+    postmortem::TimelineRun,
+};
+use wiremock::{
+    matchers::{body_partial_json, header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+// 09:00 PHT.
+fn at(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 8, 4, 1, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn config(api_url: String) -> IncidentIssuesConfig {
+    IncidentIssuesConfig {
+        repo: "grantyap/sais-incidents".to_string(),
+        after_mins: 30,
+        labels: vec!["incident".to_string(), "sais".to_string()],
+        api_url,
+    }
+}
+
+#[test]
+fn closing_comments_sum_up_the_outage() {
+    let summary = OutageSummary {
+        started_at: at(0),
+        ended_at: at(45),
+        failed_checks: 9,
+        reasons: vec![("read_timeout".to_string(), 7), ("http_502".to_string(), 2)],
+        user_checks: 3,
+    };
+    let timeline = vec![TimelineRun {
+        first_at: at(0),
+        last_at: at(40),
+        status: "down".to_string(),
+        reason: Some("read_timeout".to_string()),
+        checks: 9,
+        mean_latency_ms: 20000,
+    }];
+
+    assert_eq!(
+        incidents::closing_comment(Some(&summary), &timeline),
+        "UP SAIS works again. It was down for 45m, from Aug 4 09:00 PHT to Aug 4 09:45 PHT, \
+         with 9 failed checks (read_timeout 7, http_502 2).\n\n\
+         ## Timeline (PHT)\n\n\
+         - 09:00 to 09:40: down (read_timeout), 9 checks, 20000 ms on average\n"
+    );
+    assert_eq!(
+        incidents::closing_comment(None, &[]),
+        "UP SAIS works again."
+    );
+}
+
+#[tokio::test]
+async fn issues_are_opened_after_a_while_and_closed() {
+    let github = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/repos/grantyap/sais-incidents/issues"))
+        .and(header("authorization", "Bearer t0ken"))
+        .and(body_partial_json(serde_json::json!({
+            "title": "UP SAIS outage since Aug 4 09:00 PHT",
+            "labels": ["incident", "sais"],
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+            "number": 42,
+        })))
+        .expect(1)
+        .mount(&github)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/repos/grantyap/sais-incidents/issues/42/comments"))
+        .and(body_partial_json(
+            serde_json::json!({ "body": "UP SAIS works again." }),
+        ))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&github)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/repos/grantyap/sais-incidents/issues/42"))
+        .and(body_partial_json(serde_json::json!({ "state": "closed" })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&github)
+        .await;
+
+    let issues = IncidentIssues::new(config(github.uri()), "t0ken".to_string());
+    assert!(!issues.due(at(0), at(29)));
+    assert!(issues.due(at(0), at(30)));
+    assert_eq!(issues.open(at(0), at(30)).await.unwrap(), 42);
+    issues.close(42, "UP SAIS works again.").await.unwrap();
+}
@@ -0,0 +1,106 @@
+use chrono::{TimeZone, Utc};
+use is_sais_down_bot::{
+    exporters::CheckExporter,
+    influx::{self, InfluxConfig, InfluxExporter},
+    routes::RouteResult,
+    status::{DownReason, ServiceStatus},
+};
+use reqwest::StatusCode;
+use std::time::Duration;
+use wiremock::{
+    matchers::{body_string, header, method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn route_result(route: &str, status: ServiceStatus, latency_ms: u64) -> RouteResult {
+    RouteResult {
+        route: route.to_string(),
+        status,
+        suspicious_redirect: None,
+        failing_pages: vec![],
+        error_page: None,
+        latency: Duration::from_millis(latency_ms),
+    }
+}
+
+#[test]
+fn results_are_written_as_line_protocol() {
+    let checked_at = Utc.with_ymd_and_hms(2026, 8, 1, 0, 0, 0).unwrap();
+
+    assert_eq!(
+        influx::line(
+            "sais_check",
+            checked_at,
+            &route_result("here", ServiceStatus::Up, 420)
+        ),
+        "sais_check,route=here up=1i,status=\"up\",latency_ms=420i 1785542400000000000"
+    );
+    assert_eq!(
+        influx::line(
+            "sais_check",
+            checked_at,
+            &route_result(
+                "campus proxy",
+                ServiceStatus::Down(DownReason::HttpStatus(StatusCode::BAD_GATEWAY)),
+                30
+            )
+        ),
+        "sais_check,route=campus\\ proxy up=0i,status=\"down\",latency_ms=30i,reason=\"http_502\" 1785542400000000000"
+    );
+}
+
+#[tokio::test]
+async fn checks_are_posted_with_the_token() {
+    let influxdb = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v2/write"))
+        .and(query_param("bucket", "sais"))
+        .and(header("authorization", "Token t0ken"))
+        .and(body_string(
+            "sais,route=here up=1i,status=\"up\",latency_ms=1i 0\n\
+             sais,route=tcp up=1i,status=\"up\",latency_ms=2i 0",
+        ))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&influxdb)
+        .await;
+    let exporter = InfluxExporter::new(
+        InfluxConfig {
+            write_url: format!("{}/api/v2/write?org=up&bucket=sais", influxdb.uri()),
+            measurement: "sais".to_string(),
+        },
+        Some("t0ken".to_string()),
+    );
+
+    exporter
+        .export(
+            Utc.timestamp_opt(0, 0).unwrap(),
+            &[
+                route_result("here", ServiceStatus::Up, 1),
+                route_result("tcp", ServiceStatus::Up, 2),
+            ],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn rejected_writes_are_errors() {
+    let influxdb = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&influxdb)
+        .await;
+    let exporter = InfluxExporter::new(
+        InfluxConfig {
+            write_url: format!("{}/write?db=sais", influxdb.uri()),
+            measurement: "sais_check".to_string(),
+        },
+        None,
+    );
+
+    assert!(exporter
+        .export(Utc::now(), &[route_result("here", ServiceStatus::Up, 1)])
+        .await
+        .is_err());
+}
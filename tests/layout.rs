@@ -0,0 +1,81 @@
+use is_sais_down_bot::layout::{start_tags, LayoutWatcher, LoginForm, PageLayout};
+
+const LOGIN_PAGE: &str = r#"<html>
+<head><TITLE> Oracle PeopleSoft Sign-in </TITLE></head>
+<body>
+<!-- <input name="commented_out"> -->
+<form name="login" method="post" action="?cmd=login&languageCd=ENG">
+  <input type="hidden" name="timezoneOffset" value="0">
+  <input type=text name=userid id='userid'>
+  <input type="password" name="pwd" />
+  <input type="submit" value="Sign In">
+</form>
+</body>
+</html>"#;
+
+#[test]
+fn start_tags_reads_quoted_and_unquoted_attributes() {
+    let tags = start_tags(LOGIN_PAGE);
+    let userid = tags
+        .iter()
+        .find(|tag| tag.attribute("name") == Some("userid"))
+        .unwrap();
+    assert_eq!(userid.name, "input");
+    assert_eq!(userid.attribute("type"), Some("text"));
+    assert_eq!(userid.attribute("id"), Some("userid"));
+    assert!(tags
+        .iter()
+        .all(|tag| tag.attribute("name") != Some("commented_out")));
+}
+
+#[test]
+fn layout_keeps_title_forms_and_field_names() {
+    let layout = PageLayout::from_html(LOGIN_PAGE);
+    assert_eq!(layout.title.as_deref(), Some("Oracle PeopleSoft Sign-in"));
+    assert_eq!(layout.forms, 1);
+    assert_eq!(
+        layout.fields.iter().map(String::as_str).collect::<Vec<_>>(),
+        vec!["pwd", "timezoneOffset", "userid"]
+    );
+}
+
+#[test]
+fn changed_values_are_not_a_layout_change() {
+    let mut watcher = LayoutWatcher::default();
+    assert_eq!(watcher.observe(PageLayout::from_html(LOGIN_PAGE)), None);
+
+    let reloaded = LOGIN_PAGE.replace("value=\"0\"", "value=\"-480\"");
+    assert_eq!(watcher.observe(PageLayout::from_html(&reloaded)), None);
+}
+
+#[test]
+fn new_fields_are_a_layout_change_reported_once() {
+    let mut watcher = LayoutWatcher::default();
+    watcher.observe(PageLayout::from_html(LOGIN_PAGE));
+
+    let upgraded = LOGIN_PAGE.replace(
+        "</form>",
+        "<input type=\"hidden\" name=\"ICSID\" value=\"abc\"></form>",
+    );
+    let changes = watcher.observe(PageLayout::from_html(&upgraded)).unwrap();
+    assert!(changes.contains("ICSID"));
+    assert_eq!(watcher.observe(PageLayout::from_html(&upgraded)), None);
+}
+
+#[test]
+fn login_form_is_the_one_with_a_password_field() {
+    let page = format!(
+        r#"<form action="/search"><input type="hidden" name="q" value="x"></form>{}"#,
+        LOGIN_PAGE
+    );
+
+    let login_form = LoginForm::from_html(&page).unwrap();
+    assert_eq!(
+        login_form.action.as_deref(),
+        Some("?cmd=login&languageCd=ENG")
+    );
+    assert_eq!(
+        login_form.hidden_fields,
+        vec![("timezoneOffset".to_string(), "0".to_string())]
+    );
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "sqlite")]
+
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    history::HistoryStore,
+    leader::{self, LeaderElectionConfig, Leadership, LEASE_NAME},
+    sqlite::SqliteHistory,
+};
+
+fn config(instance_id: &str) -> LeaderElectionConfig {
+    LeaderElectionConfig {
+        instance_id: Some(instance_id.to_string()),
+        lease_secs: 30,
+    }
+}
+
+#[tokio::test]
+async fn only_one_holder_has_the_lease_until_it_expires() {
+    let history = SqliteHistory::open_in_memory().unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+    let expires_at = now + Duration::seconds(30);
+
+    assert!(history
+        .try_lease(LEASE_NAME, "a", now, expires_at)
+        .await
+        .unwrap());
+    assert!(!history
+        .try_lease(LEASE_NAME, "b", now, expires_at)
+        .await
+        .unwrap());
+    // Renewing.
+    let later = now + Duration::seconds(10);
+    assert!(history
+        .try_lease(LEASE_NAME, "a", later, later + Duration::seconds(30))
+        .await
+        .unwrap());
+    // Still held, since it was renewed.
+    let after_first_expiry = now + Duration::seconds(35);
+    assert!(!history
+        .try_lease(LEASE_NAME, "b", after_first_expiry, after_first_expiry)
+        .await
+        .unwrap());
+
+    let expired = later + Duration::seconds(31);
+    assert!(history
+        .try_lease(LEASE_NAME, "b", expired, expired + Duration::seconds(30))
+        .await
+        .unwrap());
+    assert!(!history
+        .try_lease(LEASE_NAME, "a", expired, expired + Duration::seconds(30))
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn the_standby_takes_over_when_the_leader_stops_renewing() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("history.sqlite3");
+    // Each instance has its own connection to the shared file.
+    let history_a = SqliteHistory::open(&path).unwrap();
+    let history_b = SqliteHistory::open(&path).unwrap();
+    let a = Leadership::new(&config("a"));
+    let b = Leadership::new(&config("b"));
+    let now = Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap();
+
+    assert!(a.renew(&history_a, now).await.unwrap());
+    assert!(!b.renew(&history_b, now).await.unwrap());
+    assert!(leader::leads(Some(&a)));
+    assert!(!leader::leads(Some(&b)));
+
+    // a dies, and b keeps trying.
+    let later = now + Duration::seconds(31);
+    assert!(b.renew(&history_b, later).await.unwrap());
+    assert!(b.is_leader());
+
+    // a comes back as the standby.
+    assert!(!a.renew(&history_a, later).await.unwrap());
+    assert!(!a.is_leader());
+}
+
+#[test]
+fn without_leader_election_every_instance_leads() {
+    assert!(leader::leads(None));
+}
+
+#[cfg(feature = "redb")]
+#[tokio::test]
+async fn unshareable_stores_cannot_elect_a_leader() {
+    let directory = tempfile::tempdir().unwrap();
+    let history =
+        is_sais_down_bot::embedded::RedbHistory::open(directory.path().join("history.redb"))
+            .unwrap();
+    let leadership = Leadership::new(&config("a"));
+
+    assert!(leadership.renew(&history, Utc::now()).await.is_err());
+    assert!(!leadership.is_leader());
+}
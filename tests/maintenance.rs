@@ -0,0 +1,52 @@
+use chrono::{Duration, TimeZone, Utc};
+use is_sais_down_bot::maintenance::{MaintenanceStore, MaintenanceWindow};
+
+#[test]
+fn windows_are_saved_in_order_and_found_by_time() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("data").join("maintenance.ron");
+    let start = Utc.with_ymd_and_hms(2022, 1, 10, 0, 0, 0).unwrap();
+
+    let store = MaintenanceStore::open(&path).unwrap();
+    let later = MaintenanceWindow {
+        starts_at: start + Duration::days(2),
+        ends_at: start + Duration::days(3),
+        note: "Later".to_string(),
+    };
+    let sooner = MaintenanceWindow {
+        starts_at: start,
+        ends_at: start + Duration::hours(4),
+        note: "Sooner".to_string(),
+    };
+    store.add(later.clone()).unwrap();
+    store.add(sooner.clone()).unwrap();
+
+    let store = MaintenanceStore::open(&path).unwrap();
+    assert_eq!(store.all(), vec![sooner.clone(), later.clone()]);
+    assert_eq!(store.active(start + Duration::hours(1)), Some(sooner));
+    assert_eq!(store.active(start + Duration::hours(4)), None);
+
+    store.remove_ended(start + Duration::days(1)).unwrap();
+    assert_eq!(store.all(), vec![later]);
+    store.remove(0).unwrap();
+    assert!(MaintenanceStore::open(&path).unwrap().all().is_empty());
+}
+
+#[test]
+fn event_names_fit_discord() {
+    let start = Utc.with_ymd_and_hms(2022, 1, 10, 0, 0, 0).unwrap();
+    let window = MaintenanceWindow {
+        starts_at: start,
+        ends_at: start + Duration::hours(4),
+        note: "Enlistment system upgrade".to_string(),
+    };
+    assert_eq!(
+        window.event_name(),
+        "SAIS maintenance: Enlistment system upgrade"
+    );
+    let window = MaintenanceWindow {
+        note: "é".repeat(200),
+        ..window
+    };
+    assert_eq!(window.event_name().chars().count(), 100);
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "sqlite")]
+
+use is_sais_down_bot::migrations::{latest_version, run, schema_version};
+use rusqlite::Connection;
+
+#[test]
+fn new_database_gets_every_migration() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    run(&mut connection).unwrap();
+
+    assert_eq!(schema_version(&connection).unwrap(), latest_version());
+    let count: i64 = connection
+        .query_row("SELECT COUNT(*) FROM checks", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn running_again_changes_nothing() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    run(&mut connection).unwrap();
+    run(&mut connection).unwrap();
+
+    assert_eq!(schema_version(&connection).unwrap(), latest_version());
+}
+
+#[test]
+fn untracked_database_keeps_its_history() {
+    // Databases from before migrations were tracked already had the checks
+    // table, with user_version still at 0.
+    let mut connection = Connection::open_in_memory().unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE checks (
+                id INTEGER PRIMARY KEY,
+                checked_at TEXT NOT NULL,
+                route TEXT NOT NULL,
+                status TEXT NOT NULL,
+                reason TEXT,
+                latency_ms INTEGER NOT NULL
+            );
+            INSERT INTO checks (checked_at, route, status, latency_ms)
+            VALUES ('2026-08-01 08:00:00+00:00', 'here', 'up', 100);",
+        )
+        .unwrap();
+
+    run(&mut connection).unwrap();
+
+    assert_eq!(schema_version(&connection).unwrap(), latest_version());
+    let count: i64 = connection
+        .query_row("SELECT COUNT(*) FROM checks", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn newer_database_is_refused() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    connection
+        .pragma_update(None, "user_version", latest_version() as i64 + 1)
+        .unwrap();
+
+    assert!(run(&mut connection).is_err());
+}
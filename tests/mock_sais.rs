@@ -0,0 +1,404 @@
+use is_sais_down_bot::{
+    config::{LoginDetails, RequestHeaders, SaisConfig, Timeouts},
+    sais::SaisClient,
+    status::{DownReason, ServiceStatus},
+    transport::TimeoutKind,
+};
+use std::time::Duration;
+use wiremock::{
+    matchers::{body_string_contains, header_regex, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const LOGIN_PATH: &str = "/psp/ps/";
+const LOGIN_SUCCESS_STRING: &str = "<title>Employee-facing registry content</title>";
+
+fn sais_config(server_uri: &str) -> SaisConfig {
+    SaisConfig {
+        login_url: format!("{}{}?cmd=login&languageCd=ENG", server_uri, LOGIN_PATH),
+        login_success_string: LOGIN_SUCCESS_STRING.to_string(),
+        maintenance_strings: vec![],
+        max_redirects: 10,
+        trusted_redirect_hosts: vec![],
+        timeouts: Timeouts::default(),
+        degraded_after_secs: None,
+        proxy: None,
+        ip_family: None,
+        dual_stack: false,
+        routes: vec![],
+        deep_checks: vec![],
+        tcp_probe: false,
+        timing_probe: false,
+        error_pages: vec![],
+        session: None,
+        logout_url: None,
+        headers: RequestHeaders::default(),
+    }
+}
+
+fn login_details() -> LoginDetails {
+    LoginDetails {
+        timezoneOffset: -480,
+        userid: "juan@up.edu.ph".to_string(),
+        pwd: "hunter2".to_string(),
+        request_id: 42,
+    }
+}
+
+fn sais_client(server_uri: &str) -> SaisClient {
+    SaisClient::with_config(sais_config(server_uri), login_details())
+}
+
+async fn mock_login_page(server: &MockServer, response: ResponseTemplate) {
+    Mock::given(method("GET"))
+        .and(path(LOGIN_PATH))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+async fn mock_login_post(server: &MockServer, body: &str) {
+    Mock::given(method("POST"))
+        .and(path(LOGIN_PATH))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn login_success_is_up() {
+    let server = MockServer::start().await;
+    mock_login_page(&server, ResponseTemplate::new(200)).await;
+    mock_login_post(
+        &server,
+        &format!("<html><head>{}</head></html>", LOGIN_SUCCESS_STRING),
+    )
+    .await;
+
+    let result = sais_client(&server.uri()).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Up);
+}
+
+#[tokio::test]
+async fn unreachable_server_is_refused_connection() {
+    // Nothing listens on a port that was just released.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let uri = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let result = sais_client(&uri).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Down(DownReason::ConnectionRefused));
+}
+
+#[tokio::test]
+async fn silent_server_is_read_timeout() {
+    let server = MockServer::start().await;
+    mock_login_page(
+        &server,
+        ResponseTemplate::new(200).set_delay(Duration::from_secs(5)),
+    )
+    .await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.timeouts = Timeouts {
+        connect_secs: 1,
+        read_secs: 1,
+        check_secs: 10,
+    };
+
+    let result = SaisClient::with_config(sais_config, login_details())
+        .check()
+        .await
+        .unwrap();
+    assert_eq!(
+        result,
+        ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Read))
+    );
+}
+
+#[tokio::test]
+async fn silent_login_is_login_timeout() {
+    let server = MockServer::start().await;
+    mock_login_page(&server, ResponseTemplate::new(200)).await;
+    Mock::given(method("POST"))
+        .and(path(LOGIN_PATH))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(LOGIN_SUCCESS_STRING)
+                .set_delay(Duration::from_secs(5)),
+        )
+        .mount(&server)
+        .await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.timeouts = Timeouts {
+        connect_secs: 1,
+        read_secs: 1,
+        check_secs: 10,
+    };
+
+    let result = SaisClient::with_config(sais_config, login_details())
+        .check()
+        .await
+        .unwrap();
+    assert_eq!(
+        result,
+        ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Login))
+    );
+    assert_eq!(result.reason().as_deref(), Some("login_timeout"));
+}
+
+#[tokio::test]
+async fn slow_login_is_check_timeout() {
+    let server = MockServer::start().await;
+    mock_login_page(&server, ResponseTemplate::new(200)).await;
+    Mock::given(method("POST"))
+        .and(path(LOGIN_PATH))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(LOGIN_SUCCESS_STRING)
+                .set_delay(Duration::from_secs(5)),
+        )
+        .mount(&server)
+        .await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.timeouts = Timeouts {
+        connect_secs: 1,
+        read_secs: 10,
+        check_secs: 1,
+    };
+
+    let result = SaisClient::with_config(sais_config, login_details())
+        .check()
+        .await
+        .unwrap();
+    assert_eq!(
+        result,
+        ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Check))
+    );
+}
+
+#[tokio::test]
+async fn slow_successful_login_is_degraded() {
+    let server = MockServer::start().await;
+    mock_login_page(&server, ResponseTemplate::new(200)).await;
+    Mock::given(method("POST"))
+        .and(path(LOGIN_PATH))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(LOGIN_SUCCESS_STRING)
+                .set_delay(Duration::from_millis(1500)),
+        )
+        .mount(&server)
+        .await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.degraded_after_secs = Some(1);
+
+    let result = SaisClient::with_config(sais_config, login_details())
+        .check()
+        .await
+        .unwrap();
+    assert!(
+        matches!(result, ServiceStatus::Degraded(latency) if latency >= Duration::from_secs(1))
+    );
+}
+
+#[tokio::test]
+async fn server_error_is_status_code_failure() {
+    let server = MockServer::start().await;
+    mock_login_page(&server, ResponseTemplate::new(502)).await;
+
+    let result = sais_client(&server.uri()).check().await.unwrap();
+    assert_eq!(
+        result,
+        ServiceStatus::Down(DownReason::HttpStatus(reqwest::StatusCode::BAD_GATEWAY))
+    );
+    // A failed login page should not be followed by a login attempt.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+}
+
+#[tokio::test]
+async fn maintenance_page_is_maintenance() {
+    let server = MockServer::start().await;
+    mock_login_page(&server, ResponseTemplate::new(200)).await;
+    mock_login_post(
+        &server,
+        "<html><body>UP SAIS is undergoing Scheduled Maintenance.</body></html>",
+    )
+    .await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.maintenance_strings = vec!["scheduled maintenance".to_string()];
+
+    let result = SaisClient::with_config(sais_config, login_details())
+        .check()
+        .await
+        .unwrap();
+    assert_eq!(result, ServiceStatus::Maintenance);
+}
+
+#[tokio::test]
+async fn maintenance_error_page_is_maintenance() {
+    let server = MockServer::start().await;
+    mock_login_page(
+        &server,
+        ResponseTemplate::new(503).set_body_string("SAIS is under maintenance."),
+    )
+    .await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.maintenance_strings = vec!["under maintenance".to_string()];
+
+    let result = SaisClient::with_config(sais_config, login_details())
+        .check()
+        .await
+        .unwrap();
+    assert_eq!(result, ServiceStatus::Maintenance);
+}
+
+#[tokio::test]
+async fn invalid_credentials_is_credential_problem() {
+    let server = MockServer::start().await;
+    mock_login_page(&server, ResponseTemplate::new(200)).await;
+    mock_login_post(
+        &server,
+        "<html><body>Your UP Email ID and/or Password are invalid.</body></html>",
+    )
+    .await;
+
+    let result = sais_client(&server.uri()).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::CredentialProblem);
+}
+
+#[tokio::test]
+async fn login_posts_credentials_and_cookies_from_login_page() {
+    let server = MockServer::start().await;
+    mock_login_page(
+        &server,
+        ResponseTemplate::new(200).append_header("Set-Cookie", "PS_TOKEN=abc123; Path=/"),
+    )
+    .await;
+    Mock::given(method("POST"))
+        .and(path(LOGIN_PATH))
+        .and(header_regex("cookie", "PS_TOKEN=abc123"))
+        .and(body_string_contains("userid=juan%40up.edu.ph"))
+        .and(body_string_contains("request_id=42"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(LOGIN_SUCCESS_STRING))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let result = sais_client(&server.uri()).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Up);
+}
+
+#[tokio::test]
+async fn cookies_do_not_carry_over_between_checks() {
+    let server = MockServer::start().await;
+    let mut sais_client = sais_client(&server.uri());
+
+    Mock::given(method("GET"))
+        .and(path(LOGIN_PATH))
+        .respond_with(ResponseTemplate::new(200).append_header("Set-Cookie", "PS_TOKEN=first"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    mock_login_page(
+        &server,
+        ResponseTemplate::new(200).append_header("Set-Cookie", "PS_TOKEN=second"),
+    )
+    .await;
+    mock_login_post(&server, LOGIN_SUCCESS_STRING).await;
+
+    sais_client.check().await.unwrap();
+    sais_client.check().await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let last_login = requests
+        .iter()
+        .rev()
+        .find(|request| request.method == wiremock::http::Method::POST)
+        .unwrap();
+    let cookie = last_login.headers.get("cookie").unwrap().to_str().unwrap();
+    assert!(cookie.contains("PS_TOKEN=second"));
+    assert!(!cookie.contains("PS_TOKEN=first"));
+}
+
+#[tokio::test]
+async fn checks_go_through_the_configured_proxy() {
+    // The mock server stands in for the proxy, so the check only succeeds
+    // if both requests are sent to it instead of the unresolvable host.
+    let proxy = MockServer::start().await;
+    mock_login_page(&proxy, ResponseTemplate::new(200)).await;
+    mock_login_post(&proxy, LOGIN_SUCCESS_STRING).await;
+
+    let mut sais_config = sais_config("http://sais.invalid");
+    sais_config.proxy = Some(proxy.uri());
+    let mut sais_client = SaisClient::with_config(sais_config, login_details());
+
+    let result = sais_client.check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Up);
+    assert_eq!(proxy.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn redirects_are_followed_and_recorded() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", LOGIN_PATH))
+        .mount(&server)
+        .await;
+    mock_login_page(&server, ResponseTemplate::new(200)).await;
+    mock_login_post(&server, LOGIN_SUCCESS_STRING).await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.login_url = format!("{}/", server.uri());
+
+    let mut sais_client = SaisClient::with_config(sais_config, login_details());
+    assert_eq!(sais_client.check().await.unwrap(), ServiceStatus::Up);
+    assert_eq!(
+        sais_client.redirects(),
+        &[format!("{}{}", server.uri(), LOGIN_PATH)]
+    );
+    assert_eq!(sais_client.suspicious_redirect(), None);
+}
+
+#[tokio::test]
+async fn redirect_to_another_host_is_suspicious() {
+    let server = MockServer::start().await;
+    let placeholder = server.uri().replace("127.0.0.1", "localhost");
+    mock_login_page(
+        &server,
+        ResponseTemplate::new(302).insert_header("Location", format!("{}/parked", placeholder)),
+    )
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/parked"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("This domain is parked"))
+        .mount(&server)
+        .await;
+    mock_login_post(&server, "This domain is parked").await;
+
+    let mut sais_client = sais_client(&server.uri());
+    sais_client.check().await.unwrap();
+    assert_eq!(
+        sais_client.suspicious_redirect(),
+        Some(format!("{}/parked", placeholder).as_str())
+    );
+}
+
+#[tokio::test]
+async fn redirect_loop_is_response_failure() {
+    let server = MockServer::start().await;
+    mock_login_page(
+        &server,
+        ResponseTemplate::new(302).insert_header("Location", LOGIN_PATH),
+    )
+    .await;
+    let mut sais_config = sais_config(&server.uri());
+    sais_config.max_redirects = 3;
+
+    let result = SaisClient::with_config(sais_config, login_details())
+        .check()
+        .await
+        .unwrap();
+    assert_eq!(result, ServiceStatus::Down(DownReason::NoResponse));
+    assert_eq!(server.received_requests().await.unwrap().len(), 4);
+}
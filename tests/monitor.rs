@@ -0,0 +1,217 @@
+use chrono::{TimeZone, Utc};
+use is_sais_down_bot::{
+    monitor::{
+        Alert, ConfirmedOutage, CredentialWatch, Monitor, MonitorConfig, OutageStreak, Transition,
+        TransitionTracker,
+    },
+    status::{DownReason, ServiceStatus},
+    transport::TimeoutKind,
+};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+const DOWN: ServiceStatus = ServiceStatus::Down(DownReason::NoResponse);
+
+#[test]
+fn first_status_is_not_announced() {
+    let mut tracker = TransitionTracker::new(2);
+
+    assert_eq!(tracker.observe(ServiceStatus::Up), None);
+    assert_eq!(tracker.observe(ServiceStatus::Up), None);
+    assert_eq!(tracker.confirmed(), Some(ServiceStatus::Up));
+}
+
+#[test]
+fn change_is_announced_after_enough_checks_agree() {
+    let mut tracker = TransitionTracker::new(3);
+    for _ in 0..3 {
+        tracker.observe(ServiceStatus::Up);
+    }
+
+    assert_eq!(tracker.observe(DOWN), None);
+    assert_eq!(tracker.observe(DOWN), None);
+    assert_eq!(
+        tracker.observe(DOWN),
+        Some(Transition {
+            from: ServiceStatus::Up,
+            to: DOWN,
+        })
+    );
+    assert_eq!(tracker.observe(DOWN), None);
+}
+
+#[test]
+fn single_flaky_check_is_not_announced() {
+    let mut tracker = TransitionTracker::new(2);
+    tracker.observe(ServiceStatus::Up);
+    tracker.observe(ServiceStatus::Up);
+
+    assert_eq!(
+        tracker.observe(ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Read))),
+        None
+    );
+    assert_eq!(tracker.observe(ServiceStatus::Up), None);
+    assert_eq!(tracker.observe(DOWN), None);
+    assert_eq!(tracker.confirmed(), Some(ServiceStatus::Up));
+}
+
+#[test]
+fn statuses_of_the_same_kind_agree() {
+    let mut tracker = TransitionTracker::new(2);
+    tracker.observe(ServiceStatus::Degraded(Duration::from_secs(20)));
+    tracker.observe(ServiceStatus::Degraded(Duration::from_secs(40)));
+    assert!(matches!(
+        tracker.confirmed(),
+        Some(ServiceStatus::Degraded(_))
+    ));
+
+    assert_eq!(tracker.observe(DOWN), None);
+    assert_eq!(
+        tracker.observe(ServiceStatus::Down(DownReason::HttpStatus(
+            StatusCode::BAD_GATEWAY
+        ))),
+        Some(Transition {
+            from: ServiceStatus::Degraded(Duration::from_secs(40)),
+            to: ServiceStatus::Down(DownReason::HttpStatus(StatusCode::BAD_GATEWAY)),
+        })
+    );
+}
+
+fn flapping_config() -> MonitorConfig {
+    MonitorConfig {
+        confirm_after: 1,
+        flap_window: 6,
+        flap_threshold: 3,
+        ..MonitorConfig::default()
+    }
+}
+
+#[test]
+fn flapping_is_announced_once() {
+    let mut monitor = Monitor::new(&flapping_config());
+    assert_eq!(monitor.observe(ServiceStatus::Up), None);
+    assert_eq!(
+        monitor.observe(DOWN),
+        Some(Alert::Changed(Transition {
+            from: ServiceStatus::Up,
+            to: DOWN,
+        }))
+    );
+    assert!(monitor.observe(ServiceStatus::Up).is_some());
+
+    assert_eq!(monitor.observe(DOWN), Some(Alert::Unstable));
+    assert!(monitor.is_flapping());
+    assert_eq!(monitor.observe(ServiceStatus::Up), None);
+    assert_eq!(monitor.observe(DOWN), None);
+}
+
+#[test]
+fn alerts_resume_once_flapping_settles() {
+    let mut monitor = Monitor::new(&flapping_config());
+    for status in [ServiceStatus::Up, DOWN, ServiceStatus::Up, DOWN] {
+        monitor.observe(status);
+    }
+    assert!(monitor.is_flapping());
+
+    let alerts = (0..6)
+        .filter_map(|_| monitor.observe(DOWN))
+        .collect::<Vec<_>>();
+    assert_eq!(alerts, vec![Alert::Stable(DOWN)]);
+    assert!(!monitor.is_flapping());
+
+    assert_eq!(
+        monitor.observe(ServiceStatus::Up),
+        Some(Alert::Changed(Transition {
+            from: DOWN,
+            to: ServiceStatus::Up,
+        }))
+    );
+}
+
+#[test]
+fn outage_streak_starts_at_the_first_failed_check() {
+    let start = Utc.with_ymd_and_hms(2026, 9, 1, 3, 50, 0).unwrap();
+    let at = |minutes| start + chrono::Duration::minutes(minutes);
+    let mut streak = OutageStreak::default();
+
+    streak.observe(ServiceStatus::Up, at(0));
+    assert_eq!(streak.since(), None);
+    streak.observe(DOWN, at(5));
+    streak.observe(ServiceStatus::PagesFailing, at(10));
+    streak.observe(
+        ServiceStatus::Down(DownReason::HttpStatus(StatusCode::BAD_GATEWAY)),
+        at(15),
+    );
+    assert_eq!(streak.since(), Some(at(5)));
+
+    streak.observe(ServiceStatus::Degraded(Duration::from_secs(20)), at(20));
+    assert_eq!(streak.since(), None);
+}
+
+#[test]
+fn confirmed_outages_last_through_working_checks() {
+    let start = Utc.with_ymd_and_hms(2026, 9, 1, 3, 50, 0).unwrap();
+    let at = |minutes| start + chrono::Duration::minutes(minutes);
+    let mut outage = ConfirmedOutage::default();
+
+    // Not confirmed yet, so a working check ends it.
+    assert_eq!(
+        outage.observe(Some(at(0)), Some(&ServiceStatus::Up)),
+        Some(at(0))
+    );
+    assert_eq!(outage.observe(None, Some(&ServiceStatus::Up)), None);
+
+    assert_eq!(
+        outage.observe(Some(at(5)), Some(&ServiceStatus::Up)),
+        Some(at(5))
+    );
+    assert_eq!(outage.observe(Some(at(5)), Some(&DOWN)), Some(at(5)));
+    // A working check, then a new streak, while still confirmed down.
+    assert_eq!(outage.observe(None, Some(&DOWN)), Some(at(5)));
+    assert_eq!(outage.observe(Some(at(20)), Some(&DOWN)), Some(at(5)));
+
+    assert_eq!(outage.observe(None, Some(&ServiceStatus::Up)), None);
+    assert_eq!(
+        outage.observe(Some(at(40)), Some(&ServiceStatus::Up)),
+        Some(at(40))
+    );
+}
+
+#[test]
+fn reconfiguring_keeps_the_confirmed_status() {
+    let mut monitor = Monitor::new(&flapping_config());
+    assert_eq!(monitor.observe(ServiceStatus::Up), None);
+
+    monitor.reconfigure(&MonitorConfig {
+        confirm_after: 3,
+        ..flapping_config()
+    });
+    assert_eq!(monitor.confirmed(), Some(ServiceStatus::Up));
+    assert_eq!(monitor.observe(DOWN), None);
+    assert_eq!(monitor.observe(DOWN), None);
+    assert_eq!(
+        monitor.observe(DOWN),
+        Some(Alert::Changed(Transition {
+            from: ServiceStatus::Up,
+            to: DOWN,
+        }))
+    );
+}
+
+#[test]
+fn rejected_credentials_are_reported_once_confirmed() {
+    let mut watch = CredentialWatch::new(2);
+
+    assert!(!watch.observe(ServiceStatus::CredentialProblem));
+    assert!(watch.observe(ServiceStatus::CredentialProblem));
+    assert!(!watch.observe(ServiceStatus::CredentialProblem));
+    assert!(!watch.observe(ServiceStatus::Up));
+    assert!(!watch.observe(ServiceStatus::CredentialProblem));
+    assert!(watch.observe(ServiceStatus::CredentialProblem));
+}
+
+#[test]
+fn rejected_credentials_count_as_up_publicly() {
+    assert_eq!(ServiceStatus::CredentialProblem.public(), ServiceStatus::Up);
+    assert_eq!(DOWN.public(), DOWN);
+}
@@ -0,0 +1,65 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use is_sais_down_bot::oncall::{OnCall, OnCallOverride, OnCallRotation};
+
+fn rotation() -> OnCallRotation {
+    OnCallRotation {
+        user_ids: vec![1, 2, 3],
+        starts_on: NaiveDate::from_ymd_opt(2026, 8, 3).unwrap(),
+        shift_days: 7,
+    }
+}
+
+#[test]
+fn takes_turns_by_the_week() {
+    let rotation = rotation();
+    // Before the first turn.
+    assert_eq!(
+        rotation.scheduled(Utc.with_ymd_and_hms(2026, 8, 2, 23, 59, 0).unwrap()),
+        None
+    );
+    let first = Utc.with_ymd_and_hms(2026, 8, 3, 0, 0, 0).unwrap();
+    assert_eq!(rotation.scheduled(first), Some(1));
+    assert_eq!(
+        rotation.shift_ends(first),
+        Some(Utc.with_ymd_and_hms(2026, 8, 10, 0, 0, 0).unwrap())
+    );
+    assert_eq!(
+        rotation.scheduled(Utc.with_ymd_and_hms(2026, 8, 16, 23, 0, 0).unwrap()),
+        Some(2)
+    );
+    assert_eq!(
+        rotation.scheduled(Utc.with_ymd_and_hms(2026, 8, 17, 0, 0, 0).unwrap()),
+        Some(3)
+    );
+    // Starting over after the last.
+    assert_eq!(
+        rotation.scheduled(Utc.with_ymd_and_hms(2026, 8, 24, 0, 0, 0).unwrap()),
+        Some(1)
+    );
+}
+
+#[test]
+fn overrides_cover_until_they_end_and_are_kept() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("oncall.ron");
+    let now = Utc.with_ymd_and_hms(2026, 8, 4, 0, 0, 0).unwrap();
+    let oncall = OnCall::open(rotation(), &path).unwrap();
+    assert_eq!(oncall.current(now), Some(1));
+
+    let cover = OnCallOverride {
+        user_id: 9,
+        until: Utc.with_ymd_and_hms(2026, 8, 5, 0, 0, 0).unwrap(),
+    };
+    oncall.set_override(Some(cover)).unwrap();
+    assert_eq!(oncall.current(now), Some(9));
+    assert_eq!(oncall.cover_at(now), Some(cover));
+    assert_eq!(oncall.current(cover.until), Some(1));
+
+    let reopened = OnCall::open(rotation(), &path).unwrap();
+    assert_eq!(reopened.current(now), Some(9));
+    reopened.set_override(None).unwrap();
+    assert_eq!(
+        OnCall::open(rotation(), &path).unwrap().current(now),
+        Some(1)
+    );
+}
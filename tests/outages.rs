@@ -0,0 +1,72 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    outages::OutageLog,
+    status::{DownReason, ServiceStatus},
+    transport::TimeoutKind,
+};
+use reqwest::StatusCode;
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn timed_out() -> ServiceStatus {
+    ServiceStatus::Down(DownReason::TimedOut(TimeoutKind::Read))
+}
+
+fn bad_gateway() -> ServiceStatus {
+    ServiceStatus::Down(DownReason::HttpStatus(StatusCode::BAD_GATEWAY))
+}
+
+#[test]
+fn outages_are_summed_up_once_sais_works() {
+    let log = OutageLog::default();
+    log.user_checked();
+    log.observe(at(0), &ServiceStatus::Up);
+    log.clear_if_working();
+
+    log.observe(at(5), &timed_out());
+    log.user_checked();
+    log.observe(at(10), &bad_gateway());
+    log.observe(at(15), &ServiceStatus::Maintenance);
+    log.observe(at(20), &timed_out());
+    log.user_checked();
+    assert_eq!(log.summary(), None);
+
+    log.observe(at(25), &ServiceStatus::Up);
+    // SAIS working again isn't reported by people.
+    log.user_checked();
+    log.observe(at(30), &ServiceStatus::Up);
+    let summary = log.summary().unwrap();
+    assert_eq!(summary.started_at, at(5));
+    assert_eq!(summary.ended_at, at(25));
+    assert_eq!(summary.downtime(), Duration::minutes(20));
+    assert_eq!(summary.failed_checks, 3);
+    assert_eq!(
+        summary.reasons,
+        vec![("read_timeout".to_string(), 2), ("http_502".to_string(), 1)]
+    );
+    assert_eq!(summary.user_checks, 2);
+
+    log.clear_if_working();
+    assert_eq!(log.summary(), None);
+}
+
+#[test]
+fn a_working_check_in_between_doesnt_end_it() {
+    let log = OutageLog::default();
+    log.observe(at(0), &timed_out());
+    log.observe(at(5), &ServiceStatus::Up);
+    log.observe(at(10), &timed_out());
+    // Still failing, so nothing is cleared.
+    log.clear_if_working();
+    log.observe(
+        at(15),
+        &ServiceStatus::Degraded(std::time::Duration::from_secs(9)),
+    );
+
+    let summary = log.summary().unwrap();
+    assert_eq!(summary.started_at, at(0));
+    assert_eq!(summary.ended_at, at(15));
+    assert_eq!(summary.failed_checks, 2);
+}
@@ -0,0 +1,44 @@
+use is_sais_down_bot::{phrases::DownPhrases, status::DownReason};
+use reqwest::StatusCode;
+
+#[test]
+fn status_codes_are_phrased_by_code_then_class() {
+    let phrases = DownPhrases::default();
+
+    assert_eq!(
+        phrases.describe(DownReason::HttpStatus(StatusCode::BAD_GATEWAY)),
+        "UP SAIS is down. The proxy in front of it got a bad answer from SAIS itself (502 Bad Gateway)"
+    );
+    assert_eq!(
+        phrases.describe(DownReason::HttpStatus(StatusCode::INTERNAL_SERVER_ERROR)),
+        "UP SAIS is down. Its server ran into an error (500 Internal Server Error)"
+    );
+    assert_eq!(
+        phrases.describe(DownReason::HttpStatus(StatusCode::PERMANENT_REDIRECT)),
+        "UP SAIS is down... (308 Permanent Redirect)"
+    );
+}
+
+#[test]
+fn configured_phrases_win() {
+    let phrases: DownPhrases = ron::de::from_str(
+        r#"{
+            "http_5xx": "SAIS is broken ({code})",
+            "connection_refused": "SAIS is off",
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        phrases.describe(DownReason::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)),
+        "SAIS is broken (503 Service Unavailable)"
+    );
+    assert_eq!(
+        phrases.describe(DownReason::ConnectionRefused),
+        "SAIS is off"
+    );
+    assert_eq!(
+        phrases.describe(DownReason::NoResponse),
+        "dili na gyud muload"
+    );
+}
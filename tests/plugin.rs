@@ -0,0 +1,193 @@
+#![cfg(feature = "plugins")]
+
+use is_sais_down_bot::{
+    config::RequestHeaders,
+    transaction::{self, Step, Transaction, TransactionOutcome},
+    transport::ReqwestTransport,
+};
+use std::path::Path;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+// Reports its whole input.
+const ECHO: &str = r#"(module
+  (import "bot" "report" (func $report (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) i32.const 1024)
+  (func (export "check") (param $ptr i32) (param $len i32) (result i32)
+    (call $report (local.get $ptr) (local.get $len))
+    i32.const 0))"#;
+
+const DOWN: &str = r#"(module
+  (import "bot" "report" (func $report (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "checking\nregistrar is down")
+  (func (export "alloc") (param i32) (result i32) i32.const 1024)
+  (func (export "check") (param i32 i32) (result i32)
+    (call $report (i32.const 0) (i32.const 26))
+    i32.const 2))"#;
+
+const RUNAWAY: &str = r#"(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) i32.const 1024)
+  (func (export "check") (param i32 i32) (result i32)
+    (loop $forever (br $forever))
+    i32.const 0))"#;
+
+const WANTS_FILES: &str = r#"(module
+  (import "wasi_snapshot_preview1" "path_open"
+    (func (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) i32.const 1024)
+  (func (export "check") (param i32 i32) (result i32) i32.const 0))"#;
+
+const GREEDY: &str = r#"(module
+  (memory (export "memory") 2048)
+  (func (export "alloc") (param i32) (result i32) i32.const 1024)
+  (func (export "check") (param i32 i32) (result i32) i32.const 0))"#;
+
+fn write_plugin(dir: &Path, name: &str, wat: &str) -> String {
+    let path = dir.join(format!("{}.wat", name));
+    std::fs::write(&path, wat).unwrap();
+    path.display().to_string()
+}
+
+fn plugin_transaction(steps: Vec<Step>) -> Transaction {
+    Transaction {
+        name: "registrar".to_string(),
+        timeout_secs: 5,
+        steps,
+        logout_url: None,
+        headers: RequestHeaders::default(),
+    }
+}
+
+async fn run(steps: Vec<Step>) -> TransactionOutcome {
+    transaction::run(&ReqwestTransport::default(), &plugin_transaction(steps)).await
+}
+
+#[tokio::test]
+async fn plugins_get_the_last_page_and_their_report_is_checked_like_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let echo = write_plugin(dir.path(), "echo", ECHO);
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/registrar"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Enrolment is <open>"))
+        .mount(&server)
+        .await;
+    std::env::set_var("PLUGIN_TEST_ARG", "second sem");
+
+    let outcome = run(vec![
+        Step::Get(format!("{}/registrar", server.uri())),
+        Step::ExtractBetween {
+            name: "state".to_string(),
+            before: "<".to_string(),
+            after: ">".to_string(),
+        },
+        Step::Plugin {
+            path: echo,
+            args: vec!["{state}".to_string(), "{env:PLUGIN_TEST_ARG}".to_string()],
+        },
+        Step::Expect(r#""status":200"#.to_string()),
+        Step::Expect("Enrolment is <open>".to_string()),
+        Step::Expect(r#""variables":{"state":"open"}"#.to_string()),
+        Step::Expect(r#""args":["open","second sem"]"#.to_string()),
+        Step::ExpectStatus(200),
+    ])
+    .await;
+    // The plugin's report has no status.
+    assert!(matches!(
+        outcome,
+        TransactionOutcome::Failed { step: 8, ref reason } if reason.contains("None")
+    ));
+}
+
+#[tokio::test]
+async fn failing_plugins_fail_the_step_with_their_last_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let echo = write_plugin(dir.path(), "echo", ECHO);
+    let down = write_plugin(dir.path(), "down", DOWN);
+
+    let outcome = run(vec![
+        Step::Plugin {
+            path: echo,
+            args: vec![],
+        },
+        Step::Plugin {
+            path: down,
+            args: vec![],
+        },
+    ])
+    .await;
+    assert!(matches!(
+        outcome,
+        TransactionOutcome::Failed { step: 2, ref reason }
+            if reason.ends_with("returned 2: registrar is down")
+    ));
+}
+
+#[tokio::test]
+async fn missing_plugins_say_so() {
+    let outcome = run(vec![Step::Plugin {
+        path: "/nonexistent/plugin.wasm".to_string(),
+        args: vec![],
+    }])
+    .await;
+    assert!(matches!(
+        outcome,
+        TransactionOutcome::Failed { step: 1, ref reason } if reason.contains("no plugin at")
+    ));
+}
+
+#[tokio::test]
+async fn runaway_plugins_are_stopped() {
+    let dir = tempfile::tempdir().unwrap();
+    let runaway = write_plugin(dir.path(), "runaway", RUNAWAY);
+    let mut transaction = plugin_transaction(vec![Step::Plugin {
+        path: runaway,
+        args: vec![],
+    }]);
+    transaction.timeout_secs = 1;
+
+    let started = std::time::Instant::now();
+    let outcome = transaction::run(&ReqwestTransport::default(), &transaction).await;
+    assert!(started.elapsed() < std::time::Duration::from_secs(3));
+    // Whichever of the timeout and the fuel runs out first.
+    assert!(
+        matches!(outcome, TransactionOutcome::Failed { step: 0, .. })
+            || matches!(
+                outcome,
+                TransactionOutcome::Failed { step: 1, ref reason } if reason.contains("too much work")
+            )
+    );
+}
+
+#[tokio::test]
+async fn plugins_only_get_what_the_bot_offers() {
+    let dir = tempfile::tempdir().unwrap();
+    let wants_files = write_plugin(dir.path(), "wants_files", WANTS_FILES);
+    let greedy = write_plugin(dir.path(), "greedy", GREEDY);
+
+    let outcome = run(vec![Step::Plugin {
+        path: wants_files,
+        args: vec![],
+    }])
+    .await;
+    assert!(matches!(
+        outcome,
+        TransactionOutcome::Failed { step: 1, ref reason } if reason.contains("path_open")
+    ));
+
+    let outcome = run(vec![Step::Plugin {
+        path: greedy,
+        args: vec![],
+    }])
+    .await;
+    assert!(matches!(
+        outcome,
+        TransactionOutcome::Failed { step: 1, .. }
+    ));
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{history::CheckRecord, outages::OutageSummary, postmortem::PostMortem};
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    // 09:00 PHT.
+    Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn record(minutes: i64, status: &str, reason: Option<&str>, latency_ms: u64) -> CheckRecord {
+    CheckRecord {
+        checked_at: at(minutes),
+        route: "here".to_string(),
+        status: status.to_string(),
+        reason: reason.map(str::to_string),
+        latency_ms,
+    }
+}
+
+fn summary() -> OutageSummary {
+    OutageSummary {
+        started_at: at(10),
+        ended_at: at(25),
+        failed_checks: 3,
+        reasons: vec![("read_timeout".to_string(), 2), ("http_502".to_string(), 1)],
+        user_checks: 4,
+    }
+}
+
+fn records() -> Vec<CheckRecord> {
+    vec![
+        // Too long before to be in the timeline.
+        record(-60, "up", None, 100),
+        record(0, "up", None, 400),
+        record(5, "up", None, 600),
+        record(10, "down", Some("read_timeout"), 30000),
+        record(15, "down", Some("read_timeout"), 30000),
+        record(20, "down", Some("http_502"), 200),
+        record(25, "up", None, 500),
+        CheckRecord {
+            route: "proxy".to_string(),
+            ..record(25, "down", Some("no_response"), 100)
+        },
+    ]
+}
+
+#[test]
+fn the_timeline_groups_checks_in_a_row() {
+    let post_mortem = PostMortem::new(summary(), &records());
+    let timeline = post_mortem
+        .timeline
+        .iter()
+        .map(|run| {
+            (
+                run.first_at,
+                run.last_at,
+                run.status.as_str(),
+                run.checks,
+                run.mean_latency_ms,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        timeline,
+        vec![
+            (at(0), at(5), "up", 2, 500),
+            (at(10), at(15), "down", 2, 30000),
+            (at(20), at(20), "down", 1, 200),
+            (at(25), at(25), "up", 1, 500),
+        ]
+    );
+
+    let markdown = post_mortem.to_markdown();
+    assert!(markdown.starts_with("# Post-mortem: UP SAIS outage, Aug 10 09:10 to Aug 10 09:25 PHT"));
+    assert!(markdown.contains(
+        "UP SAIS was down for 15m. 3 checks failed: read_timeout 2, http_502 1. \
+         People checked with &sais 4 times meanwhile."
+    ));
+    assert!(markdown.contains("- 09:00 to 09:05: up, 2 checks, 500 ms on average\n"));
+    assert!(markdown.contains("- 09:20: down (http_502), 1 check, 200 ms on average\n"));
+    assert!(markdown.contains("## Cause"));
+}
+
+#[test]
+fn the_latency_graph_is_a_png() {
+    let png = PostMortem::new(summary(), &records())
+        .latency_png()
+        .unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    // Even without any checks to draw.
+    let png = PostMortem::new(summary(), &[]).latency_png().unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+}
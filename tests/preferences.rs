@@ -0,0 +1,135 @@
+use chrono::{TimeZone, Utc};
+use is_sais_down_bot::{
+    digest::DigestPeriod,
+    monitor::{Alert, Transition},
+    preferences::{Delivery, NotificationKind, NotificationPreferences, PreferencesStore},
+    status::{DownReason, ServiceStatus},
+};
+use std::{collections::BTreeSet, time::Duration};
+
+fn preferences(kinds: &[NotificationKind], delivery: Delivery) -> NotificationPreferences {
+    NotificationPreferences {
+        kinds: kinds.iter().copied().collect::<BTreeSet<_>>(),
+        delivery,
+        muted_until: None,
+        digest: None,
+    }
+}
+
+#[test]
+fn alerts_are_sorted_by_what_they_are_about() {
+    let changed = |to| {
+        Alert::Changed(Transition {
+            from: ServiceStatus::Up,
+            to,
+        })
+    };
+    assert_eq!(
+        NotificationKind::of(changed(ServiceStatus::Down(DownReason::NoResponse))),
+        NotificationKind::Down
+    );
+    assert_eq!(
+        NotificationKind::of(changed(ServiceStatus::Degraded(Duration::from_secs(9)))),
+        NotificationKind::Degraded
+    );
+    assert_eq!(
+        NotificationKind::of(Alert::Stable(ServiceStatus::Up)),
+        NotificationKind::Up
+    );
+    assert_eq!(
+        NotificationKind::of(Alert::Stable(ServiceStatus::Maintenance)),
+        NotificationKind::Maintenance
+    );
+    assert_eq!(
+        NotificationKind::of(Alert::Unstable),
+        NotificationKind::Down
+    );
+}
+
+#[test]
+fn preferences_are_saved_and_matched() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("data").join("preferences.ron");
+
+    let now = Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap();
+    let store = PreferencesStore::open(&path).unwrap();
+    store
+        .set(
+            1,
+            preferences(
+                &[NotificationKind::Down, NotificationKind::Up],
+                Delivery::Dm,
+            ),
+        )
+        .unwrap();
+    store
+        .set(
+            2,
+            preferences(&[NotificationKind::Down], Delivery::Ping { guild_id: 10 }),
+        )
+        .unwrap();
+    store
+        .set(
+            3,
+            preferences(&[NotificationKind::Maintenance], Delivery::Dm),
+        )
+        .unwrap();
+
+    let store = PreferencesStore::open(&path).unwrap();
+    assert_eq!(store.dms(NotificationKind::Down, now), vec![1]);
+    assert_eq!(store.dms(NotificationKind::Up, now), vec![1]);
+    assert!(store.dms(NotificationKind::Degraded, now).is_empty());
+    assert_eq!(store.pings(10, NotificationKind::Down, now), vec![2]);
+    assert!(store.pings(11, NotificationKind::Down, now).is_empty());
+    assert!(store.pings(10, NotificationKind::Up, now).is_empty());
+
+    // Choosing nothing forgets them.
+    store.set(3, preferences(&[], Delivery::Dm)).unwrap();
+    assert_eq!(PreferencesStore::open(&path).unwrap().get(3), None);
+}
+
+#[test]
+fn muted_users_are_left_out_until_it_ends() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("preferences.ron");
+    let now = Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap();
+    let later = now + chrono::Duration::hours(6);
+
+    let store = PreferencesStore::open(&path).unwrap();
+    // Nothing to mute yet.
+    assert!(!store.mute(1, Some(later)).unwrap());
+    store
+        .set(1, preferences(&[NotificationKind::Down], Delivery::Dm))
+        .unwrap();
+    assert!(store.mute(1, Some(later)).unwrap());
+
+    let store = PreferencesStore::open(&path).unwrap();
+    assert!(store.dms(NotificationKind::Down, now).is_empty());
+    assert_eq!(store.dms(NotificationKind::Down, later), vec![1]);
+    store.mute(1, None).unwrap();
+    assert_eq!(store.dms(NotificationKind::Down, now), vec![1]);
+}
+
+#[test]
+fn digest_users_only_get_digests() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("preferences.ron");
+    let now = Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap();
+
+    let store = PreferencesStore::open(&path).unwrap();
+    store
+        .set(
+            1,
+            NotificationPreferences {
+                digest: Some(DigestPeriod::Hourly),
+                ..preferences(&[NotificationKind::Down], Delivery::Dm)
+            },
+        )
+        .unwrap();
+    assert!(store.dms(NotificationKind::Down, now).is_empty());
+    assert_eq!(
+        store.digests(DigestPeriod::Hourly, now),
+        vec![(1, BTreeSet::from([NotificationKind::Down]))]
+    );
+    assert!(store.digests(DigestPeriod::Daily, now).is_empty());
+}
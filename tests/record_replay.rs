@@ -0,0 +1,117 @@
+use is_sais_down_bot::{
+    capture::{RecordingTransport, ReplayTransport},
+    config::{LoginDetails, RequestHeaders, SaisConfig, Timeouts},
+    sais::SaisClient,
+    status::{DownReason, ServiceStatus},
+    transport::{HttpTransport, ReqwestTransport},
+};
+use std::fs;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const LOGIN_SUCCESS_STRING: &str = "<title>Employee-facing registry content</title>";
+const USER_ID: &str = "juan@up.edu.ph";
+const PASSWORD: &str = "hunter2";
+
+fn sais_client(login_url: String, transport: impl HttpTransport + 'static) -> SaisClient {
+    SaisClient::with_transport(
+        SaisConfig {
+            login_url,
+            login_success_string: LOGIN_SUCCESS_STRING.to_string(),
+            maintenance_strings: vec![],
+            max_redirects: 10,
+            trusted_redirect_hosts: vec![],
+            timeouts: Timeouts::default(),
+            degraded_after_secs: None,
+            proxy: None,
+            ip_family: None,
+            dual_stack: false,
+            routes: vec![],
+            deep_checks: vec![],
+            tcp_probe: false,
+            timing_probe: false,
+            error_pages: vec![],
+            session: None,
+            logout_url: None,
+            headers: RequestHeaders::default(),
+        },
+        LoginDetails {
+            timezoneOffset: -480,
+            userid: USER_ID.to_string(),
+            pwd: PASSWORD.to_string(),
+            request_id: 42,
+        },
+        transport,
+    )
+}
+
+#[tokio::test]
+async fn recorded_check_replays_to_the_same_result_without_secrets() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/psp/ps/"))
+        .respond_with(
+            ResponseTemplate::new(200).append_header("Set-Cookie", "PS_TOKEN=abc123; Path=/"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/psp/ps/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+            "{}<span>Welcome, {}</span>",
+            LOGIN_SUCCESS_STRING, USER_ID
+        )))
+        .mount(&server)
+        .await;
+    let login_url = format!("{}/psp/ps/?cmd=login", server.uri());
+    let dir = tempfile::tempdir().unwrap();
+
+    let recording = RecordingTransport::new(
+        ReqwestTransport::default(),
+        dir.path(),
+        vec![USER_ID.to_string(), PASSWORD.to_string()],
+    )
+    .unwrap();
+    let recorded_result = sais_client(login_url.clone(), recording)
+        .check()
+        .await
+        .unwrap();
+    assert_eq!(recorded_result, ServiceStatus::Up);
+
+    let recordings = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(recordings.len(), 2);
+    for recording in &recordings {
+        assert!(!recording.contains(USER_ID));
+        assert!(!recording.contains(PASSWORD));
+        assert!(!recording.contains("abc123"));
+    }
+
+    drop(server);
+    let replay = ReplayTransport::from_dir(dir.path()).unwrap();
+    let replayed_result = sais_client(login_url, replay).check().await.unwrap();
+    assert_eq!(replayed_result, recorded_result);
+}
+
+#[tokio::test]
+async fn recorded_connection_failure_replays_as_response_failure() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let login_url = format!("http://{}/psp/ps/", listener.local_addr().unwrap());
+    drop(listener);
+    let dir = tempfile::tempdir().unwrap();
+
+    let recording =
+        RecordingTransport::new(ReqwestTransport::default(), dir.path(), vec![]).unwrap();
+    sais_client(login_url.clone(), recording)
+        .check()
+        .await
+        .unwrap();
+
+    let replay = ReplayTransport::from_dir(dir.path()).unwrap();
+    let result = sais_client(login_url, replay).check().await.unwrap();
+    assert_eq!(result, ServiceStatus::Down(DownReason::NoResponse));
+}
@@ -0,0 +1,52 @@
+use is_sais_down_bot::redact::{self, REDACTED};
+
+#[test]
+fn added_secrets_are_redacted_wherever_they_appear() {
+    redact::add_secret("correct horse battery");
+    redact::add_secret("");
+
+    assert_eq!(
+        redact::redact("Logging in with correct horse battery failed"),
+        format!("Logging in with {} failed", REDACTED)
+    );
+    assert_eq!(redact::redact("nothing to see"), "nothing to see");
+}
+
+#[test]
+fn longer_secrets_are_redacted_before_ones_they_contain() {
+    redact::add_secret("abcd1234");
+    redact::add_secret("https://discord.com/api/webhooks/1/abcd1234efgh");
+
+    assert_eq!(
+        redact::redact("Posting to https://discord.com/api/webhooks/1/abcd1234efgh"),
+        format!("Posting to {}", REDACTED)
+    );
+}
+
+#[test]
+fn cookies_and_authorization_headers_are_redacted() {
+    assert_eq!(
+        redact::redact("Cookie: PS_TOKEN=abc; PS_LOGINLIST=def\nHost: sais"),
+        format!("Cookie: {}\nHost: sais", REDACTED)
+    );
+    assert_eq!(
+        redact::redact(r#"{"set-cookie": "PS_TOKEN=abc; Path=/", "server": "nginx"}"#),
+        format!(r#"{{"set-cookie": "{}", "server": "nginx"}}"#, REDACTED)
+    );
+    assert_eq!(
+        redact::redact("sent Bearer s3cretvalue123 to /graphql"),
+        format!("sent Bearer {} to /graphql", REDACTED)
+    );
+    assert_eq!(
+        redact::redact("paged with GenieKey 0a1b2c3d-4e5f to Opsgenie"),
+        format!("paged with GenieKey {} to Opsgenie", REDACTED)
+    );
+}
+
+#[test]
+fn password_parameters_are_redacted() {
+    assert_eq!(
+        redact::redact("POST userid=juan&pwd=hunter2&request_id=42"),
+        format!("POST userid=juan&pwd={}&request_id=42", REDACTED)
+    );
+}
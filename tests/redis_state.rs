@@ -0,0 +1,175 @@
+use chrono::{TimeZone, Utc};
+use is_sais_down_bot::{
+    history::CheckRecord,
+    redis_state::{self, RedisState, Reply},
+    state::SharedState,
+};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    task::JoinHandle,
+};
+
+// Answers one connection's commands with the given replies, in order,
+// returning the commands it got.
+async fn fake_redis(replies: Vec<&'static str>) -> (String, JoinHandle<Vec<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("redis://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        let mut commands = vec![];
+        for reply in replies {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            let count = line.trim_end()[1..].parse::<usize>().unwrap();
+            let mut args = vec![];
+            for _ in 0..count {
+                // The length, then the argument.
+                line.clear();
+                stream.read_line(&mut line).await.unwrap();
+                line.clear();
+                stream.read_line(&mut line).await.unwrap();
+                args.push(line.trim_end().to_string());
+            }
+            commands.push(args);
+            stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+        }
+        commands
+    });
+    (url, server)
+}
+
+fn key(name: &str) -> String {
+    format!("{}{}", redis_state::KEY_PREFIX, name)
+}
+
+#[test]
+fn commands_are_sent_as_arrays_of_bulk_strings() {
+    assert_eq!(
+        redis_state::encode(&["GET", "is-sais-down-bot:latest_status"]),
+        b"*2\r\n$3\r\nGET\r\n$30\r\nis-sais-down-bot:latest_status\r\n".to_vec()
+    );
+}
+
+#[test]
+fn only_redis_urls_are_accepted() {
+    assert!(RedisState::new("redis://127.0.0.1:6379/2").is_ok());
+    assert!(RedisState::new("http://127.0.0.1:6379").is_err());
+    assert!(RedisState::new("redis://127.0.0.1:6379/first").is_err());
+}
+
+#[tokio::test]
+async fn it_logs_in_and_picks_the_database_first() {
+    let (url, server) = fake_redis(vec!["+OK\r\n", "+OK\r\n", "$-1\r\n"]).await;
+    let url = url.replace("redis://", "redis://:hunter22@") + "/3";
+    let state = RedisState::new(&url).unwrap();
+
+    assert_eq!(state.latest_status().await.unwrap(), None);
+    assert_eq!(
+        server.await.unwrap(),
+        vec![
+            vec!["AUTH".to_string(), "hunter22".to_string()],
+            vec!["SELECT".to_string(), "3".to_string()],
+            vec!["GET".to_string(), key("latest_status")],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn cooldowns_left_come_from_the_key_expiry() {
+    let (url, server) = fake_redis(vec!["+OK\r\n", "$-1\r\n", ":1500\r\n"]).await;
+    let state = RedisState::new(&url).unwrap();
+
+    assert_eq!(
+        state
+            .take_cooldown("sais:1", Duration::from_secs(5))
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        state
+            .take_cooldown("sais:1", Duration::from_secs(5))
+            .await
+            .unwrap(),
+        Some(Duration::from_millis(1500))
+    );
+    let commands = server.await.unwrap();
+    assert_eq!(
+        commands[0],
+        vec!["SET", &key("cooldown:sais:1"), "1", "PX", "5000", "NX"]
+    );
+    assert_eq!(commands[2], vec!["PTTL", &key("cooldown:sais:1")]);
+}
+
+#[tokio::test]
+async fn the_latest_status_is_kept_as_json() {
+    let record = CheckRecord {
+        checked_at: Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap(),
+        route: "here".to_string(),
+        status: "up".to_string(),
+        reason: None,
+        latency_ms: 420,
+    };
+    let json = serde_json::to_string(&record).unwrap();
+    let reply: &'static str =
+        Box::leak(format!("${}\r\n{}\r\n", json.len(), json).into_boxed_str());
+    let (url, server) = fake_redis(vec!["+OK\r\n", reply]).await;
+    let state = RedisState::new(&url).unwrap();
+
+    state.set_latest_status(&record).await.unwrap();
+    assert_eq!(state.latest_status().await.unwrap(), Some(record));
+    assert_eq!(
+        server.await.unwrap()[0],
+        vec!["SET".to_string(), key("latest_status"), json]
+    );
+}
+
+#[tokio::test]
+async fn locks_are_taken_with_an_expiry_and_let_go_only_by_their_holder() {
+    let (url, server) = fake_redis(vec!["+OK\r\n", "$-1\r\n", ":1\r\n"]).await;
+    let state = RedisState::new(&url).unwrap();
+
+    assert!(state
+        .try_lock("check", "a", Duration::from_secs(60))
+        .await
+        .unwrap());
+    assert!(!state
+        .try_lock("check", "b", Duration::from_secs(60))
+        .await
+        .unwrap());
+    state.unlock("check", "a").await.unwrap();
+
+    let commands = server.await.unwrap();
+    assert_eq!(
+        commands[0],
+        vec!["SET", &key("lock:check"), "a", "PX", "60000", "NX"]
+    );
+    assert_eq!(commands[2][0], "EVAL");
+    assert_eq!(commands[2][2..], ["1", &key("lock:check"), "a"]);
+}
+
+#[tokio::test]
+async fn error_replies_are_errors() {
+    let (url, _server) = fake_redis(vec!["-NOAUTH Authentication required.\r\n"]).await;
+    let state = RedisState::new(&url).unwrap();
+
+    let why = state.command(&["GET", "x"]).await.unwrap_err();
+    assert!(why.to_string().contains("NOAUTH"));
+}
+
+#[tokio::test]
+async fn nested_replies_are_read_whole() {
+    let (url, _server) = fake_redis(vec!["*2\r\n:1\r\n*1\r\n$2\r\nhi\r\n"]).await;
+    let state = RedisState::new(&url).unwrap();
+
+    assert_eq!(
+        state.command(&["EXEC"]).await.unwrap(),
+        Reply::Array(Some(vec![
+            Reply::Integer(1),
+            Reply::Array(Some(vec![Reply::Bulk(Some("hi".to_string()))])),
+        ]))
+    );
+}
@@ -0,0 +1,103 @@
+use is_sais_down_bot::releases::{self, Release, ReleaseWatch};
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn release(tag_name: &str, body: Option<&str>) -> Release {
+    Release {
+        tag_name: tag_name.to_string(),
+        html_url: format!(
+            "https://github.com/grantyap/is-sais-down-bot/releases/tag/{}",
+            tag_name
+        ),
+        body: body.map(str::to_string),
+    }
+}
+
+#[test]
+fn versions_are_compared_by_number() {
+    assert!(release("v0.2.0", None).is_newer_than("0.1.0"));
+    assert!(release("0.10.0", None).is_newer_than("0.9.3"));
+    assert!(release("v1.0", None).is_newer_than("0.9.9"));
+    assert!(release("v0.1.1-beta", None).is_newer_than("0.1.0"));
+    assert!(!release("v0.1.0", None).is_newer_than("0.1.0"));
+    assert!(!release("v0.0.9", None).is_newer_than("0.1.0"));
+    assert!(!release("nightly", None).is_newer_than("0.1.0"));
+}
+
+#[test]
+fn long_changelogs_are_cut_at_a_line_break() {
+    let notes = "- Added &about\n- Checks for new releases\n- Fixed a typo";
+    assert_eq!(
+        release("v0.2.0", Some(notes))
+            .changelog_excerpt(30)
+            .as_deref(),
+        Some("- Added &about\n...")
+    );
+    assert_eq!(
+        release("v0.2.0", Some(notes))
+            .changelog_excerpt(1000)
+            .as_deref(),
+        Some(notes)
+    );
+    assert_eq!(
+        release("v0.2.0", Some("  \n")).changelog_excerpt(1000),
+        None
+    );
+    assert_eq!(release("v0.2.0", None).changelog_excerpt(1000), None);
+}
+
+#[test]
+fn each_newer_release_is_found_once() {
+    let watch = ReleaseWatch::default();
+
+    assert!(!watch.found(release(&format!("v{}", releases::CURRENT_VERSION), None)));
+    assert_eq!(watch.newer_release(), None);
+
+    assert!(watch.found(release("v99.0.0", None)));
+    assert!(!watch.found(release("v99.0.0", Some("Edited notes"))));
+    assert!(watch.found(release("v99.1.0", None)));
+    assert_eq!(
+        watch.newer_release().map(|release| release.tag_name),
+        Some("v99.1.0".to_string())
+    );
+}
+
+#[tokio::test]
+async fn the_latest_release_is_fetched_from_github() {
+    let github = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/repos/grantyap/is-sais-down-bot/releases/latest"))
+        .and(header("Accept", "application/vnd.github+json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{
+                "tag_name": "v0.2.0",
+                "html_url": "https://github.com/grantyap/is-sais-down-bot/releases/tag/v0.2.0",
+                "body": "- Added &about",
+                "draft": false
+            }"#,
+        ))
+        .mount(&github)
+        .await;
+
+    let latest = releases::latest_release(&format!(
+        "{}/repos/grantyap/is-sais-down-bot/releases/latest",
+        github.uri()
+    ))
+    .await
+    .unwrap();
+
+    assert_eq!(latest, release("v0.2.0", Some("- Added &about")));
+}
+
+#[tokio::test]
+async fn a_missing_release_is_an_error() {
+    let github = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&github)
+        .await;
+
+    assert!(releases::latest_release(&github.uri()).await.is_err());
+}
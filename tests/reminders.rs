@@ -0,0 +1,40 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::reminders::{ReminderConfig, ReminderSchedule};
+
+fn announced_at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap()
+}
+
+#[test]
+fn reminders_back_off_up_to_the_max_gap() {
+    let config = ReminderConfig {
+        max_gap_mins: 120,
+        ..ReminderConfig::default()
+    };
+    let mut schedule = ReminderSchedule::new(&config, announced_at()).unwrap();
+    let at = |minutes| announced_at() + Duration::minutes(minutes);
+
+    let mut reminded_at = vec![];
+    for minutes in (0..=600).step_by(5) {
+        if schedule.due(at(minutes)) {
+            reminded_at.push(minutes);
+        }
+    }
+    // 30 minutes, then an hour, then two hours each time.
+    assert_eq!(reminded_at, vec![30, 90, 210, 330, 450, 570]);
+}
+
+#[test]
+fn missed_reminders_only_come_once() {
+    let mut schedule = ReminderSchedule::new(&ReminderConfig::default(), announced_at()).unwrap();
+    let late = announced_at() + Duration::hours(5);
+    assert!(schedule.due(late));
+    assert!(!schedule.due(late + Duration::minutes(5)));
+    assert_eq!(schedule.next_at(), late + Duration::hours(1));
+}
+
+#[test]
+fn reminders_can_be_turned_off() {
+    let config: ReminderConfig = ron::de::from_str("(enabled: false)").unwrap();
+    assert_eq!(ReminderSchedule::new(&config, announced_at()), None);
+}
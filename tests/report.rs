@@ -0,0 +1,136 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{
+    history::{CheckRecord, HourlyRollup},
+    report::{MonthlyReport, Outage, Reliability, ServiceSummary},
+};
+
+fn record(checked_at: DateTime<Utc>, status: &str, reason: Option<&str>) -> CheckRecord {
+    CheckRecord {
+        checked_at,
+        route: "here".to_string(),
+        status: status.to_string(),
+        reason: reason.map(str::to_string),
+        latency_ms: 100,
+    }
+}
+
+#[test]
+fn outages_are_runs_of_failed_checks() {
+    let start = Utc.with_ymd_and_hms(2026, 8, 31, 16, 0, 0).unwrap();
+    let at = |minutes| start + Duration::minutes(minutes);
+    let records = vec![
+        record(at(0), "up", None),
+        record(at(5), "down", Some("http_502")),
+        record(at(10), "down", Some("read_timeout")),
+        record(at(15), "up", None),
+        // Maintenance neither starts nor ends an outage.
+        record(at(20), "maintenance", None),
+        record(at(25), "pages_failing", None),
+        record(at(30), "down", Some("http_502")),
+    ];
+
+    let report = MonthlyReport::from_history(start, records, &[]);
+    assert_eq!(
+        report.outages,
+        vec![
+            Outage {
+                started_at: at(5),
+                ended_at: at(15),
+                cause: "http_502".to_string(),
+            },
+            Outage {
+                started_at: at(25),
+                ended_at: at(30),
+                cause: "pages_failing".to_string(),
+            },
+        ]
+    );
+    assert_eq!(report.checks, 6);
+    assert_eq!(
+        report.causes,
+        vec![
+            ("http_502".to_string(), 2),
+            ("pages_failing".to_string(), 1),
+            ("read_timeout".to_string(), 1),
+        ]
+    );
+}
+
+#[test]
+fn rollups_count_towards_uptime_and_latency() {
+    let start = Utc.with_ymd_and_hms(2026, 8, 31, 16, 0, 0).unwrap();
+    let rollups = vec![
+        HourlyRollup {
+            hour: start,
+            route: "here".to_string(),
+            status: "up".to_string(),
+            checks: 9,
+            total_latency_ms: 9 * 200,
+        },
+        HourlyRollup {
+            hour: start,
+            route: "here".to_string(),
+            status: "down".to_string(),
+            checks: 1,
+            total_latency_ms: 30_000,
+        },
+        HourlyRollup {
+            hour: start,
+            route: "campus".to_string(),
+            status: "down".to_string(),
+            checks: 10,
+            total_latency_ms: 0,
+        },
+    ];
+    let records = vec![record(start + Duration::days(14), "up", None)];
+
+    let report = MonthlyReport::from_history(start, records, &rollups);
+    assert_eq!(report.checks, 11);
+    assert!((report.uptime_percent - 100.0 * 10.0 / 11.0).abs() < 1e-9);
+    assert_eq!(report.weekly_latency_ms, vec![3180, 0, 100]);
+    assert!(report.outages.is_empty());
+}
+
+#[test]
+fn reliability_averages_outages_and_the_time_between_them() {
+    let start = Utc.with_ymd_and_hms(2026, 8, 31, 16, 0, 0).unwrap();
+    let outage = |from, to| Outage {
+        started_at: start + Duration::hours(from),
+        ended_at: start + Duration::hours(to),
+        cause: "no_response".to_string(),
+    };
+
+    let reliability =
+        Reliability::from_outages(&[outage(1, 2), outage(10, 13)], Duration::hours(24));
+    assert_eq!(reliability.outages, 2);
+    assert_eq!(reliability.mean_time_to_recovery, Some(Duration::hours(2)));
+    assert_eq!(
+        reliability.mean_time_between_failures,
+        Some(Duration::hours(10))
+    );
+
+    let reliability = Reliability::from_outages(&[], Duration::hours(24));
+    assert_eq!(reliability.mean_time_to_recovery, None);
+}
+
+#[test]
+fn summary_has_uptime_median_latency_and_latest_status() {
+    let start = Utc.with_ymd_and_hms(2026, 9, 1, 0, 0, 0).unwrap();
+    let mut records = vec![];
+    for (minutes, status, latency_ms) in [(0, "up", 300), (5, "down", 30_000), (10, "up", 200)] {
+        let mut record = record(start + Duration::minutes(minutes), status, None);
+        record.latency_ms = latency_ms;
+        records.push(record);
+    }
+    records.push(CheckRecord {
+        route: "campus".to_string(),
+        ..record(start + Duration::minutes(15), "down", None)
+    });
+
+    let summary = ServiceSummary::from_records(&records);
+    assert!((summary.uptime_percent.unwrap() - 200.0 / 3.0).abs() < 1e-9);
+    assert_eq!(summary.median_latency_ms, Some(300));
+    assert_eq!(summary.latest_status.as_deref(), Some("up"));
+
+    assert_eq!(ServiceSummary::from_records(&[]).uptime_percent, None);
+}
@@ -0,0 +1,170 @@
+use is_sais_down_bot::{
+    config::{LoginDetails, RequestHeaders, Route, SaisConfig, Timeouts},
+    routes::{routes_disagree, RouteChecker, RouteResult, MAIN_ROUTE_NAME},
+    sais::SaisClient,
+    status::{DownReason, ServiceStatus},
+};
+use reqwest::StatusCode;
+use std::time::Duration;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const LOGIN_SUCCESS_STRING: &str = "<title>Employee-facing registry content</title>";
+
+// Mock servers stand in for the proxies each route goes through.
+async fn proxy_serving(login_page_status: u16) -> MockServer {
+    let proxy = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/psp/ps/"))
+        .respond_with(ResponseTemplate::new(login_page_status))
+        .mount(&proxy)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/psp/ps/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(LOGIN_SUCCESS_STRING))
+        .mount(&proxy)
+        .await;
+    proxy
+}
+
+fn route_checker(main_proxy: &MockServer, routes: Vec<Route>) -> RouteChecker {
+    RouteChecker::new(SaisClient::with_config(
+        SaisConfig {
+            login_url: "http://sais.invalid/psp/ps/?cmd=login".to_string(),
+            login_success_string: LOGIN_SUCCESS_STRING.to_string(),
+            maintenance_strings: vec![],
+            max_redirects: 10,
+            trusted_redirect_hosts: vec![],
+            timeouts: Timeouts::default(),
+            degraded_after_secs: None,
+            proxy: Some(main_proxy.uri()),
+            ip_family: None,
+            dual_stack: false,
+            routes,
+            deep_checks: vec![],
+            tcp_probe: false,
+            timing_probe: false,
+            error_pages: vec![],
+            session: None,
+            logout_url: None,
+            headers: RequestHeaders::default(),
+        },
+        LoginDetails {
+            timezoneOffset: -480,
+            userid: "juan@up.edu.ph".to_string(),
+            pwd: "hunter2".to_string(),
+            request_id: 42,
+        },
+    ))
+}
+
+#[tokio::test]
+async fn each_route_is_checked_through_its_own_proxy() {
+    let main_proxy = proxy_serving(503).await;
+    let campus_proxy = proxy_serving(200).await;
+    let checker = route_checker(
+        &main_proxy,
+        vec![Route {
+            name: "campus".to_string(),
+            proxy: Some(campus_proxy.uri()),
+            ip_family: None,
+        }],
+    );
+
+    let results = checker.check_all().await.unwrap();
+    // Latency varies between runs.
+    let results_without_latency = results
+        .iter()
+        .cloned()
+        .map(|result| RouteResult {
+            latency: Duration::ZERO,
+            ..result
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results_without_latency,
+        vec![
+            RouteResult {
+                route: MAIN_ROUTE_NAME.to_string(),
+                status: ServiceStatus::Down(DownReason::HttpStatus(
+                    StatusCode::SERVICE_UNAVAILABLE
+                )),
+                suspicious_redirect: None,
+                failing_pages: vec![],
+                error_page: None,
+                latency: Duration::ZERO,
+            },
+            RouteResult {
+                route: "campus".to_string(),
+                status: ServiceStatus::Up,
+                suspicious_redirect: None,
+                failing_pages: vec![],
+                error_page: None,
+                latency: Duration::ZERO,
+            },
+        ]
+    );
+    assert!(routes_disagree(&results));
+}
+
+#[tokio::test]
+async fn agreeing_routes_do_not_disagree() {
+    let main_proxy = proxy_serving(200).await;
+    let other_proxy = proxy_serving(200).await;
+    let checker = route_checker(
+        &main_proxy,
+        vec![Route {
+            name: "outside PH".to_string(),
+            proxy: Some(other_proxy.uri()),
+            ip_family: None,
+        }],
+    );
+
+    let results = checker.check_all().await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(!routes_disagree(&results));
+}
+
+#[tokio::test]
+async fn dual_stack_only_checks_families_the_host_has() {
+    let server = proxy_serving(200).await;
+    let checker = RouteChecker::new(SaisClient::with_config(
+        SaisConfig {
+            // An IPv4 literal has no IPv6 address to check.
+            login_url: format!("{}/psp/ps/?cmd=login", server.uri()),
+            login_success_string: LOGIN_SUCCESS_STRING.to_string(),
+            maintenance_strings: vec![],
+            max_redirects: 10,
+            trusted_redirect_hosts: vec![],
+            timeouts: Timeouts::default(),
+            degraded_after_secs: None,
+            proxy: None,
+            ip_family: None,
+            dual_stack: true,
+            routes: vec![],
+            deep_checks: vec![],
+            tcp_probe: false,
+            timing_probe: false,
+            error_pages: vec![],
+            session: None,
+            logout_url: None,
+            headers: RequestHeaders::default(),
+        },
+        LoginDetails {
+            timezoneOffset: -480,
+            userid: "juan@up.edu.ph".to_string(),
+            pwd: "hunter2".to_string(),
+            request_id: 42,
+        },
+    ));
+
+    let results = checker.check_all().await.unwrap();
+    let routes = results
+        .iter()
+        .map(|result| result.route.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(routes, vec![MAIN_ROUTE_NAME, "IPv4"]);
+    assert!(!routes_disagree(&results));
+}
@@ -0,0 +1,56 @@
+use is_sais_down_bot::{heartbeat::Heartbeat, runtime_stats};
+use std::time::{Duration, Instant};
+
+#[test]
+fn resident_memory_is_read_from_proc_status() {
+    let status =
+        "Name:\tis-sais-down-bo\nVmPeak:\t  812344 kB\nVmRSS:\t   45312 kB\nThreads:\t12\n";
+    assert_eq!(
+        runtime_stats::parse_resident_memory(status),
+        Some(45312 * 1024)
+    );
+    assert_eq!(runtime_stats::parse_resident_memory("Name:\tbot\n"), None);
+}
+
+#[test]
+fn sizes_are_shown_in_binary_units() {
+    assert_eq!(runtime_stats::format_bytes(512), "512 B");
+    assert_eq!(runtime_stats::format_bytes(2048), "2.0 KiB");
+    assert_eq!(runtime_stats::format_bytes(45312 * 1024), "44.2 MiB");
+    assert_eq!(
+        runtime_stats::format_bytes(3 * 1024 * 1024 * 1024),
+        "3.0 GiB"
+    );
+}
+
+#[test]
+fn a_loop_on_time_has_no_lag() {
+    let heartbeat = Heartbeat::new("monitor", Duration::from_secs(360));
+    let beat_at = Instant::now();
+    heartbeat.beat_at(beat_at);
+    let interval = Duration::from_secs(60);
+
+    assert_eq!(
+        runtime_stats::loop_lag(&heartbeat, interval, beat_at + Duration::from_secs(30)),
+        Duration::ZERO
+    );
+    assert_eq!(
+        runtime_stats::loop_lag(&heartbeat, interval, beat_at + Duration::from_secs(75)),
+        Duration::from_secs(15)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tasks_are_counted_on_the_current_runtime() {
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let task = tokio::spawn(async move {
+        let _ = stopped.await;
+    });
+
+    let counts = runtime_stats::task_counts();
+    assert_eq!(counts.workers, 2);
+    assert!(counts.alive >= 1);
+
+    stop.send(()).unwrap();
+    task.await.unwrap();
+}
@@ -0,0 +1,55 @@
+use is_sais_down_bot::{
+    capture::RecordingTransport,
+    config::{LoginDetails, SaisConfig},
+    history::CheckRecord,
+    sais::SaisClient,
+    transport::ReqwestTransport,
+};
+use std::process::Command;
+
+#[tokio::test]
+async fn replayed_outage_is_printed_and_exits_with_1() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let dir = tempfile::tempdir().unwrap();
+    let mut sais_config = SaisConfig::get().unwrap();
+    sais_config.login_url = format!("http://{}/psp/ps/", address);
+    let login_details = LoginDetails {
+        timezoneOffset: -480,
+        userid: "juan@up.edu.ph".to_string(),
+        pwd: "hunter2".to_string(),
+        request_id: 42,
+    };
+    let recording =
+        RecordingTransport::new(ReqwestTransport::default(), dir.path(), vec![]).unwrap();
+    SaisClient::with_transport(sais_config, login_details, recording)
+        .check()
+        .await
+        .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_sais-check"))
+        .arg("--json")
+        .env("SAIS_REPLAY_DIR", dir.path())
+        .env("TIMEZONE_OFFSET", "-480")
+        .env("USER_ID", "juan@up.edu.ph")
+        .env("PASSWORD", "hunter2")
+        .env("REQUEST_ID", "42")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let record: CheckRecord = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(record.route, "here");
+    assert_eq!(record.status, "down");
+    assert_eq!(record.reason.as_deref(), Some("no_response"));
+}
+
+#[test]
+fn unknown_arguments_exit_with_2() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sais-check"))
+        .arg("--bogus")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage: sais-check"));
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "discord")]
+
+use is_sais_down_bot::schema;
+use std::{fs, process::Command};
+
+#[test]
+fn samples_and_schemas_are_generated() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_is-sais-down-bot"))
+        .arg("--generate-config")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    for name in ["bot", "discord", "sais", "transactions"] {
+        let sample = fs::read_to_string(dir.path().join(format!("{}.ron", name))).unwrap();
+        assert_eq!(
+            sample,
+            fs::read_to_string(format!("config/{}.ron", name)).unwrap()
+        );
+        let schema: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(dir.path().join(format!("{}.schema.json", name))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(schema["title"], format!("{}.ron", name));
+        // Every option should be in the sample, commented if it's optional.
+        for path in schema::field_paths(&schema) {
+            let field = path.rsplit('.').next().unwrap();
+            assert!(
+                sample.contains(&format!("{}:", field)),
+                "config/{}.ron doesn't mention {}",
+                name,
+                path
+            );
+        }
+    }
+}
+
+#[test]
+fn existing_files_are_not_overwritten() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("bot.ron"), "()").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_is-sais-down-bot"))
+        .arg("--generate-config")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("bot.ron")).unwrap(),
+        "()"
+    );
+}
@@ -0,0 +1,113 @@
+use is_sais_down_bot::{config::SaisConfig, schema};
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::HashMap, fs};
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct Example {
+    name: String,
+    #[serde(default)]
+    retries: Option<u32>,
+    tags: Vec<String>,
+    headers: Vec<(String, String)>,
+    limits: HashMap<String, f64>,
+    backend: Backend,
+    #[serde(skip)]
+    cache: Vec<u8>,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+enum Backend {
+    Memory,
+    File(String),
+    Server {
+        address: std::net::SocketAddr,
+        nested: Option<Backend2>,
+    },
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+enum Backend2 {
+    A,
+    B(bool),
+}
+
+#[test]
+fn schemas_follow_the_types() {
+    let schema = schema::json_schema::<Example>("example.ron").unwrap();
+
+    assert_eq!(schema["$schema"], schema::SCHEMA_DRAFT);
+    assert_eq!(schema["title"], "example.ron");
+    assert_eq!(schema["additionalProperties"], false);
+    let properties = &schema["properties"];
+    assert_eq!(properties["name"], json!({ "type": "string" }));
+    assert_eq!(
+        properties["retries"],
+        json!({ "anyOf": [{ "type": "integer", "minimum": 0 }, { "type": "null" }] })
+    );
+    assert_eq!(
+        properties["tags"],
+        json!({ "type": "array", "items": { "type": "string" } })
+    );
+    assert_eq!(properties["headers"]["items"]["maxItems"], 2);
+    assert_eq!(
+        properties["limits"],
+        json!({ "type": "object", "additionalProperties": { "type": "number" } })
+    );
+    assert!(properties.get("cache").is_none());
+    assert_eq!(
+        properties["backend"],
+        json!({ "$ref": "#/definitions/Backend" })
+    );
+
+    let backend = &schema["definitions"]["Backend"]["oneOf"];
+    assert_eq!(backend[0], json!({ "const": "Memory" }));
+    assert_eq!(
+        backend[1]["properties"]["File"],
+        json!({ "type": "string" })
+    );
+    assert_eq!(
+        backend[2]["properties"]["Server"]["properties"]["address"],
+        json!({ "type": "string" })
+    );
+    // Only reachable through one of Backend's variants.
+    let backend2 = &schema["definitions"]["Backend2"]["oneOf"];
+    assert_eq!(backend2.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn field_paths_include_nested_and_variant_fields() {
+    let schema = schema::json_schema::<Example>("example.ron").unwrap();
+    let paths = schema::field_paths(&schema);
+
+    for path in [
+        "name",
+        "tags",
+        "backend",
+        "backend.address",
+        "backend.nested",
+    ] {
+        assert!(paths.contains(path), "{} is missing from {:?}", path, paths);
+    }
+    assert!(!paths.contains("backend.Server"));
+}
+
+// Every option should be in the sample, commented if it's optional, so it
+// can be found without reading the code.
+#[test]
+fn sample_sais_config_mentions_every_field() {
+    let schema = schema::json_schema::<SaisConfig>("sais.ron").unwrap();
+    let sample = fs::read_to_string("config/sais.ron").unwrap();
+
+    for path in schema::field_paths(&schema) {
+        let field = path.rsplit('.').next().unwrap();
+        assert!(
+            sample.contains(&format!("{}:", field)),
+            "config/sais.ron doesn't mention {}",
+            path
+        );
+    }
+}
@@ -0,0 +1,53 @@
+#![cfg(unix)]
+
+use is_sais_down_bot::screenshot::{self, ScreenshotConfig};
+use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+// Stands in for Chrome, writing the page's URL where the screenshot goes.
+fn fake_browser(directory: &Path, script: &str) -> String {
+    let path = directory.join("browser");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path.display().to_string()
+}
+
+fn screenshot_config(browser: String) -> ScreenshotConfig {
+    ScreenshotConfig {
+        browser,
+        width: 800,
+        height: 600,
+        timeout_secs: 5,
+    }
+}
+
+#[tokio::test]
+async fn screenshot_is_read_back() {
+    let directory = tempfile::tempdir().unwrap();
+    let browser = fake_browser(
+        directory.path(),
+        r#"for arg; do
+    case "$arg" in
+        --screenshot=*) out="${arg#--screenshot=}" ;;
+        --window-size=*) size="${arg#--window-size=}" ;;
+    esac
+    url="$arg"
+done
+printf '%s %s' "$size" "$url" > "$out""#,
+    );
+
+    let png = screenshot::capture(&screenshot_config(browser), "https://sais.example/")
+        .await
+        .unwrap();
+    assert_eq!(png, b"800,600 https://sais.example/");
+}
+
+#[tokio::test]
+async fn failing_browser_is_an_error() {
+    let directory = tempfile::tempdir().unwrap();
+    let browser = fake_browser(directory.path(), "exit 3");
+
+    let error = screenshot::capture(&screenshot_config(browser), "https://sais.example/")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("exited"), "{}", error);
+}
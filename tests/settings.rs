@@ -0,0 +1,119 @@
+use is_sais_down_bot::{
+    monitor::MonitorConfig,
+    phrases::DownPhrases,
+    settings::{Overrides, SettingsStore},
+    status::DownReason,
+};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+
+fn base() -> MonitorConfig {
+    MonitorConfig {
+        interval_secs: 600,
+        confirm_after: 2,
+        flap_window: 10,
+        flap_threshold: 4,
+    }
+}
+
+#[test]
+fn settings_are_saved_and_override_the_config() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("data").join("settings.ron");
+
+    let store = SettingsStore::open(&path, 600).unwrap();
+    assert_eq!(store.overrides(), Overrides::default());
+    assert_eq!(store.monitor_config(&base()), base());
+    store.set("monitor.interval_secs", "120").unwrap();
+    store.set("monitor.confirm_after", "3").unwrap();
+
+    let store = SettingsStore::open(&path, 600).unwrap();
+    assert_eq!(
+        store.monitor_config(&base()),
+        MonitorConfig {
+            interval_secs: 120,
+            confirm_after: 3,
+            ..base()
+        }
+    );
+
+    store.set("monitor.interval_secs", "default").unwrap();
+    let store = SettingsStore::open(&path, 600).unwrap();
+    assert_eq!(store.overrides().interval_secs, None);
+    assert_eq!(store.monitor_config(&base()).interval_secs, 600);
+}
+
+#[test]
+fn bad_values_are_refused_and_not_saved() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("settings.ron");
+    let store = SettingsStore::open(&path, 600).unwrap();
+
+    for (key, value) in [
+        ("monitor.interval_secs", "10"),
+        ("monitor.interval_secs", "601"),
+        ("monitor.confirm_after", "0"),
+        ("monitor.flap_window", "lots"),
+        ("alerts.channel_id", "#alerts"),
+        ("monitor.token", "1"),
+        ("down_phrases.sleepy", "Zzz"),
+        ("down_phrases.http_6xx", "Huh"),
+        ("down_phrases.http_503", " "),
+    ] {
+        assert!(store.set(key, value).is_err(), "{} = {}", key, value);
+    }
+    assert_eq!(store.overrides(), Overrides::default());
+    assert!(!path.exists());
+}
+
+#[test]
+fn down_phrases_can_be_set_by_code_or_class() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = SettingsStore::open(directory.path().join("settings.ron"), 600).unwrap();
+    let base = DownPhrases(HashMap::from([(
+        "http_502".to_string(),
+        "Bad gateway ({code})".to_string(),
+    )]));
+
+    store
+        .set("down_phrases.http_5xx", "Something broke ({code})")
+        .unwrap();
+    store
+        .set("down_phrases.connection_refused", "Nobody's home")
+        .unwrap();
+    let phrases = store.down_phrases(&base);
+    assert_eq!(
+        phrases.describe(DownReason::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)),
+        "Something broke (503 Service Unavailable)"
+    );
+    assert_eq!(
+        phrases.describe(DownReason::HttpStatus(StatusCode::BAD_GATEWAY)),
+        "Bad gateway (502 Bad Gateway)"
+    );
+    assert_eq!(
+        phrases.describe(DownReason::ConnectionRefused),
+        "Nobody's home"
+    );
+
+    store.set("down_phrases.http_5xx", "default").unwrap();
+    assert!(!store.overrides().down_phrases.contains_key("http_5xx"));
+}
+
+#[test]
+fn the_alerts_target_can_be_changed_or_added() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = SettingsStore::open(directory.path().join("settings.ron"), 600).unwrap();
+    assert_eq!(store.alerts_target(None), None);
+    assert_eq!(store.alerts_target(Some((1, Some(2)))), Some((1, Some(2))));
+
+    store.set("alerts.channel_id", "<#42>").unwrap();
+    assert_eq!(store.alerts_target(None), Some((42, None)));
+    assert_eq!(store.alerts_target(Some((1, Some(2)))), Some((42, Some(2))));
+
+    store.set("alerts.role_id", "none").unwrap();
+    assert_eq!(store.alerts_target(Some((1, Some(2)))), Some((42, None)));
+    store.set("alerts.role_id", "<@&7>").unwrap();
+    assert_eq!(store.alerts_target(Some((1, Some(2)))), Some((42, Some(7))));
+    store.set("alerts.role_id", "default").unwrap();
+    assert_eq!(store.alerts_target(Some((1, Some(2)))), Some((42, Some(2))));
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "sqlite")]
+
+use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+use is_sais_down_bot::{
+    history::{CheckRecord, HistoryStore},
+    sla::{format_duration, month_bounds, ErrorBudget},
+    sqlite::SqliteHistory,
+};
+
+fn utc_plus_8() -> FixedOffset {
+    FixedOffset::east_opt(8 * 3600).unwrap()
+}
+
+fn record(checked_at: DateTime<Utc>, route: &str, status: &str) -> CheckRecord {
+    CheckRecord {
+        checked_at,
+        route: route.to_string(),
+        status: status.to_string(),
+        reason: None,
+        latency_ms: 100,
+    }
+}
+
+#[test]
+fn months_follow_the_given_time_zone() {
+    let now = utc_plus_8()
+        .with_ymd_and_hms(2026, 12, 15, 12, 0, 0)
+        .unwrap();
+    assert_eq!(
+        month_bounds(now),
+        (
+            Utc.with_ymd_and_hms(2026, 11, 30, 16, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 12, 31, 16, 0, 0).unwrap(),
+        )
+    );
+}
+
+#[tokio::test]
+async fn downtime_is_the_share_of_failed_main_route_checks() {
+    let history = SqliteHistory::open_in_memory().unwrap();
+    // Ten days into a 30 day month.
+    let now = utc_plus_8().with_ymd_and_hms(2026, 9, 11, 0, 0, 0).unwrap();
+    let (month_start, _) = month_bounds(now);
+    let mut records = vec![];
+    for check in 0..100 {
+        let checked_at = month_start + Duration::hours(check);
+        let status = if check < 2 { "down" } else { "up" };
+        records.push(record(checked_at, "here", status));
+        // None of these count.
+        records.push(record(checked_at, "campus", "down"));
+        records.push(record(checked_at, "here", "maintenance"));
+        records.push(record(checked_at, "here", "no_data"));
+    }
+    // From the month before.
+    records.push(record(month_start - Duration::hours(1), "here", "down"));
+    history.record(&records).await.unwrap();
+
+    let budget = ErrorBudget::for_month(&history, 99.0, now).await.unwrap();
+    assert_eq!(budget.uptime_percent, 98.0);
+    // 2% of ten days, out of 1% of thirty.
+    assert_eq!(budget.downtime, Duration::minutes(288));
+    assert_eq!(budget.budget, Duration::minutes(432));
+    assert!(!budget.is_exceeded());
+    assert_eq!(format_duration(budget.remaining()), "2h 24m");
+
+    let budget = ErrorBudget::for_month(&history, 99.9, now).await.unwrap();
+    assert!(budget.is_exceeded());
+    assert_eq!(format_duration(budget.remaining()), "-4h 4m");
+}
@@ -0,0 +1,60 @@
+use is_sais_down_bot::spam::{SpamConfig, SpamGuard, Verdict};
+use std::time::{Duration, Instant};
+
+fn guard() -> SpamGuard {
+    SpamGuard::new(SpamConfig {
+        max_commands: 3,
+        window_secs: 60,
+        ignore_secs: 600,
+    })
+}
+
+#[test]
+fn too_many_commands_get_a_user_ignored() {
+    let guard = guard();
+    let start = Instant::now();
+    let at = |secs| start + Duration::from_secs(secs);
+
+    for secs in 0..3 {
+        assert_eq!(guard.record(1, at(secs)), Verdict::Allowed);
+    }
+    assert_eq!(guard.record(2, at(3)), Verdict::Allowed);
+    assert_eq!(
+        guard.record(1, at(4)),
+        Verdict::NowIgnored(Duration::from_secs(600))
+    );
+    assert_eq!(guard.record(1, at(300)), Verdict::Ignored);
+    assert_eq!(guard.ignored(at(300)), vec![(1, Duration::from_secs(304))]);
+
+    assert_eq!(guard.record(1, at(604)), Verdict::Allowed);
+    assert!(guard.ignored(at(604)).is_empty());
+}
+
+#[test]
+fn commands_spread_out_are_allowed() {
+    let guard = guard();
+    let start = Instant::now();
+    for minute in 0..10 {
+        for secs in [0, 20, 40] {
+            let at = start + Duration::from_secs(minute * 60 + secs);
+            assert_eq!(guard.record(1, at), Verdict::Allowed);
+        }
+    }
+}
+
+#[test]
+fn pardoned_users_start_over() {
+    let guard = guard();
+    let now = Instant::now();
+    for _ in 0..4 {
+        guard.record(1, now);
+    }
+    assert_eq!(guard.record(1, now), Verdict::Ignored);
+
+    assert!(guard.pardon(1, now));
+    assert!(!guard.pardon(1, now));
+    assert!(!guard.pardon(2, now));
+    for _ in 0..3 {
+        assert_eq!(guard.record(1, now), Verdict::Allowed);
+    }
+}
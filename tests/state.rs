@@ -0,0 +1,91 @@
+use chrono::{TimeZone, Utc};
+use is_sais_down_bot::{
+    history::CheckRecord,
+    state::{self, MemoryState, SharedState, CHECK_LOCK},
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[tokio::test]
+async fn cooldowns_are_per_key_until_they_run_out() {
+    let state = MemoryState::default();
+    let cooldown = Duration::from_millis(200);
+
+    assert_eq!(state.take_cooldown("sais:1", cooldown).await.unwrap(), None);
+    let left = state.take_cooldown("sais:1", cooldown).await.unwrap();
+    assert!(left.is_some_and(|left| left <= cooldown));
+    assert_eq!(state.take_cooldown("sais:2", cooldown).await.unwrap(), None);
+
+    tokio::time::sleep(cooldown).await;
+    assert_eq!(state.take_cooldown("sais:1", cooldown).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn the_latest_status_is_the_last_one_set() {
+    let state = MemoryState::default();
+    assert_eq!(state.latest_status().await.unwrap(), None);
+
+    let record = CheckRecord {
+        checked_at: Utc.with_ymd_and_hms(2026, 8, 1, 8, 0, 0).unwrap(),
+        route: "here".to_string(),
+        status: "down".to_string(),
+        reason: Some("http_503".to_string()),
+        latency_ms: 420,
+    };
+    state.set_latest_status(&record).await.unwrap();
+
+    assert_eq!(state.latest_status().await.unwrap(), Some(record));
+}
+
+#[tokio::test]
+async fn locks_are_only_let_go_by_their_holder_or_when_they_expire() {
+    let state = MemoryState::default();
+    let ttl = Duration::from_millis(200);
+
+    assert!(state.try_lock(CHECK_LOCK, "a", ttl).await.unwrap());
+    assert!(!state.try_lock(CHECK_LOCK, "b", ttl).await.unwrap());
+    state.unlock(CHECK_LOCK, "b").await.unwrap();
+    assert!(!state.try_lock(CHECK_LOCK, "b", ttl).await.unwrap());
+
+    state.unlock(CHECK_LOCK, "a").await.unwrap();
+    assert!(state.try_lock(CHECK_LOCK, "b", ttl).await.unwrap());
+
+    tokio::time::sleep(ttl).await;
+    assert!(state.try_lock(CHECK_LOCK, "a", ttl).await.unwrap());
+}
+
+#[tokio::test]
+async fn checks_holding_the_lock_never_overlap() {
+    let state = Arc::new(MemoryState::default());
+    let running = Arc::new(AtomicUsize::new(0));
+
+    let checks = (0..4).map(|holder| {
+        let state = state.clone();
+        let running = running.clone();
+        tokio::spawn(async move {
+            state::with_check_lock(
+                state.as_ref(),
+                &holder.to_string(),
+                Duration::from_secs(5),
+                async {
+                    assert_eq!(running.fetch_add(1, Ordering::SeqCst), 0);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    holder
+                },
+            )
+            .await
+        })
+    });
+
+    let mut holders = vec![];
+    for check in checks {
+        holders.push(check.await.unwrap());
+    }
+    assert_eq!(holders, vec![0, 1, 2, 3]);
+}
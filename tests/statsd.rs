@@ -0,0 +1,86 @@
+use chrono::Utc;
+use is_sais_down_bot::{
+    exporters::CheckExporter,
+    routes::RouteResult,
+    statsd::{self, StatsdConfig, StatsdExporter},
+    status::{DownReason, ServiceStatus},
+};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+fn route_result(route: &str, status: ServiceStatus) -> RouteResult {
+    RouteResult {
+        route: route.to_string(),
+        status,
+        suspicious_redirect: None,
+        failing_pages: vec![],
+        error_page: None,
+        latency: Duration::from_millis(250),
+    }
+}
+
+fn statsd_config(address: String, tags: bool) -> StatsdConfig {
+    StatsdConfig {
+        address,
+        prefix: "sais".to_string(),
+        tags,
+    }
+}
+
+#[test]
+fn route_names_are_made_safe_for_metric_names() {
+    let config = statsd_config("127.0.0.1:8125".to_string(), false);
+
+    assert_eq!(
+        statsd::metrics(
+            &config,
+            &route_result("campus proxy", ServiceStatus::Down(DownReason::NoResponse))
+        ),
+        vec![
+            "sais.check.campus_proxy.down:1|c",
+            "sais.latency_ms.campus_proxy:250|ms",
+            "sais.up.campus_proxy:0|g",
+        ]
+    );
+}
+
+#[test]
+fn tags_can_be_used_instead() {
+    let config = statsd_config("127.0.0.1:8125".to_string(), true);
+
+    assert_eq!(
+        statsd::metrics(&config, &route_result("here", ServiceStatus::Up)),
+        vec![
+            "sais.check:1|c|#route:here,status:up",
+            "sais.latency_ms:250|ms|#route:here",
+            "sais.up:1|g|#route:here",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn checks_are_sent_in_one_packet() {
+    let agent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let exporter = StatsdExporter::new(statsd_config(
+        agent.local_addr().unwrap().to_string(),
+        false,
+    ));
+
+    exporter
+        .export(
+            Utc::now(),
+            &[
+                route_result("here", ServiceStatus::Up),
+                route_result("tcp", ServiceStatus::Up),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let mut packet = [0; 1024];
+    let length = agent.recv(&mut packet).await.unwrap();
+    let packet = String::from_utf8_lossy(&packet[..length]);
+    assert_eq!(packet.lines().count(), 6);
+    assert!(packet.starts_with("sais.check.here.up:1|c\n"));
+    assert!(packet.ends_with("sais.up.tcp:1|g"));
+}
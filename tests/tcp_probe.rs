@@ -0,0 +1,81 @@
+use is_sais_down_bot::{
+    config::{RequestHeaders, SaisConfig, Timeouts},
+    status::{DownReason, ServiceStatus},
+    tcp_probe::{TcpProbe, TCP_ROUTE_NAME},
+};
+use tokio::net::TcpListener;
+
+fn sais_config(login_url: String, proxy: Option<String>) -> SaisConfig {
+    SaisConfig {
+        login_url,
+        login_success_string: "<title>Employee-facing registry content</title>".to_string(),
+        maintenance_strings: vec![],
+        max_redirects: 10,
+        trusted_redirect_hosts: vec![],
+        timeouts: Timeouts::default(),
+        degraded_after_secs: None,
+        proxy,
+        ip_family: None,
+        dual_stack: false,
+        routes: vec![],
+        deep_checks: vec![],
+        tcp_probe: true,
+        timing_probe: false,
+        error_pages: vec![],
+        session: None,
+        logout_url: None,
+        headers: RequestHeaders::default(),
+    }
+}
+
+#[tokio::test]
+async fn listening_port_is_up() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let probe = TcpProbe::new(&sais_config(
+        format!("http://127.0.0.1:{}/psp/ps/?cmd=login", port),
+        None,
+    ))
+    .unwrap();
+
+    let result = probe.run().await;
+
+    assert_eq!(result.route, TCP_ROUTE_NAME);
+    assert_eq!(result.status, ServiceStatus::Up);
+}
+
+#[tokio::test]
+async fn closed_port_is_refused() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+    let probe = TcpProbe::new(&sais_config(
+        format!("http://127.0.0.1:{}/psp/ps/?cmd=login", port),
+        None,
+    ))
+    .unwrap();
+
+    let result = probe.run().await;
+
+    assert_eq!(
+        result.status,
+        ServiceStatus::Down(DownReason::ConnectionRefused)
+    );
+}
+
+#[test]
+fn checks_through_a_proxy_are_not_probed() {
+    let config = sais_config(
+        "https://sais.up.edu.ph/psp/ps/?cmd=login".to_string(),
+        Some("socks5://127.0.0.1:1080".to_string()),
+    );
+
+    assert!(TcpProbe::new(&config).is_none());
+}
+
+#[test]
+fn https_defaults_to_port_443() {
+    let config = sais_config("https://sais.up.edu.ph/psp/ps/?cmd=login".to_string(), None);
+
+    assert!(format!("{:?}", TcpProbe::new(&config).unwrap()).contains("port: 443"));
+}
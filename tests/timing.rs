@@ -0,0 +1,72 @@
+use is_sais_down_bot::{
+    config::SaisConfig,
+    timing::{TimingHistograms, TimingProbe, Timings},
+};
+use std::time::Duration;
+use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn phases_of_a_plain_http_login_page_are_timed() {
+    let server = MockServer::start().await;
+    Mock::given(path("/login"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("Sign In")
+                .set_delay(Duration::from_millis(300)),
+        )
+        .mount(&server)
+        .await;
+    let sais_config = SaisConfig::new(format!("{}/login?cmd=login", server.uri()), "Welcome");
+
+    let timings = TimingProbe::new(&sais_config).unwrap().run().await.unwrap();
+    assert_eq!(timings.tls, None);
+    assert!(timings.first_byte >= Duration::from_millis(300));
+    assert!(timings.total() >= timings.first_byte + timings.connect);
+    assert_eq!(
+        timings
+            .phases()
+            .iter()
+            .map(|(phase, _)| *phase)
+            .collect::<Vec<_>>(),
+        ["dns", "connect", "first_byte", "download", "total"]
+    );
+}
+
+#[tokio::test]
+async fn unreachable_hosts_fail_to_be_timed() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let sais_config = SaisConfig::new(format!("http://{}/login", address), "Welcome");
+
+    assert!(TimingProbe::new(&sais_config).unwrap().run().await.is_err());
+}
+
+#[test]
+fn proxied_checks_are_not_timed() {
+    let mut sais_config = SaisConfig::new("https://sais.up.edu.ph/login", "Welcome");
+    sais_config.proxy = Some("socks5://127.0.0.1:1080".to_string());
+    assert!(TimingProbe::new(&sais_config).is_none());
+}
+
+#[test]
+fn timings_are_described_phase_by_phase() {
+    let timings = Timings {
+        dns: Duration::from_millis(4),
+        connect: Duration::from_millis(25),
+        tls: Some(Duration::from_millis(60)),
+        first_byte: Duration::from_millis(900),
+        download: Duration::from_millis(11),
+    };
+    assert_eq!(
+        timings.to_string(),
+        "DNS 4 ms, connect 25 ms, TLS 60 ms, first byte 900 ms, download 11 ms, total 1000 ms"
+    );
+
+    let histograms = TimingHistograms::default();
+    assert_eq!(histograms.to_prometheus(), "");
+    histograms.record(&timings);
+    assert!(histograms
+        .to_prometheus()
+        .contains("sais_check_phase_seconds_bucket{phase=\"tls\",le=\"0.05\"} 0\n"));
+}
@@ -0,0 +1,61 @@
+use is_sais_down_bot::trace::Trace;
+use std::{net::IpAddr, time::Duration};
+
+const UNREACHABLE: &str = "\
+traceroute to sais.up.edu.ph (198.51.100.7), 20 hops max, 60 byte packets
+ 1  10.0.0.1  0.512 ms  0.430 ms  0.401 ms
+ 2  203.0.113.9  1.200 ms *  1.400 ms
+ 3  192.0.2.33  9.000 ms  9.500 ms  10.000 ms
+ 4  * * *
+ 5  * * *
+";
+
+fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+}
+
+#[test]
+fn hops_are_parsed() {
+    let trace = Trace::parse("sais.up.edu.ph", UNREACHABLE, vec![]);
+
+    assert_eq!(trace.target, Some(ip("198.51.100.7")));
+    assert_eq!(trace.hops.len(), 5);
+    assert_eq!(trace.hops[1].address, Some(ip("203.0.113.9")));
+    assert_eq!(
+        trace.hops[1].round_trips,
+        vec![Duration::from_micros(1200), Duration::from_micros(1400)]
+    );
+    assert_eq!(trace.hops[1].loss_percent(), 33);
+    assert_eq!(trace.hops[3].address, None);
+    assert_eq!(trace.hops[3].loss_percent(), 100);
+}
+
+#[test]
+fn replies_stopping_outside_campus_are_named() {
+    let trace = Trace::parse(
+        "sais.up.edu.ph",
+        UNREACHABLE,
+        vec!["203.0.113.0/24".parse().unwrap()],
+    );
+
+    assert!(trace.is_on_campus(ip("10.0.0.1")));
+    assert!(trace.is_on_campus(ip("203.0.113.9")));
+    assert!(!trace.is_on_campus(ip("192.0.2.33")));
+    assert!(!trace.reached_target());
+    assert!(trace
+        .to_string()
+        .ends_with("Replies stop after hop 3 (192.0.2.33), which is outside the campus network."));
+}
+
+#[test]
+fn reaching_sais_is_said() {
+    let output = "\
+traceroute to sais.up.edu.ph (198.51.100.7), 20 hops max, 60 byte packets
+ 1  10.0.0.1  0.512 ms  0.430 ms  0.401 ms
+ 2  198.51.100.7  3.100 ms  3.000 ms  2.900 ms
+";
+    let trace = Trace::parse("sais.up.edu.ph", output, vec![]);
+
+    assert!(trace.reached_target());
+    assert!(trace.to_string().ends_with("Reached sais.up.edu.ph."));
+}
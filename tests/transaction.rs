@@ -0,0 +1,127 @@
+use is_sais_down_bot::{
+    config::RequestHeaders,
+    transaction::{self, get_transactions, Step, Transaction, TransactionOutcome},
+    transport::ReqwestTransport,
+};
+use std::time::Duration;
+use wiremock::{
+    matchers::{body_string_contains, header_regex, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn login_transaction(server_uri: &str) -> Transaction {
+    Transaction {
+        name: "portal".to_string(),
+        timeout_secs: 10,
+        steps: vec![
+            Step::Get(format!("{}/login", server_uri)),
+            Step::ExtractHiddenField("logintoken".to_string()),
+            Step::Post {
+                url: format!("{}/login", server_uri),
+                form: vec![
+                    ("logintoken".to_string(), "{logintoken}".to_string()),
+                    (
+                        "username".to_string(),
+                        "{env:PROBE_TEST_USERNAME}".to_string(),
+                    ),
+                ],
+            },
+            Step::ExpectStatus(200),
+            Step::Expect("Dashboard".to_string()),
+        ],
+        logout_url: None,
+        headers: RequestHeaders::default(),
+    }
+}
+
+#[tokio::test]
+async fn steps_carry_extracted_values_and_cookies() {
+    std::env::set_var("PROBE_TEST_USERNAME", "juan");
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/login"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .append_header("Set-Cookie", "session=abc; Path=/")
+                .set_body_string(r#"<input type="hidden" name="logintoken" value="t0k3n">"#),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .and(header_regex("cookie", "^session=abc$"))
+        .and(body_string_contains("logintoken=t0k3n"))
+        .and(body_string_contains("username=juan"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<h1>Dashboard</h1>"))
+        .mount(&server)
+        .await;
+
+    let outcome = transaction::run(
+        &ReqwestTransport::default(),
+        &login_transaction(&server.uri()),
+    )
+    .await;
+    assert_eq!(outcome, TransactionOutcome::Passed);
+}
+
+#[tokio::test]
+async fn first_failing_step_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/login"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Under maintenance"))
+        .mount(&server)
+        .await;
+
+    let outcome = transaction::run(
+        &ReqwestTransport::default(),
+        &login_transaction(&server.uri()),
+    )
+    .await;
+    assert!(matches!(
+        outcome,
+        TransactionOutcome::Failed { step: 2, ref reason } if reason.contains("logintoken")
+    ));
+}
+
+#[tokio::test]
+async fn all_transactions_run_at_once() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/slow"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("Dashboard")
+                .set_delay(Duration::from_secs(1)),
+        )
+        .mount(&server)
+        .await;
+    let mut transactions = ["a", "b", "c"]
+        .iter()
+        .map(|name| Transaction {
+            name: name.to_string(),
+            timeout_secs: 2,
+            steps: vec![Step::Get(format!("{}/slow", server.uri()))],
+            logout_url: None,
+            headers: RequestHeaders::default(),
+        })
+        .collect::<Vec<_>>();
+    transactions[1]
+        .steps
+        .push(Step::Expect("Enrolment".to_string()));
+
+    let started = std::time::Instant::now();
+    let outcomes = transaction::run_all(&ReqwestTransport::default(), &transactions).await;
+    assert!(started.elapsed() < Duration::from_millis(2500));
+    assert_eq!(outcomes[0], TransactionOutcome::Passed);
+    assert!(matches!(
+        outcomes[1],
+        TransactionOutcome::Failed { step: 2, .. }
+    ));
+    assert_eq!(outcomes[2], TransactionOutcome::Passed);
+}
+
+#[test]
+fn example_transactions_file_parses() {
+    get_transactions().unwrap();
+}
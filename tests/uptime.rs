@@ -0,0 +1,50 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use is_sais_down_bot::{history::CheckRecord, uptime::HourlyUptime};
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 8, 10, 1, 0, 0).unwrap()
+}
+
+fn record(minutes_ago: i64, route: &str, status: &str) -> CheckRecord {
+    CheckRecord {
+        checked_at: now() - Duration::minutes(minutes_ago),
+        route: route.to_string(),
+        status: status.to_string(),
+        reason: None,
+        latency_ms: 100,
+    }
+}
+
+#[test]
+fn each_hour_gets_a_square() {
+    let records = vec![
+        // Too long ago.
+        record(24 * 60, "here", "down"),
+        // The first hour: most checks failed.
+        record(24 * 60 - 10, "here", "down"),
+        record(24 * 60 - 20, "here", "down"),
+        record(24 * 60 - 30, "here", "up"),
+        // The second: some failed.
+        record(22 * 60 + 30, "here", "degraded"),
+        record(22 * 60 + 20, "here", "pages_failing"),
+        // The last hour: all worked, apart from other routes and checks that
+        // don't count.
+        record(0, "here", "up"),
+        record(30, "here", "up"),
+        record(30, "proxy", "down"),
+        record(40, "here", "maintenance"),
+    ];
+    let uptime = HourlyUptime::from_records(&records, now());
+    assert_eq!(uptime.hours[0], (2, 3));
+    assert_eq!(uptime.hours[1], (1, 2));
+    assert_eq!(uptime.hours[23], (0, 2));
+    assert_eq!(uptime.to_bar(), format!("🟥🟨{}🟩", "⬜".repeat(21)));
+    assert_eq!(uptime.uptime_percent(), Some(100.0 * 4.0 / 7.0));
+}
+
+#[test]
+fn no_checks_means_no_uptime() {
+    let uptime = HourlyUptime::from_records(&[], now());
+    assert_eq!(uptime.uptime_percent(), None);
+    assert_eq!(uptime.to_bar(), "⬜".repeat(24));
+}
@@ -0,0 +1,250 @@
+#![cfg(feature = "web")]
+
+use futures::{Stream, StreamExt};
+use is_sais_down_bot::{
+    api_keys::{ApiKey, ApiKeys},
+    health::Readiness,
+    metrics::CommandMetrics,
+    monitor::MonitorHealth,
+    status::{DownReason, ServiceStatus},
+    timing::{TimingHistograms, Timings},
+    web::{self, StatusEvent, WebState},
+};
+use std::{net::TcpListener, sync::Arc, time::Duration};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite;
+
+fn readiness() -> Arc<Readiness> {
+    Arc::new(Readiness::new(
+        Arc::new(MonitorHealth::default()),
+        Duration::from_secs(60),
+        false,
+    ))
+}
+
+#[test]
+fn command_metrics_are_kept_per_command() {
+    let metrics = CommandMetrics::default();
+    metrics.record("sais", Duration::from_millis(100), true);
+    metrics.record("sais", Duration::from_millis(300), false);
+    metrics.record("help", Duration::from_millis(5), true);
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot[0].0, "help");
+    let sais = &snapshot[1].1;
+    assert_eq!((sais.invocations, sais.errors), (2, 1));
+    assert_eq!(sais.mean_latency(), Duration::from_millis(200));
+    assert_eq!(sais.max_latency, Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn metrics_are_served_for_prometheus() {
+    let command_metrics = Arc::new(CommandMetrics::default());
+    command_metrics.record("sais", Duration::from_millis(1500), false);
+    let check_timings = Arc::new(TimingHistograms::default());
+    check_timings.record(&Timings {
+        dns: Duration::from_millis(3),
+        connect: Duration::from_millis(20),
+        tls: Some(Duration::from_millis(40)),
+        first_byte: Duration::from_millis(700),
+        download: Duration::from_millis(30),
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(web::serve(
+        listener,
+        WebState {
+            command_metrics,
+            check_timings,
+            readiness: readiness(),
+            history: None,
+            statuses: watch::channel(None).1,
+            dashboard: None,
+            api_keys: None,
+        },
+    ));
+
+    let body = reqwest::get(format!("http://{}/metrics", address))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("# TYPE sais_bot_command_invocations_total counter\n"));
+    assert!(body.contains("sais_bot_command_errors_total{command=\"sais\"} 1\n"));
+    assert!(body.contains("sais_bot_command_latency_seconds_total{command=\"sais\"} 1.5\n"));
+    assert!(body.contains("# TYPE sais_check_phase_seconds histogram\n"));
+    assert!(body.contains("sais_check_phase_seconds_bucket{phase=\"first_byte\",le=\"0.5\"} 0\n"));
+    assert!(body.contains("sais_check_phase_seconds_bucket{phase=\"first_byte\",le=\"1\"} 1\n"));
+    assert!(body.contains("sais_check_phase_seconds_count{phase=\"total\"} 1\n"));
+}
+
+async fn next_event<S>(socket: &mut S) -> StatusEvent
+where
+    S: Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+{
+    let message = socket.next().await.unwrap().unwrap();
+    serde_json::from_str(message.to_text().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn status_changes_are_pushed_to_the_status_page() {
+    let (statuses, receiver) = watch::channel(Some(ServiceStatus::Up));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(web::serve(
+        listener,
+        WebState {
+            command_metrics: Arc::new(CommandMetrics::default()),
+            check_timings: Arc::default(),
+            readiness: readiness(),
+            history: None,
+            statuses: receiver,
+            dashboard: None,
+            api_keys: None,
+        },
+    ));
+
+    let page = reqwest::get(format!("http://{}/", address))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(page.contains("UP SAIS is <span id=\"status\">up</span>"));
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", address))
+        .await
+        .unwrap();
+    assert_eq!(next_event(&mut socket).await.kind, "up");
+
+    statuses
+        .send(Some(ServiceStatus::Down(DownReason::NoResponse)))
+        .unwrap();
+    assert_eq!(
+        next_event(&mut socket).await,
+        StatusEvent {
+            kind: "down".to_string(),
+            reason: Some("no_response".to_string()),
+            description: "not loading".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn graphql_queries_are_served() {
+    let (_statuses, receiver) = watch::channel(Some(ServiceStatus::Maintenance));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(web::serve(
+        listener,
+        WebState {
+            command_metrics: Arc::new(CommandMetrics::default()),
+            check_timings: Arc::default(),
+            readiness: readiness(),
+            history: None,
+            statuses: receiver,
+            dashboard: None,
+            api_keys: None,
+        },
+    ));
+
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("http://{}/graphql", address))
+        .json(&serde_json::json!({ "query": "{ latestStatus { kind description } }" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        response["data"]["latestStatus"],
+        serde_json::json!({ "kind": "maintenance", "description": "under maintenance" })
+    );
+}
+
+#[tokio::test]
+async fn graphql_needs_an_api_key_when_any_are_set() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(web::serve(
+        listener,
+        WebState {
+            command_metrics: Arc::new(CommandMetrics::default()),
+            check_timings: Arc::default(),
+            readiness: readiness(),
+            history: None,
+            statuses: watch::channel(Some(ServiceStatus::Up)).1,
+            dashboard: None,
+            api_keys: Some(Arc::new(ApiKeys::new(vec![ApiKey {
+                name: "student council".to_string(),
+                key: "s3cret".to_string(),
+                requests_per_minute: 1,
+            }]))),
+        },
+    ));
+    let client = reqwest::Client::new();
+    let query = serde_json::json!({ "query": "{ latestStatus { kind } }" });
+    let graphql_url = format!("http://{}/graphql", address);
+
+    let response = client.post(&graphql_url).json(&query).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .post(&graphql_url)
+        .bearer_auth("s3cret")
+        .json(&query)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = client
+        .post(&graphql_url)
+        .header("X-API-Key", "s3cret")
+        .json(&query)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+
+    // Other endpoints stay open.
+    let response = reqwest::get(format!("http://{}/healthz", address))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn readiness_follows_the_monitor_while_liveness_does_not() {
+    let monitor_health = Arc::new(MonitorHealth::default());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(web::serve(
+        listener,
+        WebState {
+            command_metrics: Arc::new(CommandMetrics::default()),
+            check_timings: Arc::default(),
+            readiness: Arc::new(Readiness::new(
+                monitor_health.clone(),
+                Duration::from_secs(60),
+                false,
+            )),
+            history: None,
+            statuses: watch::channel(None).1,
+            dashboard: None,
+            api_keys: None,
+        },
+    ));
+    let get = |path: &'static str| reqwest::get(format!("http://{}{}", address, path));
+
+    assert_eq!(get("/healthz").await.unwrap().status(), 200);
+    let response = get("/readyz").await.unwrap();
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.text().await.unwrap(), "the monitor hasn't run yet");
+
+    monitor_health.checked(chrono::Utc::now(), ServiceStatus::Up);
+    assert_eq!(get("/readyz").await.unwrap().status(), 200);
+}